@@ -0,0 +1,353 @@
+//! `mlang!{}` macro, compiles an inline `mlang` schema at macro-expansion time and splices the generated
+//! `opcode` and `serde` modules into the caller's crate, no `build.rs` and no generated files to commit.
+//!
+//! ```ignore
+//! mlang_macros::mlang! {
+//!     /// A rectangle element.
+//!     el Rect { x: float, y: float }
+//! }
+//!
+//! let rect = opcode::Rect { x: 1.0, y: 2.0 };
+//! ```
+//!
+//! The generated code refers to `mlang_rs`, so the caller's crate also depends on `mlang-rs`.
+//!
+//! Code generation options are set by a leading `#![codegen(...)]` attr, named after the [`CodeGen`] methods:
+//!
+//! ```ignore
+//! mlang_macros::mlang! {
+//!     #![codegen(visitor, ordered_enums, extra_derives(Hash), extra_derives_for(data, Eq))]
+//!     el Rect { x: float, y: float }
+//! }
+//! ```
+//!
+//! [`CodeGen`]: mlang_rs::lang::rustgen::CodeGen
+
+use mlang_rs::lang::rustgen::{CodeGen, TypeKind};
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::quote;
+
+/// Compile the inline `mlang` schema and expand to `pub mod opcode { ... } pub mod serde { ... }`.
+///
+/// The schema is written as tokens, or as one string literal if it isn't valid rust tokens:
+/// `mlang!(r#"el Rect { x: float }"#)`. Errors are reported by `compile_error!`.
+#[proc_macro]
+pub fn mlang(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (codegen, input) = match parse_options(TokenStream::from(input)) {
+        Ok(options) => options,
+        Err(err) => return quote! { compile_error!(#err); }.into(),
+    };
+
+    let source = match syn::parse2::<syn::LitStr>(input.clone()) {
+        Ok(lit) => lit.value(),
+        Err(_) => to_source(input),
+    };
+
+    match mlang_rs::lang::compile_to_tokens(&source, &codegen) {
+        Ok(codes) => codes.into(),
+        Err(err) => quote! { compile_error!(#err); }.into(),
+    }
+}
+
+/// Split the leading `#![codegen(...)]` attr from the schema, returns the configured [`CodeGen`] and the schema.
+///
+/// Options are flags, e.g. `visitor` or `serde = false`, `serde_feature = "name"`,
+/// `extra_derives(Hash, PartialOrd)` and `extra_derives_for(data, Eq)`.
+fn parse_options(input: TokenStream) -> Result<(CodeGen, TokenStream), String> {
+    let mut tokens = input.clone().into_iter();
+
+    let options = match (tokens.next(), tokens.next(), tokens.next()) {
+        (
+            Some(TokenTree::Punct(hash)),
+            Some(TokenTree::Punct(bang)),
+            Some(TokenTree::Group(attr)),
+        ) if hash.as_char() == '#'
+            && bang.as_char() == '!'
+            && attr.delimiter() == Delimiter::Bracket =>
+        {
+            match attr.stream().into_iter().collect::<Vec<_>>().as_slice() {
+                [TokenTree::Ident(ident), TokenTree::Group(options)]
+                    if ident == "codegen" && options.delimiter() == Delimiter::Parenthesis =>
+                {
+                    options.stream()
+                }
+                _ => {
+                    return Err(format!(
+                        "Expect `#![codegen(...)]`, found `#![{}]`.",
+                        attr.stream()
+                    ));
+                }
+            }
+        }
+        _ => return Ok((CodeGen::default(), input)),
+    };
+
+    let mut codegen = CodeGen::default();
+
+    for option in split_commas(options) {
+        codegen = apply_option(codegen, &option)?;
+    }
+
+    Ok((codegen, tokens.collect()))
+}
+
+fn apply_option(codegen: CodeGen, option: &[TokenTree]) -> Result<CodeGen, String> {
+    let (name, value) = match option {
+        [TokenTree::Ident(name)] => (name.to_string(), None),
+        [TokenTree::Ident(name), TokenTree::Punct(punct), value] if punct.as_char() == '=' => {
+            (name.to_string(), Some(value))
+        }
+        [TokenTree::Ident(name), TokenTree::Group(args)]
+            if args.delimiter() == Delimiter::Parenthesis =>
+        {
+            let args = split_commas(args.stream())
+                .into_iter()
+                .map(|arg| {
+                    arg.into_iter()
+                        .collect::<TokenStream>()
+                        .to_string()
+                        .replace(' ', "")
+                })
+                .collect::<Vec<_>>();
+
+            return match (name.to_string().as_str(), args.split_first()) {
+                ("extra_derives", _) => Ok(codegen.extra_derives(args)),
+                ("extra_derives_for", Some((kind, derives))) => {
+                    Ok(codegen.extra_derives_for(to_type_kind(kind)?, derives))
+                }
+                (name, _) => Err(format!("Unknown codegen option `{}(...)`.", name)),
+            };
+        }
+        _ => {
+            return Err(format!(
+                "Invalid codegen option `{}`.",
+                option.iter().cloned().collect::<TokenStream>()
+            ));
+        }
+    };
+
+    if name == "serde_feature" {
+        return match value.map(|value| syn::parse2::<syn::LitStr>(value.clone().into())) {
+            Some(Ok(feature)) => Ok(codegen.serde_feature(feature.value())),
+            _ => Err("Expect `serde_feature = \"name\"`.".to_string()),
+        };
+    }
+
+    let on = match value.map(ToString::to_string).as_deref() {
+        None | Some("true") => true,
+        Some("false") => false,
+        Some(value) => {
+            return Err(format!(
+                "Expect `{} = true|false`, found `{}`.",
+                name, value
+            ));
+        }
+    };
+
+    Ok(match name.as_str() {
+        "serde" => codegen.with_serde(on),
+        "layout_report" => codegen.with_layout_report(on),
+        "visitor" => codegen.with_visitor(on),
+        "cow_string" => codegen.with_cow_string(on),
+        "ordered_enums" => codegen.with_ordered_enums(on),
+        "strip_names" => codegen.strip_names(on),
+        "no_std" => codegen.no_std(on),
+        name => return Err(format!("Unknown codegen option `{}`.", name)),
+    })
+}
+
+/// Returns the [`TypeKind`] of a declaration keyword.
+fn to_type_kind(keyword: &str) -> Result<TypeKind, String> {
+    match keyword {
+        "el" => Ok(TypeKind::Element),
+        "leaf" => Ok(TypeKind::Leaf),
+        "attr" => Ok(TypeKind::Attr),
+        "data" => Ok(TypeKind::Data),
+        "enum" => Ok(TypeKind::Enum),
+        keyword => Err(format!(
+            "Expect one of `el`, `leaf`, `attr`, `data` or `enum`, found `{}`.",
+            keyword
+        )),
+    }
+}
+
+/// Split `tokens` at top-level commas, empty items are skipped.
+fn split_commas(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut items = vec![vec![]];
+
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => items.push(vec![]),
+            token => items.last_mut().unwrap().push(token),
+        }
+    }
+
+    items.retain(|item| !item.is_empty());
+
+    items
+}
+
+/// Print `tokens` as `mlang` source code, doc attrs are printed back as `///` comments.
+///
+/// Tokens are only separated where required, the parser doesn't accept whitespace between a node name
+/// and its tuple fields: `data Length(float);`.
+fn to_source(tokens: TokenStream) -> String {
+    let mut source = String::new();
+
+    print_tokens(tokens, &mut source);
+
+    source
+}
+
+/// Push an ident or literal, separated from the previous one.
+fn push_word(source: &mut String, word: &str) {
+    if source
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '"' | '\''))
+    {
+        source.push(' ');
+    }
+
+    source.push_str(word);
+}
+
+fn print_tokens(tokens: TokenStream, source: &mut String) {
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.peek()
+                    && let Some(doc) = to_doc(group.stream())
+                {
+                    tokens.next();
+
+                    if !source.is_empty() && !source.ends_with('\n') {
+                        source.push('\n');
+                    }
+
+                    source.push_str("///");
+                    source.push_str(&doc);
+                    source.push('\n');
+                    continue;
+                }
+
+                source.push('#');
+            }
+            TokenTree::Punct(punct) => {
+                source.push(punct.as_char());
+
+                // `- >` isn't an arrow.
+                if punct.spacing() == Spacing::Alone
+                    && matches!(tokens.peek(), Some(TokenTree::Punct(_)))
+                {
+                    source.push(' ');
+                }
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+
+                source.push_str(open);
+                print_tokens(group.stream(), source);
+                source.push_str(close);
+            }
+            TokenTree::Ident(ident) => push_word(source, &ident.to_string()),
+            TokenTree::Literal(lit) => push_word(source, &lit.to_string()),
+        }
+    }
+}
+
+/// Returns the text of a `[doc = "..."]` attr.
+fn to_doc(attr: TokenStream) -> Option<String> {
+    let tokens = attr.into_iter().collect::<Vec<_>>();
+
+    match tokens.as_slice() {
+        [
+            TokenTree::Ident(ident),
+            TokenTree::Punct(punct),
+            TokenTree::Literal(lit),
+        ] if ident == "doc" && punct.as_char() == '=' => {
+            syn::parse2::<syn::LitStr>(TokenTree::Literal(lit.clone()).into())
+                .ok()
+                .map(|lit| lit.value())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use mlang_rs::lang::{compile_to_tokens, rustgen::CodeGen};
+    use proc_macro2::TokenStream;
+
+    use super::{parse_options, to_source};
+
+    #[test]
+    fn test_to_source() {
+        let source = r#"
+            /// A length value.
+            data Length(float);
+
+            #[derive(Hash, Eq)]
+            enum Unit { Px, Em }
+
+            el Rect {
+                #[option, variable]
+                width: Length,
+                map: [string -> vec[Unit]],
+                price: decimal(10, 2),
+            }
+
+            attr Fill(string);
+            apply Fill to Rect;
+            mod a { data B(int); }
+            el C(a::B);
+        "#;
+
+        let codegen = CodeGen::default();
+
+        let tokens = to_source(TokenStream::from_str(source).unwrap());
+
+        assert!(tokens.starts_with("/// A length value.\n"));
+
+        assert_eq!(
+            compile_to_tokens(tokens, &codegen).unwrap().to_string(),
+            compile_to_tokens(source, &codegen).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let (_, schema) = parse_options(
+            TokenStream::from_str("#![codegen(visitor, serde = false)] el A;").unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(schema.to_string(), "el A ;");
+
+        for (options, err) in [
+            ("#![codegen(unknown)]", "Unknown codegen option `unknown`."),
+            (
+                "#![codegen(visitor = 1)]",
+                "Expect `visitor = true|false`, found `1`.",
+            ),
+            (
+                "#![codegen(extra_derives_for(node, Eq))]",
+                "Expect one of `el`, `leaf`, `attr`, `data` or `enum`, found `node`.",
+            ),
+        ] {
+            assert_eq!(
+                parse_options(TokenStream::from_str(options).unwrap()).err(),
+                Some(err.to_string())
+            );
+        }
+    }
+}
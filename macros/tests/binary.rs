@@ -0,0 +1,148 @@
+//! Round-trips of generated types with every kind of field, and the schema version of binary documents.
+
+#![allow(unexpected_cfgs)]
+
+mod common;
+
+use mlang_rs::rt::{
+    opcode::{Path, Target, Variable},
+    serde::{
+        binary::{Deserializer, Serializer},
+        de::Deserialize,
+        ser::Serialize,
+    },
+};
+
+use common::round_trip;
+
+mod schema {
+    mlang_macros::mlang! {
+        version "1.2.0";
+
+        enum Unit { Px, Em }
+
+        enum Paint {
+            None,
+            Color(ubyte, ubyte, ubyte),
+            Named { name: string, #[option] alpha: float },
+        }
+
+        data Length(float, Unit);
+
+        el Shape {
+            #[option] id: string,
+            x: float = 1.5,
+            visible: bool = true,
+            name: string = "none",
+            #[variable] width: Length,
+            #[variable] opacity: double = -0.5,
+            #[option, variable] height: Length,
+            paint: Paint,
+            #[option] stroke: Paint,
+            from: (float, float),
+            pair: vec[(int, Unit)],
+            #[option] tag: (string,),
+        }
+
+        leaf Text(string, #[option] Unit);
+
+        attr Fill(Paint);
+
+        apply Fill to Shape;
+    }
+}
+
+use schema::opcode::*;
+
+fn shapes() -> Vec<Opcode> {
+    let full = Shape {
+        id: Some("a".to_string()),
+        x: -2.0,
+        visible: false,
+        name: "full".to_string(),
+        width: Variable::Constant(Length(3.0, Unit::Em)),
+        opacity: Variable::Reference {
+            path: Path::Named("o".to_string()),
+            target: Target::Register,
+        },
+        height: Some(Variable::Constant(Length(1.0, Unit::Px))),
+        paint: Paint::Color(1, 2, 3),
+        stroke: Some(Paint::Named {
+            name: "red".to_string(),
+            alpha: Some(0.5),
+        }),
+        from: (0.5, -0.5),
+        pair: vec![(1, Unit::Px), (-1, Unit::Em)],
+        tag: Some(("t".to_string(),)),
+    };
+
+    // every defaulted field keeps its default value, every option is `None`.
+    let defaults = Shape {
+        id: None,
+        x: 1.5,
+        visible: true,
+        name: "none".to_string(),
+        width: Variable::Constant(Length(0.0, Unit::Px)),
+        opacity: Variable::Constant(-0.5),
+        height: None,
+        paint: Paint::None,
+        stroke: None,
+        from: (0.0, 0.0),
+        pair: vec![],
+        tag: None,
+    };
+
+    let mut opcodes = ShapeNode::new(full)
+        .apply(Fill(Paint::Named {
+            name: "blue".to_string(),
+            alpha: None,
+        }))
+        .into_opcodes();
+
+    opcodes.extend(ShapeNode::new(defaults).into_opcodes());
+    opcodes.push(Leaf::from(Text("hello".to_string(), Some(Unit::Em))).into());
+    opcodes.push(Leaf::from(Text(String::new(), None)).into());
+
+    opcodes
+}
+
+#[test]
+fn test_round_trip() {
+    round_trip(&shapes());
+}
+
+#[test]
+fn test_schema_version() {
+    let opcodes = shapes();
+
+    let mut serializer = Serializer::new().with_schema_version(SCHEMA_VERSION);
+
+    for opcode in &opcodes {
+        opcode.serialize(&mut serializer).unwrap();
+    }
+
+    let bytes = serializer.finish();
+
+    let mut deserializer = Deserializer::new(&bytes)
+        .unwrap()
+        .with_schema_check(schema::serde::check_schema_version)
+        .unwrap();
+
+    let mut read = vec![];
+
+    while let Some(batch) = Opcode::deserialize(&mut deserializer).unwrap() {
+        read.extend(batch);
+    }
+
+    assert_eq!(read, opcodes);
+
+    // documents of a newer minor version are rejected.
+    let bytes = Serializer::new().with_schema_version("1.3.0").finish();
+
+    assert!(
+        Deserializer::new(&bytes)
+            .unwrap()
+            .with_schema_check(schema::serde::check_schema_version)
+            .is_err()
+    );
+}
@@ -0,0 +1,181 @@
+//! `#[borrow]` fields, which borrow their strings and bytes from the deserializer input.
+
+#![allow(unexpected_cfgs)]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    borrow::Cow,
+    cell::Cell,
+};
+
+use mlang_rs::rt::serde::{binary, json, sexpr, xml};
+
+mod schema {
+    mlang_macros::mlang! {
+        #![codegen(visitor, layout_report)]
+
+        data Label { #[borrow] text: string, size: float = 1.0 }
+
+        enum Paint { None, Named(#[borrow] string) }
+
+        el Group { #[option, borrow] id: string }
+
+        leaf Text { #[borrow] text: string, label: Label, paint: Paint }
+
+        leaf Image { #[borrow] data: bytes, #[borrow] mime: string = "image/png" }
+
+        attr Title(#[borrow] string);
+
+        apply Title to (Group, Text);
+        children (Group, Text, Image) of Group;
+    }
+}
+
+use schema::opcode::*;
+
+/// Counts the bytes allocated by the current thread, tests run in parallel.
+struct Counter;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static COUNTER: Counter = Counter;
+
+/// Returns the value of `f` with the bytes it allocated.
+fn allocated<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = ALLOCATED.with(Cell::get);
+    let value = f();
+
+    (value, ALLOCATED.with(Cell::get) - start)
+}
+
+/// A document with a `text` bytes long text and an `image` bytes long image.
+fn document(text: usize, image: usize) -> Vec<Opcode<'static>> {
+    let text = "a".repeat(text);
+
+    Group::new()
+        .with_id("g".into())
+        .apply(Title("title".into()))
+        .with_child(
+            Text::new(text.into(), Label::new("label".into(), 1.0), Paint::None)
+                .apply(Title("text".into())),
+        )
+        .with_child(
+            Text::new(
+                "b".into(),
+                Label::new("c".into(), 2.0),
+                Paint::Named("red".into()),
+            )
+            .apply(Title("escaped \"title\"".into())),
+        )
+        .with_child(Image::new(vec![0; image].into()))
+        .into_opcodes()
+}
+
+/// Returns `true` if `value` is borrowed from `input`, owned values are allocated elsewhere.
+fn is_borrowed(value: &[u8], input: &[u8]) -> bool {
+    input.as_ptr_range().contains(&value.as_ptr())
+}
+
+fn texts<'a>(opcodes: &'a [Opcode<'_>]) -> Vec<&'a Text<'a>> {
+    opcodes
+        .iter()
+        .filter_map(|opcode| match opcode {
+            Opcode::Leaf(Leaf::Text(text)) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_json() {
+    // the bytes of text formats are encoded, so they are decoded to owned bytes.
+    let json = json::to_string(&document(100_000, 10)).unwrap();
+
+    let (opcodes, bytes) = allocated(|| json::from_str::<Opcode>(&json).unwrap());
+
+    assert_eq!(opcodes, document(100_000, 10));
+    assert!(bytes < 10_000, "{}", bytes);
+
+    let input = json.as_bytes();
+    let texts = texts(&opcodes);
+
+    assert!(is_borrowed(texts[0].text.as_bytes(), input));
+    assert!(is_borrowed(texts[0].label.text.as_bytes(), input));
+    assert!(matches!(&texts[1].paint, Paint::Named(name) if is_borrowed(name.as_bytes(), input)));
+
+    match &opcodes[..3] {
+        [
+            Opcode::Apply(Attr::Title(title)),
+            Opcode::Element(Element::Group(group)),
+            Opcode::Apply(Attr::Title(_)),
+        ] => {
+            assert!(is_borrowed(title.0.as_bytes(), input));
+            assert!(is_borrowed(group.id.as_ref().unwrap().as_bytes(), input));
+        }
+        opcodes => panic!("{:?}", opcodes),
+    }
+
+    // escaped strings can't be borrowed.
+    assert!(opcodes.iter().any(|opcode| matches!(
+        opcode,
+        Opcode::Apply(Attr::Title(title)) if matches!(&title.0, Cow::Owned(title) if title == "escaped \"title\"")
+    )));
+}
+
+#[test]
+fn test_binary() {
+    let bytes = binary::to_bytes(&document(100_000, 100_000)).unwrap();
+
+    let (opcodes, allocated) = allocated(|| binary::from_bytes::<Opcode>(&bytes).unwrap());
+
+    assert_eq!(opcodes, document(100_000, 100_000));
+    assert!(allocated < 10_000, "{}", allocated);
+
+    let texts = texts(&opcodes);
+
+    assert!(is_borrowed(texts[0].text.as_bytes(), &bytes));
+    assert!(is_borrowed(texts[1].text.as_bytes(), &bytes));
+
+    let image = opcodes
+        .iter()
+        .find_map(|opcode| match opcode {
+            Opcode::Leaf(Leaf::Image(image)) => Some(image),
+            _ => None,
+        })
+        .unwrap();
+
+    assert!(is_borrowed(&image.data, &bytes));
+    assert!(is_borrowed(image.mime.as_bytes(), &bytes));
+}
+
+#[test]
+fn test_text_formats() {
+    let xml = xml::to_string(&document(10, 10)).unwrap();
+    assert_eq!(xml::from_str::<Opcode>(&xml).unwrap(), document(10, 10));
+
+    let sexpr = sexpr::to_string(&document(10, 10)).unwrap();
+    assert_eq!(sexpr::from_str::<Opcode>(&sexpr).unwrap(), document(10, 10));
+}
+
+#[test]
+fn test_default() {
+    let image = Image::new(Cow::Borrowed(b"png"));
+
+    assert_eq!(image.mime, "image/png");
+    assert!(matches!(image.mime, Cow::Borrowed(_)));
+}
@@ -0,0 +1,259 @@
+//! The code generation options, checked on the behavior of the generated code.
+
+#![allow(unexpected_cfgs)]
+
+mod common;
+
+use std::collections::{BTreeSet, HashSet};
+
+use common::round_trip;
+
+mod derives {
+    mlang_macros::mlang! {
+        #![codegen(extra_derives(Hash, Eq, PartialOrd), extra_derives_for(data, Ord))]
+        el A { x: int }
+        #[derive(Hash)]
+        data B(int);
+        el C(B);
+    }
+}
+
+mod ordered {
+    mlang_macros::mlang! {
+        #![codegen(ordered_enums)]
+        el A { u: U, v: V }
+        enum U { X, Y }
+        enum V { X(int), Y }
+        attr B(int);
+        apply B to A;
+    }
+}
+
+mod builder {
+    mlang_macros::mlang! {
+        el A { x: int, #[option] y: int, #[variable] z: int = 1, #[init, option] w: int }
+        el B { #[option] x: int }
+        el C { #[option] x: int, y: int = 1 }
+        data D(#[option] int);
+        el E(D);
+    }
+}
+
+mod constants {
+    mlang_macros::mlang! {
+        data Color(ubyte, ubyte, ubyte);
+        data Label { text: string, #[option] size: float, id: uuid }
+        data Outline(pathdata);
+        el Swatch { color: Color, label: Label, outline: Outline }
+    }
+}
+
+mod names {
+    mlang_macros::mlang! {
+        el A {}
+        leaf B {}
+        #[rename("c-attr")]
+        attr C(int);
+        enum U { Px, #[rename("%")] Percent, FontSize }
+        el D(U);
+        apply C to D;
+    }
+}
+
+mod stripped {
+    mlang_macros::mlang! {
+        #![codegen(strip_names)]
+        el Rect { width: float, u: U }
+        enum U { X, Y(int) }
+    }
+}
+
+mod visitor {
+    mlang_macros::mlang! {
+        #![codegen(visitor)]
+        mod shape { el Rect {} }
+        leaf Text(string);
+        attr Fill(string);
+        apply Fill to shape::Rect;
+    }
+}
+
+#[test]
+fn test_extra_derives() {
+    use derives::opcode::*;
+
+    assert_eq!(HashSet::from([A { x: 1 }, A { x: 1 }]).len(), 1);
+    assert_eq!(
+        BTreeSet::from([B(2), B(1)]).into_iter().collect::<Vec<_>>(),
+        [B(1), B(2)]
+    );
+
+    round_trip(&[
+        Element::from(A { x: 1 }).into(),
+        Opcode::Pop,
+        Element::from(C(B(2))).into(),
+        Opcode::Pop,
+    ]);
+}
+
+#[test]
+fn test_ordered_enums() {
+    use mlang_rs::rt::opcode::Id;
+    use ordered::opcode::*;
+
+    assert_eq!(
+        BTreeSet::from([U::Y, U::X]).into_iter().collect::<Vec<_>>(),
+        [U::X, U::Y]
+    );
+
+    let a = Element::from(A {
+        u: U::Y,
+        v: V::X(1),
+    });
+
+    assert_eq!(a.type_id(), ids::A);
+
+    let b = Attr::from(B(1));
+
+    assert_eq!(b.type_id(), ids::B);
+
+    let opcodes: [Opcode; 3] = [b.into(), a.into(), Opcode::Pop];
+
+    assert_eq!(
+        opcodes.each_ref().map(Opcode::type_id),
+        [Some(Id::Attr(ids::B)), Some(Id::Type(ids::A)), None]
+    );
+
+    round_trip(&opcodes);
+}
+
+#[test]
+fn test_builder() {
+    use builder::opcode::*;
+    use mlang_rs::rt::opcode::Variable;
+
+    let a = A::new(1, 4).with_y(2).with_z(3);
+
+    assert_eq!((a.x, a.y, a.w), (1, Some(2), Some(4)));
+    assert_eq!(a.z, Variable::Constant(3));
+    assert_eq!(A::new(1, 4).z, Variable::Constant(1));
+
+    assert_eq!(B::default().x, None);
+    assert_eq!(C::default(), C { x: None, y: 1 });
+    assert_eq!(D::default(), D(None));
+
+    round_trip(&[
+        Element::from(a).into(),
+        Opcode::Pop,
+        Element::from(B::default()).into(),
+        Opcode::Pop,
+        Element::from(E(D::default())).into(),
+        Opcode::Pop,
+    ]);
+}
+
+#[test]
+fn test_const_constructors() {
+    use constants::opcode::*;
+    use mlang_rs::rt::value::Uuid;
+
+    const RED: Color = Color::new(255, 0, 0);
+    const EMPTY: Label = Label::new(String::new(), None, Uuid::from_u128(1));
+
+    assert_eq!(RED, Color(255, 0, 0));
+    assert_eq!((EMPTY.text.as_str(), EMPTY.size), ("", None));
+
+    // `pathdata` values are heap-backed, `Outline` is built as a tuple struct.
+    assert!(Outline(Default::default()).0.0.is_empty());
+}
+
+#[test]
+fn test_names() {
+    use names::{opcode::*, serde};
+
+    assert_eq!(serde::ELEMENT_NAMES, ["a", "d"]);
+    assert_eq!(serde::LEAF_NAMES, ["b"]);
+    assert_eq!(serde::ATTR_NAMES, ["c-attr"]);
+    assert_eq!(serde::ENUM_NAMES, [("u", &["px", "%", "fontSize"][..])]);
+
+    assert_eq!(U::Percent.to_string(), "%");
+    assert_eq!("fontSize".parse::<U>().unwrap(), U::FontSize);
+    assert!("font-size".parse::<U>().is_err());
+
+    let mut opcodes = DNode::new(D(U::Percent)).apply(C(1)).into_opcodes();
+    opcodes.extend([Element::from(A).into(), Opcode::Pop, Leaf::from(B).into()]);
+
+    round_trip(&opcodes);
+}
+
+#[test]
+fn test_all_variants() {
+    use names::opcode::U;
+
+    assert_eq!(U::ALL, [U::Px, U::Percent, U::FontSize]);
+    assert_eq!(U::iter().collect::<Vec<_>>(), U::ALL);
+    assert_eq!(
+        U::iter().map(|u| u.to_string()).collect::<Vec<_>>(),
+        ["px", "%", "fontSize"]
+    );
+}
+
+#[test]
+fn test_strip_names() {
+    use mlang_rs::rt::serde::binary;
+    use stripped::opcode::*;
+
+    let opcodes: Vec<Opcode> = vec![
+        Element::from(Rect {
+            width: 1.5,
+            u: U::Y(2),
+        })
+        .into(),
+        Opcode::Pop,
+    ];
+
+    let bytes = binary::to_bytes(&opcodes).unwrap();
+
+    assert_eq!(binary::from_bytes::<Opcode>(&bytes).unwrap(), opcodes);
+}
+
+#[test]
+fn test_visitor() {
+    use visitor::{opcode::*, visitor::*};
+
+    #[derive(Default)]
+    struct Visits(Vec<String>);
+
+    impl OpcodeVisitor for Visits {
+        fn visit_shape_rect(&mut self, _: &shape::Rect) {
+            self.0.push("rect".to_string());
+        }
+
+        fn visit_text(&mut self, value: &Text) {
+            self.0.push(value.0.clone());
+        }
+
+        fn visit_fill(&mut self, value: &Fill) {
+            self.0.push(value.0.clone());
+        }
+
+        fn visit_pop(&mut self) {
+            self.0.push("pop".to_string());
+        }
+    }
+
+    let opcodes: Vec<Opcode> = vec![
+        Attr::from(Fill("red".to_string())).into(),
+        Element::from(shape::Rect {}).into(),
+        Opcode::Pop,
+        Leaf::from(Text("hello".to_string())).into(),
+    ];
+
+    let mut visits = Visits::default();
+
+    for opcode in &opcodes {
+        visit(opcode, &mut visits);
+    }
+
+    assert_eq!(visits.0, ["red", "rect", "pop", "hello"]);
+}
@@ -0,0 +1,23 @@
+//! Helpers shared by the tests of the generated code.
+
+use std::fmt::Debug;
+
+use mlang_rs::rt::serde::{binary, de::Deserialize, json, ser::Serialize, sexpr, xml};
+
+/// Round-trip `opcodes` through every runtime format, the decoded opcodes must equal `opcodes`.
+pub fn round_trip<T>(opcodes: &[T])
+where
+    T: Serialize + for<'de> Deserialize<'de, Value = Option<Vec<T>>> + PartialEq + Debug,
+{
+    let bytes = binary::to_bytes(opcodes).unwrap();
+    assert_eq!(binary::from_bytes::<T>(&bytes).unwrap(), opcodes, "binary");
+
+    let text = json::to_string(opcodes).unwrap();
+    assert_eq!(json::from_str::<T>(&text).unwrap(), opcodes, "{}", text);
+
+    let text = xml::to_string(opcodes).unwrap();
+    assert_eq!(xml::from_str::<T>(&text).unwrap(), opcodes, "{}", text);
+
+    let text = sexpr::to_string(opcodes).unwrap();
+    assert_eq!(sexpr::from_str::<T>(&text).unwrap(), opcodes, "{}", text);
+}
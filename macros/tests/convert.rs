@@ -0,0 +1,64 @@
+//! Conversions of a document of generated types between every pair of formats.
+
+#![allow(unexpected_cfgs)]
+
+use mlang_rs::rt::serde::convert::{Error, Format, convert, from_slice, to_vec};
+
+mod schema {
+    mlang_macros::mlang! {
+        enum Unit { Px, Em }
+
+        data Length(float, Unit);
+
+        el Group { #[option] id: string }
+
+        leaf Rect { width: Length, height: float = 1.0 }
+
+        attr Fill(string);
+
+        apply Fill to (Group, Rect);
+        children (Group, Rect) of Group;
+    }
+}
+
+use schema::opcode::*;
+
+fn document() -> Vec<Opcode> {
+    vec![
+        Element::from(Group {
+            id: Some("g".to_string()),
+        })
+        .into(),
+        Attr::from(Fill("red".to_string())).into(),
+        Leaf::from(Rect {
+            width: Length(10.0, Unit::Em),
+            height: 2.5,
+        })
+        .into(),
+        Opcode::Pop,
+    ]
+}
+
+#[test]
+fn test_convert() {
+    for from in Format::ALL {
+        let input = to_vec(&document(), from).unwrap();
+
+        for to in Format::ALL {
+            let output = convert::<Opcode>(&input, from, to).unwrap();
+
+            assert_eq!(
+                from_slice::<Opcode>(&output, to).unwrap(),
+                document(),
+                "{} -> {}",
+                from,
+                to
+            );
+        }
+    }
+
+    assert!(matches!(
+        convert::<Opcode>(b"MLB\x01\x09", Format::Binary, Format::Json),
+        Err(Error::Binary(_))
+    ));
+}
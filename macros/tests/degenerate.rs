@@ -0,0 +1,69 @@
+//! Degenerate schemas accepted by the analyzer must also generate code that compiles.
+
+#![allow(unexpected_cfgs)]
+
+mod empty {
+    mlang_macros::mlang! {}
+}
+
+mod version_only {
+    mlang_macros::mlang! {
+        version "1.0.0";
+    }
+}
+
+mod unit_nodes {
+    mlang_macros::mlang! {
+        el A; attr B(int); apply B to A;
+    }
+}
+
+mod empty_group {
+    mlang_macros::mlang! {
+        group G := (); el A; apply G to A; children G of A;
+    }
+}
+
+mod empty_variants {
+    mlang_macros::mlang! {
+        el A { e: E } enum E { X(), Y {}, Z }
+    }
+}
+
+mod empty_fields {
+    mlang_macros::mlang! {
+        el A(); leaf B {} attr C {} data D(); el E(D); apply C to A;
+    }
+}
+
+mod empty_mixin {
+    mlang_macros::mlang! {
+        mixin M {} el A mixin M; el B mixin M {}
+    }
+}
+
+#[test]
+fn test_degenerate_schemas() {
+    version_only::serde::check_schema_version("1.0.0").unwrap();
+
+    let _ = unit_nodes::opcode::A.apply(unit_nodes::opcode::B(1));
+
+    let _ = empty_group::opcode::ANode::new(empty_group::opcode::A).into_opcodes();
+
+    for e in [
+        // empty field lists are unit variants.
+        empty_variants::opcode::E::X,
+        empty_variants::opcode::E::Y,
+        empty_variants::opcode::E::Z,
+    ] {
+        let _ = empty_variants::opcode::A { e };
+    }
+
+    let _ = empty_fields::opcode::A.apply(empty_fields::opcode::C);
+    let _ = (
+        empty_fields::opcode::B,
+        empty_fields::opcode::E(empty_fields::opcode::D),
+    );
+
+    let _ = (empty_mixin::opcode::A, empty_mixin::opcode::B);
+}
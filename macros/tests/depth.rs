@@ -0,0 +1,127 @@
+//! Deeply nested documents, elements are read with an explicit stack and values have a depth limit.
+
+#![allow(unexpected_cfgs)]
+
+use mlang_rs::rt::serde::{
+    binary,
+    convert::{Format, from_slice, to_vec},
+    de::Deserialize,
+    json, sexpr, xml,
+};
+
+mod schema {
+    mlang_macros::mlang! {
+        data Tree { children: vec[Tree] }
+
+        el Group;
+
+        leaf Rect { tree: Tree }
+
+        children (Group, Rect) of Group;
+    }
+}
+
+use schema::opcode::*;
+
+/// `depth` nested groups around a rect.
+fn nested(depth: usize) -> Vec<Opcode> {
+    let mut opcodes = vec![Element::from(Group).into(); depth];
+
+    opcodes.push(
+        Leaf::from(Rect {
+            tree: Tree { children: vec![] },
+        })
+        .into(),
+    );
+    opcodes.extend(vec![Opcode::Pop; depth]);
+
+    opcodes
+}
+
+/// Read the opcodes of a deserializer.
+macro_rules! read {
+    ($deserializer:expr) => {{
+        let mut deserializer = $deserializer;
+        let mut opcodes = vec![];
+
+        loop {
+            match Opcode::deserialize(&mut deserializer) {
+                Ok(Some(batch)) => opcodes.extend(batch),
+                Ok(None) => break Ok(opcodes),
+                Err(err) => break Err(err),
+            }
+        }
+    }};
+}
+
+#[test]
+fn test_deep_elements() {
+    for format in Format::ALL {
+        let bytes = to_vec(&nested(100_000), format).unwrap();
+
+        assert_eq!(
+            from_slice::<Opcode>(&bytes, format).unwrap(),
+            nested(100_000),
+            "{}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_max_depth() {
+    let json = json::to_string(&nested(10)).unwrap();
+
+    assert!(read!(json::Deserializer::new(&json).with_max_depth(11)).is_ok());
+    assert!(matches!(
+        read!(json::Deserializer::new(&json).with_max_depth(10)),
+        Err(json::Error::Syntax(_, "elements are nested too deeply"))
+    ));
+
+    let xml = xml::to_string(&nested(10)).unwrap();
+
+    assert!(read!(xml::Deserializer::new(&xml).with_max_depth(11)).is_ok());
+    assert!(matches!(
+        read!(xml::Deserializer::new(&xml).with_max_depth(10)),
+        Err(xml::Error::Syntax(_, "elements are nested too deeply"))
+    ));
+
+    let sexpr = sexpr::to_string(&nested(10)).unwrap();
+
+    assert!(read!(sexpr::Deserializer::new(&sexpr).with_max_depth(11)).is_ok());
+    assert!(matches!(
+        read!(sexpr::Deserializer::new(&sexpr).with_max_depth(10)),
+        Err(sexpr::Error::Syntax(_, "elements are nested too deeply"))
+    ));
+}
+
+#[test]
+fn test_deep_values() {
+    let tree = |depth: usize| {
+        let mut tree = Tree { children: vec![] };
+
+        for _ in 0..depth {
+            tree = Tree {
+                children: vec![tree],
+            };
+        }
+
+        vec![Opcode::from(Leaf::from(Rect { tree }))]
+    };
+
+    // every level of a tree nests a data and a vector.
+    for format in Format::ALL {
+        let bytes = to_vec(&tree(100), format).unwrap();
+
+        assert_eq!(from_slice::<Opcode>(&bytes, format).unwrap(), tree(100));
+
+        let bytes = to_vec(&tree(150), format).unwrap();
+
+        assert!(from_slice::<Opcode>(&bytes, format).is_err(), "{}", format);
+    }
+
+    assert!(matches!(
+        binary::from_bytes::<Opcode>(&binary::to_bytes(&tree(150)).unwrap()),
+        Err(binary::Error::Depth(_))
+    ));
+}
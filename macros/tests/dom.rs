@@ -0,0 +1,119 @@
+//! Documents of generated types, and subtrees of them serialized as standalone documents.
+
+#![allow(unexpected_cfgs)]
+
+use mlang_rs::rt::{
+    dom::{Document, Node},
+    serde::{binary, json, sexpr, xml},
+};
+
+mod schema {
+    mlang_macros::mlang! {
+        el Group { #[option] id: string }
+
+        leaf Rect { width: float }
+
+        attr Fill(string);
+
+        apply Fill to (Group, Rect);
+        children (Group, Rect) of Group;
+    }
+}
+
+use schema::opcode::*;
+
+fn group(id: &str) -> Element {
+    Group {
+        id: Some(id.to_string()),
+    }
+    .into()
+}
+
+fn rect(width: f32) -> Leaf {
+    Rect { width }.into()
+}
+
+#[test]
+fn test_serialize_subtree() {
+    let mut document = Document::<Opcode>::from_nodes([Node::element(group("a"))
+        .with_child(Node::leaf(rect(1.0)))
+        .with_child(
+            Node::element(group("b"))
+                .with_attr(Fill("red".to_string()).into())
+                .with_child(Node::leaf(rect(2.0))),
+        )]);
+
+    let root = document.roots().next().unwrap();
+    let selected = document.last_child(root).unwrap();
+
+    // the selected element with its attrs and descendants, without its parent and siblings.
+    let subtree = vec![
+        Attr::from(Fill("red".to_string())).into(),
+        group("b").into(),
+        rect(2.0).into(),
+        Opcode::Pop,
+    ];
+
+    let mut serializer = json::Serializer::new();
+
+    assert_eq!(
+        document.serialize_subtree(selected, &mut serializer),
+        Ok(true)
+    );
+    assert_eq!(
+        json::from_str::<Opcode>(&serializer.finish().unwrap()).unwrap(),
+        subtree
+    );
+
+    let mut serializer = xml::Serializer::new();
+
+    assert_eq!(
+        document.serialize_subtree(selected, &mut serializer),
+        Ok(true)
+    );
+    assert_eq!(
+        xml::from_str::<Opcode>(&serializer.finish().unwrap()).unwrap(),
+        subtree
+    );
+
+    let mut serializer = sexpr::Serializer::new();
+
+    assert_eq!(
+        document.serialize_subtree(selected, &mut serializer),
+        Ok(true)
+    );
+    assert_eq!(
+        sexpr::from_str::<Opcode>(&serializer.finish().unwrap()).unwrap(),
+        subtree
+    );
+
+    let mut serializer = binary::Serializer::new();
+
+    assert_eq!(
+        document.serialize_subtree(selected, &mut serializer),
+        Ok(true)
+    );
+    assert_eq!(
+        binary::from_bytes::<Opcode>(&serializer.finish()).unwrap(),
+        subtree
+    );
+
+    // a leaf is a document of one opcode.
+    let leaf = document.first_child(root).unwrap();
+    let mut serializer = json::Serializer::new();
+
+    assert_eq!(document.serialize_subtree(leaf, &mut serializer), Ok(true));
+    assert_eq!(
+        json::from_str::<Opcode>(&serializer.finish().unwrap()).unwrap(),
+        [rect(1.0).into()]
+    );
+
+    document.remove(selected);
+
+    let mut serializer = json::Serializer::new();
+
+    assert_eq!(
+        document.serialize_subtree(selected, &mut serializer),
+        Ok(false)
+    );
+}
@@ -0,0 +1,64 @@
+//! Code generated with `no_std` must compile in a `#![no_std]` crate.
+
+#![no_std]
+#![allow(unexpected_cfgs)]
+
+extern crate alloc;
+
+use alloc::{string::ToString, vec};
+
+mod schema {
+    mlang_macros::mlang! {
+        #![codegen(no_std, visitor)]
+        version "1.0.0";
+
+        enum Unit { Px, #[rename("%")] Percent }
+        enum Paint { None, Color(ubyte, ubyte, ubyte), Named { name: string } }
+
+        data Length(float, Unit);
+
+        mixin Id { #[option] id: string }
+
+        el Shape mixin Id {
+            #[variable] width: Length,
+            paint: Paint,
+            opacity: float = 1.0,
+            points: vec[(float, float)],
+            #[option] label: string,
+        }
+
+        leaf Text(string);
+        attr Fill(Paint);
+
+        group Content := (Shape, Text);
+
+        apply Fill to Shape;
+        children Content of Shape;
+    }
+}
+
+#[test]
+fn test_no_std() {
+    use mlang_rs::rt::{opcode::Variable, serde::json};
+    use schema::opcode::*;
+
+    let mut opcodes = ShapeNode::new(Shape {
+        id: Some("a".to_string()),
+        width: Variable::Constant(Length(1.0, Unit::Percent)),
+        paint: Paint::Named {
+            name: "red".to_string(),
+        },
+        opacity: 0.5,
+        points: vec![(0.0, 1.0)],
+        label: None,
+    })
+    .apply(Fill(Paint::Color(1, 2, 3)))
+    .into_opcodes();
+
+    opcodes.insert(opcodes.len() - 1, Leaf::from(Text("t".to_string())).into());
+
+    let text = json::to_string(&opcodes).unwrap();
+
+    assert_eq!(json::from_str::<Opcode>(&text).unwrap(), opcodes);
+    assert_eq!(Unit::Percent.to_string(), "%");
+}
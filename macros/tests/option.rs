@@ -0,0 +1,59 @@
+//! Nested options of generated types, `Some(D(None))` must not be read back as `None`.
+
+#![allow(unexpected_cfgs)]
+
+mod common;
+
+use mlang_rs::rt::serde::xml;
+
+use common::round_trip;
+
+mod schema {
+    mlang_macros::mlang! {
+        data Inner(#[option] int);
+        el A { #[option] x: Inner, #[option] y: Inner }
+        leaf B(#[option] Inner);
+        attr C { #[option] v: Inner }
+        attr D(#[option] Inner);
+        apply C to A;
+        apply D to A;
+    }
+}
+
+use schema::opcode::*;
+
+#[test]
+fn test_nested_option() {
+    for value in [None, Some(Inner(None)), Some(Inner(Some(7)))] {
+        let opcodes = ANode::new(A {
+            x: value.clone(),
+            y: Some(Inner(None)),
+        })
+        .apply(C { v: value.clone() })
+        .into_opcodes();
+
+        round_trip(&opcodes);
+        round_trip::<Opcode>(&[Leaf::from(B(value)).into()]);
+    }
+}
+
+#[test]
+fn test_xml_unnamed_attr() {
+    let opcodes = ANode::new(A { x: None, y: None })
+        .apply(D(None))
+        .into_opcodes();
+
+    // the attr isn't dropped, its unnamed fields are keyed by the first one.
+    assert_eq!(
+        xml::to_string(&opcodes).unwrap(),
+        r#"<a d='{"$none":null}'/>"#
+    );
+
+    round_trip(&opcodes);
+
+    let opcodes = ANode::new(A { x: None, y: None })
+        .apply(D(Some(Inner(None))))
+        .into_opcodes();
+
+    round_trip(&opcodes);
+}
@@ -0,0 +1,73 @@
+//! The decimals of `float` and `double` values in the text formats, globally and per `#[precision(n)]` field.
+
+#![allow(unexpected_cfgs)]
+
+use mlang_rs::rt::{
+    opcode::Variable,
+    serde::{float::FloatFormat, json, ser::Serialize, sexpr, xml},
+};
+
+mod schema {
+    mlang_macros::mlang! {
+        data Point(float, float);
+
+        leaf Shape {
+            x: double,
+            #[precision(2)] y: double,
+            #[precision(1), variable] width: float,
+            #[precision(3), option] points: vec[Point],
+        }
+    }
+}
+
+use schema::opcode::*;
+
+fn shape() -> Vec<Opcode> {
+    vec![
+        Leaf::from(Shape {
+            x: 0.123456789,
+            y: 0.123456789,
+            width: Variable::Constant(2.25),
+            points: Some(vec![Point(1.0 / 3.0, -0.0001)]),
+        })
+        .into(),
+    ]
+}
+
+#[test]
+fn test_precision() {
+    assert_eq!(
+        json::to_string(&shape()).unwrap(),
+        r#"[{"shape":{"x":0.123456789,"y":0.12,"width":2.2,"points":[[0.333,0]]}}]"#
+    );
+    assert_eq!(
+        xml::to_string(&shape()).unwrap(),
+        r#"<shape x="0.123456789" y="0.12" width="2.2" points="[[0.333,0]]"/>"#
+    );
+    assert_eq!(
+        sexpr::to_string(&shape()).unwrap(),
+        "(shape :x 0.123456789 :y 0.12 :width 2.2 :points [[0.333 0]])"
+    );
+}
+
+#[test]
+fn test_float_format() {
+    let format = FloatFormat::Decimals(4);
+
+    let mut serializer = json::Serializer::new().with_float_format(format);
+
+    for opcode in shape() {
+        opcode.serialize(&mut serializer).unwrap();
+    }
+
+    // fields keep their own precision.
+    assert_eq!(
+        serializer.finish().unwrap(),
+        r#"[{"shape":{"x":0.1235,"y":0.12,"width":2.2,"points":[[0.333,0]]}}]"#
+    );
+
+    let text = json::to_string(&shape()).unwrap();
+    let shapes = json::from_str::<Opcode>(&text).unwrap();
+
+    assert_eq!(json::to_string(&shapes).unwrap(), text);
+}
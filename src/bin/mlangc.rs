@@ -0,0 +1,66 @@
+//! `mlangc` command line tool.
+//!
+//! ```sh
+//! mlangc new my-vocab
+//! mlangc convert input.xml --to binary -o out.mlb
+//! ```
+//!
+//! Documents are read and written through the generated types of a vocabulary, so `convert` runs the `convert`
+//! binary of the vocabulary crate created by `mlangc new`, in the current directory or at `--manifest-path`.
+
+use std::process::{Command, ExitCode};
+
+const USAGE: &str = "usage: mlangc new <path>    create a new vocabulary crate at <path>
+       mlangc convert [--manifest-path <Cargo.toml>] <input> [--from <format>] --to <format> [-o <output>]
+                              convert a document of the vocabulary crate between json, xml, sexpr and binary";
+
+/// Run the `convert` binary of the vocabulary crate with `args`.
+fn convert(args: &[&str]) -> ExitCode {
+    let mut command = Command::new(std::env::var_os("CARGO").unwrap_or("cargo".into()));
+
+    command.args(["run", "--quiet", "--bin", "convert"]);
+
+    let args = match args {
+        ["--manifest-path", manifest, args @ ..] => {
+            command.args(["--manifest-path", manifest]);
+            args
+        }
+        args => args,
+    };
+
+    match command.arg("--").args(args).status() {
+        Ok(status) if status.success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: run cargo: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["new", path] => match mlang_rs::lang::scaffold::new_crate(path) {
+            Ok(()) => {
+                println!("created vocabulary crate `{}`", path);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("error: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        ["convert", args @ ..] if !args.is_empty() => convert(args),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::lang::ir::Property;
+
+/// Named checks of the analyzer that report style or usage problems instead of invalid schemas.
+///
+/// Each lint can be allowed, warned or denied globally by [`LintLevels`], or allowed for one declaration
+/// by the `#[allow("lint-name", ...)]` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Lint {
+    /// A `data`, `enum` or `group` declaration is never referenced.
+    Unused,
+    /// A `mixin` is never mixed into any node.
+    UnusedMixin,
+    /// An `attr` isn't applied to any element by `apply ... to` stats.
+    OrphanAttr,
+    /// A `#[override]` field doesn't replace any mixin field.
+    UnusedOverride,
+    /// An enum variant is much larger than the other variants.
+    LargeVariant,
+    /// Type names aren't `UpperCamelCase`, or field and mod names aren't `snake_case`/`lowerCamelCase`.
+    NamingConvention,
+    /// Two attrs applied to the same element map a name to different attrs after case conversion and
+    /// `rename`, only the first one is matched by the serde codegen. Denied by default.
+    NameCollision,
+}
+
+impl Lint {
+    /// All lints, in declaration order.
+    pub const ALL: [Lint; 7] = [
+        Lint::Unused,
+        Lint::UnusedMixin,
+        Lint::OrphanAttr,
+        Lint::UnusedOverride,
+        Lint::LargeVariant,
+        Lint::NamingConvention,
+        Lint::NameCollision,
+    ];
+
+    /// Returns the name used by `#[allow(...)]` properties and reports: `unused-mixin`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::Unused => "unused",
+            Lint::UnusedMixin => "unused-mixin",
+            Lint::OrphanAttr => "orphan-attr",
+            Lint::UnusedOverride => "unused-override",
+            Lint::LargeVariant => "large-variant",
+            Lint::NamingConvention => "naming-convention",
+            Lint::NameCollision => "name-collision",
+        }
+    }
+
+    /// Returns the level of this lint if it isn't set by [`LintLevels`].
+    pub fn default_level(&self) -> LintLevel {
+        match self {
+            Lint::NameCollision => LintLevel::Deny,
+            _ => LintLevel::Warn,
+        }
+    }
+
+    /// Lookup a lint by name, `_` and `-` are interchangeable.
+    pub fn from_name(name: &str) -> Option<Lint> {
+        let name = name.replace('_', "-");
+
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How a [`Lint`] is reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum LintLevel {
+    /// Not reported.
+    Allow,
+    /// Reported as a warning.
+    #[default]
+    Warn,
+    /// Reported as an error, the compilation fails.
+    Deny,
+}
+
+/// The levels of lints, lints that are not set use their [`default_level`](Lint::default_level).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LintLevels(HashMap<Lint, LintLevel>);
+
+impl LintLevels {
+    /// Set the level of `lint`.
+    pub fn set(&mut self, lint: Lint, level: LintLevel) -> &mut Self {
+        self.0.insert(lint, level);
+        self
+    }
+
+    /// Set the level of all lints.
+    pub fn set_all(&mut self, level: LintLevel) -> &mut Self {
+        for lint in Lint::ALL {
+            self.0.insert(lint, level);
+        }
+
+        self
+    }
+
+    /// Returns the level of `lint`.
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.0.get(&lint).copied().unwrap_or(lint.default_level())
+    }
+}
+
+/// Returns true if `lint` is allowed by one of the `#[allow(...)]` `properties` of a declaration.
+pub(super) fn is_allowed(properties: &[Property], lint: Lint) -> bool {
+    properties
+        .iter()
+        .flat_map(|property| &property.calls)
+        .filter(|call| call.target.1 == "allow")
+        .flat_map(|call| &call.params)
+        .any(|param| Lint::from_name(&param.1) == Some(lint))
+}
@@ -0,0 +1,1530 @@
+//! semantic analyzer for `mlang`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use heck::{ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use parserc::Span;
+
+use super::ir::*;
+
+mod lint;
+pub use lint::*;
+
+const ANALYZER_ERROR: &str = "MLANG_ANALYZER";
+
+/// Error report by semantic analyze step.
+#[derive(Debug, thiserror::Error)]
+pub enum AnalyzerError {
+    #[error("duplicate symbol `{0}`, previous declaration is here {1}")]
+    Duplicate(String, Span),
+
+    #[error("Unknown symbol `{0}`.")]
+    Unknown(String),
+
+    #[error("Use group `{0}` as field type declaration, group declaration is here {1}")]
+    Group(String, Span),
+
+    #[error("Unable merge mixin({0})'s fields into node, mixin declaration is here {1}.")]
+    Merge(String, Span),
+
+    #[error(
+        "Field `{0}` conflicts with a field of mixin `{1}` declared here {2}, add `#[override]` to replace the mixin field."
+    )]
+    MixinField(String, String, Span),
+
+    #[error("Field `{0}` is marked `#[override]`, but there is no mixin field with the same name.")]
+    Override(String),
+
+    #[error("Custom property `{0}`, expect empty call list.")]
+    VariableOption(String),
+
+    #[error("Custom property `rename`, expect one `literial str` as call list.")]
+    Rename,
+
+    #[error("Enum `{0}` has no variants, a value of it can't be created.")]
+    EmptyEnum(String),
+
+    #[error("Custom property `id`, expect one unsigned integer as call list.")]
+    Id,
+
+    #[error(
+        "duplicate type id `{0}` of `{1}`, previous declaration is here {2}, declarations without `#[id(...)]` use their position as type id."
+    )]
+    DuplicateId(usize, String, Span),
+
+    #[error("Custom property `derive`, expect a list of rust paths as call list.")]
+    Derive,
+
+    #[error(
+        "Custom property `rust_attr`, expect one `literial str` of rust attribute tokens as call list."
+    )]
+    RustAttr,
+
+    #[error("Custom property `cfg`, expect a list of rust configuration predicates as call list.")]
+    Cfg,
+
+    #[error(
+        "Custom property `precision` of field `{0}`, expect one integer of at most 17 decimals on a field of `float` or `double` values."
+    )]
+    Precision(String),
+
+    #[error(
+        "Variant `{0}` is large (about {1} bytes more than the others), consider adding `#[boxed]`."
+    )]
+    LargeVariant(String, usize),
+
+    #[error(
+        "Invalid default value of field `{0}`, expect a literal of the field's primitive type on a non-`#[option]` field."
+    )]
+    Default(String),
+
+    #[error("Default value of field `{0}`, default values are not supported by enum variants.")]
+    VariantDefault(String),
+
+    #[error("Invalid schema version `{0}`, expect `major.minor.patch`.")]
+    Version(String),
+
+    #[error("duplicate version stat, previous declaration is here {0}")]
+    DuplicateVersion(Span),
+
+    #[error("duplicate meta stat, previous declaration is here {0}")]
+    DuplicateMeta(Span),
+
+    #[error("Unknown meta key `{0}`, expect `name`, `version` or `namespace`.")]
+    MetaKey(String),
+
+    #[error("duplicate meta key `{0}`, previous declaration is here {1}")]
+    DuplicateMetaKey(String, Span),
+
+    #[error("Meta stat without `name`, expect the name of the vocabulary.")]
+    MetaName,
+
+    #[error("Invalid map key type, expect `string` or an integer type.")]
+    MapKey,
+
+    #[error("Tuple type with {0} components, expect 1 to 12 components.")]
+    Tuple(usize),
+
+    #[error("Custom property `raw` of field `{0}`, expect a `string` field.")]
+    Raw(String),
+
+    #[error("Custom property `external` of field `{0}`, expect a `bytes` or `iri` field.")]
+    External(String),
+
+    #[error(
+        "Custom property `borrow` of field `{0}`, expect a `string` or `bytes` field without `raw` or `external`."
+    )]
+    Borrow(String),
+
+    #[error("Symbol `{0}` is never used.")]
+    Unused(String),
+
+    #[error("Attr `{0}` isn't applied to any element, add it to an `apply ... to` stat.")]
+    OrphanAttr(String),
+
+    #[error("Name `{0}` should be {1}: `{2}`.")]
+    NamingConvention(String, &'static str, String),
+
+    #[error("Custom property `allow`, expect a list of lint names as call list, `{0}` is unknown.")]
+    Allow(String),
+
+    #[error(
+        "Invalid decimal type `decimal({0}, {1})`, expect a precision of 1 to 28 digits and a scale not greater than the precision."
+    )]
+    Decimal(usize, usize),
+
+    #[error("`bytes` can't be the component type of `vec`, array, map or tuple types.")]
+    Bytes,
+
+    #[error(
+        "Recursive type `{0}` has infinite size, declaration is here {1}, break the cycle with `vec[...]` or a `#[boxed]` enum variant."
+    )]
+    Recursive(String, Span),
+
+    #[error(
+        "`{0}` is mapped to the rust keyword `{1}`, which can't be escaped as a raw identifier."
+    )]
+    Keyword(String, String),
+
+    #[error("duplicate display name `{0}` of `{1}`, previous declaration is here {2}")]
+    DisplayName(String, String, Span),
+
+    #[error(
+        "Name `{0}` of attr `{1}` collides with attr `{2}` applied to the same element, previous declaration is here {3}, rename one of them or add `#[allow(\"name-collision\")]` to alias it."
+    )]
+    NameCollision(String, String, String, Span),
+
+    #[error(
+        "`{0}` is generated as `ids::{1}`, which collides with the declaration here {2}, rename one of them."
+    )]
+    IdName(String, String, Span),
+}
+
+/// An error or warning reported by the semantic analyzer.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// [`Level::Error`](log::Level::Error) or [`Level::Warn`](log::Level::Warn).
+    pub level: log::Level,
+    /// The source span the diagnostic points to.
+    pub span: Span,
+    /// The error or warning.
+    pub error: AnalyzerError,
+    /// The lint that reported this diagnostic, `None` for schema errors.
+    pub lint: Option<Lint>,
+}
+
+/// The max precision of decimal types, limited by `rust_decimal`.
+const MAX_DECIMAL_PRECISION: usize = 28;
+
+/// The max length of tuple types, limited by the tuple impls of `rt::serde`.
+const MAX_TUPLE_LEN: usize = 12;
+
+/// The max decimals of `#[precision(n)]` fields, more are the noise digits of a `double`.
+const MAX_FLOAT_PRECISION: u32 = 17;
+
+/// A variant at least this many estimated bytes larger than the second-largest one is reported.
+const LARGE_VARIANT_THRESHOLD: usize = 200;
+
+#[derive(Default)]
+struct SymbolTable(HashMap<String, (Span, usize)>);
+
+impl SymbolTable {
+    /// Add a new symbol to the checker.
+    fn add(&mut self, index: usize, ident: &Ident) -> Result<(), AnalyzerError> {
+        if let Some((span, _)) = self.0.insert(ident.1.clone(), (ident.0, index)) {
+            Err(AnalyzerError::Duplicate(ident.1.clone(), span))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Search symbol.
+    fn lookup(&self, ident: &Ident) -> Option<usize> {
+        self.0.get(&ident.1).map(|(_, index)| *index)
+    }
+}
+
+#[derive(Default)]
+struct MixinTable {
+    mixin: HashMap<String, (Span, usize)>,
+}
+
+impl MixinTable {
+    /// add new mixin item.
+    ///
+    /// This function delegate symbol conflict check to `SymbolChecker`
+    fn add(&mut self, index: usize, ident: &Ident) {
+        self.mixin.insert(ident.1.clone(), (ident.0, index));
+    }
+
+    fn lookup(&self, ident: &Ident) -> Option<usize> {
+        self.mixin.get(ident.1.as_str()).map(|(_, index)| *index)
+    }
+}
+
+#[derive(Default)]
+struct GroupTable {
+    groups: HashMap<String, (Span, usize)>,
+}
+
+impl GroupTable {
+    /// See a group item.
+    fn add(&mut self, index: usize, ident: &Ident) {
+        self.groups.insert(ident.1.clone(), (ident.0, index));
+    }
+
+    fn lookup(&self, ident: &Ident) -> Option<usize> {
+        self.groups.get(ident.1.as_str()).map(|(_, index)| *index)
+    }
+}
+
+/// A semantic analyzer for `mlang`.
+#[derive(Default)]
+struct SemanticAnalyzer {
+    /// A symbol index database.
+    symbol_table: SymbolTable,
+    /// A mixin fields merger.
+    merger: MixinTable,
+    /// `apply..to..` `chidlren..of..` syntax checker.
+    digraph_analyzer: GroupTable,
+    /// the number of reported errors.
+    errors: usize,
+    /// the span of the version stat.
+    version: Option<Span>,
+    /// the span of the meta stat.
+    meta: Option<Span>,
+    /// reported errors and warnings.
+    diagnostics: Vec<Diagnostic>,
+    /// display names of elements, leaves and attrs, which share the name lookup of the serde codegen.
+    display_names: HashMap<String, Span>,
+    /// the levels of lints.
+    lints: LintLevels,
+}
+
+impl SemanticAnalyzer {
+    fn analyze(mut self, opcodes: &mut [Stat]) -> (bool, Vec<Diagnostic>) {
+        self.qualify(opcodes);
+        self.build_index(opcodes);
+        self.resolve(opcodes);
+        self.unused_check(opcodes);
+        self.check(opcodes);
+        (self.errors == 0, self.diagnostics)
+    }
+
+    /// Report an error.
+    fn error(&mut self, span: Span, error: AnalyzerError) {
+        log::error!(target: ANALYZER_ERROR, span:serde = span; "{}", error);
+        self.errors += 1;
+        self.diagnostics.push(Diagnostic {
+            level: log::Level::Error,
+            span,
+            error,
+            lint: None,
+        });
+    }
+
+    /// Report a `lint` warning, unless the lint is allowed by the `properties` of the declaration.
+    fn lint(&mut self, lint: Lint, properties: &[Property], span: Span, warning: AnalyzerError) {
+        let level = match self.lints.level(lint) {
+            LintLevel::Allow => return,
+            _ if is_allowed(properties, lint) => return,
+            LintLevel::Warn => {
+                log::warn!(target: ANALYZER_ERROR, span:serde = span; "{}", warning);
+                log::Level::Warn
+            }
+            LintLevel::Deny => {
+                log::error!(target: ANALYZER_ERROR, span:serde = span; "{}", warning);
+                self.errors += 1;
+                log::Level::Error
+            }
+        };
+
+        self.diagnostics.push(Diagnostic {
+            level,
+            span,
+            error: warning,
+            lint: Some(lint),
+        });
+    }
+
+    /// Report `data`/`enum`/`mixin`/`group` declarations that are never referenced and `attr`s that are never applied,
+    /// must run before [`check`](Self::check) expands the groups of `apply`/`children` stats.
+    fn unused_check(&mut self, opcodes: &[Stat]) {
+        let mut used = HashSet::new();
+
+        fn type_refs(owner: &Ident, ty: &Type, used: &mut HashSet<String>) {
+            match ty {
+                Type::Data(ident) if ident.1 != owner.1 => {
+                    used.insert(ident.1.clone());
+                }
+                Type::ListOf(component, _) | Type::ArrayOf(component, _, _) => {
+                    type_refs(owner, component, used)
+                }
+                Type::MapOf(key, value, _) => {
+                    type_refs(owner, key, used);
+                    type_refs(owner, value, used);
+                }
+                Type::Tuple(components, _) => {
+                    for component in components {
+                        type_refs(owner, component, used);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for opcode in opcodes {
+            match opcode {
+                Stat::Element(node)
+                | Stat::Leaf(node)
+                | Stat::Attr(node)
+                | Stat::Mixin(node)
+                | Stat::Data(node) => {
+                    if let Some(mixin) = &node.mixin {
+                        used.insert(mixin.1.clone());
+                    }
+
+                    for field in node.fields.iter() {
+                        type_refs(&node.ident, field.ty(), &mut used);
+                    }
+                }
+                Stat::Enum(node) => {
+                    for field in node.fields.iter().flat_map(|variant| variant.fields.iter()) {
+                        type_refs(&node.ident, field.ty(), &mut used);
+                    }
+                }
+                Stat::Group(node) => {
+                    used.extend(node.children.iter().map(|ident| ident.1.clone()));
+                }
+                Stat::ApplyTo(node) => {
+                    used.extend(
+                        node.from
+                            .iter()
+                            .chain(&node.to)
+                            .map(|ident| ident.1.clone()),
+                    );
+                }
+                Stat::ChildrenOf(node) => {
+                    used.extend(
+                        node.from
+                            .iter()
+                            .chain(&node.to)
+                            .map(|ident| ident.1.clone()),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        for opcode in opcodes {
+            let (lint, properties, ident) = match opcode {
+                Stat::Data(node) => (Lint::Unused, &node.properties, &node.ident),
+                Stat::Mixin(node) => (Lint::UnusedMixin, &node.properties, &node.ident),
+                Stat::Attr(node) => (Lint::OrphanAttr, &node.properties, &node.ident),
+                Stat::Enum(node) => (Lint::Unused, &node.properties, &node.ident),
+                Stat::Group(node) => (Lint::Unused, &node.properties, &node.ident),
+                _ => continue,
+            };
+
+            if !used.contains(&ident.1) {
+                let warning = match lint {
+                    Lint::OrphanAttr => AnalyzerError::OrphanAttr(ident.1.clone()),
+                    _ => AnalyzerError::Unused(ident.1.clone()),
+                };
+
+                self.lint(lint, properties, ident.0, warning);
+            }
+        }
+    }
+
+    /// Prefix the idents of declarations inside `mod` blocks with their namespace: `geometry::Rect`.
+    fn qualify(&mut self, opcodes: &mut [Stat]) {
+        let mut namespace: Vec<String> = vec![];
+
+        for opcode in opcodes.iter_mut() {
+            let ident = match opcode {
+                Stat::Element(node)
+                | Stat::Leaf(node)
+                | Stat::Attr(node)
+                | Stat::Mixin(node)
+                | Stat::Data(node) => &mut node.ident,
+                Stat::Enum(node) => &mut node.ident,
+                Stat::Group(node) => &mut node.ident,
+                Stat::Mod(node) => {
+                    namespace.push(node.ident.1.clone());
+                    continue;
+                }
+                Stat::ModEnd(_) => {
+                    namespace.pop();
+                    continue;
+                }
+                Stat::ApplyTo(_) | Stat::ChildrenOf(_) | Stat::Version(_) | Stat::Meta(_) => {
+                    continue;
+                }
+            };
+
+            if !namespace.is_empty() {
+                ident.1 = format!("{}::{}", namespace.join("::"), ident.1);
+            }
+        }
+    }
+
+    /// Rewrite symbol references to the qualified ident of the declaration they refer to.
+    ///
+    /// References are searched from the innermost namespace outward, unknown references are left as is
+    /// and reported by [`check`](Self::check).
+    fn resolve(&mut self, opcodes: &mut [Stat]) {
+        let mut namespace: Vec<String> = vec![];
+
+        for opcode in opcodes.iter_mut() {
+            match opcode {
+                Stat::Element(node)
+                | Stat::Leaf(node)
+                | Stat::Attr(node)
+                | Stat::Mixin(node)
+                | Stat::Data(node) => {
+                    self.resolve_node(&namespace, node);
+                }
+                Stat::Enum(node) => {
+                    for variant in node.fields.iter_mut() {
+                        self.resolve_node(&namespace, variant);
+                    }
+                }
+                Stat::Group(node) => {
+                    for ident in node.children.iter_mut() {
+                        self.resolve_ident(&namespace, ident);
+                    }
+                }
+                Stat::ApplyTo(node) => {
+                    for ident in node.from.iter_mut().chain(node.to.iter_mut()) {
+                        self.resolve_ident(&namespace, ident);
+                    }
+                }
+                Stat::ChildrenOf(node) => {
+                    for ident in node.from.iter_mut().chain(node.to.iter_mut()) {
+                        self.resolve_ident(&namespace, ident);
+                    }
+                }
+                Stat::Mod(node) => {
+                    namespace.push(node.ident.1.clone());
+                }
+                Stat::ModEnd(_) => {
+                    namespace.pop();
+                }
+                Stat::Version(_) | Stat::Meta(_) => {}
+            }
+        }
+    }
+
+    fn resolve_node(&self, namespace: &[String], node: &mut Node) {
+        if let Some(mixin) = &mut node.mixin {
+            self.resolve_ident(namespace, mixin);
+        }
+
+        for mut field in node.fields.iter_mut() {
+            self.resolve_type(namespace, field.ty());
+        }
+    }
+
+    fn resolve_type(&self, namespace: &[String], ty: &mut Type) {
+        match ty {
+            Type::Data(ident) => self.resolve_ident(namespace, ident),
+            Type::ListOf(component, _) | Type::ArrayOf(component, _, _) => {
+                self.resolve_type(namespace, component)
+            }
+            Type::MapOf(key, value, _) => {
+                self.resolve_type(namespace, key);
+                self.resolve_type(namespace, value);
+            }
+            Type::Tuple(components, _) => {
+                for component in components {
+                    self.resolve_type(namespace, component);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve_ident(&self, namespace: &[String], ident: &mut Ident) {
+        for len in (0..=namespace.len()).rev() {
+            let mut path = namespace[..len].to_vec();
+            path.push(ident.1.clone());
+
+            let path = path.join("::");
+
+            if self.symbol_table.0.contains_key(&path) {
+                ident.1 = path;
+                return;
+            }
+        }
+    }
+
+    /// Check that `ident` isn't mapped to one of the rust keywords that `r#` can't escape,
+    /// and that it follows the naming convention of type names or field names.
+    ///
+    /// The other keywords are escaped by [`IdentMapping`](crate::lang::rustgen::mapping::IdentMapping).
+    fn ident_check(&mut self, ident: &Ident, type_name: bool, properties: &[Property]) {
+        let name = ident.1.rsplit("::").next().unwrap();
+
+        let (mapped, case, valid) = if type_name {
+            let mapped = name.to_upper_camel_case();
+            let valid = mapped == name;
+            (mapped, "UpperCamelCase", valid)
+        } else {
+            let mapped = name.to_snake_case();
+            let valid = mapped == name || name.to_lower_camel_case() == name;
+            (mapped, "snake_case or lowerCamelCase", valid)
+        };
+
+        if matches!(mapped.as_str(), "self" | "Self" | "super" | "crate") {
+            self.error(ident.0, AnalyzerError::Keyword(ident.1.clone(), mapped));
+        } else if !valid {
+            self.lint(
+                Lint::NamingConvention,
+                properties,
+                ident.0,
+                AnalyzerError::NamingConvention(name.to_string(), case, mapped),
+            );
+        }
+    }
+
+    /// Check that the serde display name of `node` isn't used by another element, leaf or attr.
+    fn display_name_check(&mut self, node: &Node) {
+        let name = match node.rename() {
+            Some(name) => name.to_string(),
+            None => node.ident.1.to_lower_camel_case(),
+        };
+
+        if let Some(span) = self.display_names.get(&name) {
+            let error = AnalyzerError::DisplayName(name, node.ident.1.clone(), *span);
+            self.error(node.ident.0, error);
+        } else {
+            self.display_names.insert(name, node.ident.0);
+        }
+    }
+
+    fn build_index(&mut self, opcodes: &mut [Stat]) {
+        for (index, opcode) in opcodes.iter().enumerate() {
+            match opcode {
+                Stat::Element(node)
+                | Stat::Leaf(node)
+                | Stat::Attr(node)
+                | Stat::Data(node)
+                | Stat::Mixin(node) => {
+                    self.ident_check(&node.ident, true, &node.properties);
+
+                    for field in node.fields.iter() {
+                        if let Some(ident) = field.ident() {
+                            self.ident_check(ident, false, &node.properties);
+                        }
+                    }
+                }
+                Stat::Enum(node) => {
+                    self.ident_check(&node.ident, true, &node.properties);
+
+                    for variant in &node.fields {
+                        self.ident_check(&variant.ident, true, &node.properties);
+
+                        for field in variant.fields.iter() {
+                            if let Some(ident) = field.ident() {
+                                self.ident_check(ident, false, &node.properties);
+                            }
+                        }
+                    }
+                }
+                Stat::Group(node) => self.ident_check(&node.ident, true, &node.properties),
+                Stat::Mod(node) => self.ident_check(&node.ident, false, &[]),
+                _ => {}
+            }
+
+            match opcode {
+                Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) => {
+                    if let Err(err) = self.symbol_table.add(index, &node.ident) {
+                        self.error(node.ident.0, err);
+                    }
+                    self.display_name_check(node);
+                }
+                Stat::Data(node) => {
+                    if let Err(err) = self.symbol_table.add(index, &node.ident) {
+                        self.error(node.ident.0, err);
+                    }
+                }
+                Stat::Mixin(node) => {
+                    if let Err(err) = self.symbol_table.add(index, &node.ident) {
+                        self.error(node.ident.0, err);
+                    }
+                    self.merger.add(index, &node.ident);
+                }
+                Stat::Enum(node) => {
+                    if let Err(err) = self.symbol_table.add(index, &node.ident) {
+                        self.error(node.ident.0, err);
+                    }
+                }
+                Stat::Group(node) => {
+                    if let Err(err) = self.symbol_table.add(index, &node.ident) {
+                        self.error(node.ident.0, err);
+                    }
+                    self.digraph_analyzer.add(index, &node.ident);
+                }
+                Stat::ApplyTo(_) => {}
+                Stat::ChildrenOf(_) => {}
+                Stat::Mod(_) | Stat::ModEnd(_) => {}
+                Stat::Version(version) => {
+                    if let Some(span) = self.version.replace(version.span) {
+                        self.error(version.span, AnalyzerError::DuplicateVersion(span));
+                    }
+                }
+                Stat::Meta(meta) => {
+                    if let Some(span) = self.meta.replace(meta.span) {
+                        self.error(meta.span, AnalyzerError::DuplicateMeta(span));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check(&mut self, opcodes: &mut [Stat]) {
+        let mut updates = vec![];
+        for (index, opcode) in opcodes.iter().enumerate() {
+            match opcode {
+                Stat::Element(node) => {
+                    if let Some(node) = self.node_check(opcodes, node) {
+                        updates.push((index, Stat::Element(Box::new(node))));
+                    }
+                }
+                Stat::Leaf(node) => {
+                    if let Some(node) = self.node_check(opcodes, node) {
+                        updates.push((index, Stat::Leaf(Box::new(node))));
+                    }
+                }
+                Stat::Attr(node) => {
+                    if let Some(node) = self.node_check(opcodes, node) {
+                        updates.push((index, Stat::Attr(Box::new(node))));
+                    }
+                }
+                Stat::Mixin(node) => {
+                    assert_eq!(
+                        self.node_check(opcodes, node),
+                        None,
+                        "Mixin: inner error, mixin can't mixin other one."
+                    );
+                }
+                Stat::Data(node) => {
+                    if let Some(node) = self.node_check(opcodes, node) {
+                        updates.push((index, Stat::Data(Box::new(node))));
+                    }
+                }
+                Stat::Enum(node) => {
+                    self.enum_check(opcodes, node);
+                }
+                Stat::Group(group) => {
+                    self.group_check(opcodes, group);
+                }
+                Stat::ApplyTo(apply_to) => {
+                    if let Some(opcode) = self.apply_to_check(opcodes, apply_to) {
+                        updates.push((index, opcode));
+                    }
+                }
+                Stat::ChildrenOf(children_of) => {
+                    if let Some(opcode) = self.children_of_check(opcodes, children_of) {
+                        updates.push((index, opcode));
+                    }
+                }
+                Stat::Mod(_) | Stat::ModEnd(_) => {}
+                Stat::Version(version) => {
+                    let valid = version.version.1.split('.').count() == 3
+                        && version
+                            .version
+                            .1
+                            .split('.')
+                            .all(|v| !v.is_empty() && v.bytes().all(|c| c.is_ascii_digit()));
+
+                    if !valid {
+                        self.error(
+                            version.version.0,
+                            AnalyzerError::Version(version.version.1.clone()),
+                        );
+                    }
+                }
+                Stat::Meta(meta) => self.meta_check(meta),
+            }
+        }
+
+        for (index, update) in updates {
+            opcodes[index] = update;
+        }
+
+        Self::unit_fields(opcodes);
+
+        self.recursion_check(opcodes);
+        self.attr_name_check(opcodes);
+        self.type_id_check(opcodes);
+    }
+
+    /// Declarations with an empty field list, `el A();` or `leaf B {}`, are unit nodes like `el A;`.
+    fn unit_fields(opcodes: &mut [Stat]) {
+        let unit = |fields: &mut Fields| {
+            if fields.iter().next().is_none() {
+                *fields = Fields::None;
+            }
+        };
+
+        for opcode in opcodes {
+            match opcode {
+                Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                    unit(&mut node.fields)
+                }
+                Stat::Enum(node) => {
+                    for variant in &mut node.fields {
+                        unit(&mut variant.fields);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Report declarations that share a type id, explicit `#[id(...)]` ids may collide with positional ones.
+    ///
+    /// Also report declarations that share a constant of the flat `ids` module, `shape::Rect` and `ShapeRect`
+    /// are both `SHAPE_RECT`, and both `ShapeRect` opcode variants.
+    fn type_id_check(&mut self, opcodes: &[Stat]) {
+        let mut type_ids: HashMap<usize, Span> = HashMap::new();
+        let mut names: HashMap<String, Span> = HashMap::new();
+
+        for (index, opcode) in opcodes.iter().enumerate() {
+            let ident = match opcode {
+                Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                    &node.ident
+                }
+                Stat::Enum(node) => &node.ident,
+                _ => continue,
+            };
+
+            let type_id = opcode.type_id(index);
+
+            if let Some(span) = type_ids.get(&type_id) {
+                let error = AnalyzerError::DuplicateId(type_id, ident.1.clone(), *span);
+                self.error(ident.0, error);
+            } else {
+                type_ids.insert(type_id, ident.0);
+            }
+
+            let name = ident.1.to_shouty_snake_case();
+
+            if let Some(span) = names.get(&name) {
+                let error = AnalyzerError::IdName(ident.1.clone(), name, *span);
+                self.error(ident.0, error);
+            } else {
+                names.insert(name, ident.0);
+            }
+        }
+    }
+
+    /// Report attrs applied to the same element whose display names or field display names collide,
+    /// the serde codegen looks up the attr of a name per element, and only matches the first one.
+    fn attr_name_check(&mut self, opcodes: &[Stat]) {
+        let mut attrs = HashMap::new();
+
+        for opcode in opcodes {
+            if let Stat::Attr(node) = opcode {
+                let mut names = vec![(
+                    node.rename()
+                        .map_or_else(|| node.ident.1.to_lower_camel_case(), str::to_string),
+                    node.ident.0,
+                )];
+
+                for field in node.fields.iter() {
+                    if let Some(ident) = field.ident() {
+                        let name = field
+                            .rename()
+                            .map_or_else(|| ident.1.to_lower_camel_case(), str::to_string);
+
+                        names.push((name, ident.0));
+                    }
+                }
+
+                attrs.insert(node.ident.1.as_str(), (node, names));
+            }
+        }
+
+        // same order as the generated match arms.
+        let mut apply_attrs: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+        for opcode in opcodes {
+            if let Stat::ApplyTo(apply_to) = opcode {
+                for to in &apply_to.to {
+                    for from in &apply_to.from {
+                        apply_attrs
+                            .entry(to.1.as_str())
+                            .or_default()
+                            .insert(from.1.as_str());
+                    }
+                }
+            }
+        }
+
+        let mut reported = HashSet::new();
+
+        for from in apply_attrs.into_values() {
+            let mut names: HashMap<&str, (&str, Span)> = HashMap::new();
+
+            for attr in from {
+                let Some((node, attr_names)) = attrs.get(attr) else {
+                    continue;
+                };
+
+                for (name, span) in attr_names {
+                    match names.get(name.as_str()) {
+                        Some((other, previous)) if *other != attr => {
+                            if reported.insert((name.as_str(), attr, *other)) {
+                                // either attr can allow the alias.
+                                let properties = [&node.properties, &attrs[other].0.properties]
+                                    .into_iter()
+                                    .flatten()
+                                    .cloned()
+                                    .collect::<Vec<_>>();
+
+                                self.lint(
+                                    Lint::NameCollision,
+                                    &properties,
+                                    *span,
+                                    AnalyzerError::NameCollision(
+                                        name.clone(),
+                                        attr.to_string(),
+                                        other.to_string(),
+                                        *previous,
+                                    ),
+                                );
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            names.insert(name, (attr, *span));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Report `data`/`enum` types that contain themselves without indirection, the generated rust types would have infinite size.
+    fn recursion_check(&mut self, opcodes: &[Stat]) {
+        // 0: unvisited, 1: on the visiting path, 2: visited.
+        let mut states = vec![0u8; opcodes.len()];
+
+        for index in 0..opcodes.len() {
+            self.recursion_visit(opcodes, index, &mut states);
+        }
+    }
+
+    fn recursion_visit(&mut self, opcodes: &[Stat], index: usize, states: &mut [u8]) {
+        if states[index] != 0 {
+            return;
+        }
+
+        let variants = match &opcodes[index] {
+            Stat::Data(node) => vec![node.as_ref()],
+            Stat::Enum(node) => node.fields.iter().filter(|v| !v.is_boxed()).collect(),
+            _ => return,
+        };
+
+        states[index] = 1;
+
+        let mut refs = vec![];
+
+        for variant in variants {
+            for field in variant.fields.iter() {
+                Self::inline_refs(field.ty(), &mut refs);
+            }
+        }
+
+        for ident in refs {
+            let Some(target) = self.symbol_table.lookup(&ident) else {
+                continue;
+            };
+
+            if states[target] == 1 {
+                let span = match &opcodes[target] {
+                    Stat::Data(node) => node.ident.0,
+                    Stat::Enum(node) => node.ident.0,
+                    _ => continue,
+                };
+
+                self.error(ident.0, AnalyzerError::Recursive(ident.1.clone(), span));
+            } else {
+                self.recursion_visit(opcodes, target, states);
+            }
+        }
+
+        states[index] = 2;
+    }
+
+    /// Collect the data/enum types stored inline by `ty`, i.e. not behind a `Vec` or map.
+    fn inline_refs(ty: &Type, refs: &mut Vec<Ident>) {
+        match ty {
+            Type::Data(ident) => refs.push(ident.clone()),
+            Type::ArrayOf(component, _, _) => Self::inline_refs(component, refs),
+            Type::Tuple(components, _) => {
+                for component in components {
+                    Self::inline_refs(component, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn symbol_check(&mut self, opcodes: &[Stat], ident: &Ident, expect_type: bool) -> bool {
+        if let Some(index) = self.symbol_table.lookup(ident) {
+            if let Stat::Group(group) = &opcodes[index] {
+                if expect_type {
+                    self.error(
+                        ident.0,
+                        AnalyzerError::Group(group.ident.1.clone(), group.ident.0),
+                    );
+                }
+
+                return false;
+            }
+
+            return true;
+        } else {
+            self.error(ident.0, AnalyzerError::Unknown(ident.1.clone()));
+            return false;
+        }
+    }
+
+    fn type_check(&mut self, opcodes: &[Stat], ty: &Type) {
+        match ty {
+            Type::Data(ident) => {
+                self.symbol_check(opcodes, ident, true);
+            }
+            Type::Decimal(precision, scale, span)
+                if precision.0 == 0
+                    || precision.0 > MAX_DECIMAL_PRECISION
+                    || scale.0 > precision.0 =>
+            {
+                self.error(*span, AnalyzerError::Decimal(precision.0, scale.0));
+            }
+
+            Type::ListOf(component, _) => {
+                self.component_check(opcodes, component);
+            }
+            Type::ArrayOf(component, _, _) => {
+                self.component_check(opcodes, component);
+            }
+            Type::MapOf(key, value, _) => {
+                if !matches!(
+                    **key,
+                    Type::String(_)
+                        | Type::Byte(_)
+                        | Type::Ubyte(_)
+                        | Type::Short(_)
+                        | Type::Ushort(_)
+                        | Type::Int(_)
+                        | Type::Uint(_)
+                        | Type::Long(_)
+                        | Type::Ulong(_)
+                        | Type::Int128(_)
+                        | Type::Uint128(_)
+                ) {
+                    self.error(*key.span(), AnalyzerError::MapKey);
+                }
+
+                self.component_check(opcodes, value);
+            }
+            Type::Tuple(components, span) => {
+                if components.is_empty() || components.len() > MAX_TUPLE_LEN {
+                    self.error(*span, AnalyzerError::Tuple(components.len()));
+                }
+
+                for component in components {
+                    self.component_check(opcodes, component);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check if `ty` may hold `float` or `double` values, data types are not looked into.
+    fn has_float(ty: &Type) -> bool {
+        match ty {
+            Type::Float(_) | Type::Double(_) | Type::Data(_) => true,
+            Type::ListOf(component, _) | Type::ArrayOf(component, _, _) => {
+                Self::has_float(component)
+            }
+            Type::MapOf(_, value, _) => Self::has_float(value),
+            Type::Tuple(components, _) => components.iter().any(Self::has_float),
+            _ => false,
+        }
+    }
+
+    /// Check the component type of a `vec`, array, map or tuple type.
+    fn component_check(&mut self, opcodes: &[Stat], ty: &Type) {
+        if let Type::Bytes(span) = ty {
+            self.error(*span, AnalyzerError::Bytes);
+        }
+
+        self.type_check(opcodes, ty);
+    }
+
+    fn node_check(&mut self, opcodes: &[Stat], node: &Node) -> Option<Node> {
+        for field in node.fields.iter() {
+            self.type_check(opcodes, &field.ty());
+            self.default_check(&field);
+            self.raw_check(&field);
+
+            if node.mixin.is_none() && field.is_override() {
+                let span = field.ident().map_or(*field.ty().span(), |v| v.0);
+                let name = field.ident().map(|v| v.1.clone()).unwrap_or_default();
+                self.lint(
+                    Lint::UnusedOverride,
+                    &node.properties,
+                    span,
+                    AnalyzerError::Override(name),
+                );
+            }
+        }
+
+        for property in &node.properties {
+            for call in &property.calls {
+                match call.target.1.as_str() {
+                    "option" | "variable" | "init" | "boxed" => {
+                        if call.params.len() != 0 {
+                            self.error(
+                                call.target.0,
+                                AnalyzerError::VariableOption(call.target.1.clone()),
+                            );
+                        }
+                    }
+                    "rename" => {
+                        if call.params.len() != 1 {
+                            self.error(call.target.0, AnalyzerError::Rename);
+                        }
+                    }
+                    "allow" => self.allow_check(call),
+                    "id" => self.id_check(call),
+                    _ => self.rust_attr_check(call),
+                }
+            }
+        }
+
+        if let Some(mixin) = &node.mixin {
+            if let Some(index) = self.merger.lookup(mixin) {
+                if let Stat::Mixin(mixin) = &opcodes[index] {
+                    let expand = self.mixin_fields(node, mixin);
+                    let fields = node.fields.clone();
+
+                    let fields = match fields.append(expand) {
+                        Ok(fields) => fields,
+                        Err(fields) => {
+                            self.error(
+                                node.ident.0,
+                                AnalyzerError::Merge(mixin.ident.1.clone(), mixin.ident.0),
+                            );
+                            fields
+                        }
+                    };
+
+                    return Some(Node {
+                        span: node.span,
+                        comments: node.comments.clone(),
+                        mixin: None,
+                        properties: node.properties.clone(),
+                        ident: node.ident.clone(),
+                        fields,
+                    });
+                } else {
+                    panic!("node_check(mxin): inner error.");
+                }
+            } else {
+                self.error(mixin.0, AnalyzerError::Unknown(mixin.1.clone()));
+                return None;
+            }
+        }
+
+        return None;
+    }
+
+    /// Returns the fields of `mixin` merged into `node`, reports the named fields that conflict with `node`'s fields,
+    /// fields overridden by a `#[override]` field of `node` are skipped.
+    fn mixin_fields(&mut self, node: &Node, mixin: &Node) -> Fields {
+        let (Fields::Named(fields), Fields::Named(mixin_fields)) = (&node.fields, &mixin.fields)
+        else {
+            return mixin.fields.clone();
+        };
+
+        let mut merged = vec![];
+        let mut overridden = HashSet::new();
+
+        for mixin_field in mixin_fields {
+            match fields
+                .iter()
+                .find(|field| field.ident.1 == mixin_field.ident.1)
+            {
+                Some(field) if Field::Named(field).is_override() => {
+                    overridden.insert(field.ident.1.as_str());
+                }
+                Some(field) => self.error(
+                    field.ident.0,
+                    AnalyzerError::MixinField(
+                        field.ident.1.clone(),
+                        mixin.ident.1.clone(),
+                        mixin_field.ident.0,
+                    ),
+                ),
+                None => merged.push(mixin_field.clone()),
+            }
+        }
+
+        for field in fields {
+            if Field::Named(field).is_override() && !overridden.contains(field.ident.1.as_str()) {
+                self.lint(
+                    Lint::UnusedOverride,
+                    &node.properties,
+                    field.ident.0,
+                    AnalyzerError::Override(field.ident.1.clone()),
+                );
+            }
+        }
+
+        Fields::Named(merged)
+    }
+
+    fn raw_check(&mut self, field: &Field<'_>) {
+        let name = || field.ident().map(|v| v.1.clone()).unwrap_or_default();
+
+        for property in field.properties() {
+            for call in &property.calls {
+                let error = match call.target.1.as_str() {
+                    "raw" if !matches!(field.ty(), Type::String(_)) => AnalyzerError::Raw(name()),
+                    "external" if !matches!(field.ty(), Type::Bytes(_) | Type::Iri(_)) => {
+                        AnalyzerError::External(name())
+                    }
+                    "precision"
+                        if call.params.len() != 1
+                            || call.params[0]
+                                .1
+                                .parse::<u32>()
+                                .map_or(true, |decimals| decimals > MAX_FLOAT_PRECISION)
+                            || !Self::has_float(field.ty()) =>
+                    {
+                        AnalyzerError::Precision(name())
+                    }
+                    "borrow"
+                        if !matches!(field.ty(), Type::String(_) | Type::Bytes(_))
+                            || field.is_raw()
+                            || field.is_external() =>
+                    {
+                        AnalyzerError::Borrow(name())
+                    }
+                    _ => continue,
+                };
+
+                self.error(call.target.0, error);
+            }
+        }
+    }
+
+    /// Check the `derive`, `rust_attr` and `cfg` properties of a type declaration.
+    fn rust_attr_check(&mut self, call: &CallExpr) {
+        let is_path = |path: &str| {
+            path.split("::").all(|segment| {
+                segment.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+            })
+        };
+
+        let is_tokens = |tokens: &str| {
+            tokens
+                .parse::<proc_macro2::TokenStream>()
+                .is_ok_and(|tokens| !tokens.is_empty())
+        };
+
+        let error = match call.target.1.as_str() {
+            "derive" if call.params.is_empty() || !call.params.iter().all(|p| is_path(&p.1)) => {
+                AnalyzerError::Derive
+            }
+            "rust_attr" if call.params.len() != 1 || !is_tokens(&call.params[0].1) => {
+                AnalyzerError::RustAttr
+            }
+            "cfg" if call.params.is_empty() || !call.params.iter().all(|p| is_tokens(&p.1)) => {
+                AnalyzerError::Cfg
+            }
+            _ => return,
+        };
+
+        self.error(call.span, error);
+    }
+
+    /// Check the `#[id(...)]` property, the explicit type id of a declaration.
+    fn id_check(&mut self, call: &CallExpr) {
+        if call.params.len() != 1 || call.params[0].1.parse::<usize>().is_err() {
+            self.error(call.target.0, AnalyzerError::Id);
+        }
+    }
+
+    fn meta_check(&mut self, meta: &Meta) {
+        let mut keys: HashMap<&str, Span> = HashMap::new();
+
+        for (key, _) in &meta.fields {
+            if !matches!(key.1.as_str(), "name" | "version" | "namespace") {
+                self.error(key.0, AnalyzerError::MetaKey(key.1.clone()));
+            } else if let Some(span) = keys.insert(&key.1, key.0) {
+                self.error(key.0, AnalyzerError::DuplicateMetaKey(key.1.clone(), span));
+            }
+        }
+
+        if meta.get("name").is_none() {
+            self.error(meta.span, AnalyzerError::MetaName);
+        }
+    }
+
+    fn allow_check(&mut self, call: &CallExpr) {
+        if call.params.is_empty() {
+            self.error(call.span, AnalyzerError::Allow(String::new()));
+        }
+
+        for param in &call.params {
+            if Lint::from_name(&param.1).is_none() {
+                self.error(param.0, AnalyzerError::Allow(param.1.clone()));
+            }
+        }
+    }
+
+    fn default_check(&mut self, field: &Field<'_>) {
+        let Some(default) = field.default_value() else {
+            return;
+        };
+
+        let valid = !field.is_option()
+            && match (field.ty(), default) {
+                (Type::Bool(_), Lit::Bool(_, _)) => true,
+                (Type::String(_), Lit::Str(_)) => true,
+                (Type::Char(_), Lit::Str(v)) => v.1.chars().count() == 1,
+                (Type::Byte(_), Lit::Num(v, _)) => v.parse::<i8>().is_ok(),
+                (Type::Ubyte(_), Lit::Num(v, _)) => v.parse::<u8>().is_ok(),
+                (Type::Short(_), Lit::Num(v, _)) => v.parse::<i16>().is_ok(),
+                (Type::Ushort(_), Lit::Num(v, _)) => v.parse::<u16>().is_ok(),
+                (Type::Int(_), Lit::Num(v, _)) => v.parse::<i32>().is_ok(),
+                (Type::Uint(_), Lit::Num(v, _)) => v.parse::<u32>().is_ok(),
+                (Type::Long(_), Lit::Num(v, _)) => v.parse::<i64>().is_ok(),
+                (Type::Ulong(_), Lit::Num(v, _)) => v.parse::<u64>().is_ok(),
+                (Type::Int128(_), Lit::Num(v, _)) => v.parse::<i128>().is_ok(),
+                (Type::Uint128(_), Lit::Num(v, _)) => v.parse::<u128>().is_ok(),
+                (Type::Float(_), Lit::Num(v, _)) => v.parse::<f32>().is_ok(),
+                (Type::Double(_), Lit::Num(v, _)) => v.parse::<f64>().is_ok(),
+                _ => false,
+            };
+
+        if !valid {
+            self.error(
+                *default.span(),
+                AnalyzerError::Default(field.ident().map(|v| v.1.clone()).unwrap_or_default()),
+            );
+        }
+    }
+
+    fn enum_check(&mut self, opcodes: &[Stat], node: &Enum) {
+        for property in &node.properties {
+            for call in &property.calls {
+                match call.target.1.as_str() {
+                    "allow" => self.allow_check(call),
+                    "id" => self.id_check(call),
+                    _ => self.rust_attr_check(call),
+                }
+            }
+        }
+
+        if node.fields.is_empty() {
+            self.error(node.ident.0, AnalyzerError::EmptyEnum(node.ident.1.clone()));
+        }
+
+        for field_node in &node.fields {
+            for field in field_node.fields.iter() {
+                self.type_check(opcodes, field.ty());
+                self.raw_check(&field);
+
+                if let (Some(ident), Some(default)) = (field.ident(), field.default_value()) {
+                    self.error(
+                        *default.span(),
+                        AnalyzerError::VariantDefault(ident.1.clone()),
+                    );
+                }
+            }
+        }
+
+        let mut sizes = node
+            .fields
+            .iter()
+            .map(|variant| (variant, self.estimate_variant_size(opcodes, variant, 0)))
+            .collect::<Vec<_>>();
+
+        sizes.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+        if let [(largest, size), (_, second), ..] = sizes.as_slice()
+            && *size >= second + LARGE_VARIANT_THRESHOLD
+        {
+            self.lint(
+                Lint::LargeVariant,
+                &node.properties,
+                largest.ident.0,
+                AnalyzerError::LargeVariant(largest.ident.1.clone(), size - second),
+            );
+        }
+    }
+
+    /// Roughly estimate the in-memory size of an enum variant's payload.
+    fn estimate_variant_size(&self, opcodes: &[Stat], variant: &Node, depth: usize) -> usize {
+        if variant.is_boxed() {
+            return variant.fields.iter().count() * 8;
+        }
+
+        variant
+            .fields
+            .iter()
+            .map(|field| {
+                let mut size = self.estimate_size(opcodes, field.ty(), depth);
+
+                if field.is_variable() {
+                    size = size.max(24) + 8;
+                }
+
+                if field.is_option() {
+                    size += 8;
+                }
+
+                size
+            })
+            .sum()
+    }
+
+    /// Roughly estimate the in-memory size of `ty`, recursive references are counted as pointers.
+    fn estimate_size(&self, opcodes: &[Stat], ty: &Type, depth: usize) -> usize {
+        match ty {
+            Type::Bool(_) | Type::Byte(_) | Type::Ubyte(_) => 1,
+            Type::Short(_) | Type::Ushort(_) => 2,
+            Type::Char(_) | Type::Int(_) | Type::Uint(_) | Type::Float(_) => 4,
+            Type::Long(_) | Type::Ulong(_) | Type::Double(_) => 8,
+            Type::Duration(_) => 8,
+            Type::Int128(_) | Type::Uint128(_) => 16,
+            Type::Rect(_)
+            | Type::ViewBox(_)
+            | Type::DateTime(_)
+            | Type::Uuid(_)
+            | Type::Decimal(_, _, _) => 16,
+            Type::String(_)
+            | Type::PathData(_)
+            | Type::Transform(_)
+            | Type::Iri(_)
+            | Type::Bytes(_)
+            | Type::ListOf(_, _)
+            | Type::MapOf(_, _, _) => 24,
+            Type::Tuple(components, _) => components
+                .iter()
+                .map(|component| self.estimate_size(opcodes, component, depth))
+                .sum(),
+            Type::ArrayOf(component, len, _) => {
+                self.estimate_size(opcodes, component, depth) * len.0
+            }
+            Type::Data(ident) => {
+                if depth > 8 {
+                    return 8;
+                }
+
+                match self.symbol_table.lookup(ident).map(|index| &opcodes[index]) {
+                    Some(Stat::Data(node)) => self.estimate_variant_size(opcodes, node, depth + 1),
+                    Some(Stat::Enum(node)) => {
+                        node.fields
+                            .iter()
+                            .map(|variant| self.estimate_variant_size(opcodes, variant, depth + 1))
+                            .max()
+                            .unwrap_or(0)
+                            + 8
+                    }
+                    _ => 8,
+                }
+            }
+        }
+    }
+
+    fn group_check(&mut self, opcodes: &[Stat], node: &Group) {
+        for ident in &node.children {
+            self.symbol_check(opcodes, ident, true);
+        }
+    }
+
+    /// Returns the children of group `ident`, `None` if `ident` is an unknown symbol,
+    /// which is already reported by [`symbol_check`](Self::symbol_check).
+    fn expand_with_group(&mut self, opcodes: &[Stat], ident: &Ident) -> Option<Vec<Ident>> {
+        if let Some(index) = self.digraph_analyzer.lookup(ident) {
+            if let Stat::Group(group) = &opcodes[index] {
+                return Some(group.children.clone());
+            } else {
+                panic!("expand_with_group: inner error.");
+            }
+        } else {
+            None
+        }
+    }
+
+    fn apply_to_check(&mut self, opcodes: &[Stat], node: &ApplyTo) -> Option<Stat> {
+        let mut from_expand = vec![];
+
+        for ident in &node.from {
+            if !self.symbol_check(opcodes, ident, false) {
+                if let Some(mut expand) = self.expand_with_group(opcodes, ident) {
+                    from_expand.append(&mut expand);
+                }
+            } else {
+                from_expand.push(ident.clone());
+            }
+        }
+
+        let mut to_expand = vec![];
+
+        for ident in &node.to {
+            if !self.symbol_check(opcodes, ident, false) {
+                if let Some(mut expand) = self.expand_with_group(opcodes, ident) {
+                    to_expand.append(&mut expand);
+                }
+            } else {
+                to_expand.push(ident.clone());
+            }
+        }
+
+        Some(Stat::ApplyTo(Box::new(ApplyTo {
+            from: from_expand,
+            to: to_expand,
+            span: node.span,
+            comments: node.comments.clone(),
+            properties: node.properties.clone(),
+        })))
+    }
+
+    fn children_of_check(&mut self, opcodes: &[Stat], node: &ChildrenOf) -> Option<Stat> {
+        let mut from_expand = vec![];
+
+        for ident in &node.from {
+            if !self.symbol_check(opcodes, ident, false) {
+                if let Some(mut expand) = self.expand_with_group(opcodes, ident) {
+                    from_expand.append(&mut expand);
+                }
+            } else {
+                from_expand.push(ident.clone());
+            }
+        }
+
+        let mut to_expand = vec![];
+
+        for ident in &node.to {
+            if !self.symbol_check(opcodes, ident, false) {
+                if let Some(mut expand) = self.expand_with_group(opcodes, ident) {
+                    to_expand.append(&mut expand);
+                }
+            } else {
+                to_expand.push(ident.clone());
+            }
+        }
+
+        Some(Stat::ChildrenOf(Box::new(ChildrenOf {
+            from: from_expand,
+            to: to_expand,
+            span: node.span,
+            comments: node.comments.clone(),
+            properties: node.properties.clone(),
+        })))
+    }
+}
+
+/// Process semantic analyze on `opcodes` slice.
+pub fn semantic_analyze(opcodes: &mut [Stat]) -> bool {
+    semantic_analyze_with_diagnostics(opcodes).0
+}
+
+/// Process semantic analyze on `opcodes` slice, returns `true` if no error is reported, and the reported warnings.
+pub fn semantic_analyze_with_warnings(opcodes: &mut [Stat]) -> (bool, Vec<Diagnostic>) {
+    let (ok, mut diagnostics) = semantic_analyze_with_diagnostics(opcodes);
+
+    diagnostics.retain(|diagnostic| diagnostic.level == log::Level::Warn);
+
+    (ok, diagnostics)
+}
+
+/// Process semantic analyze on `opcodes` slice, returns `true` if no error is reported,
+/// and the reported errors and warnings in reporting order.
+pub fn semantic_analyze_with_diagnostics(opcodes: &mut [Stat]) -> (bool, Vec<Diagnostic>) {
+    semantic_analyze_with_lints(opcodes, &LintLevels::default())
+}
+
+/// Like [`semantic_analyze_with_diagnostics`], reports lints with the given `lints` levels.
+pub fn semantic_analyze_with_lints(
+    opcodes: &mut [Stat],
+    lints: &LintLevels,
+) -> (bool, Vec<Diagnostic>) {
+    SemanticAnalyzer {
+        lints: lints.clone(),
+        ..Default::default()
+    }
+    .analyze(opcodes)
+}
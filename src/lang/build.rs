@@ -0,0 +1,100 @@
+//! Build script helper, compiles a schema into `OUT_DIR` and tells cargo when to rerun:
+//!
+//! ```no_run
+//! // build.rs
+//! use mlang_rs::lang::build::MlangBuild;
+//!
+//! fn main() {
+//!     MlangBuild::new().file("schema.ml").out_dir_env().compile().unwrap();
+//! }
+//! ```
+//!
+//! ```ignore
+//! // lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/mlang.rs"));
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use parserc::{ControlFlow, Result};
+
+use super::{
+    ext::{Sources, analyze_and_codegen, parse_file_and_print},
+    parser::ParseError,
+    rustgen::CodeGen,
+};
+
+/// A builder to compile a schema from a build script.
+///
+/// The generated files are written to the output directory, together with an `mlang.rs` wrapper that
+/// declares them as inline `opcode`/`serde`/... modules, so it can be pulled in by `include!`.
+#[derive(Default)]
+pub struct MlangBuild {
+    file: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    codegen: CodeGen,
+}
+
+impl MlangBuild {
+    /// Create a builder with the default [`CodeGen`] config.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the root schema file, files imported by it are compiled too.
+    pub fn file(mut self, path: impl AsRef<Path>) -> Self {
+        self.file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the output directory of the generated files.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the output directory to the `OUT_DIR` of the running build script.
+    pub fn out_dir_env(mut self) -> Self {
+        self.out_dir = std::env::var_os("OUT_DIR").map(PathBuf::from);
+        self
+    }
+
+    /// Reset the code generation config, its [`target`](CodeGen::target) is replaced by the output directory.
+    pub fn codegen(mut self, codegen: CodeGen) -> Self {
+        self.codegen = codegen;
+        self
+    }
+
+    /// Compile the schema, returns the path of the `mlang.rs` wrapper.
+    ///
+    /// A `cargo:rerun-if-changed` line is printed for the schema file and every file imported by it,
+    /// errors and warnings are printed to stderr.
+    pub fn compile(self) -> Result<PathBuf, ParseError> {
+        let Some(file) = self.file else {
+            return Err(ControlFlow::Fatal(ParseError::Io(
+                "schema file isn't set.".to_string(),
+            )));
+        };
+
+        let Some(out_dir) = self.out_dir else {
+            return Err(ControlFlow::Fatal(ParseError::Io(
+                "output directory isn't set, or `OUT_DIR` isn't set outside of build scripts."
+                    .to_string(),
+            )));
+        };
+
+        // failed build scripts always rerun, so files are only tracked once the schema is parsed.
+        let (stats, sources) = parse_file_and_print(&file)?;
+
+        for file in sources.files() {
+            println!("cargo:rerun-if-changed={}", file.path.display());
+        }
+
+        let mut codegen = self.codegen.target(&out_dir);
+        codegen.include_wrapper = true;
+
+        analyze_and_codegen(stats, codegen, Sources::Files(&sources))?;
+
+        Ok(out_dir.join("mlang.rs"))
+    }
+}
@@ -0,0 +1,381 @@
+//! Hash-stable fingerprint of a schema.
+
+use crate::lang::ir::{Field, Fields, Lit, Node, Property, Stat, Type};
+
+/// Version of the canonical form hashed by [`fingerprint`], the first input of the hash.
+const CANONICAL_VERSION: u8 = 1;
+
+/// Returns the SHA-256 hash of the canonical form of an analyzed schema.
+///
+/// `stats` should be processed by [`semantic_analyze`](crate::lang::analyzer::semantic_analyze) first,
+/// so mixins and groups are expanded and symbol references are qualified.
+///
+/// The canonical form contains, in declaration order:
+///
+/// - the type id, kind, ident, properties and fields of every `el`, `leaf`, `attr`, `data` and `enum`;
+/// - the expanded `apply ... to` and `children ... of` links;
+/// - the `version` and `meta` stats.
+///
+/// Spans, comments, whitespace, `#[allow(...)]` lint properties, the declarations of mixins and groups,
+/// and how the schema is split into imported files don't change the fingerprint. The canonical form is
+/// stable across releases of this crate, a change of it bumps the internal canonical version so old and
+/// new fingerprints never collide.
+pub fn fingerprint(stats: &[Stat]) -> [u8; 32] {
+    let mut canonical = Canonical(vec![CANONICAL_VERSION]);
+
+    for (index, stat) in stats.iter().enumerate() {
+        let type_id = stat.type_id(index);
+
+        match stat {
+            Stat::Element(node) => canonical.node("el", type_id, node),
+            Stat::Leaf(node) => canonical.node("leaf", type_id, node),
+            Stat::Attr(node) => canonical.node("attr", type_id, node),
+            Stat::Data(node) => canonical.node("data", type_id, node),
+            Stat::Enum(node) => {
+                canonical.tag("enum");
+                canonical.num(type_id);
+                canonical.str(&node.ident.1);
+                canonical.properties(&node.properties);
+                canonical.num(node.fields.len());
+
+                for variant in &node.fields {
+                    canonical.str(&variant.ident.1);
+                    canonical.properties(&variant.properties);
+                    canonical.fields(&variant.fields);
+                }
+            }
+            Stat::ApplyTo(apply_to) => {
+                canonical.tag("apply");
+                canonical.idents(apply_to.from.iter().map(|ident| ident.1.as_str()));
+                canonical.idents(apply_to.to.iter().map(|ident| ident.1.as_str()));
+            }
+            Stat::ChildrenOf(children_of) => {
+                canonical.tag("children");
+                canonical.idents(children_of.from.iter().map(|ident| ident.1.as_str()));
+                canonical.idents(children_of.to.iter().map(|ident| ident.1.as_str()));
+            }
+            Stat::Version(version) => {
+                canonical.tag("version");
+                canonical.str(&version.version.1);
+            }
+            Stat::Meta(meta) => {
+                canonical.tag("meta");
+                canonical.num(meta.fields.len());
+
+                for (key, value) in &meta.fields {
+                    canonical.str(&key.1);
+                    canonical.str(&value.1);
+                }
+            }
+            Stat::Mixin(_) | Stat::Group(_) | Stat::Mod(_) | Stat::ModEnd(_) => {}
+        }
+    }
+
+    sha256(&canonical.0)
+}
+
+/// The canonical form writer, strings are length prefixed so that no two forms share an encoding.
+struct Canonical(Vec<u8>);
+
+impl Canonical {
+    fn num(&mut self, value: usize) {
+        self.0.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.num(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn tag(&mut self, tag: &str) {
+        self.str(tag);
+    }
+
+    fn idents<'a>(&mut self, idents: impl ExactSizeIterator<Item = &'a str>) {
+        self.num(idents.len());
+
+        for ident in idents {
+            self.str(ident);
+        }
+    }
+
+    fn properties(&mut self, properties: &[Property]) {
+        let calls = properties
+            .iter()
+            .flat_map(|property| &property.calls)
+            .filter(|call| call.target.1 != "allow")
+            .collect::<Vec<_>>();
+
+        self.num(calls.len());
+
+        for call in calls {
+            self.str(&call.target.1);
+            self.idents(call.params.iter().map(|param| param.1.as_str()));
+        }
+    }
+
+    fn node(&mut self, kind: &str, type_id: usize, node: &Node) {
+        self.tag(kind);
+        self.num(type_id);
+        self.str(&node.ident.1);
+        self.properties(&node.properties);
+        self.fields(&node.fields);
+    }
+
+    fn fields(&mut self, fields: &Fields) {
+        match fields {
+            Fields::None => self.tag("none"),
+            Fields::Named(_) => self.tag("named"),
+            Fields::Unnamed(_) => self.tag("unnamed"),
+        }
+
+        self.num(fields.iter().count());
+
+        for field in fields.iter() {
+            self.field(&field);
+        }
+    }
+
+    fn field(&mut self, field: &Field<'_>) {
+        self.str(field.ident().map_or("", |ident| ident.1.as_str()));
+        self.properties(field.properties());
+        self.ty(field.ty());
+
+        match field.default_value() {
+            Some(Lit::Bool(value, _)) => {
+                self.tag("bool");
+                self.str(if *value { "true" } else { "false" });
+            }
+            Some(Lit::Num(value, _)) => {
+                self.tag("num");
+                self.str(value);
+            }
+            Some(Lit::Str(value)) => {
+                self.tag("str");
+                self.str(&value.1);
+            }
+            None => self.tag("none"),
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Bool(_) => self.tag("bool"),
+            Type::Char(_) => self.tag("char"),
+            Type::String(_) => self.tag("string"),
+            Type::Byte(_) => self.tag("byte"),
+            Type::Ubyte(_) => self.tag("ubyte"),
+            Type::Short(_) => self.tag("short"),
+            Type::Ushort(_) => self.tag("ushort"),
+            Type::Int(_) => self.tag("int"),
+            Type::Uint(_) => self.tag("uint"),
+            Type::Long(_) => self.tag("long"),
+            Type::Ulong(_) => self.tag("ulong"),
+            Type::Int128(_) => self.tag("int128"),
+            Type::Uint128(_) => self.tag("uint128"),
+            Type::Float(_) => self.tag("float"),
+            Type::Double(_) => self.tag("double"),
+            Type::PathData(_) => self.tag("path"),
+            Type::Transform(_) => self.tag("transform"),
+            Type::Rect(_) => self.tag("rect"),
+            Type::ViewBox(_) => self.tag("viewbox"),
+            Type::Duration(_) => self.tag("duration"),
+            Type::DateTime(_) => self.tag("datetime"),
+            Type::Uuid(_) => self.tag("uuid"),
+            Type::Iri(_) => self.tag("iri"),
+            Type::Bytes(_) => self.tag("bytes"),
+            Type::Decimal(precision, scale, _) => {
+                self.tag("decimal");
+                self.num(precision.0);
+                self.num(scale.0);
+            }
+            Type::Data(ident) => {
+                self.tag("data");
+                self.str(&ident.1);
+            }
+            Type::ListOf(component, _) => {
+                self.tag("vec");
+                self.ty(component);
+            }
+            Type::ArrayOf(component, len, _) => {
+                self.tag("array");
+                self.ty(component);
+                self.num(len.0);
+            }
+            Type::MapOf(key, value, _) => {
+                self.tag("map");
+                self.ty(key);
+                self.ty(value);
+            }
+            Type::Tuple(components, _) => {
+                self.tag("tuple");
+                self.num(components.len());
+
+                for component in components {
+                    self.ty(component);
+                }
+            }
+        }
+    }
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4) of `input`.
+fn sha256(input: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+
+    message.extend_from_slice(&(input.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+
+        for (idx, word) in block.chunks(4).enumerate() {
+            w[idx] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+
+        for idx in 16..64 {
+            let s0 =
+                w[idx - 15].rotate_right(7) ^ w[idx - 15].rotate_right(18) ^ (w[idx - 15] >> 3);
+            let s1 = w[idx - 2].rotate_right(17) ^ w[idx - 2].rotate_right(19) ^ (w[idx - 2] >> 10);
+
+            w[idx] = w[idx - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[idx - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+
+        for idx in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[idx])
+                .wrapping_add(w[idx]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut output = [0u8; 32];
+
+    for (chunk, value) in output.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ParseContext;
+
+    use super::{fingerprint, sha256};
+    use crate::lang::{analyzer::semantic_analyze, parser::parse};
+
+    fn hex(bytes: [u8; 32]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn analyzed_fingerprint(source: &str) -> String {
+        let mut stats = parse(&mut ParseContext::from(source)).unwrap();
+
+        assert!(semantic_analyze(&mut stats));
+
+        hex(fingerprint(&stats))
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            hex(sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let source = r#"
+            version "1.0.0";
+            mixin Common { id: string }
+            el Rect mixin Common { width: float = 1.0, #[option] fill: vec[Rgb] }
+            data Rgb(ubyte, ubyte, ubyte);
+            attr Fill(Rgb);
+            apply Fill to Rect;
+        "#;
+
+        let reformatted = r#"version "1.0.0";
+            /// the common fields.
+            mixin Common {
+                id: string,
+            }
+            el Rect mixin Common {
+                /// the width.
+                width: float = 1.0,
+                #[option]
+                fill: vec[Rgb]
+            }
+            data Rgb(ubyte, ubyte, ubyte);
+            #[allow("orphan-attr")]
+            attr Fill(Rgb);
+            apply Fill to Rect;"#;
+
+        let renamed = source.replace("width", "w");
+
+        assert_eq!(
+            analyzed_fingerprint(source),
+            "74b12de074966e4d83252d218636610b7c3308c795b5e66a7ecd214b28833e21"
+        );
+        assert_eq!(
+            analyzed_fingerprint(source),
+            analyzed_fingerprint(reformatted)
+        );
+        assert_ne!(analyzed_fingerprint(source), analyzed_fingerprint(&renamed));
+    }
+}
@@ -46,6 +46,27 @@ impl LitUint {
     }
 }
 
+/// Literal value, used as the default value of a field: `1.0`, `true` or `"hello"`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Lit {
+    Bool(bool, Span),
+    /// The source text of a numeric literal, checked against the field type by the analyzer.
+    Num(String, Span),
+    Str(LitStr),
+}
+
+impl Lit {
+    /// Returns the span of this literal.
+    pub fn span(&self) -> &Span {
+        match self {
+            Lit::Bool(_, span) => span,
+            Lit::Num(_, span) => span,
+            Lit::Str(lit) => &lit.0,
+        }
+    }
+}
+
 /// Call expr of a property.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -72,6 +93,8 @@ pub struct Property {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Bool(Span),
+    /// A unicode scalar value.
+    Char(Span),
     String(Span),
     Byte(Span),
     Ubyte(Span),
@@ -81,14 +104,42 @@ pub enum Type {
     Uint(Span),
     Long(Span),
     Ulong(Span),
+    /// 128-bit signed integer.
+    Int128(Span),
+    /// 128-bit unsigned integer.
+    Uint128(Span),
     Float(Span),
     Double(Span),
+    /// SVG-style path data.
+    PathData(Span),
+    /// SVG-style transform list.
+    Transform(Span),
+    /// Rect `x y width height`.
+    Rect(Span),
+    /// View box `x y width height`.
+    ViewBox(Span),
+    /// Animation duration `2s`, `150ms`, `50%`.
+    Duration(Span),
+    /// RFC3339 timestamp `2024-05-01T12:30:00Z`.
+    DateTime(Span),
+    /// Universally unique identifier `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    Uuid(Span),
+    /// Validated IRI reference, e.g. `href` values.
+    Iri(Span),
+    /// Binary blob, base64 in text formats.
+    Bytes(Span),
+    /// Fixed-point decimal `decimal(precision, scale)`, `scale` of the `precision` digits are fractional.
+    Decimal(LitUint, LitUint, Span),
     /// A data/enum reference.
     Data(Ident),
     /// This type is `vec[T]`.
     ListOf(Box<Type>, Span),
     /// Array type `[T;30]`
     ArrayOf(Box<Type>, LitUint, Span),
+    /// Map type `[K -> V]`
+    MapOf(Box<Type>, Box<Type>, Span),
+    /// Tuple type `(T, U)`
+    Tuple(Vec<Type>, Span),
 }
 
 impl Type {
@@ -96,6 +147,7 @@ impl Type {
     pub fn span(&self) -> &Span {
         match self {
             Type::Bool(span) => span,
+            Type::Char(span) => span,
             Type::String(span) => span,
             Type::Byte(span) => span,
             Type::Ubyte(span) => span,
@@ -105,13 +157,49 @@ impl Type {
             Type::Uint(span) => span,
             Type::Long(span) => span,
             Type::Ulong(span) => span,
+            Type::Int128(span) => span,
+            Type::Uint128(span) => span,
             Type::Float(span) => span,
             Type::Double(span) => span,
+            Type::PathData(span) => span,
+            Type::Transform(span) => span,
+            Type::Rect(span) => span,
+            Type::ViewBox(span) => span,
+            Type::Duration(span) => span,
+            Type::DateTime(span) => span,
+            Type::Uuid(span) => span,
+            Type::Iri(span) => span,
+            Type::Decimal(_, _, span) => span,
+            Type::Bytes(span) => span,
             Type::Data(ident) => &ident.0,
             Type::ListOf(_, span) => span,
             Type::ArrayOf(_, _, span) => span,
+            Type::MapOf(_, _, span) => span,
+            Type::Tuple(_, span) => span,
         }
     }
+
+    /// Returns true if this type is one of the builtin primitive types.
+    pub fn is_primitive(&self) -> bool {
+        !matches!(
+            self,
+            Type::Data(_)
+                | Type::ListOf(_, _)
+                | Type::ArrayOf(_, _, _)
+                | Type::MapOf(_, _, _)
+                | Type::Tuple(_, _)
+        )
+    }
+
+    /// Returns true if values of this type can be built in `const` contexts, the primitive types except the
+    /// heap-backed `pathdata`, `transform`, `bytes` and `iri`.
+    pub fn is_const_constructible(&self) -> bool {
+        self.is_primitive()
+            && !matches!(
+                self,
+                Type::PathData(_) | Type::Transform(_) | Type::Bytes(_) | Type::Iri(_)
+            )
+    }
 }
 
 /// Named field for nodes.
@@ -128,6 +216,8 @@ pub struct NamedField {
     pub ident: Ident,
     /// The type of this field.
     pub ty: Type,
+    /// The default value of this field: `width: float = 1.0`.
+    pub default: Option<Lit>,
 }
 
 /// Unnamed field for tuple body.
@@ -317,6 +407,14 @@ impl<'a> Field<'a> {
         }
     }
 
+    /// Get default value.
+    pub fn default_value(&self) -> Option<&Lit> {
+        match self {
+            Field::Named(named_field) => named_field.default.as_ref(),
+            Field::Unnamed(_) => None,
+        }
+    }
+
     pub fn is_option(&self) -> bool {
         for property in self.properties() {
             for callexpr in &property.calls {
@@ -353,6 +451,53 @@ impl<'a> Field<'a> {
         return false;
     }
 
+    /// Check if this field captures the unparsed source text of its value.
+    pub fn is_raw(&self) -> bool {
+        for property in self.properties() {
+            for callexpr in &property.calls {
+                if callexpr.target.1 == "raw" {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Check if this field references an external resource that is loaded lazily.
+    pub fn is_external(&self) -> bool {
+        self.properties()
+            .iter()
+            .flat_map(|property| &property.calls)
+            .any(|call| call.target.1 == "external")
+    }
+
+    /// Check if this field borrows its `string` or `bytes` value from the deserializer input.
+    pub fn is_borrow(&self) -> bool {
+        self.properties()
+            .iter()
+            .flat_map(|property| &property.calls)
+            .any(|call| call.target.1 == "borrow")
+    }
+
+    /// Check if this field replaces the mixin field with the same name.
+    pub fn is_override(&self) -> bool {
+        self.properties()
+            .iter()
+            .flat_map(|property| &property.calls)
+            .any(|call| call.target.1 == "override")
+    }
+
+    /// The decimals of the `float` and `double` values of this field in text formats, `#[precision(n)]`.
+    pub fn precision(&self) -> Option<u32> {
+        self.properties()
+            .iter()
+            .flat_map(|property| &property.calls)
+            .find(|call| call.target.1 == "precision")
+            .and_then(|call| call.params.first())
+            .and_then(|param| param.1.parse().ok())
+    }
+
     /// Serialize and deserialize this field with the given name instead of its Rust name.
     pub fn rename(&self) -> Option<&str> {
         for prop in self.properties() {
@@ -403,6 +548,19 @@ impl Node {
         self.fields.is_tuple()
     }
 
+    /// Check if the fields of this enum variant should be boxed.
+    pub fn is_boxed(&self) -> bool {
+        for prop in &self.properties {
+            for param in &prop.calls {
+                if param.target.1 == "boxed" {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
     /// Serialize and deserialize this node with the given name instead of its Rust name.
     pub fn rename(&self) -> Option<&str> {
         for prop in &self.properties {
@@ -415,6 +573,46 @@ impl Node {
 
         None
     }
+
+    /// The explicit type id declared by `#[id(42)]`, `None` if it isn't declared or isn't a number.
+    pub fn id(&self) -> Option<usize> {
+        for prop in &self.properties {
+            for param in &prop.calls {
+                if param.target.1 == "id" {
+                    return param.params.first().and_then(|v| v.1.parse().ok());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Extra traits derived by the generated rust type, declared by `#[derive(...)]`.
+    pub fn derives(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "derive")
+            .flat_map(|call| call.params.iter().map(|param| param.1.as_str()))
+    }
+
+    /// Rust attributes copied verbatim onto the generated rust type, declared by `#[rust_attr("...")]`.
+    pub fn rust_attrs(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "rust_attr")
+            .flat_map(|call| call.params.first().map(|param| param.1.as_str()))
+    }
+
+    /// Conditional compilation predicates of the generated code, declared by `#[cfg(...)]`.
+    pub fn cfgs(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "cfg")
+            .flat_map(|call| call.params.iter().map(|param| param.1.as_str()))
+    }
 }
 
 /// Defines an enum data.
@@ -445,6 +643,53 @@ impl Enum {
 
         None
     }
+
+    /// The explicit type id declared by `#[id(42)]`, `None` if it isn't declared or isn't a number.
+    pub fn id(&self) -> Option<usize> {
+        for prop in &self.properties {
+            for param in &prop.calls {
+                if param.target.1 == "id" {
+                    return param.params.first().and_then(|v| v.1.parse().ok());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Extra traits derived by the generated rust type, declared by `#[derive(...)]`.
+    pub fn derives(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "derive")
+            .flat_map(|call| call.params.iter().map(|param| param.1.as_str()))
+    }
+
+    /// Rust attributes copied verbatim onto the generated rust type, declared by `#[rust_attr("...")]`.
+    pub fn rust_attrs(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "rust_attr")
+            .flat_map(|call| call.params.first().map(|param| param.1.as_str()))
+    }
+
+    /// Conditional compilation predicates of the generated code, declared by `#[cfg(...)]`.
+    pub fn cfgs(&self) -> impl Iterator<Item = &str> {
+        self.properties
+            .iter()
+            .flat_map(|prop| &prop.calls)
+            .filter(|call| call.target.1 == "cfg")
+            .flat_map(|call| call.params.iter().map(|param| param.1.as_str()))
+    }
+
+    /// Returns true if all variants are unit-like.
+    pub fn is_unit_like(&self) -> bool {
+        self.fields
+            .iter()
+            .all(|node| node.fields.iter().next().is_none())
+    }
 }
 
 /// Defines a group.
@@ -463,6 +708,48 @@ pub struct Group {
     pub children: Vec<Ident>,
 }
 
+/// Import statement: `import "path/other.ml";`
+///
+/// Imports are resolved by the parser and never appear in the output [`Stat`] list.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    /// The span of import stat.
+    pub span: Span,
+    /// The path of imported file, relative to the importing file.
+    pub path: LitStr,
+}
+
+/// Defines the version of the schema: `version "1.2.0";`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version {
+    /// The span of version stat.
+    pub span: Span,
+    /// The version string, in the form `major.minor.patch`.
+    pub version: LitStr,
+}
+
+/// Defines the metadata of the vocabulary: `meta { name: "svg", version: "1.1", namespace: "..." }`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Meta {
+    /// The span of meta stat.
+    pub span: Span,
+    /// `key: "value"` pairs in declaration order.
+    pub fields: Vec<(Ident, LitStr)>,
+}
+
+impl Meta {
+    /// Returns the value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(ident, _)| ident.1 == key)
+            .map(|(_, value)| value.1.as_str())
+    }
+}
+
 /// Defines apply to link
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -495,6 +782,22 @@ pub struct ChildrenOf {
     pub to: Vec<Ident>,
 }
 
+/// Defines the start of a namespace block: `mod geometry { ... }`.
+///
+/// The stats inside the block follow it in the stat list, up to the matching [`Stat::ModEnd`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mod {
+    /// The span of the mod header.
+    pub span: Span,
+    /// comment of this mod.
+    pub comments: Vec<Comment>,
+    /// custom propert list.
+    pub properties: Vec<Property>,
+    /// The name of the mod.
+    pub ident: Ident,
+}
+
 /// Defines a vglang metadata stat.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -508,4 +811,23 @@ pub enum Stat {
     Group(Box<Group>),
     ApplyTo(Box<ApplyTo>),
     ChildrenOf(Box<ChildrenOf>),
+    Mod(Box<Mod>),
+    /// The end of the innermost [`Stat::Mod`] block.
+    ModEnd(Span),
+    Version(Box<Version>),
+    Meta(Box<Meta>),
+}
+
+impl Stat {
+    /// Returns the type id of the stat at `index` of the analyzed stats: the explicit `#[id(...)]` of
+    /// elements, leaves, attrs, data and enums, or `index` if it isn't declared.
+    pub fn type_id(&self, index: usize) -> usize {
+        match self {
+            Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                node.id().unwrap_or(index)
+            }
+            Stat::Enum(node) => node.id().unwrap_or(index),
+            _ => index,
+        }
+    }
 }
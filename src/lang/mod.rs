@@ -1,34 +1,199 @@
 //! Compile and code generation tools for mlang.
 
 pub mod analyzer;
+pub mod build;
+mod fingerprint;
+pub use fingerprint::*;
 pub mod ir;
 pub mod parser;
+pub mod report;
 pub mod rustgen;
+pub mod scaffold;
 
 mod ext {
 
-    use parserc::{ParseContext, Result};
+    use std::{fmt::Display, path::Path};
+
+    use parserc::{ControlFlow, ParseContext, Result, Span};
+    use proc_macro2::TokenStream;
 
     use super::{
-        analyzer::semantic_analyze,
-        parser::{ParseError, parse},
+        analyzer::semantic_analyze_with_lints,
+        ir::Stat,
+        parser::{ParseError, SourceMap, parse, parse_file_with_sources},
+        report,
         rustgen::CodeGen,
     };
 
     /// Compile `mlang` source code and generate rust source code.
     ///
-    /// This function will output any errors encountered during compilation directly to the terminal
+    /// This function will output any errors encountered during compilation directly to the terminal,
+    /// with the offending source line.
     pub fn compile<S: AsRef<str>>(source: S, codegen: CodeGen) -> Result<(), ParseError> {
-        let mut ctx = ParseContext::from(source.as_ref());
+        let source = source.as_ref();
+        let mut ctx = ParseContext::from(source);
 
-        let mut stats = match parse(&mut ctx) {
+        let stats = match parse(&mut ctx) {
             Ok(stats) => stats,
             Err(err) => {
+                let (ControlFlow::Recoverable(inner)
+                | ControlFlow::Incomplete(inner)
+                | ControlFlow::Fatal(inner)) = &err;
+
+                report::eprint(log::Level::Error, inner, ctx.span(), Some(source));
+
                 return Err(err);
             }
         };
 
-        if !semantic_analyze(&mut stats) {
+        analyze_and_codegen(stats, codegen, Sources::Str(source))
+    }
+
+    /// Compile the `mlang` source file at `path`, together with all files imported by it,
+    /// and generate rust source code.
+    ///
+    /// This function will output any errors encountered during compilation directly to the terminal,
+    /// with the path of the offending file and the offending source line.
+    pub fn compile_file<P: AsRef<Path>>(path: P, codegen: CodeGen) -> Result<(), ParseError> {
+        let (stats, sources) = parse_file_and_print(path)?;
+
+        analyze_and_codegen(stats, codegen, Sources::Files(&sources))
+    }
+
+    /// Parse the source file at `path` with [`parse_file_with_sources`], errors are printed
+    /// with the path of the offending file and the offending source line.
+    pub(super) fn parse_file_and_print<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Vec<Stat>, SourceMap), ParseError> {
+        let mut sources = SourceMap::default();
+
+        match parse_file_with_sources(path, &mut sources) {
+            Ok(stats) => Ok((stats, sources)),
+            Err(err) => {
+                let (ControlFlow::Recoverable(inner)
+                | ControlFlow::Incomplete(inner)
+                | ControlFlow::Fatal(inner)) = &err;
+
+                // the root file can't be read if parsing didn't start.
+                match sources.failed() {
+                    Some(span) => Sources::Files(&sources).eprint(log::Level::Error, inner, span),
+                    None => eprintln!("error: {}\n", inner),
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Compile `mlang` source code to the token stream of the generated modules, see [`CodeGen::codegen_to_tokens`].
+    ///
+    /// Nothing is written to disk, on failure the rendered errors are returned instead of printed,
+    /// so the `mlang!{}` macro can report them with `compile_error!`. Warnings are printed to stderr.
+    pub fn compile_to_tokens<S: AsRef<str>>(
+        source: S,
+        codegen: &CodeGen,
+    ) -> std::result::Result<TokenStream, String> {
+        let source = source.as_ref();
+        let mut ctx = ParseContext::from(source);
+
+        let mut stats = match parse(&mut ctx) {
+            Ok(stats) => stats,
+            Err(err) => {
+                let (ControlFlow::Recoverable(inner)
+                | ControlFlow::Incomplete(inner)
+                | ControlFlow::Fatal(inner)) = &err;
+
+                return Err(report::render(
+                    log::Level::Error,
+                    inner,
+                    ctx.span(),
+                    Some(source),
+                ));
+            }
+        };
+
+        let (ok, diagnostics) = semantic_analyze_with_lints(&mut stats, &codegen.lints);
+
+        if !ok {
+            return Err(diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.level == log::Level::Error)
+                .map(|diagnostic| {
+                    report::render(
+                        diagnostic.level,
+                        &diagnostic.error,
+                        diagnostic.span,
+                        Some(source),
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"));
+        }
+
+        for diagnostic in &diagnostics {
+            report::eprint(
+                diagnostic.level,
+                &diagnostic.error,
+                diagnostic.span,
+                Some(source),
+            );
+        }
+
+        Ok(codegen.codegen_to_tokens(stats))
+    }
+
+    /// The source code that diagnostic spans point into.
+    pub(super) enum Sources<'a> {
+        /// Spans of [`parse`], into a single source string.
+        Str(&'a str),
+        /// Spans of [`parse_file_with_sources`], into the files of a [`SourceMap`].
+        Files(&'a SourceMap),
+    }
+
+    impl Sources<'_> {
+        /// Print a diagnostic with [`report::eprint`], or [`report::eprint_file`] if `span` is in a file.
+        fn eprint(&self, level: log::Level, message: impl Display, span: Span) {
+            match self {
+                Sources::Str(source) => report::eprint(level, message, span, Some(source)),
+                Sources::Files(sources) => match sources.lookup(span) {
+                    Some((file, span)) => {
+                        report::eprint_file(level, message, span, &file.path, &file.source)
+                    }
+                    None => report::eprint(level, message, span, None),
+                },
+            }
+        }
+
+        /// Returns `span` relative to the start of its file.
+        fn local(&self, span: Span) -> Span {
+            match self {
+                Sources::Str(_) => span,
+                Sources::Files(sources) => sources.lookup(span).map_or(span, |(_, span)| span),
+            }
+        }
+    }
+
+    pub(super) fn analyze_and_codegen(
+        mut stats: Vec<Stat>,
+        codegen: CodeGen,
+        sources: Sources<'_>,
+    ) -> Result<(), ParseError> {
+        let (ok, mut diagnostics) = semantic_analyze_with_lints(&mut stats, &codegen.lints);
+
+        for diagnostic in &mut diagnostics {
+            sources.eprint(diagnostic.level, &diagnostic.error, diagnostic.span);
+            // lint reports have no file, the positions are at least local to the file.
+            diagnostic.span = sources.local(diagnostic.span);
+        }
+
+        if let Some(path) = &codegen.lint_report
+            && let Err(err) = std::fs::write(path, report::to_json(&diagnostics))
+        {
+            eprintln!("lint report: {}", err);
+            return Err(parserc::ControlFlow::Fatal(ParseError::Io(err.to_string())));
+        }
+
+        if !ok {
             return Err(parserc::ControlFlow::Fatal(ParseError::Semantic));
         }
 
@@ -7,6 +7,9 @@ pub enum ParseError {
     #[error("Invalid ident.")]
     Ident,
 
+    #[error("Invalid literal value.")]
+    Lit,
+
     #[error("semantic analyze error.")]
     Semantic,
 
@@ -55,6 +58,21 @@ pub enum ParseError {
 
     #[error("Syntax error of children ... of ..., {0}")]
     ChildrenOf(ChildrenOfKind),
+
+    #[error("Syntax error of import, {0}")]
+    Import(ImportKind),
+
+    #[error("Syntax error of mod, {0}")]
+    Mod(ModKind),
+
+    #[error("import cycle detected: {0}")]
+    ImportCycle(String),
+
+    #[error("Syntax error of version, {0}")]
+    Version(VersionKind),
+
+    #[error("Syntax error of meta, {0}")]
+    Meta(MetaKind),
 }
 
 impl parserc::ParseError for ParseError {}
@@ -68,6 +86,50 @@ pub enum TupleKind {
     BodyEnd,
 }
 
+/// Error kind of parsing mod block.
+#[derive(Debug, thiserror::Error, PartialEq, PartialOrd, Clone)]
+pub enum ModKind {
+    #[error("invalid mod ident.")]
+    Ident,
+    #[error("expect `{{`.")]
+    BodyStart,
+    #[error("expect `}}`.")]
+    BodyEnd,
+}
+
+/// Error kind of parsing import stat.
+#[derive(Debug, thiserror::Error, PartialEq, PartialOrd, Clone)]
+pub enum ImportKind {
+    #[error("expect a literal string path following by `import` keyword.")]
+    Path,
+    #[error("expect `;`.")]
+    End,
+    #[error("expect a source file, imports are resolved relative to the importing file.")]
+    NoFile,
+}
+
+/// Error kind of parsing version stat.
+#[derive(Debug, thiserror::Error, PartialEq, PartialOrd, Clone)]
+pub enum VersionKind {
+    #[error("expect a literal string following by `version` keyword.")]
+    Lit,
+    #[error("expect `;`.")]
+    End,
+}
+
+/// Error kind of parsing meta stat.
+#[derive(Debug, thiserror::Error, PartialEq, PartialOrd, Clone)]
+pub enum MetaKind {
+    #[error("expect `{{`.")]
+    BodyStart,
+    #[error("expect `}}`.")]
+    BodyEnd,
+    #[error("expect `:`.")]
+    Colon,
+    #[error("expect a literal string value.")]
+    Value,
+}
+
 /// Error kind of parsing children .. of ... stat.
 #[derive(Debug, thiserror::Error, PartialEq, PartialOrd, Clone)]
 pub enum ChildrenOfKind {
@@ -125,6 +187,9 @@ pub enum NamedFieldKind {
 
     #[error("expect field type declaration.")]
     Type,
+
+    #[error("expect literal default value following by `=`.")]
+    Default,
 }
 
 /// Error kind of enum parsing.
@@ -153,9 +218,15 @@ pub enum TypeKind {
     #[error("unrecognized array length.")]
     Uint,
 
-    #[error("miss `;` for array declaration.")]
+    #[error("miss `;` for array declaration or `->` for map declaration.")]
     Semicolons,
 
+    #[error("miss map value type.")]
+    MapValue,
+
+    #[error("miss tuple end tag `)`")]
+    ParenEnd,
+
     #[error("miss array/list start tag `[`")]
     SquareBracketStart,
 
@@ -164,6 +235,9 @@ pub enum TypeKind {
 
     #[error("miss data name.")]
     Data,
+
+    #[error("expect `(precision, scale)` of decimal type.")]
+    Decimal,
 }
 
 /// Error kind of unit parsing.
@@ -171,6 +245,8 @@ pub enum TypeKind {
 pub enum CallKind {
     #[error("expect call expr parameter list end tag `)`")]
     ParamEnd,
+    #[error("expect string literal value of call expr parameter")]
+    Value,
 }
 
 /// Error kind of unit parsing.
@@ -1,7 +1,7 @@
 use parserc::{FromSrc, IntoParser, ParseContext, Parser, ParserExt, Result, ensure_char};
 
 use crate::lang::{
-    ir::{Fields, Ident, NamedField, Type, UnnamedField},
+    ir::{Fields, Ident, Lit, NamedField, Type, UnnamedField},
     parser::{FieldsKind, ParseError},
 };
 
@@ -36,12 +36,31 @@ impl FromSrc for NamedField {
             .fatal(ParseError::NamedField(NamedFieldKind::SemiColons))
             .parse(ctx)?;
 
+        let mut end = *ty.span();
+
+        skip_ws(ctx)?;
+
+        let default = if ensure_char('=').ok().parse(ctx)?.is_some() {
+            skip_ws(ctx)?;
+
+            let lit = Lit::into_parser()
+                .fatal(ParseError::NamedField(NamedFieldKind::Default))
+                .parse(ctx)?;
+
+            end = *lit.span();
+
+            Some(lit)
+        } else {
+            None
+        };
+
         Ok(NamedField {
-            span: start.extend_to_inclusive(*ty.span()),
+            span: start.extend_to_inclusive(end),
             comments,
             properties,
             ident,
             ty,
+            default,
         })
     }
 }
@@ -150,7 +169,8 @@ mod tests {
                     comments: vec![],
                     properties: vec![],
                     ident: Ident(Span::new(2, 4, 1, 3), "name".to_string()),
-                    ty: Type::String(Span::new(8, 6, 1, 9))
+                    ty: Type::String(Span::new(8, 6, 1, 9)),
+                    default: None,
                 },
                 NamedField {
                     span: Span::new(17, 32, 2, 1),
@@ -171,9 +191,26 @@ mod tests {
                         ]
                     }],
                     ident: Ident(Span::new(40, 3, 3, 1), "len".to_string()),
-                    ty: Type::Uint(Span::new(45, 4, 3, 6))
+                    ty: Type::Uint(Span::new(45, 4, 3, 6)),
+                    default: None,
                 }
             ]))
         );
     }
+
+    #[test]
+    fn test_int128() {
+        assert_eq!(
+            Type::parse(&mut ParseContext::from("int128")),
+            Ok(Type::Int128(Span::new(0, 6, 1, 1)))
+        );
+        assert_eq!(
+            Type::parse(&mut ParseContext::from("uint128")),
+            Ok(Type::Uint128(Span::new(0, 7, 1, 1)))
+        );
+        assert_eq!(
+            Type::parse(&mut ParseContext::from("uint")),
+            Ok(Type::Uint(Span::new(0, 4, 1, 1)))
+        );
+    }
 }
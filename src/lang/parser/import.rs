@@ -0,0 +1,39 @@
+use parserc::{
+    FromSrc, IntoParser, ParseContext, Parser, ParserExt, Result, ensure_char, ensure_keyword,
+};
+
+use crate::lang::ir::{Import, LitStr};
+
+use super::{
+    ImportKind, ParseError,
+    utils::{parse_prefix, skip_ws},
+};
+
+impl FromSrc for Import {
+    type Error = ParseError;
+    fn parse(ctx: &mut ParseContext<'_>) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        parse_prefix(ctx)?;
+
+        let start = ensure_keyword("import").parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let path = LitStr::into_parser()
+            .fatal(ParseError::Import(ImportKind::Path))
+            .parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let end = ensure_char(';')
+            .fatal(ParseError::Import(ImportKind::End))
+            .parse(ctx)?;
+
+        Ok(Self {
+            span: start.extend_to_inclusive(end),
+            path,
+        })
+    }
+}
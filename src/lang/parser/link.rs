@@ -6,7 +6,7 @@ use crate::lang::ir::{ApplyTo, ChildrenOf, Group, Ident};
 
 use super::{
     ApplyToKind, ChildrenOfKind, GroupKind, ParseError, TupleKind,
-    utils::{parse_prefix, skip_ws},
+    utils::{parse_path, parse_prefix, skip_ws},
 };
 
 fn parse_tuple_idents(ctx: &mut ParseContext<'_>) -> Result<Vec<Ident>, ParseError> {
@@ -18,7 +18,7 @@ fn parse_tuple_idents(ctx: &mut ParseContext<'_>) -> Result<Vec<Ident>, ParseErr
 
     let mut children = vec![];
 
-    while let Some(ident) = Ident::into_parser().ok().parse(ctx)? {
+    while let Some(ident) = parse_path.ok().parse(ctx)? {
         children.push(ident);
 
         skip_ws(ctx)?;
@@ -93,7 +93,7 @@ impl FromSrc for ApplyTo {
 
         skip_ws(ctx)?;
 
-        let from = Ident::into_parser()
+        let from = parse_path
             .map(|v| vec![v])
             .or(parse_tuple_idents)
             .parse(ctx)?;
@@ -106,7 +106,7 @@ impl FromSrc for ApplyTo {
 
         skip_ws(ctx)?;
 
-        let to = Ident::into_parser()
+        let to = parse_path
             .map(|v| vec![v])
             .or(parse_tuple_idents)
             .fatal(ParseError::ApplyTo(ApplyToKind::Target))
@@ -142,7 +142,7 @@ impl FromSrc for ChildrenOf {
 
         skip_ws(ctx)?;
 
-        let from = Ident::into_parser()
+        let from = parse_path
             .map(|v| vec![v])
             .or(parse_tuple_idents)
             .fatal(ParseError::ChildrenOf(ChildrenOfKind::From))
@@ -156,7 +156,7 @@ impl FromSrc for ChildrenOf {
 
         skip_ws(ctx)?;
 
-        let to = Ident::into_parser()
+        let to = parse_path
             .map(|v| vec![v])
             .or(parse_tuple_idents)
             .fatal(ParseError::ChildrenOf(ChildrenOfKind::To))
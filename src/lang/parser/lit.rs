@@ -1,10 +1,10 @@
 use parserc::{
-    ControlFlow, FromSrc, Parser, ParserExt, Result, ensure_char, ensure_keyword, take_till,
-    take_while,
+    ControlFlow, FromSrc, IntoParser, Parser, ParserExt, Result, ensure_char, ensure_keyword,
+    take_till, take_while,
 };
 
 use crate::lang::{
-    ir::{LitStr, LitUint},
+    ir::{Lit, LitStr, LitUint},
     parser::{ParseError, UnitKind},
 };
 
@@ -71,11 +71,50 @@ impl FromSrc for LitStr {
     }
 }
 
+impl FromSrc for Lit {
+    type Error = ParseError;
+    fn parse(input: &mut parserc::ParseContext<'_>) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        if let Some(span) = ensure_keyword("true").ok().parse(input)? {
+            return Ok(Lit::Bool(true, span));
+        }
+
+        if let Some(span) = ensure_keyword("false").ok().parse(input)? {
+            return Ok(Lit::Bool(false, span));
+        }
+
+        if let Some(lit) = LitStr::into_parser().ok().parse(input)? {
+            return Ok(Lit::Str(lit));
+        }
+
+        let (c, start) = input.next();
+
+        match c {
+            Some(c) if c == '-' || c.is_ascii_digit() => {}
+            Some(_) => return Err(ControlFlow::Recoverable(ParseError::Lit)),
+            None => return Err(ControlFlow::Incomplete(ParseError::Lit)),
+        }
+
+        let body = take_while(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+')
+            .parse(input)?;
+
+        let span = if let Some(body) = body {
+            start.extend_to_inclusive(body)
+        } else {
+            start
+        };
+
+        Ok(Lit::Num(input.as_str(span).to_string(), span))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use parserc::{FromSrc, ParseContext, Span};
 
-    use crate::lang::ir::LitUint;
+    use crate::lang::ir::{Lit, LitUint};
 
     #[test]
     fn test_num() {
@@ -89,4 +128,17 @@ mod tests {
             Ok(LitUint(0x123, Span::new(0, 5, 1, 1)))
         );
     }
+
+    #[test]
+    fn test_lit() {
+        assert_eq!(
+            Lit::parse(&mut ParseContext::from("-1.5e3,")),
+            Ok(Lit::Num("-1.5e3".to_string(), Span::new(0, 6, 1, 1)))
+        );
+
+        assert_eq!(
+            Lit::parse(&mut ParseContext::from("true")),
+            Ok(Lit::Bool(true, Span::new(0, 4, 1, 1)))
+        );
+    }
 }
@@ -0,0 +1,69 @@
+use parserc::{
+    FromSrc, IntoParser, ParseContext, Parser, ParserExt, Result, ensure_char, ensure_keyword,
+};
+
+use crate::lang::ir::{Ident, LitStr, Meta};
+
+use super::{
+    MetaKind, ParseError,
+    utils::{parse_prefix, skip_ws},
+};
+
+impl FromSrc for Meta {
+    type Error = ParseError;
+    fn parse(ctx: &mut ParseContext<'_>) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        parse_prefix(ctx)?;
+
+        let start = ensure_keyword("meta").parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        ensure_char('{')
+            .fatal(ParseError::Meta(MetaKind::BodyStart))
+            .parse(ctx)?;
+
+        let mut fields = vec![];
+
+        loop {
+            skip_ws(ctx)?;
+
+            let Some(key) = Ident::into_parser().ok().parse(ctx)? else {
+                break;
+            };
+
+            skip_ws(ctx)?;
+
+            ensure_char(':')
+                .fatal(ParseError::Meta(MetaKind::Colon))
+                .parse(ctx)?;
+
+            skip_ws(ctx)?;
+
+            let value = LitStr::into_parser()
+                .fatal(ParseError::Meta(MetaKind::Value))
+                .parse(ctx)?;
+
+            fields.push((key, value));
+
+            skip_ws(ctx)?;
+
+            if ensure_char(',').ok().parse(ctx)?.is_none() {
+                break;
+            }
+        }
+
+        skip_ws(ctx)?;
+
+        let end = ensure_char('}')
+            .fatal(ParseError::Meta(MetaKind::BodyEnd))
+            .parse(ctx)?;
+
+        Ok(Self {
+            span: start.extend_to_inclusive(end),
+            fields,
+        })
+    }
+}
@@ -3,24 +3,394 @@
 mod error;
 pub use error::*;
 mod field;
+mod import;
 mod link;
 mod lit;
+mod meta;
+mod namespace;
 mod node;
 mod prop;
 mod stat;
 mod utils;
+mod version;
 
-use parserc::{IntoParser, ParseContext, Parser, ParserExt, Result};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
-use crate::lang::ir::Stat;
+use parserc::{
+    ControlFlow, IntoParser, ParseContext, Parser, ParserExt, Result, Span, ensure_char,
+};
+
+use crate::lang::ir::{Import, Mod, Stat};
 
 /// Parse input source code.
+///
+/// The source isn't read from a file, so it can't `import` other files, see [`parse_file`].
 pub fn parse(input: &mut ParseContext<'_>) -> Result<Vec<Stat>, ParseError> {
-    let mut opcodes = vec![];
+    let mut entries = vec![];
+
+    parse_entries(input, &mut entries, false)?;
+
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Entry::Stat(stat) => Ok(stat),
+            Entry::Import(import) => {
+                input.seek(import.span);
+                Err(ControlFlow::Fatal(ParseError::Import(ImportKind::NoFile)))
+            }
+        })
+        .collect()
+}
+
+/// Parse the source file at `path`, together with all files imported by it.
+///
+/// `import` statements are resolved relative to the importing file. A file is included once per mod
+/// it is imported in, so a file imported in a mod and at the root declares its types in both.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Stat>, ParseError> {
+    parse_file_with_sources(path, &mut SourceMap::default())
+}
+
+/// Parse the source file at `path` like [`parse_file`], and also returns the canonical paths of all parsed files,
+/// in load order, e.g. to emit `cargo:rerun-if-changed` lines.
+pub fn parse_file_with_imports(
+    path: impl AsRef<Path>,
+) -> Result<(Vec<Stat>, Vec<PathBuf>), ParseError> {
+    let mut sources = SourceMap::default();
+
+    let stats = parse_file_with_sources(path, &mut sources)?;
+
+    Ok((
+        stats,
+        sources.files.into_iter().map(|file| file.path).collect(),
+    ))
+}
+
+/// Parse the source file at `path` like [`parse_file`], the parsed files are recorded in `sources`,
+/// also on failure, so errors can be rendered with the file and the source line they point to.
+pub fn parse_file_with_sources(
+    path: impl AsRef<Path>,
+    sources: &mut SourceMap,
+) -> Result<Vec<Stat>, ParseError> {
+    let mut imports = Imports {
+        sources: std::mem::take(sources),
+        ..Default::default()
+    };
+
+    let result = imports.parse_file(path.as_ref(), &mut vec![]);
+
+    *sources = imports.sources;
+
+    result.map(|_| imports.stats)
+}
+
+/// The source files of a schema, in load order.
+///
+/// Spans don't record their file, so each file is parsed at its own offset range, after the end of the
+/// previously loaded file, and [`lookup`](Self::lookup) tells which file a span belongs to.
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// The position where parsing failed, in the innermost file being parsed.
+    failed: Option<Span>,
+}
+
+/// A source file of a schema, see [`SourceMap`].
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// The canonical path of the file.
+    pub path: PathBuf,
+    /// The source code of the file.
+    pub source: String,
+    /// The offset of the file's first char in the spans of the schema.
+    pub start: usize,
+}
+
+impl SourceMap {
+    /// Returns the parsed files, in load order.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// Returns the position where parsing failed, `None` if parsing succeeded or failed before reading the
+    /// root file.
+    pub fn failed(&self) -> Option<Span> {
+        self.failed
+    }
+
+    /// Returns the file of `span`, and the span relative to the start of that file.
+    pub fn lookup(&self, span: Span) -> Option<(&SourceFile, Span)> {
+        let file = self
+            .files
+            .iter()
+            .rev()
+            .find(|file| file.start <= span.offset)?;
+
+        // lines and cols are counted from the start of each file.
+        Some((
+            file,
+            Span::new(span.offset - file.start, span.len(), span.lines, span.cols),
+        ))
+    }
+}
+
+/// A parsed stat, or an `import` statement, which is resolved after its file is parsed.
+enum Entry {
+    Stat(Stat),
+    Import(Import),
+}
+
+/// Parse entries until the end of input, or until the `}` of a mod block if `in_mod` is true.
+fn parse_entries(
+    input: &mut ParseContext<'_>,
+    entries: &mut Vec<Entry>,
+    in_mod: bool,
+) -> Result<(), ParseError> {
+    loop {
+        if let Some(import) = Import::into_parser().ok().parse(input)? {
+            entries.push(Entry::Import(import));
+            continue;
+        }
+
+        if let Some(module) = Mod::into_parser().ok().parse(input)? {
+            entries.push(Entry::Stat(Stat::Mod(Box::new(module))));
+            parse_entries(input, entries, true)?;
+            continue;
+        }
+
+        if in_mod {
+            utils::skip_ws(input)?;
+
+            if let Some(end) = ensure_char('}').ok().parse(input)? {
+                entries.push(Entry::Stat(Stat::ModEnd(end)));
+                return Ok(());
+            }
+        }
+
+        if let Some(stat) = Stat::into_parser().ok().parse(input)? {
+            entries.push(Entry::Stat(stat));
+            continue;
+        }
+
+        if in_mod {
+            return Err(ControlFlow::Fatal(ParseError::Mod(ModKind::BodyEnd)));
+        }
+
+        return Ok(());
+    }
+}
+
+/// `import` statements resolver.
+#[derive(Default)]
+struct Imports {
+    /// files that are being parsed, used to detect import cycles.
+    stack: Vec<PathBuf>,
+    /// files that have already been parsed, with the mod they are imported in, each file is only included
+    /// once per mod.
+    loaded: HashSet<(PathBuf, Vec<String>)>,
+    /// files that have already been parsed, in load order.
+    sources: SourceMap,
+    /// the sources of all loaded files, separated by a newline, spans are offsets in it.
+    text: String,
+    /// collection of parsed stats.
+    stats: Vec<Stat>,
+}
+
+impl Imports {
+    /// Parse the file at `path`, imported in the mod `namespace`, and the files imported by it.
+    fn parse_file(&mut self, path: &Path, namespace: &mut Vec<String>) -> Result<(), ParseError> {
+        let path = path
+            .canonicalize()
+            .map_err(|err| ControlFlow::Fatal(ParseError::Io(format!("{:?}: {}", path, err))))?;
+
+        if self.stack.contains(&path) {
+            return Err(ControlFlow::Fatal(ParseError::ImportCycle(format!(
+                "{:?}",
+                path
+            ))));
+        }
+
+        if !self.loaded.insert((path.clone(), namespace.clone())) {
+            return Ok(());
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| ControlFlow::Fatal(ParseError::Io(format!("{:?}: {}", path, err))))?;
+
+        // one past the end of the previous file, so the end of a file isn't the start of the next.
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+
+        let start = self.text.len();
 
-    while let Some(opcode) = Stat::into_parser().ok().parse(input)? {
-        opcodes.push(opcode);
+        self.text.push_str(&source);
+
+        self.sources.files.push(SourceFile {
+            path: path.clone(),
+            source,
+            start,
+        });
+
+        let mut entries = vec![];
+
+        {
+            let mut ctx = ParseContext::from(self.text.as_str());
+
+            ctx.seek(Span::new(start, 0, 1, 1));
+
+            if let Err(err) = parse_entries(&mut ctx, &mut entries, false) {
+                self.sources.failed.get_or_insert(ctx.span());
+                return Err(err);
+            }
+        }
+
+        let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        self.stack.push(path);
+
+        for entry in entries {
+            match entry {
+                Entry::Import(import) => {
+                    if let Err(err) = self.parse_file(&base.join(&import.path.1), namespace) {
+                        self.sources.failed.get_or_insert(import.span);
+                        return Err(err);
+                    }
+                }
+                Entry::Stat(stat) => {
+                    match &stat {
+                        Stat::Mod(module) => namespace.push(module.ident.1.clone()),
+                        Stat::ModEnd(_) => _ = namespace.pop(),
+                        _ => {}
+                    }
+
+                    self.stats.push(stat);
+                }
+            }
+        }
+
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::{ControlFlow, ParseContext};
+
+    use crate::lang::ir::Stat;
+
+    use super::{
+        ImportKind, ParseError, SourceMap, parse, parse_file, parse_file_with_imports,
+        parse_file_with_sources,
+    };
+
+    #[test]
+    fn test_mod() {
+        let stats = parse(&mut ParseContext::from(
+            "mod a { data X(int); mod b { data Y(X); } } data Z(a::b::Y);",
+        ))
+        .unwrap();
+
+        assert_eq!(stats.len(), 7);
+        assert!(matches!(&stats[0], Stat::Mod(m) if m.ident.1 == "a"));
+        assert!(matches!(&stats[4], Stat::ModEnd(_)));
+        assert!(matches!(&stats[5], Stat::ModEnd(_)));
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join(format!("mlang-import-{}", std::process::id()));
+
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+
+        std::fs::write(
+            dir.join("main.ml"),
+            "import \"sub/a.ml\";\ndata B(string);\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("sub/a.ml"), "import 'b.ml';\ndata A(int);\n").unwrap();
+        std::fs::write(dir.join("sub/b.ml"), "data C(bool);\n").unwrap();
+
+        assert_eq!(parse_file(dir.join("main.ml")).unwrap().len(), 3);
+
+        let (_, files) = parse_file_with_imports(dir.join("main.ml")).unwrap();
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| file.file_name().unwrap().to_str().unwrap())
+                .collect::<Vec<_>>(),
+            ["main.ml", "a.ml", "b.ml"]
+        );
+
+        std::fs::write(dir.join("sub/b.ml"), "import '../main.ml';\n").unwrap();
+
+        assert!(matches!(
+            parse_file(dir.join("main.ml")),
+            Err(ControlFlow::Fatal(ParseError::ImportCycle(_)))
+        ));
+
+        // included once per mod.
+        std::fs::write(
+            dir.join("main.ml"),
+            "import 'sub/b.ml'; mod m { import 'sub/b.ml'; import 'sub/b.ml'; }",
+        )
+        .unwrap();
+        std::fs::write(dir.join("sub/b.ml"), "data C(bool);\n").unwrap();
+
+        let stats = parse_file(dir.join("main.ml")).unwrap();
+
+        assert_eq!(stats.len(), 4);
+        assert!(matches!(&stats[0], Stat::Data(node) if node.ident.1 == "C"));
+        assert!(matches!(&stats[2], Stat::Data(node) if node.ident.1 == "C"));
+
+        // a source without a file has no base to resolve imports.
+        let mut ctx = ParseContext::from("data A(int);\nimport 'sub/b.ml';");
+
+        assert_eq!(
+            parse(&mut ctx),
+            Err(ControlFlow::Fatal(ParseError::Import(ImportKind::NoFile)))
+        );
+        assert_eq!(ctx.span().lines, 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 
-    Ok(opcodes)
+    #[test]
+    fn test_source_map() {
+        let dir = std::env::temp_dir().join(format!("mlang-sources-{}", std::process::id()));
+
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("main.ml"), "import 'a.ml';\ndata B(string);\n").unwrap();
+        std::fs::write(dir.join("a.ml"), "data A(int);\ndata C(;\n").unwrap();
+
+        let mut sources = SourceMap::default();
+
+        assert!(parse_file_with_sources(dir.join("main.ml"), &mut sources).is_err());
+
+        let (file, span) = sources.lookup(sources.failed().unwrap()).unwrap();
+
+        assert!(file.path.ends_with("a.ml"));
+        assert_eq!(span.lines, 2);
+
+        // the cols of the first line of an imported file don't count the files before it.
+        std::fs::write(dir.join("a.ml"), "data C(;\n").unwrap();
+
+        let mut sources = SourceMap::default();
+
+        assert!(parse_file_with_sources(dir.join("main.ml"), &mut sources).is_err());
+
+        let (file, span) = sources.lookup(sources.failed().unwrap()).unwrap();
+
+        assert!(file.path.ends_with("a.ml"));
+        assert_eq!((span.lines, span.cols), (1, span.offset + 1));
+        assert_eq!(span.offset + file.start, sources.failed().unwrap().offset);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
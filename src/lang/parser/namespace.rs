@@ -0,0 +1,41 @@
+use parserc::{
+    FromSrc, IntoParser, ParseContext, Parser, ParserExt, Result, ensure_char, ensure_keyword,
+};
+
+use crate::lang::ir::{Ident, Mod};
+
+use super::{
+    ModKind, ParseError,
+    utils::{parse_prefix, skip_ws},
+};
+
+impl FromSrc for Mod {
+    type Error = ParseError;
+    fn parse(ctx: &mut ParseContext<'_>) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let (comments, properties) = parse_prefix(ctx)?;
+
+        let start = ensure_keyword("mod").parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let ident = Ident::into_parser()
+            .fatal(ParseError::Mod(ModKind::Ident))
+            .parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let end = ensure_char('{')
+            .fatal(ParseError::Mod(ModKind::BodyStart))
+            .parse(ctx)?;
+
+        Ok(Self {
+            span: start.extend_to_inclusive(end),
+            comments,
+            properties,
+            ident,
+        })
+    }
+}
@@ -6,7 +6,7 @@ use crate::lang::{
     ir::{Enum, Fields, Ident, Node, Stat},
     parser::{
         EnumKind, NodeKind, ParseError,
-        utils::{parse_prefix, skip_ws},
+        utils::{parse_path, parse_prefix, skip_ws},
     },
 };
 
@@ -19,7 +19,7 @@ fn parse_node_body(ctx: &mut ParseContext<'_>) -> Result<Node, ParseError> {
 
     let mixin = if let Some(_) = ensure_keyword("mixin").ok().parse(ctx)? {
         skip_ws(ctx)?;
-        let mixin = Ident::into_parser()
+        let mixin = parse_path
             .fatal(ParseError::Node(NodeKind::MixinIdent))
             .parse(ctx)?;
 
@@ -1,10 +1,82 @@
-use parserc::{FromSrc, IntoParser, Parser, ParserExt, ensure_char, ensure_keyword};
+use parserc::{FromSrc, IntoParser, Parser, ParserExt, ensure_char, ensure_keyword, take_while};
 
 use crate::lang::{
     ir::{CallExpr, Ident, LitStr, Property},
-    parser::{CallKind, ParseError, PropKind, utils::skip_ws},
+    parser::{
+        CallKind, ParseError, PropKind,
+        utils::{parse_path, skip_ws},
+    },
 };
 
+/// Parse a comma-separated call param list.
+fn parse_params(ctx: &mut parserc::ParseContext<'_>) -> parserc::Result<Vec<LitStr>, ParseError> {
+    let mut params = vec![];
+
+    while let Some(param) = parse_param.ok().parse(ctx)? {
+        params.push(param);
+
+        skip_ws(ctx)?;
+
+        if ensure_char(',').ok().parse(ctx)?.is_none() {
+            break;
+        }
+
+        skip_ws(ctx)?;
+    }
+
+    Ok(params)
+}
+
+/// Parse one call param: a string literal, an unsigned integer or a path.
+///
+/// A path param, e.g. `derive(Hash)`, may be followed by `= "value"` or a nested param list,
+/// e.g. `cfg(all(unix, feature = "text"))`, and is kept as its normalized source text.
+fn parse_param(ctx: &mut parserc::ParseContext<'_>) -> parserc::Result<LitStr, ParseError> {
+    if let Some(lit) = LitStr::into_parser().ok().parse(ctx)? {
+        return Ok(lit);
+    }
+
+    if let Some(span) = take_while(|c| c.is_ascii_digit()).parse(ctx)? {
+        return Ok(LitStr(span, ctx.as_str(span).to_string()));
+    }
+
+    let Ident(mut span, mut text) = parse_path(ctx)?;
+
+    skip_ws(ctx)?;
+
+    if ensure_char('=').ok().parse(ctx)?.is_some() {
+        skip_ws(ctx)?;
+
+        let value = LitStr::into_parser()
+            .fatal(ParseError::Call(CallKind::Value))
+            .parse(ctx)?;
+
+        span = span.extend_to_inclusive(value.0);
+        text = format!("{} = {:?}", text, value.1);
+    } else if ensure_char('(').ok().parse(ctx)?.is_some() {
+        skip_ws(ctx)?;
+
+        let params = parse_params(ctx)?;
+
+        let end = ensure_char(')')
+            .fatal(ParseError::Call(CallKind::ParamEnd))
+            .parse(ctx)?;
+
+        span = span.extend_to_inclusive(end);
+        text = format!(
+            "{}({})",
+            text,
+            params
+                .iter()
+                .map(|param| param.1.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    Ok(LitStr(span, text))
+}
+
 impl FromSrc for CallExpr {
     type Error = ParseError;
     fn parse(ctx: &mut parserc::ParseContext<'_>) -> parserc::Result<Self, Self::Error>
@@ -21,17 +93,7 @@ impl FromSrc for CallExpr {
         let end = if let Some(_) = ensure_char('(').ok().parse(ctx)? {
             skip_ws(ctx)?;
 
-            while let Some(call) = LitStr::into_parser().ok().parse(ctx)? {
-                params.push(call);
-
-                skip_ws(ctx)?;
-
-                if ensure_char(',').ok().parse(ctx)?.is_none() {
-                    break;
-                }
-
-                skip_ws(ctx)?;
-            }
+            params = parse_params(ctx)?;
 
             ensure_char(')')
                 .fatal(ParseError::Call(CallKind::ParamEnd))
@@ -130,4 +192,47 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_path_params() {
+        let prop = Property::parse(&mut ParseContext::from(
+            "#[derive(Hash, serde::Serialize), rust_attr('non_exhaustive')]",
+        ))
+        .unwrap();
+
+        let params = prop
+            .calls
+            .iter()
+            .map(|call| call.params.iter().map(|p| p.1.as_str()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            params,
+            vec![vec!["Hash", "serde::Serialize"], vec!["non_exhaustive"]]
+        );
+
+        let prop = Property::parse(&mut ParseContext::from(
+            "#[cfg(all(unix, not(feature='text')), feature = \"serde\")]",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            prop.calls[0]
+                .params
+                .iter()
+                .map(|p| p.1.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                r#"all(unix, not(feature = "text"))"#,
+                r#"feature = "serde""#
+            ]
+        );
+
+        let prop = Property::parse(&mut ParseContext::from("#[id(42)]")).unwrap();
+
+        assert_eq!(
+            prop.calls[0].params,
+            vec![LitStr(Span::new(5, 2, 1, 6), "42".to_string())]
+        );
+    }
 }
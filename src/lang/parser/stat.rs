@@ -1,7 +1,7 @@
 use parserc::{ControlFlow, FromSrc, IntoParser, Parser, ParserExt};
 
 use crate::lang::{
-    ir::{ApplyTo, ChildrenOf, Enum, Group, Stat},
+    ir::{ApplyTo, ChildrenOf, Enum, Group, Meta, Stat, Version},
     parser::{node::parse_node, utils::skip_ws},
 };
 
@@ -13,6 +13,14 @@ impl FromSrc for Stat {
     where
         Self: Sized,
     {
+        if let Some(version) = Version::into_parser().ok().parse(ctx)? {
+            return Ok(Stat::Version(Box::new(version)));
+        }
+
+        if let Some(meta) = Meta::into_parser().ok().parse(ctx)? {
+            return Ok(Stat::Meta(Box::new(meta)));
+        }
+
         if let Some(opcode) = Enum::into_parser()
             .map(|v| Stat::Enum(Box::new(v)))
             .ok()
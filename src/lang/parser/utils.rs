@@ -41,6 +41,20 @@ impl FromSrc for Ident {
     }
 }
 
+/// Parse a symbol reference: `ident` or a namespace qualified `ident::ident...`.
+pub(super) fn parse_path(ctx: &mut ParseContext<'_>) -> Result<Ident, ParseError> {
+    let mut path = Ident::parse(ctx)?;
+
+    while ensure_keyword("::").ok().parse(ctx)?.is_some() {
+        let ident = Ident::into_parser().fatal(ParseError::Ident).parse(ctx)?;
+
+        path.0 = path.0.extend_to_inclusive(ident.0);
+        path.1 = format!("{}::{}", path.1, ident.1);
+    }
+
+    Ok(path)
+}
+
 pub(super) fn skip_ws(ctx: &mut ParseContext<'_>) -> Result<Option<Span>, ParseError> {
     let span = take_while(|c| c.is_whitespace()).parse(ctx)?;
 
@@ -78,17 +92,31 @@ impl FromSrc for Type {
     {
         let ty = ensure_keyword("bool")
             .map(|span| Type::Bool(span))
+            .or(ensure_keyword("char").map(Type::Char))
             .or(ensure_keyword("string").map(|span| Type::String(span)))
+            // `bytes` must be tried before its prefix `byte`.
+            .or(ensure_keyword("bytes").map(Type::Bytes))
             .or(ensure_keyword("byte").map(|span| Type::Byte(span)))
             .or(ensure_keyword("ubyte").map(|span| Type::Ubyte(span)))
             .or(ensure_keyword("short").map(|span| Type::Short(span)))
             .or(ensure_keyword("ushort").map(|span| Type::Ushort(span)))
+            // `int128` and `uint128` must be tried before their prefixes `int` and `uint`.
+            .or(ensure_keyword("int128").map(Type::Int128))
             .or(ensure_keyword("int").map(|span| Type::Int(span)))
+            .or(ensure_keyword("uint128").map(Type::Uint128))
             .or(ensure_keyword("uint").map(|span| Type::Uint(span)))
             .or(ensure_keyword("long").map(|span| Type::Long(span)))
             .or(ensure_keyword("ulong").map(|span| Type::Ulong(span)))
             .or(ensure_keyword("float").map(|span| Type::Float(span)))
             .or(ensure_keyword("double").map(|span| Type::Double(span)))
+            .or(ensure_keyword("pathdata").map(Type::PathData))
+            .or(ensure_keyword("transform").map(Type::Transform))
+            .or(ensure_keyword("rect").map(Type::Rect))
+            .or(ensure_keyword("viewbox").map(Type::ViewBox))
+            .or(ensure_keyword("duration").map(Type::Duration))
+            .or(ensure_keyword("datetime").map(Type::DateTime))
+            .or(ensure_keyword("uuid").map(Type::Uuid))
+            .or(ensure_keyword("iri").map(Type::Iri))
             .ok()
             .parse(input)?;
 
@@ -96,6 +124,44 @@ impl FromSrc for Type {
             return Ok(ty);
         }
 
+        if let Some(start) = ensure_keyword("decimal").ok().parse(input)? {
+            skip_ws(input)?;
+
+            ensure_char('(')
+                .fatal(ParseError::Type(super::TypeKind::Decimal))
+                .parse(input)?;
+
+            skip_ws(input)?;
+
+            let precision = LitUint::into_parser()
+                .fatal(ParseError::Type(super::TypeKind::Decimal))
+                .parse(input)?;
+
+            skip_ws(input)?;
+
+            ensure_char(',')
+                .fatal(ParseError::Type(super::TypeKind::Decimal))
+                .parse(input)?;
+
+            skip_ws(input)?;
+
+            let scale = LitUint::into_parser()
+                .fatal(ParseError::Type(super::TypeKind::Decimal))
+                .parse(input)?;
+
+            skip_ws(input)?;
+
+            let end = ensure_char(')')
+                .fatal(ParseError::Type(super::TypeKind::Decimal))
+                .parse(input)?;
+
+            return Ok(Type::Decimal(
+                precision,
+                scale,
+                start.extend_to_inclusive(end),
+            ));
+        }
+
         if let Some(start) = ensure_keyword("vec").ok().parse(input)? {
             skip_ws(input)?;
 
@@ -126,6 +192,26 @@ impl FromSrc for Type {
 
             skip_ws(input)?;
 
+            if ensure_keyword("->").ok().parse(input)?.is_some() {
+                skip_ws(input)?;
+
+                let value = Type::into_parser()
+                    .fatal(ParseError::Type(super::TypeKind::MapValue))
+                    .parse(input)?;
+
+                skip_ws(input)?;
+
+                let end = ensure_char(']')
+                    .fatal(ParseError::Type(super::TypeKind::SquareBracketEnd))
+                    .parse(input)?;
+
+                return Ok(Type::MapOf(
+                    Box::new(component),
+                    Box::new(value),
+                    start.extend_to_inclusive(end),
+                ));
+            }
+
             ensure_char(';')
                 .fatal(ParseError::Type(super::TypeKind::Semicolons))
                 .parse(input)?;
@@ -149,10 +235,32 @@ impl FromSrc for Type {
             ));
         }
 
+        if let Some(start) = ensure_char('(').ok().parse(input)? {
+            let mut components = vec![];
+
+            skip_ws(input)?;
+
+            while let Some(component) = Type::into_parser().ok().parse(input)? {
+                components.push(component);
+
+                skip_ws(input)?;
+
+                if ensure_char(',').ok().parse(input)?.is_none() {
+                    break;
+                }
+
+                skip_ws(input)?;
+            }
+
+            let end = ensure_char(')')
+                .fatal(ParseError::Type(super::TypeKind::ParenEnd))
+                .parse(input)?;
+
+            return Ok(Type::Tuple(components, start.extend_to_inclusive(end)));
+        }
+
         // try parse as ident at last.
-        Ident::into_parser()
-            .parse(input)
-            .map(|ident| Type::Data(ident))
+        parse_path(input).map(|ident| Type::Data(ident))
     }
 }
 
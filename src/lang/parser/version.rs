@@ -0,0 +1,39 @@
+use parserc::{
+    FromSrc, IntoParser, ParseContext, Parser, ParserExt, Result, ensure_char, ensure_keyword,
+};
+
+use crate::lang::ir::{LitStr, Version};
+
+use super::{
+    ParseError, VersionKind,
+    utils::{parse_prefix, skip_ws},
+};
+
+impl FromSrc for Version {
+    type Error = ParseError;
+    fn parse(ctx: &mut ParseContext<'_>) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        parse_prefix(ctx)?;
+
+        let start = ensure_keyword("version").parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let version = LitStr::into_parser()
+            .fatal(ParseError::Version(VersionKind::Lit))
+            .parse(ctx)?;
+
+        skip_ws(ctx)?;
+
+        let end = ensure_char(';')
+            .fatal(ParseError::Version(VersionKind::End))
+            .parse(ctx)?;
+
+        Ok(Self {
+            span: start.extend_to_inclusive(end),
+            version,
+        })
+    }
+}
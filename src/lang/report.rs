@@ -0,0 +1,267 @@
+//! Terminal rendering of compile errors and warnings.
+
+use std::{fmt::Display, io::IsTerminal, path::Path};
+
+use parserc::Span;
+
+use crate::lang::analyzer::Diagnostic;
+
+/// Render a diagnostic `message` at `span`, with the offending line of `source` and a caret under the span:
+///
+/// ```text
+/// error: Unknown symbol `Fill`.
+///  --> 3:17
+///   |
+/// 3 | el Rect { fill: Fill }
+///   |                 ^^^^
+/// ```
+///
+/// Only the position is rendered if `source` is `None`.
+pub fn render(
+    level: log::Level,
+    message: impl Display,
+    span: Span,
+    source: Option<&str>,
+) -> String {
+    render_with_color(level, message, span, source, None, false)
+}
+
+/// Render a diagnostic like [`render`], with the `path` of the file that contains `source`:
+///
+/// ```text
+/// error: Unknown symbol `Fill`.
+///  --> shapes.ml:3:17
+///   |
+/// 3 | el Rect { fill: Fill }
+///   |                 ^^^^
+/// ```
+pub fn render_file(
+    level: log::Level,
+    message: impl Display,
+    span: Span,
+    path: &Path,
+    source: &str,
+) -> String {
+    render_with_color(level, message, span, Some(source), Some(path), false)
+}
+
+/// Print the rendered diagnostic to stderr, colored if stderr is a terminal.
+pub fn eprint(level: log::Level, message: impl Display, span: Span, source: Option<&str>) {
+    eprintln!(
+        "{}\n",
+        render_with_color(
+            level,
+            message,
+            span,
+            source,
+            None,
+            std::io::stderr().is_terminal()
+        )
+    );
+}
+
+/// Print the diagnostic rendered by [`render_file`] to stderr, colored if stderr is a terminal.
+pub fn eprint_file(
+    level: log::Level,
+    message: impl Display,
+    span: Span,
+    path: &Path,
+    source: &str,
+) {
+    eprintln!(
+        "{}\n",
+        render_with_color(
+            level,
+            message,
+            span,
+            Some(source),
+            Some(path),
+            std::io::stderr().is_terminal()
+        )
+    );
+}
+
+fn render_with_color(
+    level: log::Level,
+    message: impl Display,
+    span: Span,
+    source: Option<&str>,
+    path: Option<&Path>,
+    color: bool,
+) -> String {
+    let file = path.map_or(String::new(), |path| format!("{}:", path.display()));
+
+    let (label, code) = match level {
+        log::Level::Error => ("error", "\x1b[1;31m"),
+        log::Level::Warn => ("warning", "\x1b[1;33m"),
+        _ => ("note", "\x1b[1;36m"),
+    };
+
+    let paint = |text: &str, code: &str| {
+        if color {
+            format!("{}{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let Some(source) = source.filter(|source| span.offset <= source.len()) else {
+        return format!(
+            "{}: {}\n {} {}{}:{}",
+            paint(label, code),
+            message,
+            paint("-->", "\x1b[1;34m"),
+            file,
+            span.lines,
+            span.cols
+        );
+    };
+
+    let start = source[..span.offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = source[span.offset..]
+        .find('\n')
+        .map_or(source.len(), |i| span.offset + i);
+
+    let line = source[..start].matches('\n').count() + 1;
+    let col = source[start..span.offset].chars().count();
+    // spans over several lines are cut at the end of the first line.
+    let span_end = (span.offset + span.len()).clamp(span.offset, end);
+    let len = source[span.offset..span_end].chars().count().max(1);
+
+    let gutter = " ".repeat(line.to_string().len());
+    let bar = paint("|", "\x1b[1;34m");
+
+    format!(
+        "{}: {}\n{}{} {}{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+        paint(label, code),
+        message,
+        gutter,
+        paint("-->", "\x1b[1;34m"),
+        file,
+        line,
+        col + 1,
+        gutter,
+        bar,
+        paint(&line.to_string(), "\x1b[1;34m"),
+        bar,
+        source[start..end].trim_end_matches('\r'),
+        gutter,
+        bar,
+        " ".repeat(col),
+        paint(&"^".repeat(len), code),
+    )
+}
+
+/// Render `diagnostics` as a json array, for tools that consume compile results:
+///
+/// ```text
+/// [{"level":"warning","lint":"orphan-attr","message":"...","line":3,"column":6,"offset":40,"length":4}]
+/// ```
+///
+/// `lint` is `null` for schema errors.
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    let escape = |text: &str| {
+        let mut escaped = String::with_capacity(text.len() + 2);
+
+        escaped.push('"');
+
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+
+        escaped.push('"');
+        escaped
+    };
+
+    let items = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                r#"{{"level":{},"lint":{},"message":{},"line":{},"column":{},"offset":{},"length":{}}}"#,
+                escape(match diagnostic.level {
+                    log::Level::Error => "error",
+                    log::Level::Warn => "warning",
+                    _ => "note",
+                }),
+                diagnostic
+                    .lint
+                    .map_or("null".to_string(), |lint| escape(lint.name())),
+                escape(&diagnostic.error.to_string()),
+                diagnostic.span.lines,
+                diagnostic.span.cols,
+                diagnostic.span.offset,
+                diagnostic.span.len()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::Span;
+
+    use super::{render, render_file, to_json};
+    use crate::lang::analyzer::{AnalyzerError, Diagnostic, Lint};
+
+    #[test]
+    fn test_render() {
+        let source = "data A(int);\nel Rect { fill: Fill }\n";
+
+        assert_eq!(
+            render(
+                log::Level::Error,
+                "Unknown symbol `Fill`.",
+                Span::new(29, 4, 2, 17),
+                Some(source)
+            ),
+            "error: Unknown symbol `Fill`.\n --> 2:17\n  |\n2 | el Rect { fill: Fill }\n  |                 ^^^^"
+        );
+
+        assert_eq!(
+            render(log::Level::Warn, "Unused.", Span::new(0, 0, 1, 1), None),
+            "warning: Unused.\n --> 1:1"
+        );
+
+        assert_eq!(
+            render_file(
+                log::Level::Error,
+                "Unknown symbol `Fill`.",
+                Span::new(29, 4, 2, 17),
+                std::path::Path::new("shapes.ml"),
+                source
+            ),
+            "error: Unknown symbol `Fill`.\n --> shapes.ml:2:17\n  |\n2 | el Rect { fill: Fill }\n  |                 ^^^^"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let diagnostics = [
+            Diagnostic {
+                level: log::Level::Warn,
+                span: Span::new(3, 4, 1, 4),
+                error: AnalyzerError::OrphanAttr("Fill".to_string()),
+                lint: Some(Lint::OrphanAttr),
+            },
+            Diagnostic {
+                level: log::Level::Error,
+                span: Span::new(0, 1, 1, 1),
+                error: AnalyzerError::Unknown("\"A\"".to_string()),
+                lint: None,
+            },
+        ];
+
+        assert_eq!(
+            to_json(&diagnostics),
+            r#"[{"level":"warning","lint":"orphan-attr","message":"Attr `Fill` isn't applied to any element, add it to an `apply ... to` stat.","line":1,"column":4,"offset":3,"length":4},{"level":"error","lint":null,"message":"Unknown symbol `\"A\"`.","line":1,"column":1,"offset":0,"length":1}]"#
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::lang::{
+    ir::{Enum, Field, Node, Stat, Type},
+    rustgen::mapping::{CfgMapping, ComplexTypeMapping, FieldMapping, MappingOptions},
+};
+
+/// Generate the `Result` expr of an arbitrary value of `ty`, which reads from `u`.
+fn gen_type_value(ty: &Type, options: &MappingOptions) -> TokenStream {
+    match ty {
+        Type::Float(_) => quote! { mlang_rs::rt::arbitrary::float(u) },
+        Type::Double(_) => quote! { mlang_rs::rt::arbitrary::double(u) },
+        Type::String(_) if options.cow_string => {
+            quote! { u.arbitrary::<String>().map(std::borrow::Cow::Owned) }
+        }
+        Type::Decimal(precision, scale, _) => {
+            let (precision, scale) = (precision.0 as u32, scale.0 as u32);
+
+            quote! { mlang_rs::rt::arbitrary::decimal(u, #precision, #scale) }
+        }
+        Type::ListOf(component, _) => {
+            let component = gen_type_value(component, options);
+
+            quote! { mlang_rs::rt::arbitrary::vec(u, |u| #component) }
+        }
+        Type::ArrayOf(component, _, _) => {
+            let component = gen_type_value(component, options);
+
+            quote! { mlang_rs::rt::arbitrary::array(u, |u| #component) }
+        }
+        Type::MapOf(key, value, _) => {
+            let key = gen_type_value(key, options);
+            let value = gen_type_value(value, options);
+
+            quote! { mlang_rs::rt::arbitrary::map(u, |u| #key, |u| #value) }
+        }
+        Type::Tuple(components, _) => {
+            let components = components
+                .iter()
+                .map(|component| gen_type_value(component, options));
+
+            quote! { Ok((#(#components?,)*)) }
+        }
+        _ => quote! { u.arbitrary() },
+    }
+}
+
+/// Generate the `Result` expr of an arbitrary value of `field`, wrapped as the `#[variable]`/`#[option]` field type.
+fn gen_field_value(field: &Field<'_>, options: &MappingOptions) -> TokenStream {
+    let mut value = if field.is_external() {
+        quote! { u.arbitrary() }
+    } else {
+        gen_type_value(field.ty(), options)
+    };
+
+    if field.is_variable() {
+        value = quote! { mlang_rs::rt::arbitrary::variable(u, |u| #value) };
+    }
+
+    if field.is_option() {
+        value = quote! { mlang_rs::rt::arbitrary::option(u, |u| #value) };
+    }
+
+    value
+}
+
+fn gen_node_arbitrary(
+    opcode_mod: &TokenStream,
+    options: &MappingOptions,
+    node: &Node,
+) -> TokenStream {
+    let ident = node.to_ident();
+    let cfg = node.to_cfg();
+
+    let fields = node
+        .fields
+        .iter()
+        .map(|field| {
+            let value = gen_field_value(&field, options);
+
+            field.to_init_clause(&quote! { #value? })
+        })
+        .collect::<Vec<_>>();
+
+    // unit-like nodes don't read any data.
+    let unused = if fields.is_empty() {
+        quote! { let _ = u; }
+    } else {
+        quote! {}
+    };
+
+    let body = node.to_struct_body(fields);
+
+    let lifetime = options.to_lifetime_params(&node.ident.1);
+
+    quote! {
+        #cfg
+        impl<'a> mlang_rs::rt::arbitrary::Arbitrary<'a> for #opcode_mod #ident #lifetime {
+            fn arbitrary(u: &mut mlang_rs::rt::arbitrary::Unstructured<'a>) -> mlang_rs::rt::arbitrary::Result<Self> {
+                #unused
+
+                Ok(#opcode_mod #ident #body)
+            }
+        }
+    }
+}
+
+fn gen_enum_arbitrary(
+    opcode_mod: &TokenStream,
+    options: &MappingOptions,
+    node: &Enum,
+) -> TokenStream {
+    let ident = node.to_ident();
+    let cfg = node.to_cfg();
+    let count = node.fields.len();
+
+    // the variant with the fewest fields is picked once the data is exhausted, so recursive types terminate.
+    let fallback = node
+        .fields
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, variant)| variant.fields.iter().count())
+        .map_or(0, |(index, _)| index);
+
+    let clauses = node.fields.iter().enumerate().map(|(index, variant)| {
+        let fields = variant
+            .fields
+            .iter()
+            .map(|field| {
+                let value = gen_field_value(&field, options);
+                let mut value = quote! { #value? };
+
+                if variant.is_boxed() {
+                    value = quote! { Box::new(#value) };
+                }
+
+                field.to_init_clause(&value)
+            })
+            .collect::<Vec<_>>();
+
+        let variant_ident = variant.to_ident();
+        let body = variant.to_struct_body(fields);
+
+        quote! {
+            #index => #opcode_mod #ident::#variant_ident #body
+        }
+    });
+
+    let lifetime = options.to_lifetime_params(&node.ident.1);
+
+    quote! {
+        #cfg
+        impl<'a> mlang_rs::rt::arbitrary::Arbitrary<'a> for #opcode_mod #ident #lifetime {
+            fn arbitrary(u: &mut mlang_rs::rt::arbitrary::Unstructured<'a>) -> mlang_rs::rt::arbitrary::Result<Self> {
+                let index = if u.is_empty() { #fallback } else { u.choose_index(#count)? };
+
+                Ok(match index {
+                    #(#clauses,)*
+                    _ => unreachable!("variant index out of range."),
+                })
+            }
+        }
+    }
+}
+
+/// Generate `Arbitrary` impls of the types generated from [`stats`](Stat), which produce schema-valid values.
+///
+/// The generated module requires the `arbitrary` feature of `mlang-rs`.
+pub fn gen_arbitrary_mod(
+    stats: impl AsRef<[Stat]>,
+    opcode_mod: impl AsRef<str>,
+    options: &MappingOptions,
+) -> TokenStream {
+    let opcode_mod: TokenStream = opcode_mod.as_ref().parse().unwrap();
+
+    // borrowed fields of arbitrary values borrow from the data of `Unstructured<'a>`.
+    let options = &options.with_borrows(stats.as_ref()).with_lifetime("'a");
+
+    let mut impls = vec![];
+
+    for stat in stats.as_ref() {
+        match stat {
+            Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                impls.push(gen_node_arbitrary(&opcode_mod, options, node));
+            }
+            Stat::Enum(node) => {
+                impls.push(gen_enum_arbitrary(&opcode_mod, options, node));
+            }
+            _ => {}
+        }
+    }
+
+    quote! {
+        #(#impls)*
+    }
+}
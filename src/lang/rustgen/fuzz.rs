@@ -0,0 +1,224 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::lang::{
+    ir::Stat,
+    rustgen::mapping::{CfgMapping, ComplexTypeMapping, MappingOptions},
+};
+
+/// Returns the rust path of the generated `name` module in the schema crate, which is imported as `schema`.
+fn to_mod_path(module: &str, name: &str) -> TokenStream {
+    let module = module.trim_matches(':');
+
+    if module.is_empty() {
+        format!("schema::{}", name)
+    } else {
+        format!("schema::{}::{}", module, name)
+    }
+    .parse()
+    .expect("generated module path")
+}
+
+/// Generate the `fragment` target, which decodes random bytes as a [`Fragment`](crate::rt::fragment::Fragment)
+/// and verifies it against the schema fingerprint.
+fn gen_fragment_target(module: &str) -> TokenStream {
+    let opcode_mod = to_mod_path(module, "opcode");
+
+    quote! {
+        #![no_main]
+
+        use libfuzzer_sys::fuzz_target;
+        use mlang_rs::rt::fragment::Fragment;
+
+        fuzz_target!(|data: &[u8]| {
+            if let Ok(fragment) = Fragment::decode(data) {
+                let _ = fragment.verify(&#opcode_mod::SCHEMA_FINGERPRINT);
+
+                if let Ok(bytes) = fragment.encode() {
+                    assert_eq!(Fragment::decode(&bytes), Ok(fragment));
+                }
+            }
+        });
+    }
+}
+
+/// The formats of `rt::serde`, and whether they need the display names of the `serde` module.
+const FORMATS: [(&str, bool); 4] = [
+    ("json", true),
+    ("xml", true),
+    ("sexpr", true),
+    ("binary", false),
+];
+
+/// Generate the target of `format`, which decodes random input as a document of the vocabulary,
+/// and checks that the document it is written back as can be decoded again.
+fn gen_format_target(module: &str, format: &str) -> TokenStream {
+    let opcode_mod = to_mod_path(module, "opcode");
+    let format = format_ident!("{}", format);
+
+    let (input, decode, encode) = if format == "binary" {
+        (quote! {}, quote! { from_bytes }, quote! { to_bytes })
+    } else {
+        (
+            quote! {
+                let Ok(data) = std::str::from_utf8(data) else {
+                    return;
+                };
+            },
+            quote! { from_str },
+            quote! { to_string },
+        )
+    };
+
+    quote! {
+        #![no_main]
+
+        use libfuzzer_sys::fuzz_target;
+        use mlang_rs::rt::serde::#format;
+
+        fuzz_target!(|data: &[u8]| {
+            #input
+
+            let Ok(opcodes) = #format::#decode::<#opcode_mod::Opcode>(data) else {
+                return;
+            };
+
+            if let Ok(output) = #format::#encode(&opcodes) {
+                #format::#decode::<#opcode_mod::Opcode>(&output).unwrap();
+            }
+        });
+    }
+}
+
+/// Generate the `from_str` target, which parses random text as every unit-like enum and as a schema version.
+///
+/// Returns `None` if the schema has neither unit-like enums nor a version.
+fn gen_from_str_target(stats: &[Stat], module: &str) -> Option<TokenStream> {
+    let opcode_mod = to_mod_path(module, "opcode");
+    let serde_mod = to_mod_path(module, "serde");
+
+    let mut clauses = vec![];
+
+    for stat in stats {
+        match stat {
+            Stat::Enum(node) if node.is_unit_like() && !node.fields.is_empty() => {
+                let ident = node.to_ident();
+                let cfg = node.to_cfg();
+
+                clauses.push(quote! {
+                    #cfg
+                    if let Ok(value) = text.parse::<#opcode_mod::#ident>() {
+                        assert_eq!(value.to_string(), text);
+                    }
+                });
+            }
+            Stat::Version(_) => {
+                clauses.push(quote! {
+                    let _ = #serde_mod::check_schema_version(text);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if clauses.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        #![no_main]
+
+        use libfuzzer_sys::fuzz_target;
+
+        fuzz_target!(|data: &[u8]| {
+            let Ok(text) = std::str::from_utf8(data) else {
+                return;
+            };
+
+            #(#clauses)*
+        });
+    })
+}
+
+/// Generate the cargo-fuzz targets of the schema crate `module`, the rust path of the generated modules
+/// in the schema crate, e.g. `gen`.
+///
+/// The format targets, `json`, `xml`, `sexpr` and `binary`, require the `serde` module, and all of them but `binary`
+/// and the `from_str` target require its display names, see [`strip_names`](super::CodeGen::strip_names).
+pub fn gen_fuzz_targets(
+    stats: impl AsRef<[Stat]>,
+    module: impl AsRef<str>,
+    options: &MappingOptions,
+    with_serde: bool,
+) -> Vec<(&'static str, TokenStream)> {
+    let (stats, module) = (stats.as_ref(), module.as_ref());
+
+    let mut targets = vec![("fragment", gen_fragment_target(module))];
+
+    if !with_serde {
+        return targets;
+    }
+
+    for (format, names) in FORMATS {
+        if !names || !options.strip_names {
+            targets.push((format, gen_format_target(module, format)));
+        }
+    }
+
+    if !options.strip_names {
+        targets.extend(gen_from_str_target(stats, module).map(|target| ("from_str", target)));
+    }
+
+    targets
+}
+
+/// Generate the `Cargo.toml` of the fuzz crate, which depends on the schema crate `package` in its parent directory,
+/// with its `features` enabled.
+pub fn gen_fuzz_manifest(package: impl AsRef<str>, features: &[&str], targets: &[&str]) -> String {
+    let package = package.as_ref();
+
+    let features = if features.is_empty() {
+        String::new()
+    } else {
+        format!(", features = {:?}", features)
+    };
+
+    let mut manifest = format!(
+        r#"# This crate is automatically generated by the ml compiler, do not modify it manually.
+
+[package]
+edition = "2024"
+name = "{package}-fuzz"
+publish = false
+version = "0.0.0"
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+libfuzzer-sys = "0.4"
+mlang-rs = "{version}"
+schema = {{path = "..", package = "{package}"{features}}}
+
+# not a member of the schema crate's workspace.
+[workspace]
+members = ["."]
+"#,
+        version = env!("CARGO_PKG_VERSION"),
+    );
+
+    for target in targets {
+        manifest.push_str(&format!(
+            r#"
+[[bin]]
+bench = false
+doc = false
+name = "{target}"
+path = "fuzz_targets/{target}.rs"
+test = false
+"#
+        ));
+    }
+
+    manifest
+}
@@ -0,0 +1,128 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::lang::{
+    ir::{Enum, Node, Stat},
+    rustgen::mapping::{CfgMapping, ComplexTypeMapping, MappingOptions, VariantMapping},
+};
+
+fn gen_node_layout(opcode_mod: &TokenStream, options: &MappingOptions, node: &Node) -> TokenStream {
+    let ident = node.to_ident();
+    let name = ident.to_string().replace(' ', "");
+    let cfg = node.to_cfg();
+    let lifetime = options.to_lifetime_params(&node.ident.1);
+
+    quote! {
+        #cfg
+        mlang_rs::rt::layout::TypeLayout {
+            name: #name,
+            size: core::mem::size_of::<#opcode_mod #ident #lifetime>(),
+            align: core::mem::align_of::<#opcode_mod #ident #lifetime>(),
+            variants: &[],
+        }
+    }
+}
+
+fn gen_enum_layout(opcode_mod: &TokenStream, options: &MappingOptions, node: &Enum) -> TokenStream {
+    let ident = node.to_ident();
+    let name = ident.to_string().replace(' ', "");
+
+    let variants = node
+        .fields
+        .iter()
+        .map(|variant| {
+            let name = variant.to_ident().to_string();
+
+            let fields = variant
+                .fields
+                .iter()
+                .map(|field| variant.to_field_type_definition(&field, opcode_mod, options))
+                .collect::<Vec<_>>();
+
+            quote! {
+                mlang_rs::rt::layout::VariantLayout {
+                    name: #name,
+                    size: core::mem::size_of::<(#(#fields,)*)>(),
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let cfg = node.to_cfg();
+    let lifetime = options.to_lifetime_params(&node.ident.1);
+
+    quote! {
+        #cfg
+        mlang_rs::rt::layout::TypeLayout {
+            name: #name,
+            size: core::mem::size_of::<#opcode_mod #ident #lifetime>(),
+            align: core::mem::align_of::<#opcode_mod #ident #lifetime>(),
+            variants: &[#(#variants),*],
+        }
+    }
+}
+
+/// Generate layout report module from [`stats`](Stat).
+pub fn gen_layout_mod(
+    stats: impl AsRef<[Stat]>,
+    opcode_mod: impl AsRef<str>,
+    options: &MappingOptions,
+) -> TokenStream {
+    let opcode_mod: TokenStream = opcode_mod.as_ref().parse().unwrap();
+
+    // the sizes of borrowing types don't depend on the lifetime of the input.
+    let options = &options
+        .with_borrows(stats.as_ref())
+        .with_lifetime("'static");
+
+    let mut layouts = vec![];
+
+    for stat in stats.as_ref() {
+        match stat {
+            Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                layouts.push(gen_node_layout(&opcode_mod, options, node));
+            }
+            Stat::Enum(node) => {
+                layouts.push(gen_enum_layout(&opcode_mod, options, node));
+            }
+            _ => {}
+        }
+    }
+
+    for name in ["Data", "Element", "Leaf", "Attr", "Opcode"] {
+        let ident: TokenStream = name.parse().unwrap();
+        let lifetime = options.to_lifetime_params(name);
+
+        layouts.push(quote! {
+            mlang_rs::rt::layout::TypeLayout {
+                name: #name,
+                size: core::mem::size_of::<#opcode_mod #ident #lifetime>(),
+                align: core::mem::align_of::<#opcode_mod #ident #lifetime>(),
+                variants: &[],
+            }
+        });
+    }
+
+    // `std` isn't linked by `no_std` crates, but tests always have it.
+    let extern_std = if options.no_std {
+        quote! { extern crate std; }
+    } else {
+        quote! {}
+    };
+
+    let prelude = options.to_alloc_prelude();
+
+    quote! {
+        #prelude
+
+        /// `size_of`/`align_of` of every generated type.
+        pub const LAYOUTS: &[mlang_rs::rt::layout::TypeLayout] = &[#(#layouts),*];
+
+        /// Print the layout report, run with `cargo test layout_report -- --nocapture`.
+        #[test]
+        fn layout_report() {
+            #extern_std
+            std::println!("{}", mlang_rs::rt::layout::report(LAYOUTS, 200));
+        }
+    }
+}
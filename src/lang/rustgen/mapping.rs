@@ -1,10 +1,12 @@
 //! This module defines `trait`s and `fn`s that help map `mlang` types to `rust` types.
 
+use std::collections::BTreeSet;
+
 use heck::{ToSnakeCase, ToUpperCamelCase};
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::lang::ir::{Comment, Enum, Field, Ident, Node, Type};
+use crate::lang::ir::{Comment, Enum, Field, Ident, Lit, Mod, Node, Stat, Type};
 
 /// A trait to help mapping [`Ident`] to rust type/field ident.
 pub trait CommentMapping {
@@ -14,7 +16,29 @@ pub trait CommentMapping {
 
 impl CommentMapping for Comment {
     fn to_comment(&self) -> TokenStream {
-        format!("/// {0}", self.1).parse().expect("to_comment")
+        let doc = format!(" {}", self.1);
+
+        quote! { #[doc = #doc] }
+    }
+}
+
+/// Strict and reserved keywords of rust 2024, that must be escaped as raw identifiers.
+///
+/// `self`, `super`, `crate` and `Self` can't be raw identifiers, the analyzer rejects them.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Escape `ident` with `r#` if it is a rust keyword.
+fn escape_keyword(ident: String) -> String {
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{}", ident)
+    } else {
+        ident
     }
 }
 
@@ -24,33 +48,236 @@ pub trait IdentMapping {
     fn to_field_name(&self) -> TokenStream;
 
     /// Convert [`Ident`] to rust type(struct, enum, enum field) name: xMinYMin => XMinYMin.
+    ///
+    /// Namespace qualified idents are converted to paths: geometry::rect => geometry::Rect.
     fn to_type_name(&self) -> TokenStream;
+
+    /// Convert [`Ident`] to rust opcode variant name: geometry::rect => GeometryRect.
+    fn to_variant_name(&self) -> TokenStream;
 }
 
 impl IdentMapping for Ident {
     fn to_field_name(&self) -> TokenStream {
-        match self.1.to_snake_case().as_str() {
-            "type" => "r#type".parse().expect("to_field_name"),
-            "in" => "r#in".parse().expect("to_field_name"),
-            "for" => "r#for".parse().expect("to_field_name"),
-            ident => ident.parse().expect("to_field_name"),
-        }
+        escape_keyword(self.1.to_snake_case())
+            .parse()
+            .expect("to_field_name")
     }
 
     fn to_type_name(&self) -> TokenStream {
-        self.1.to_upper_camel_case().parse().expect("to_type_name")
+        let mut segments = self.1.split("::").collect::<Vec<_>>();
+
+        let name = segments.pop().unwrap().to_upper_camel_case();
+
+        segments
+            .into_iter()
+            .map(|segment| escape_keyword(segment.to_snake_case()))
+            .chain(Some(name))
+            .collect::<Vec<_>>()
+            .join("::")
+            .parse()
+            .expect("to_type_name")
+    }
+
+    fn to_variant_name(&self) -> TokenStream {
+        self.1
+            .to_upper_camel_case()
+            .parse()
+            .expect("to_variant_name")
+    }
+}
+
+/// Options to customize how `mlang` types are mapped to rust types.
+#[derive(Debug, Default, Clone)]
+pub struct MappingOptions {
+    /// Map `string` to `Cow<'static, str>` instead of `String`.
+    pub cow_string: bool,
+    /// Derive `schemars::JsonSchema` for generated types, behind the `schemars` feature of the generated crate.
+    pub json_schema: bool,
+    /// Traits derived by generated types besides the builtin ones, a `None` kind applies to all types.
+    pub extra_derives: Vec<(Option<TypeKind>, String)>,
+    /// Derive `Eq` and `Ord` for unit-like enums, variants are ordered by declaration.
+    pub ordered_enums: bool,
+    /// Don't generate display names in the `serde` module, only the `type_id` based paths of binary formats work.
+    pub strip_names: bool,
+    /// Generate code for `#![no_std]` crates, which only depends on `core` and `alloc`.
+    pub no_std: bool,
+    /// The generated types that borrow from the deserializer input, see [`with_borrows`](Self::with_borrows).
+    pub(crate) borrows: BTreeSet<String>,
+    /// The lifetime of borrowed data, see [`to_lifetime`](Self::to_lifetime).
+    pub(crate) lifetime: Option<&'static str>,
+}
+
+/// Kinds of the types generated from schema declarations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+    /// `el` declarations.
+    Element,
+    /// `leaf` declarations.
+    Leaf,
+    /// `attr` declarations.
+    Attr,
+    /// `data` declarations.
+    Data,
+    /// `enum` declarations.
+    Enum,
+}
+
+impl MappingOptions {
+    /// Returns the [`extra_derives`](Self::extra_derives) of the `kind` types.
+    pub fn extra_derives_of(&self, kind: TypeKind) -> impl Iterator<Item = &str> {
+        self.extra_derives
+            .iter()
+            .filter(move |(target, _)| target.is_none_or(|target| target == kind))
+            .map(|(_, derive)| derive.as_str())
+    }
+
+    /// Returns the crate of the `alloc` types used by generated code, `alloc` for [`no_std`](Self::no_std) code.
+    pub fn to_alloc_crate(&self) -> TokenStream {
+        if self.no_std {
+            quote! { alloc }
+        } else {
+            quote! { std }
+        }
+    }
+
+    /// Generate the imports of the std prelude items used by generated code, empty if [`no_std`](Self::no_std) is off.
+    pub fn to_alloc_prelude(&self) -> TokenStream {
+        if self.no_std {
+            quote! {
+                #[allow(unused_imports)]
+                use alloc::{
+                    borrow::ToOwned,
+                    boxed::Box,
+                    format,
+                    string::{String, ToString},
+                    vec,
+                    vec::Vec,
+                };
+            }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Returns a copy of these options with the types generated from `stats` that borrow from the deserializer
+    /// input: the types with `#[borrow]` fields or fields of such types, and the `Data`, `Attr`, `Element`, `Leaf`
+    /// and `Opcode` enums of them.
+    pub fn with_borrows(&self, stats: &[Stat]) -> Self {
+        let mut borrows = BTreeSet::new();
+
+        loop {
+            let len = borrows.len();
+
+            for stat in stats {
+                // enums borrow if a field of a variant does.
+                let (ident, shared, nodes) = match stat {
+                    Stat::Element(node) => (&node.ident, "Element", vec![node.as_ref()]),
+                    Stat::Leaf(node) => (&node.ident, "Leaf", vec![node.as_ref()]),
+                    Stat::Attr(node) => (&node.ident, "Attr", vec![node.as_ref()]),
+                    Stat::Data(node) => (&node.ident, "Data", vec![node.as_ref()]),
+                    Stat::Enum(node) => (&node.ident, "Data", node.fields.iter().collect()),
+                    _ => continue,
+                };
+
+                let borrowed = nodes
+                    .iter()
+                    .flat_map(|node| node.fields.iter())
+                    .any(|field| field.is_borrow() || is_borrowed_type(field.ty(), &borrows));
+
+                if borrowed {
+                    borrows.insert(ident.1.clone());
+                    borrows.insert(shared.to_string());
+                }
+            }
+
+            if borrows.len() == len {
+                break;
+            }
+        }
+
+        if ["Attr", "Element", "Leaf"]
+            .iter()
+            .any(|shared| borrows.contains(*shared))
+        {
+            borrows.insert("Opcode".to_string());
+        }
+
+        Self {
+            borrows,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options which maps borrowed data to `lifetime`, e.g. `'de` in deserialize impls.
+    pub fn with_lifetime(&self, lifetime: &'static str) -> Self {
+        Self {
+            lifetime: Some(lifetime),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the lifetime of borrowed data, `'doc` in the definitions of generated types.
+    pub fn to_lifetime(&self) -> TokenStream {
+        self.lifetime
+            .unwrap_or("'doc")
+            .parse()
+            .expect("to_lifetime")
+    }
+
+    /// Returns `true` if the generated type `ident` borrows from the deserializer input.
+    pub fn is_borrowed(&self, ident: &str) -> bool {
+        self.borrows.contains(ident)
+    }
+
+    /// Generate the lifetime parameter of the generated type `ident`, `<'doc>` if it borrows from the deserializer
+    /// input, empty otherwise.
+    pub fn to_lifetime_params(&self, ident: &str) -> TokenStream {
+        if self.is_borrowed(ident) {
+            let lifetime = self.to_lifetime();
+
+            quote! { <#lifetime> }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Generate the `JsonSchema` derive attr of a generated type, empty if [`json_schema`](Self::json_schema) is off.
+    pub fn to_json_schema_derive(&self) -> TokenStream {
+        if self.json_schema {
+            quote! { #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))] }
+        } else {
+            quote! {}
+        }
+    }
+}
+
+/// Returns `true` if values of `ty` contain a type of `borrows`.
+fn is_borrowed_type(ty: &Type, borrows: &BTreeSet<String>) -> bool {
+    match ty {
+        Type::Data(ident) => borrows.contains(&ident.1),
+        Type::ListOf(component, _) | Type::ArrayOf(component, _, _) => {
+            is_borrowed_type(component, borrows)
+        }
+        Type::MapOf(key, value, _) => {
+            is_borrowed_type(key, borrows) || is_borrowed_type(value, borrows)
+        }
+        Type::Tuple(components, _) => components
+            .iter()
+            .any(|component| is_borrowed_type(component, borrows)),
+        _ => false,
     }
 }
 
 /// A trait to help mapping [`Type`] to rust type.
 pub trait TypeMapping {
     /// Convert [`Type`] to rust type definition.
-    fn to_definition(&self, ty_mod: &TokenStream) -> TokenStream;
+    fn to_definition(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream;
 
     /// Convert [`Type`] to rust where clause.
     fn to_from_where_clause(
         &self,
         ty_mod: &TokenStream,
+        options: &MappingOptions,
         sexpr_mod: &TokenStream,
         generic_ty: &TokenStream,
     ) -> TokenStream;
@@ -60,10 +287,19 @@ pub trait TypeMapping {
 }
 
 impl TypeMapping for Type {
-    fn to_definition(&self, ty_mod: &TokenStream) -> TokenStream {
+    fn to_definition(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream {
         match self {
             Type::Bool(_) => quote! {bool},
-            Type::String(_) => quote! {String},
+            Type::Char(_) => quote! {char},
+            Type::String(_) => {
+                if options.cow_string {
+                    let alloc = options.to_alloc_crate();
+
+                    quote! {#alloc::borrow::Cow<'static, str>}
+                } else {
+                    quote! {String}
+                }
+            }
             Type::Byte(_) => quote! {i8},
             Type::Ubyte(_) => quote! {u8},
             Type::Short(_) => quote! {i16},
@@ -72,22 +308,50 @@ impl TypeMapping for Type {
             Type::Uint(_) => quote! {u32},
             Type::Long(_) => quote! {i64},
             Type::Ulong(_) => quote! {u64},
+            Type::Int128(_) => quote! {i128},
+            Type::Uint128(_) => quote! {u128},
             Type::Float(_) => quote! {f32},
             Type::Double(_) => quote! {f64},
+            Type::PathData(_) => quote! {mlang_rs::rt::value::PathData},
+            Type::Transform(_) => quote! {mlang_rs::rt::value::Transform},
+            Type::Rect(_) => quote! {mlang_rs::rt::value::Rect},
+            Type::ViewBox(_) => quote! {mlang_rs::rt::value::ViewBox},
+            Type::Duration(_) => quote! {mlang_rs::rt::value::Duration},
+            Type::DateTime(_) => quote! {mlang_rs::rt::value::DateTime},
+            Type::Uuid(_) => quote! {mlang_rs::rt::value::Uuid},
+            Type::Iri(_) => quote! {mlang_rs::rt::value::Iri},
+            Type::Bytes(_) => quote! {Vec<u8>},
+            Type::Decimal(_, _, _) => quote! {mlang_rs::rt::value::Decimal},
             Type::Data(ident) => {
+                let lifetime = options.to_lifetime_params(&ident.1);
                 let ident = ident.to_type_name();
 
                 let ty_mod = ty_mod;
 
-                quote! { #ty_mod #ident }
+                quote! { #ty_mod #ident #lifetime }
             }
             Type::ListOf(component, _) => {
-                let component = component.to_definition(ty_mod);
+                let component = component.to_definition(ty_mod, options);
 
                 quote! { Vec<#component> }
             }
+            Type::Tuple(components, _) => {
+                let components = components
+                    .iter()
+                    .map(|component| component.to_definition(ty_mod, options));
+
+                quote! { (#(#components,)*) }
+            }
+            Type::MapOf(key, value, _) => {
+                let key = key.to_definition(ty_mod, options);
+                let value = value.to_definition(ty_mod, options);
+
+                let alloc = options.to_alloc_crate();
+
+                quote! { #alloc::collections::BTreeMap<#key, #value> }
+            }
             Type::ArrayOf(component, lit_num, _) => {
-                let component = component.to_definition(ty_mod);
+                let component = component.to_definition(ty_mod, options);
                 let num = lit_num.0;
 
                 quote! { [#component;#num] }
@@ -98,6 +362,7 @@ impl TypeMapping for Type {
     fn to_from_where_clause(
         &self,
         ty_mod: &TokenStream,
+        options: &MappingOptions,
         sexpr_mod: &TokenStream,
         generic_ty: &TokenStream,
     ) -> TokenStream {
@@ -111,7 +376,7 @@ impl TypeMapping for Type {
                 }
             }
             Type::ListOf(component, _) => {
-                let ty = component.to_definition(ty_mod);
+                let ty = component.to_definition(ty_mod, options);
                 let generic_ty = generic_ty;
                 quote! {
                     #generic_ty: #sexpr_mod MapCollect<#ty>
@@ -122,7 +387,7 @@ impl TypeMapping for Type {
                 quote! { #sexpr_mod Number: From<#generic_ty> }
             }
             _ => {
-                let ty = self.to_definition(ty_mod);
+                let ty = self.to_definition(ty_mod, options);
                 let generic_ty = generic_ty;
                 quote! {
                    #ty : From<#generic_ty>
@@ -161,12 +426,31 @@ pub trait FieldMapping: CommentMapping {
     fn to_init_clause(&self, param: &TokenStream) -> TokenStream;
 
     /// Convert field [`Type`] to rust filed type.
-    fn to_type_definition(&self, ty_mod: &TokenStream) -> TokenStream;
+    fn to_type_definition(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream;
+
+    /// Convert field [`Type`] to the rust type of the field value, without the `Variable`/`Option` wrappers:
+    /// `Resource<Vec<u8>>` for `#[external]` fields, `Cow<'doc, str>` or `Cow<'doc, [u8]>` for `#[borrow]` fields.
+    fn to_value_type_definition(
+        &self,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream;
+
+    /// Convert field [`Type`] to the type passed to `deserialize_field`,
+    /// which differs from the field type for `#[raw]`, `#[borrow]`, `bytes` and `decimal` fields.
+    fn to_deserialize_type(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream;
+
+    /// Generate the reference passed to `serialize_field` from the field `value` reference,
+    /// `bytes` fields are wrapped with `rt::serde::ser::Bytes`, `#[precision(n)]` fields with `rt::serde::ser::Precision`.
+    fn to_serialize_value(&self, value: &TokenStream) -> TokenStream;
+
+    /// Generate the rust expr of the field's default value: `1.0f32` or `String::from("a")`.
+    fn to_default_value(&self, options: &MappingOptions) -> Option<TokenStream>;
 
     /// Generate rust field ident for [`Field`]
     fn to_ident(&self) -> Option<TokenStream>;
 
-    /// Generate field definition clause for this field.
+    /// Generate field definition clause for this field, including its doc comments.
     fn to_definition_clause(&self, vis: &TokenStream, ty: &TokenStream) -> TokenStream;
 }
 
@@ -191,6 +475,45 @@ impl<'a> FieldMapping for Field<'a> {
         param
     }
 
+    fn to_default_value(&self, options: &MappingOptions) -> Option<TokenStream> {
+        let mut value = match self.default_value()? {
+            Lit::Bool(value, _) => quote! { #value },
+            Lit::Str(value) if matches!(self.ty(), Type::Char(_)) => {
+                let value = value.1.chars().next().expect("to_default_value");
+
+                quote! { #value }
+            }
+            Lit::Str(value) => {
+                let value = &value.1;
+
+                if options.cow_string || self.is_borrow() {
+                    let alloc = options.to_alloc_crate();
+
+                    quote! { #alloc::borrow::Cow::Borrowed(#value) }
+                } else {
+                    quote! { String::from(#value) }
+                }
+            }
+            Lit::Num(value, _) => {
+                let ty = self.ty().to_definition(&quote! {}, options);
+
+                format!("{}{}", value, ty)
+                    .parse()
+                    .expect("to_default_value")
+            }
+        };
+
+        if self.is_variable() {
+            value = quote! { mlang_rs::rt::opcode::Variable::Constant(#value) };
+        }
+
+        if self.is_option() {
+            value = quote! { Some(#value) };
+        }
+
+        Some(value)
+    }
+
     fn to_init_clause(&self, param: &TokenStream) -> TokenStream {
         if let Some(ident) = self.to_ident() {
             quote! { #ident: #param }
@@ -199,27 +522,79 @@ impl<'a> FieldMapping for Field<'a> {
         }
     }
 
-    fn to_type_definition(&self, ty_mod: &TokenStream) -> TokenStream {
-        let mut ty = self.ty().to_definition(ty_mod);
+    fn to_type_definition(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream {
+        to_field_type(self, self.to_value_type_definition(ty_mod, options))
+    }
 
-        if self.is_variable() {
-            ty = quote! {
-                mlang_rs::rt::opcode::Variable<#ty>
+    fn to_value_type_definition(
+        &self,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream {
+        if self.is_external() {
+            return quote! { mlang_rs::rt::resource::Resource<Vec<u8>> };
+        }
+
+        if self.is_borrow() {
+            let alloc = options.to_alloc_crate();
+            let lifetime = options.to_lifetime();
+
+            return match self.ty() {
+                Type::Bytes(_) => quote! { #alloc::borrow::Cow<#lifetime, [u8]> },
+                _ => quote! { #alloc::borrow::Cow<#lifetime, str> },
             };
         }
 
-        if self.is_option() {
-            ty = quote! { Option<#ty> };
+        self.ty().to_definition(ty_mod, options)
+    }
+
+    fn to_deserialize_type(&self, ty_mod: &TokenStream, options: &MappingOptions) -> TokenStream {
+        if self.is_external() {
+            return self.to_type_definition(ty_mod, options);
         }
 
-        ty
+        if self.is_borrow() {
+            let ty = self.to_value_type_definition(ty_mod, options);
+
+            return to_field_type(self, quote! { mlang_rs::rt::serde::de::Borrow<#ty> });
+        }
+
+        let mut ty = self.ty().to_definition(ty_mod, options);
+
+        if self.is_raw() {
+            ty = quote! { mlang_rs::rt::serde::de::Raw<#ty> };
+        } else if matches!(self.ty(), Type::Bytes(_)) {
+            ty = quote! { mlang_rs::rt::serde::de::Bytes<#ty> };
+        } else if let Type::Decimal(precision, scale, _) = self.ty() {
+            let precision = proc_macro2::Literal::u32_unsuffixed(precision.0 as u32);
+            let scale = proc_macro2::Literal::u32_unsuffixed(scale.0 as u32);
+
+            ty = quote! { mlang_rs::rt::serde::de::Fixed<#precision, #scale> };
+        }
+
+        to_field_type(self, ty)
+    }
+
+    fn to_serialize_value(&self, value: &TokenStream) -> TokenStream {
+        let value = if matches!(self.ty(), Type::Bytes(_)) && !self.is_external() {
+            quote! { &mlang_rs::rt::serde::ser::Bytes(#value) }
+        } else {
+            value.clone()
+        };
+
+        match self.precision() {
+            Some(decimals) => quote! { &mlang_rs::rt::serde::ser::Precision(#decimals, #value) },
+            None => value,
+        }
     }
 
     fn to_definition_clause(&self, vis: &TokenStream, ty: &TokenStream) -> TokenStream {
+        let comments = self.to_comment();
+
         let attrs = if self.is_option() {
-            quote! { #[serde(skip_serializing_if = "Option::is_none")] }
+            quote! { #comments #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))] }
         } else {
-            quote! {}
+            comments
         };
 
         if let Some(ident) = self.to_ident() {
@@ -234,11 +609,80 @@ impl<'a> FieldMapping for Field<'a> {
     }
 }
 
+/// Wrap the rust type of the field value with `Variable`/`Option` if necessary.
+fn to_field_type(field: &Field<'_>, mut ty: TokenStream) -> TokenStream {
+    if field.is_variable() {
+        ty = quote! {
+            mlang_rs::rt::opcode::Variable<#ty>
+        };
+    }
+
+    if field.is_option() {
+        ty = quote! { Option<#ty> };
+    }
+
+    ty
+}
+
+/// A trait to help mapping the fields of an enum variant [`Node`] to rust types.
+pub trait VariantMapping {
+    /// Convert field [`Type`] to rust field type, boxed if this variant has the `boxed` property.
+    fn to_field_type_definition(
+        &self,
+        field: &Field<'_>,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream;
+
+    /// Convert field [`Type`] to the type passed to `deserialize_field`, see [`FieldMapping::to_deserialize_type`].
+    fn to_field_deserialize_type(
+        &self,
+        field: &Field<'_>,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream;
+}
+
+impl VariantMapping for Node {
+    fn to_field_type_definition(
+        &self,
+        field: &Field<'_>,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream {
+        let ty = field.to_type_definition(ty_mod, options);
+
+        if self.is_boxed() {
+            quote! { Box<#ty> }
+        } else {
+            ty
+        }
+    }
+
+    fn to_field_deserialize_type(
+        &self,
+        field: &Field<'_>,
+        ty_mod: &TokenStream,
+        options: &MappingOptions,
+    ) -> TokenStream {
+        let ty = field.to_deserialize_type(ty_mod, options);
+
+        if self.is_boxed() {
+            quote! { Box<#ty> }
+        } else {
+            ty
+        }
+    }
+}
+
 /// A trait to help mapping [`Node`] to rust struct.
 pub trait ComplexTypeMapping: CommentMapping {
     /// Generate rust struct ident for [`Node`] .
     fn to_ident(&self) -> TokenStream;
 
+    /// Generate rust struct ident without the namespace path, used by the type definition.
+    fn to_local_ident(&self) -> TokenStream;
+
     /// Generate `;` token if necessary
     fn to_semi_token(&self) -> TokenStream;
 
@@ -258,11 +702,141 @@ impl CommentMapping for Enum {
     }
 }
 
+impl CommentMapping for Mod {
+    fn to_comment(&self) -> TokenStream {
+        self.comments.iter().map(|c| c.to_comment()).collect()
+    }
+}
+
+/// A trait to help mapping `derive`/`rust_attr` properties to rust attributes.
+pub trait RustAttrMapping {
+    /// Generate the extra `#[derive(...)]` and pass-through attributes of the rust type,
+    /// including the [`extra_derives`](MappingOptions::extra_derives) of the `kind` types.
+    fn to_rust_attrs(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream;
+}
+
+/// Traits that are always derived by generated types.
+const BUILTIN_DERIVES: [&str; 4] = ["Debug", "PartialEq", "PartialOrd", "Clone"];
+
+fn to_rust_attrs<'a>(
+    derives: impl Iterator<Item = &'a str>,
+    attrs: impl Iterator<Item = &'a str>,
+) -> TokenStream {
+    let mut extra: Vec<&str> = vec![];
+
+    for derive in derives {
+        if !BUILTIN_DERIVES.contains(&derive) && !extra.contains(&derive) {
+            extra.push(derive);
+        }
+    }
+
+    let derives = extra
+        .into_iter()
+        .map(|derive| derive.parse::<TokenStream>().expect("derive path"))
+        .collect::<Vec<_>>();
+
+    let attrs = attrs
+        .map(|attr| attr.parse::<TokenStream>().expect("rust_attr tokens"))
+        .collect::<Vec<_>>();
+
+    let derives = if derives.is_empty() {
+        quote! {}
+    } else {
+        quote! { #[derive(#(#derives),*)] }
+    };
+
+    quote! {
+        #derives
+        #(#[#attrs])*
+    }
+}
+
+impl RustAttrMapping for Node {
+    fn to_rust_attrs(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream {
+        to_rust_attrs(
+            options.extra_derives_of(kind).chain(self.derives()),
+            self.rust_attrs(),
+        )
+    }
+}
+
+impl RustAttrMapping for Enum {
+    fn to_rust_attrs(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream {
+        let ordered: &[&str] = if options.ordered_enums && self.is_unit_like() {
+            &["Eq", "Ord"]
+        } else {
+            &[]
+        };
+
+        to_rust_attrs(
+            ordered
+                .iter()
+                .copied()
+                .chain(options.extra_derives_of(kind))
+                .chain(self.derives()),
+            self.rust_attrs(),
+        )
+    }
+}
+
+/// A trait to help mapping `cfg` properties to the `#[cfg(...)]` attribute of all generated code of a statement.
+pub trait CfgMapping {
+    /// Generate the `#[cfg(...)]` attribute, or an empty stream if the statement is unconditional.
+    fn to_cfg(&self) -> TokenStream;
+}
+
+fn to_cfg<'a>(cfgs: impl Iterator<Item = &'a str>) -> TokenStream {
+    let cfgs = cfgs
+        .map(|cfg| cfg.parse::<TokenStream>().expect("cfg predicate"))
+        .collect::<Vec<_>>();
+
+    match cfgs.len() {
+        0 => quote! {},
+        1 => quote! { #[cfg(#(#cfgs)*)] },
+        _ => quote! { #[cfg(all(#(#cfgs),*))] },
+    }
+}
+
+impl CfgMapping for Node {
+    fn to_cfg(&self) -> TokenStream {
+        to_cfg(self.cfgs())
+    }
+}
+
+impl CfgMapping for Enum {
+    fn to_cfg(&self) -> TokenStream {
+        to_cfg(self.cfgs())
+    }
+}
+
+impl CfgMapping for Stat {
+    fn to_cfg(&self) -> TokenStream {
+        match self {
+            Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) | Stat::Data(node) => {
+                node.to_cfg()
+            }
+            Stat::Enum(node) => node.to_cfg(),
+            _ => quote! {},
+        }
+    }
+}
+
 impl ComplexTypeMapping for Node {
     fn to_ident(&self) -> TokenStream {
         self.ident.to_type_name()
     }
 
+    fn to_local_ident(&self) -> TokenStream {
+        self.ident
+            .1
+            .rsplit("::")
+            .next()
+            .unwrap()
+            .to_upper_camel_case()
+            .parse()
+            .expect("to_local_ident")
+    }
+
     fn to_semi_token(&self) -> TokenStream {
         if self.is_tuple() {
             quote! {;}
@@ -291,6 +865,17 @@ impl ComplexTypeMapping for Enum {
         self.ident.to_type_name()
     }
 
+    fn to_local_ident(&self) -> TokenStream {
+        self.ident
+            .1
+            .rsplit("::")
+            .next()
+            .unwrap()
+            .to_upper_camel_case()
+            .parse()
+            .expect("to_local_ident")
+    }
+
     fn to_semi_token(&self) -> TokenStream {
         quote! {}
     }
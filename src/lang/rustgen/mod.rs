@@ -1,14 +1,25 @@
 //! `mlang` code generator for rust language.
 
+#[doc(hidden)]
 pub mod mapping;
+pub use mapping::{MappingOptions, TypeKind};
 
+mod arbitrary;
+pub use arbitrary::*;
+mod fuzz;
+pub use fuzz::*;
+mod layout;
+pub use layout::*;
 mod opcode;
 pub use opcode::*;
 mod serde;
 pub use serde::*;
+mod visitor;
+pub use visitor::*;
 
 mod ext {
     use std::{
+        collections::HashMap,
         io::{Error, ErrorKind, Result},
         path::{Path, PathBuf},
     };
@@ -16,11 +27,21 @@ mod ext {
     use proc_macro2::TokenStream;
     use quote::quote;
 
-    use crate::lang::{ir::Stat, rustgen::gen_opcode_mod};
+    use crate::lang::{
+        analyzer::{Lint, LintLevel, LintLevels},
+        ir::Stat,
+        rustgen::{
+            gen_opcode_files, gen_opcode_mod,
+            mapping::{MappingOptions, TypeKind},
+        },
+    };
 
-    use super::gen_serde_mod;
+    use super::{
+        gen_arbitrary_mod, gen_fuzz_manifest, gen_fuzz_targets, gen_layout_mod, gen_serde_mod,
+        gen_visitor_mod,
+    };
 
-    fn write_and_fmt_rs<C: AsRef<[u8]>, P: AsRef<Path>>(path: P, content: C) -> Result<()> {
+    fn write_rs<C: AsRef<[u8]>, P: AsRef<Path>>(path: P, content: C) -> Result<()> {
         println!("codegen({:?}):", path.as_ref());
 
         std::fs::write(path.as_ref(), content).map_err(|err| {
@@ -32,32 +53,82 @@ mod ext {
 
         println!("    write file ... ok");
 
-        std::process::Command::new("rustfmt")
-            .arg(path.as_ref())
-            .output()
-            .map_err(|err| {
-                Error::new(
-                    ErrorKind::Other,
-                    format!("run rustfmt for {:?} error: {}", path.as_ref(), err),
-                )
-            })?;
+        #[cfg(not(feature = "prettyplease"))]
+        {
+            std::process::Command::new("rustfmt")
+                .arg(path.as_ref())
+                .output()
+                .map_err(|err| {
+                    Error::new(
+                        ErrorKind::Other,
+                        format!("run rustfmt for {:?} error: {}", path.as_ref(), err),
+                    )
+                })?;
 
-        println!("    run rustfmt ... ok");
+            println!("    run rustfmt ... ok");
+        }
 
         Ok(())
     }
 
+    /// Format generated code in-process.
+    #[cfg(feature = "prettyplease")]
+    fn fmt_rs(name: &str, codes: TokenStream) -> Result<String> {
+        let file = syn::parse2::<syn::File>(codes).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("parse generated {} error: {}", name, err),
+            )
+        })?;
+
+        Ok(prettyplease::unparse(&file))
+    }
+
+    /// Without the `prettyplease` feature the generated code is returned unformatted.
+    #[cfg(not(feature = "prettyplease"))]
+    fn fmt_rs(_name: &str, codes: TokenStream) -> Result<String> {
+        Ok(codes.to_string())
+    }
+
+    /// The cargo-fuzz crate written by [`CodeGen::fuzz_scaffold`].
+    struct FuzzScaffold {
+        dir: PathBuf,
+        package: String,
+        module: String,
+    }
+
     /// A builder to config and generate rust source code.
     pub struct CodeGen {
         with_serde: bool,
+        serde_feature: Option<String>,
+        with_layout_report: bool,
+        with_arbitrary: bool,
+        with_visitor: bool,
+        split_files: bool,
+        fuzz: Option<FuzzScaffold>,
+        mapping: MappingOptions,
         target: PathBuf,
+        pub(crate) lints: LintLevels,
+        pub(crate) lint_report: Option<PathBuf>,
+        /// Write `mlang.rs`, which includes the generated files as inline modules, used by build scripts.
+        pub(crate) include_wrapper: bool,
     }
 
     impl Default for CodeGen {
         fn default() -> Self {
             Self {
                 with_serde: true,
+                serde_feature: None,
+                with_layout_report: false,
+                with_arbitrary: false,
+                with_visitor: false,
+                split_files: false,
+                fuzz: None,
+                mapping: Default::default(),
                 target: Path::new("./").to_path_buf(),
+                lints: Default::default(),
+                lint_report: None,
+                include_wrapper: false,
             }
         }
     }
@@ -69,34 +140,297 @@ mod ext {
             self
         }
 
+        /// Gate the `serde` module behind the cargo `feature` of the generated crate, e.g. `serde`, and turn
+        /// its generation on, the module is ungated by default.
+        ///
+        /// The module is generated along with the others, so a crate can make serialization optional,
+        /// including its dependency on the `rt_serde` feature of `mlang-rs`, without running the code generation twice.
+        pub fn serde_feature(mut self, feature: impl AsRef<str>) -> Self {
+            self.with_serde = true;
+            self.serde_feature = Some(feature.as_ref().to_string());
+            self
+        }
+
+        /// Reset `layout` module generation flag, the default value is false.
+        ///
+        /// The `layout` module lists `size_of`/`align_of` of every generated type
+        /// and flags enum variants that are much larger than the others.
+        pub fn with_layout_report(mut self, on: bool) -> Self {
+            self.with_layout_report = on;
+            self
+        }
+
+        /// Reset `arbitrary` module generation flag, the default value is false.
+        ///
+        /// The `arbitrary` module implements [`Arbitrary`](crate::rt::arbitrary::Arbitrary) for every
+        /// generated type, values are constrained by the schema so they can be used by fuzzers and
+        /// round-trip tests directly. The generated code requires the `arbitrary` feature of `mlang-rs`.
+        pub fn with_arbitrary(mut self, on: bool) -> Self {
+            self.with_arbitrary = on;
+            self
+        }
+
+        /// Reset `visitor` module generation flag, the default value is false.
+        ///
+        /// The `visitor` module defines an `OpcodeVisitor` trait with a `visit_xxx` method of every element,
+        /// leaf and attr, and the `visit`/`walk` functions that dispatch opcodes to it, so renderers don't
+        /// match over `Element`/`Leaf`/`Attr` by hand.
+        pub fn with_visitor(mut self, on: bool) -> Self {
+            self.with_visitor = on;
+            self
+        }
+
+        /// Reset one file per type flag, the default value is false.
+        ///
+        /// When on, the `opcode` module is written to the `opcode` directory as one file per type, e.g. `opcode/rect.rs`,
+        /// with a `mod.rs` that re-exports them, so big schemas don't generate one huge file. The paths of the
+        /// generated types don't change. [`codegen_to_tokens`](Self::codegen_to_tokens) ignores it.
+        pub fn split_files(mut self, on: bool) -> Self {
+            self.split_files = on;
+            self
+        }
+
+        /// Write a cargo-fuzz crate to `dir`, which is regenerated with the code.
+        ///
+        /// `dir` is a direct child of the root of the schema crate `package`, usually `fuzz`, and `module` is the rust
+        /// path of the generated modules in the schema crate, e.g. `gen` for `src/gen`. The targets feed random bytes
+        /// to the decoders of the vocabulary: [`Fragment`](crate::rt::fragment::Fragment)s checked against the schema
+        /// fingerprint, documents of every format of `rt::serde`, `json`, `xml`, `sexpr` and `binary`, decoded through
+        /// the `serde` module, and the `FromStr` impls of enums and the schema version check of the `serde` module.
+        /// Run them with `cargo fuzz run <target>` in the schema crate.
+        pub fn fuzz_scaffold(
+            mut self,
+            dir: impl AsRef<Path>,
+            package: impl AsRef<str>,
+            module: impl AsRef<str>,
+        ) -> Self {
+            self.fuzz = Some(FuzzScaffold {
+                dir: dir.as_ref().to_path_buf(),
+                package: package.as_ref().to_string(),
+                module: module.as_ref().to_string(),
+            });
+            self
+        }
+
+        /// Reset `string` mapping flag, the default value is false.
+        ///
+        /// When on, `string` fields are generated as `Cow<'static, str>` instead of `String`,
+        /// so documents built from string literals don't allocate.
+        pub fn with_cow_string(mut self, on: bool) -> Self {
+            self.mapping.cow_string = on;
+            self
+        }
+
+        /// Reset `JsonSchema` derive flag, the default value is false.
+        ///
+        /// When on, generated types derive `schemars::JsonSchema` behind the `schemars` feature of the generated
+        /// crate, the schemas follow the `serde` encoding, so API docs of JSON documents stay in sync with the
+        /// schema. Requires the `schemars` feature of `mlang-rs` for the runtime types.
+        pub fn with_json_schema(mut self, on: bool) -> Self {
+            self.mapping.json_schema = on;
+            self
+        }
+
+        /// Reset ordered enums flag, the default value is false.
+        ///
+        /// When on, unit-like enums also derive `Eq` and `Ord`, variants are ordered by declaration,
+        /// so they can be used as sort keys and in ordered collections.
+        pub fn with_ordered_enums(mut self, on: bool) -> Self {
+            self.mapping.ordered_enums = on;
+            self
+        }
+
+        /// Reset strip names flag, the default value is false.
+        ///
+        /// When on, the `serde` module passes empty names and `None` field names to serializers and deserializers,
+        /// and omits the name tables, the enum `Display`/`FromStr` impls and the name-based
+        /// `visit_opcode_with`/`visit_enum_with` dispatch, which fall back to the default visitor methods.
+        /// This shrinks the generated code of deployments that only use a binary format, which identifies
+        /// types by `type_id` and fields by index.
+        pub fn strip_names(mut self, on: bool) -> Self {
+            self.mapping.strip_names = on;
+            self
+        }
+
+        /// Reset `no_std` flag, the default value is false.
+        ///
+        /// When on, the generated code only uses `core` and `alloc`, e.g. `alloc::vec::Vec` and `alloc::string::String`,
+        /// so it can be used by `#![no_std]` crates, which must declare `extern crate alloc`. Such crates depend on
+        /// `mlang-rs` without default features, with `rt`/`rt_serde`. The `arbitrary` module, `#[external]` fields
+        /// and `decimal` fields require `std`.
+        pub fn no_std(mut self, on: bool) -> Self {
+            self.mapping.no_std = on;
+            self
+        }
+
+        /// Add `derives` to every generated struct and enum, besides the builtin `Debug`, `PartialEq`,
+        /// `PartialOrd` and `Clone`.
+        ///
+        /// Each derive is a rust path, e.g. `Hash` or `my_crate::MyDerive`. Builtin derives and derives
+        /// that are also set by a `#[derive(...)]` property are ignored.
+        pub fn extra_derives<I, S>(mut self, derives: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+        {
+            self.mapping.extra_derives.extend(
+                derives
+                    .into_iter()
+                    .map(|derive| (None, derive.as_ref().to_string())),
+            );
+            self
+        }
+
+        /// Add `derives` to the generated types of `kind` declarations only, see [`extra_derives`](Self::extra_derives).
+        pub fn extra_derives_for<I, S>(mut self, kind: TypeKind, derives: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+        {
+            self.mapping.extra_derives.extend(
+                derives
+                    .into_iter()
+                    .map(|derive| (Some(kind), derive.as_ref().to_string())),
+            );
+            self
+        }
+
+        /// Set the level of `lint` reported by the analyzer before code generation, lints are warned by default.
+        ///
+        /// A lint can also be allowed for one declaration by the `#[allow("lint-name")]` property.
+        pub fn lint(mut self, lint: Lint, level: LintLevel) -> Self {
+            self.lints.set(lint, level);
+            self
+        }
+
+        /// Set the level of all lints, lints set by [`lint`](Self::lint) afterwards override it.
+        pub fn lints(mut self, level: LintLevel) -> Self {
+            self.lints.set_all(level);
+            self
+        }
+
+        /// Write the errors and warnings of the analyzer as json to `path`, see [`to_json`](crate::lang::report::to_json).
+        pub fn lint_report(mut self, path: impl AsRef<Path>) -> Self {
+            self.lint_report = Some(path.as_ref().to_path_buf());
+            self
+        }
+
         /// Reset the target path of the code generation, the default value is `current directory`.
         pub fn target(mut self, path: impl AsRef<Path>) -> Self {
             self.target = path.as_ref().to_path_buf();
             self
         }
 
-        /// invoke real rust code generation processing.
-        pub fn codegen(self, stats: impl AsRef<[Stat]>) -> Result<()> {
-            if !self.target.exists() {
-                std::fs::create_dir_all(&self.target)?;
+        /// Returns the `#[cfg(...)]` attribute of the generated module `name`, if it's feature gated.
+        fn mod_cfg(&self, name: &str) -> TokenStream {
+            match &self.serde_feature {
+                Some(feature) if name == "serde" => quote! { #[cfg(feature = #feature)] },
+                _ => quote! {},
             }
+        }
 
-            let mut mods = vec![("opcode", gen_opcode_mod(stats.as_ref()))];
+        /// Generate the token stream of every module but `opcode`, in file order.
+        fn gen_mods(&self, stats: &[Stat]) -> Vec<(&'static str, TokenStream)> {
+            let mut mods = vec![];
 
             if self.with_serde {
-                mods.push(("serde", gen_serde_mod(stats.as_ref(), "super::opcode::")));
+                mods.push((
+                    "serde",
+                    gen_serde_mod(stats, "super::opcode::", &self.mapping),
+                ));
             }
 
+            if self.with_layout_report {
+                mods.push((
+                    "layout",
+                    gen_layout_mod(stats, "super::opcode::", &self.mapping),
+                ));
+            }
+
+            if self.with_arbitrary {
+                mods.push((
+                    "arbitrary",
+                    gen_arbitrary_mod(stats, "super::opcode::", &self.mapping),
+                ));
+            }
+
+            if self.with_visitor {
+                mods.push(("visitor", gen_visitor_mod(stats, "super::opcode::")));
+            }
+
+            mods
+        }
+
+        /// Generate rust source code as one token stream of inline modules: `pub mod opcode { ... }`,
+        /// followed by the optional `serde`, `layout`, `arbitrary` and `visitor` modules.
+        ///
+        /// This is used by the `mlang!{}` macro to splice the generated code into the caller's crate.
+        pub fn codegen_to_tokens(&self, stats: impl AsRef<[Stat]>) -> TokenStream {
+            let stats = stats.as_ref();
+
+            let mods = Some(("opcode", gen_opcode_mod(stats, &self.mapping)))
+                .into_iter()
+                .chain(self.gen_mods(stats))
+                .map(|(name, codes)| {
+                    let ident = name.parse::<TokenStream>().unwrap();
+                    let cfg = self.mod_cfg(name);
+
+                    quote! {
+                        #cfg
+                        pub mod #ident {
+                            #codes
+                        }
+                    }
+                });
+
+            quote! {
+                #(#mods)*
+            }
+        }
+
+        /// Generate rust source code in memory, returns the source of every generated file by file name:
+        /// `mod.rs`, `opcode.rs` and the optional `serde.rs`, `layout.rs`, `arbitrary.rs` and `visitor.rs`.
+        ///
+        /// With [`split_files`](Self::split_files) `opcode.rs` is replaced by the files of the `opcode` directory,
+        /// named by their paths, e.g. `opcode/mod.rs` and `opcode/rect.rs`.
+        ///
+        /// With the `prettyplease` feature the source code is formatted in-process,
+        /// otherwise it's unformatted, which is still valid rust code.
+        pub fn codegen_to_string(
+            &self,
+            stats: impl AsRef<[Stat]>,
+        ) -> Result<HashMap<String, String>> {
+            let stats = stats.as_ref();
+
+            let mut files = HashMap::new();
             let mut impls = vec![];
 
+            let mut mods = self.gen_mods(stats);
+
+            if self.split_files {
+                for (path, codes) in gen_opcode_files(stats, &self.mapping) {
+                    let file_name = format!("opcode/{}", path);
+
+                    files.insert(file_name.clone(), fmt_rs(&file_name, codes)?);
+                }
+
+                impls.push(quote! {
+                    pub mod opcode;
+                });
+            } else {
+                mods.insert(0, ("opcode", gen_opcode_mod(stats, &self.mapping)));
+            }
+
             for (name, codes) in mods {
-                let target_file = self.target.join(format!("{}.rs", name));
+                let file_name = format!("{}.rs", name);
 
-                write_and_fmt_rs(target_file, codes.to_string())?;
+                files.insert(file_name.clone(), fmt_rs(&file_name, codes)?);
 
                 let ident = name.parse::<TokenStream>().unwrap();
+                let cfg = self.mod_cfg(name);
 
                 impls.push(quote! {
+                    #cfg
                     pub mod #ident;
                 });
             }
@@ -107,9 +441,130 @@ mod ext {
                 #(#impls)*
             };
 
-            let target_file = self.target.join("mod.rs");
+            files.insert("mod.rs".to_string(), fmt_rs("mod.rs", codes)?);
+
+            Ok(files)
+        }
+
+        /// invoke real rust code generation processing.
+        ///
+        /// The generated files are formatted by `rustfmt`, or in-process with the `prettyplease` feature.
+        pub fn codegen(self, stats: impl AsRef<[Stat]>) -> Result<()> {
+            let stats = stats.as_ref();
+
+            if !self.target.exists() {
+                std::fs::create_dir_all(&self.target)?;
+            }
+
+            let files = self.codegen_to_string(stats)?;
+
+            // the `opcode` module of the other layout, `opcode.rs` and `opcode/mod.rs` can't coexist,
+            // the directory is cleared to drop the files of removed types.
+            let opcode_dir = self.target.join("opcode");
+
+            if opcode_dir.join("mod.rs").exists() {
+                std::fs::remove_dir_all(&opcode_dir)?;
+            }
+
+            if self.split_files && self.target.join("opcode.rs").exists() {
+                std::fs::remove_file(self.target.join("opcode.rs"))?;
+            }
+
+            // `mod.rs` last, as before.
+            let mut names = files.keys().collect::<Vec<_>>();
+            names.sort_by_key(|name| (name.as_str() == "mod.rs", name.as_str()));
+
+            for name in &names {
+                let path = self.target.join(name);
 
-            write_and_fmt_rs(target_file, codes.to_string())?;
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+
+                write_rs(path, &files[*name])?;
+            }
+
+            if self.include_wrapper {
+                let target = self.target.canonicalize()?;
+
+                let mods = names
+                    .iter()
+                    .filter(|name| name.as_str() != "mod.rs" && !name.contains('/'))
+                    .map(|name| {
+                        let name = name.trim_end_matches(".rs");
+                        let ident = name.parse::<TokenStream>().unwrap();
+                        let cfg = self.mod_cfg(name);
+                        let path = target
+                            .join(format!("{}.rs", name))
+                            .to_string_lossy()
+                            .into_owned();
+
+                        quote! {
+                            #cfg
+                            pub mod #ident {
+                                include!(#path);
+                            }
+                        }
+                    });
+
+                // the files of `opcode/mod.rs` are out-of-line modules, which `include!` can't locate.
+                let opcode = self.split_files.then(|| {
+                    let path = target.join("opcode/mod.rs").to_string_lossy().into_owned();
+
+                    quote! {
+                        #[path = #path]
+                        pub mod opcode;
+                    }
+                });
+
+                let codes = quote! {
+                    #opcode
+                    #(#mods)*
+                };
+
+                write_rs(target.join("mlang.rs"), fmt_rs("mlang.rs", codes)?)?;
+            }
+
+            if let Some(fuzz) = &self.fuzz {
+                self.write_fuzz_scaffold(fuzz, stats)?;
+            }
+
+            Ok(())
+        }
+
+        fn write_fuzz_scaffold(&self, fuzz: &FuzzScaffold, stats: &[Stat]) -> Result<()> {
+            let targets = gen_fuzz_targets(stats, &fuzz.module, &self.mapping, self.with_serde);
+
+            std::fs::create_dir_all(fuzz.dir.join("fuzz_targets"))?;
+
+            let names = targets.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+            let features = self
+                .serde_feature
+                .iter()
+                // all the targets but `fragment` use the `serde` module.
+                .filter(|_| names.iter().any(|name| *name != "fragment"))
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            std::fs::write(
+                fuzz.dir.join("Cargo.toml"),
+                gen_fuzz_manifest(&fuzz.package, &features, &names),
+            )?;
+
+            std::fs::write(
+                fuzz.dir.join(".gitignore"),
+                "target\ncorpus\nartifacts\ncoverage\n",
+            )?;
+
+            for (name, codes) in targets {
+                let file_name = format!("{}.rs", name);
+
+                write_rs(
+                    fuzz.dir.join("fuzz_targets").join(&file_name),
+                    fmt_rs(&file_name, codes)?,
+                )?;
+            }
 
             Ok(())
         }
@@ -117,3 +572,307 @@ mod ext {
 }
 
 pub use ext::*;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parserc::ParseContext;
+
+    use super::{
+        CodeGen, gen_fuzz_manifest, gen_fuzz_targets, gen_layout_mod, gen_opcode_mod,
+        gen_serde_mod, mapping::MappingOptions,
+    };
+    use crate::lang::{
+        analyzer::semantic_analyze,
+        ir::{Fields, Stat},
+        parser::parse,
+    };
+
+    /// Degenerate schemas, and whether the analyzer accepts them.
+    ///
+    /// The generated code of the valid ones is compiled by `macros/tests/degenerate.rs`.
+    const DEGENERATE: &[(&str, bool)] = &[
+        ("", true),
+        ("version \"1.0.0\";", true),
+        ("apply B to A;", false),
+        ("children B of A;", false),
+        ("el A; attr B(int); apply B to A;", true),
+        ("group G := (); el A; apply G to A; children G of A;", true),
+        ("el A { e: E } enum E {}", false),
+        ("el A { e: E } enum E { X(), Y {}, Z }", true),
+        (
+            "el A(); leaf B {} attr C {} data D(); el E(D); apply C to A;",
+            true,
+        ),
+        ("mixin M {} el A mixin M; el B mixin M {}", true),
+        // `ids::SHAPE_RECT` and the `ShapeRect` opcode variants collide.
+        ("mod shape { el Rect; } el ShapeRect;", false),
+        ("el HTMLElement; data HtmlElement(int);", false),
+    ];
+
+    /// Returns the files generated by `codegen` for the valid schema `source`, without whitespace,
+    /// so the checks don't depend on the `prettyplease` formatting.
+    ///
+    /// Only the file layout and the items that are left out are checked here, the behavior of the
+    /// generated code is checked by the `mlang!{}` tests in `macros/tests`.
+    fn codegen(source: &str, codegen: CodeGen) -> HashMap<String, String> {
+        let mut stats = parse(&mut ParseContext::from(source)).unwrap();
+
+        assert!(semantic_analyze(&mut stats), "{}", source);
+
+        codegen
+            .codegen_to_string(&stats)
+            .unwrap()
+            .into_iter()
+            .map(|(name, code)| (name, code.replace(char::is_whitespace, "")))
+            .collect()
+    }
+
+    #[test]
+    fn test_degenerate_schemas() {
+        let options = MappingOptions::default();
+
+        for (source, valid) in DEGENERATE {
+            let mut stats = parse(&mut ParseContext::from(*source)).unwrap();
+
+            assert_eq!(semantic_analyze(&mut stats), *valid, "{}", source);
+
+            if !valid {
+                continue;
+            }
+
+            for stat in &stats {
+                if let Stat::Element(node)
+                | Stat::Leaf(node)
+                | Stat::Attr(node)
+                | Stat::Data(node) = stat
+                {
+                    // empty field lists are unit nodes.
+                    assert!(node.fields.iter().next().is_some() || node.fields == Fields::None);
+                }
+            }
+
+            gen_opcode_mod(&stats, &options);
+            gen_layout_mod(&stats, "super::opcode::", &options);
+
+            // `match v {}` doesn't compile for a reference to an empty enum.
+            let serde = gen_serde_mod(&stats, "super::opcode::", &options).to_string();
+
+            assert!(!serde.contains("match v { }"), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_schema_driven() {
+        let mut stats = parse(&mut ParseContext::from("el A { x: int = 1 }")).unwrap();
+
+        assert!(semantic_analyze(&mut stats));
+
+        let files = CodeGen::default().codegen_to_string(&stats).unwrap();
+
+        let serde = files["serde.rs"].replace([' ', '\n'], "");
+
+        // the defaulted field is an option only if the format is self-describing.
+        assert!(serde.contains("letself_describing=deserializer.is_self_describing();"));
+        assert!(serde.contains("ifself.self_describing{data.deserialize_field::<Option<i32>>"));
+        assert!(serde.contains("}else{data.deserialize_field::<i32>"));
+    }
+
+    #[test]
+    fn test_codegen_to_string() {
+        let files = codegen("el A { x: int } leaf B;", CodeGen::default());
+
+        let mut names = files.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(names, ["mod.rs", "opcode.rs", "serde.rs"]);
+        assert!(files["mod.rs"].contains("pubmodopcode"));
+        assert!(files["opcode.rs"].contains("pubstructA"));
+
+        let files = codegen(
+            "el A { x: int } leaf B;",
+            CodeGen::default()
+                .with_serde(false)
+                .with_arbitrary(true)
+                .with_json_schema(true),
+        );
+
+        assert!(files["mod.rs"].contains("pubmodarbitrary"));
+        assert!(files["arbitrary.rs"].contains("Arbitrary"));
+        assert!(files["opcode.rs"].contains("JsonSchema"));
+    }
+
+    #[test]
+    fn test_strip_names() {
+        let files = codegen(
+            "el Rect { width: float } enum U { X, Y(int) }",
+            CodeGen::default().strip_names(true),
+        );
+
+        let serde = &files["serde.rs"];
+
+        assert!(!serde.contains(r#""rect""#));
+        assert!(!serde.contains(r#""width""#));
+        assert!(!serde.contains("ELEMENT_NAMES"));
+        assert!(!serde.contains("fnvisit_opcode_with"));
+        assert!(!serde.contains("fnvisit_enum_with"));
+        assert!(serde.contains("fnvisit_opcode<D>"));
+    }
+
+    #[test]
+    fn test_children() {
+        let mut stats = parse(&mut ParseContext::from(
+            "el Rect; el Group; leaf Text(string); attr Fill(string); apply Fill to (Rect, Text); children (Rect, Group) of Group; children Text of Group;",
+        ))
+        .unwrap();
+
+        assert!(semantic_analyze(&mut stats));
+
+        let opcode = gen_opcode_mod(&stats, &MappingOptions::default())
+            .to_string()
+            .replace(' ', "");
+
+        assert!(opcode.contains(
+            "pubenumGroupChild{Rect(Box<RectNode>),Group(Box<GroupNode>),Text(Box<TextNode>)}"
+        ));
+        assert!(opcode.contains("pubfnappend_child(&mutself,child:implInto<GroupChild>)"));
+        assert!(opcode.contains("pubenumTextApplicableAttr{Fill(Box<Fill>)}"));
+        assert!(opcode.contains("pubfnapply(self,attr:implInto<RectApplicableAttr>)->RectNode"));
+        assert!(!opcode.contains("RectChild"));
+        assert!(!opcode.contains("GroupApplicableAttr"));
+    }
+
+    #[test]
+    fn test_serde_feature() {
+        let options = || {
+            CodeGen::default()
+                .with_serde(false)
+                .serde_feature("mlang-serde")
+        };
+
+        let files = codegen("el Rect;", options());
+
+        assert!(files["mod.rs"].contains(r#"#[cfg(feature="mlang-serde")]pubmodserde;"#));
+        assert!(files["mod.rs"].contains("pubmodopcode;"));
+        assert!(!files["mod.rs"].contains(r#")]pubmodopcode;"#));
+
+        let mut stats = parse(&mut ParseContext::from("el Rect;")).unwrap();
+
+        assert!(semantic_analyze(&mut stats));
+
+        let tokens = options()
+            .codegen_to_tokens(&stats)
+            .to_string()
+            .replace(' ', "");
+
+        assert!(tokens.contains(r#"#[cfg(feature="mlang-serde")]pubmodserde{"#));
+    }
+
+    #[test]
+    fn test_split_files() {
+        let files = codegen(
+            "el Rect; leaf Ids(string); mod geometry { data Point { x: float, y: float } }",
+            CodeGen::default().split_files(true),
+        );
+
+        let mut names = files.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(
+            names,
+            [
+                "mod.rs",
+                "opcode/geometry/mod.rs",
+                "opcode/geometry/point.rs",
+                "opcode/ids_ty.rs",
+                "opcode/mod.rs",
+                "opcode/rect.rs",
+                "serde.rs"
+            ]
+        );
+
+        let opcode = &files["opcode/mod.rs"];
+
+        assert!(opcode.contains("modrect;pubuserect::Rect;"));
+        assert!(opcode.contains("modids_ty;pubuseids_ty::Ids;"));
+        assert!(opcode.contains("pubmodgeometry;"));
+        assert!(opcode.contains("pubenumOpcode"));
+
+        assert!(files["mod.rs"].contains("pubmodopcode;pubmodserde;"));
+    }
+
+    #[test]
+    fn test_no_std() {
+        let files = codegen(
+            "el Rect { tags: [string -> float], #[option] label: string }",
+            CodeGen::default().no_std(true),
+        );
+
+        let opcode = &files["opcode.rs"];
+
+        assert!(opcode.contains("usealloc::{"));
+        assert!(opcode.contains("alloc::collections::BTreeMap<String,f32>"));
+        assert!(!opcode.contains("std::"));
+    }
+
+    #[test]
+    fn test_fuzz_targets() {
+        let mut stats = parse(&mut ParseContext::from(
+            "version \"1.0.0\"; mod a { enum U { X, Y } } enum V { X(int) }",
+        ))
+        .unwrap();
+
+        assert!(semantic_analyze(&mut stats));
+
+        let targets = gen_fuzz_targets(&stats, "gen", &MappingOptions::default(), true);
+
+        assert_eq!(
+            targets.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            ["fragment", "json", "xml", "sexpr", "binary", "from_str"]
+        );
+
+        let json = targets[1].1.to_string().replace(' ', "");
+
+        assert!(json.contains("json::from_str::<schema::gen::opcode::Opcode>(data)"));
+        assert!(json.contains("json::to_string(&opcodes)"));
+
+        let binary = targets[4].1.to_string().replace(' ', "");
+
+        assert!(binary.contains("binary::from_bytes::<schema::gen::opcode::Opcode>(data)"));
+        assert!(!binary.contains("from_utf8"));
+
+        let from_str = targets[5].1.to_string().replace(' ', "");
+
+        assert!(from_str.contains("parse::<schema::gen::opcode::a::U>()"));
+        assert!(!from_str.contains("opcode::V"));
+        assert!(from_str.contains("schema::gen::serde::check_schema_version(text)"));
+
+        let options = MappingOptions {
+            strip_names: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            gen_fuzz_targets(&stats, "", &options, true)
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>(),
+            ["fragment", "binary"]
+        );
+        assert_eq!(gen_fuzz_targets(&stats, "", &options, false).len(), 1);
+
+        let manifest = gen_fuzz_manifest("my-schema", &[], &["fragment"]);
+
+        assert!(manifest.contains(r#"schema = {path = "..", package = "my-schema"}"#));
+        assert!(manifest.contains(r#"path = "fuzz_targets/fragment.rs""#));
+
+        let manifest = gen_fuzz_manifest("my-schema", &["serde"], &["fragment"]);
+
+        assert!(
+            manifest
+                .contains(r#"schema = {path = "..", package = "my-schema", features = ["serde"]}"#)
+        );
+        assert!(manifest.contains(r#"path = "fuzz_targets/fragment.rs""#));
+    }
+}
@@ -1,26 +1,30 @@
-use heck::ToUpperCamelCase;
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
 use crate::lang::{
-    ir::{Enum, Node, Stat},
-    rustgen::mapping::{CommentMapping, ComplexTypeMapping, FieldMapping},
+    fingerprint,
+    ir::{Enum, Field, Ident, Meta, Mod, Node, Stat},
+    rustgen::mapping::{
+        CfgMapping, CommentMapping, ComplexTypeMapping, FieldMapping, IdentMapping, MappingOptions,
+        RustAttrMapping, TypeKind, VariantMapping,
+    },
 };
 
 trait TypeDefinitionCodeGen {
-    fn codegen(&self) -> TokenStream;
+    fn codegen(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream;
 }
 
 impl TypeDefinitionCodeGen for Node {
-    fn codegen(&self) -> TokenStream {
+    fn codegen(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream {
         let comments = self.to_comment();
-        let ident = self.to_ident();
+        let ident = self.to_local_ident();
 
         let fields = self
             .fields
             .iter()
             .map(|field| {
-                let ty = field.to_type_definition(&quote! {});
+                let ty = field.to_type_definition(&quote! {}, options);
 
                 field.to_definition_clause(&quote! { pub }, &ty)
             })
@@ -30,19 +34,30 @@ impl TypeDefinitionCodeGen for Node {
 
         let semi_token = self.to_semi_token();
 
+        let rust_attrs = self.to_rust_attrs(kind, options);
+
+        let cfg = self.to_cfg();
+
+        let json_schema = options.to_json_schema_derive();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
         quote! {
             #comments
+            #cfg
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub struct #ident #body #semi_token
+            #json_schema
+            #rust_attrs
+            pub struct #ident #lifetime #body #semi_token
         }
     }
 }
 
 impl TypeDefinitionCodeGen for Enum {
-    fn codegen(&self) -> TokenStream {
+    fn codegen(&self, kind: TypeKind, options: &MappingOptions) -> TokenStream {
         let comments = self.to_comment();
-        let ident = self.to_ident();
+        let ident = self.to_local_ident();
 
         let fields = self
             .fields
@@ -55,7 +70,7 @@ impl TypeDefinitionCodeGen for Enum {
                     .fields
                     .iter()
                     .map(|field| {
-                        let ty = field.to_type_definition(&quote! {});
+                        let ty = node.to_field_type_definition(&field, &quote! {}, options);
 
                         field.to_definition_clause(&quote! {}, &ty)
                     })
@@ -70,67 +85,663 @@ impl TypeDefinitionCodeGen for Enum {
             })
             .collect::<Vec<_>>();
 
+        let variants = self.gen_all_variants();
+
+        let rust_attrs = self.to_rust_attrs(kind, options);
+
+        let cfg = self.to_cfg();
+
+        let json_schema = options.to_json_schema_derive();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
         quote! {
             #comments
+            #cfg
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum #ident { #(#fields),* }
+            #json_schema
+            #rust_attrs
+            pub enum #ident #lifetime { #(#fields),* }
+
+            #variants
+        }
+    }
+}
+
+trait ModCodeGen {
+    /// Wrap the items of this namespace into a nested rust module.
+    fn gen_mod(&self, items: Vec<TokenStream>) -> TokenStream;
+
+    /// Declare the out-of-line rust module of this namespace, see [`gen_opcode_files`].
+    fn gen_mod_decl(&self) -> TokenStream;
+}
+
+impl ModCodeGen for Mod {
+    fn gen_mod_decl(&self) -> TokenStream {
+        let comments = self.to_comment();
+        let ident = self.ident.to_field_name();
+
+        quote! {
+            #comments
+            pub mod #ident;
+        }
+    }
+
+    fn gen_mod(&self, items: Vec<TokenStream>) -> TokenStream {
+        let comments = self.to_comment();
+        let ident = self.ident.to_field_name();
+
+        quote! {
+            #comments
+            pub mod #ident {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #(#items)*
+            }
+        }
+    }
+}
+
+trait AllVariantsCodeGen {
+    /// Generate `ALL` and `iter()` helpers, if all variants are unit-like.
+    fn gen_all_variants(&self) -> TokenStream;
+}
+
+impl AllVariantsCodeGen for Enum {
+    fn gen_all_variants(&self) -> TokenStream {
+        if !self.is_unit_like() {
+            return quote! {};
+        }
+
+        let ident = self.to_local_ident();
+        let variants = self.fields.iter().map(|node| node.to_ident());
+        let len = self.fields.len();
+        let cfg = self.to_cfg();
+
+        quote! {
+            #cfg
+            impl #ident {
+                /// All variants of this enum, in declaration order.
+                pub const ALL: [Self; #len] = [#(Self::#variants),*];
+
+                /// Returns an iterator over all variants of this enum, in declaration order.
+                pub fn iter() -> impl Iterator<Item = Self> {
+                    Self::ALL.into_iter()
+                }
+            }
+        }
+    }
+}
+
+trait ConstConstructorCodeGen {
+    /// Generate a `pub const fn new(...)` constructor, if the values of all fields can be built in `const` contexts.
+    fn gen_const_constructor(&self, options: &MappingOptions) -> Option<TokenStream>;
+}
+
+impl ConstConstructorCodeGen for Node {
+    fn gen_const_constructor(&self, options: &MappingOptions) -> Option<TokenStream> {
+        if self.fields.iter().next().is_none()
+            || !self
+                .fields
+                .iter()
+                .all(|field| field.ty().is_const_constructible())
+        {
+            return None;
+        }
+
+        let ident = self.to_local_ident();
+
+        let mut params = vec![];
+        let mut values = vec![];
+
+        for (idx, field) in self.fields.iter().enumerate() {
+            let value = if let Some(ident) = field.to_ident() {
+                ident
+            } else {
+                format!("p{}", idx).parse::<TokenStream>().unwrap()
+            };
+
+            let ty = field.to_type_definition(&quote! {}, options);
+
+            params.push(quote! { #value: #ty });
+            values.push(value);
+        }
+
+        let body = self.to_struct_body(values);
+        let cfg = self.to_cfg();
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
+        Some(quote! {
+            #cfg
+            impl #lifetime #ident #lifetime {
+                /// Create a new value from its fields, usable in `const` contexts.
+                pub const fn new(#(#params),*) -> Self {
+                    Self #body
+                }
+            }
+        })
+    }
+}
+
+trait DefaultCodeGen {
+    /// Generate a `Default` impl, if every field is `#[option]` or has a default value.
+    ///
+    /// `#[init]` fields must be set by the caller, so nodes with them aren't `Default`, neither are nodes
+    /// that already derive `Default`.
+    fn gen_default(&self, kind: TypeKind, options: &MappingOptions) -> Option<TokenStream>;
+}
+
+impl DefaultCodeGen for Node {
+    fn gen_default(&self, kind: TypeKind, options: &MappingOptions) -> Option<TokenStream> {
+        if self.fields.iter().next().is_none()
+            || options
+                .extra_derives_of(kind)
+                .chain(self.derives())
+                .any(|derive| derive == "Default" || derive.ends_with("::Default"))
+        {
+            return None;
+        }
+
+        let mut values = vec![];
+
+        for field in self.fields.iter() {
+            if field.is_init_field() {
+                return None;
+            }
+
+            let value = if let Some(value) = field.to_default_value(options) {
+                value
+            } else if field.is_option() {
+                quote! { None }
+            } else {
+                return None;
+            };
+
+            values.push(field.to_init_clause(&value));
+        }
+
+        let ident = self.to_local_ident();
+        let body = self.to_struct_body(values);
+        let cfg = self.to_cfg();
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
+        // nodes with only `#[option]` fields could derive it, the impl keeps the derives independent of fields.
+        Some(quote! {
+            #cfg
+            #[allow(clippy::derivable_impls)]
+            impl #lifetime Default for #ident #lifetime {
+                fn default() -> Self {
+                    Self #body
+                }
+            }
+        })
+    }
+}
+
+trait BuilderCodeGen {
+    /// Generate a `new(...)` constructor taking the required fields and a `with_xxx(...)` setter for each
+    /// other field, if the node has named fields.
+    ///
+    /// `#[init]` fields and fields that are neither `#[option]` nor have a default value are required.
+    fn gen_builder(&self, options: &MappingOptions) -> Option<TokenStream>;
+}
+
+/// Returns the parameter type and the field value expr of a builder parameter of `field`,
+/// `#[option]` fields take the inner value.
+fn to_builder_param(
+    field: &Field<'_>,
+    param: &TokenStream,
+    options: &MappingOptions,
+) -> (TokenStream, TokenStream) {
+    let ty = field.to_value_type_definition(&quote! {}, options);
+
+    let (ty, mut value) = if field.is_variable() {
+        (
+            quote! { impl Into<mlang_rs::rt::opcode::Variable<#ty>> },
+            quote! { #param.into() },
+        )
+    } else {
+        (ty, param.clone())
+    };
+
+    if field.is_option() {
+        value = quote! { Some(#value) };
+    }
+
+    (ty, value)
+}
+
+impl BuilderCodeGen for Node {
+    fn gen_builder(&self, options: &MappingOptions) -> Option<TokenStream> {
+        if self.is_tuple() || self.fields.iter().next().is_none() {
+            return None;
+        }
+
+        let mut params = vec![];
+        let mut values = vec![];
+        let mut setters = vec![];
+
+        for field in self.fields.iter() {
+            let ident = field.to_ident()?;
+            let name = field.ident()?.1.to_snake_case();
+
+            let default_value = field.to_default_value(options);
+
+            if field.is_init_field() || (!field.is_option() && default_value.is_none()) {
+                let (ty, value) = to_builder_param(&field, &ident, options);
+
+                params.push(quote! { #ident: #ty });
+
+                if field.is_option() || field.is_variable() {
+                    values.push(quote! { #ident: #value });
+                } else {
+                    values.push(ident);
+                }
+
+                continue;
+            }
+
+            let default_value = default_value.unwrap_or(quote! { None });
+
+            values.push(quote! { #ident: #default_value });
+
+            let setter = format_ident!("with_{}", name);
+            let (ty, value) = to_builder_param(&field, &quote! { value }, options);
+            let doc = format!(" Set the `{}` field.", name);
+
+            setters.push(quote! {
+                #[doc = #doc]
+                pub fn #setter(mut self, value: #ty) -> Self {
+                    self.#ident = #value;
+                    self
+                }
+            });
+        }
+
+        let ident = self.to_local_ident();
+        let cfg = self.to_cfg();
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
+        Some(quote! {
+            #cfg
+            impl #lifetime #ident #lifetime {
+                /// Create a new value from its required fields, other fields are `None` or their default values.
+                pub fn new(#(#params),*) -> Self {
+                    Self { #(#values),* }
+                }
+
+                #(#setters)*
+            }
+        })
+    }
+}
+
+/// A generated item of the opcode module.
+enum Item<'a> {
+    /// The definitions of a type, with its `#[cfg(...)]` attribute.
+    Type(&'a Ident, TokenStream, TokenStream),
+    /// A namespace block with its items.
+    Mod(&'a Mod, Vec<Item<'a>>),
+}
+
+impl Item<'_> {
+    /// Returns the token streams of `items`, namespaces are inline modules.
+    fn flatten(items: Vec<Self>) -> Vec<TokenStream> {
+        items
+            .into_iter()
+            .map(|item| match item {
+                Item::Type(_, _, token_stream) => token_stream,
+                Item::Mod(node, items) => node.gen_mod(Self::flatten(items)),
+            })
+            .collect()
+    }
+
+    /// Write `items` to `files` as one file per type in the directory `dir`, with a `mod.rs` that declares the files
+    /// and re-exports their types, followed by `shared`. Namespaces are written to sub-directories.
+    fn write_files(
+        dir: &str,
+        items: Vec<Self>,
+        shared: Vec<TokenStream>,
+        files: &mut Vec<(String, TokenStream)>,
+    ) {
+        // names of the namespace modules and of the shared `ids`/`variable` modules.
+        let mut names = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Mod(node, _) => Some(node.ident.1.to_snake_case()),
+                Item::Type(..) => None,
+            })
+            .chain(["ids".to_string(), "variable".to_string()])
+            .collect::<Vec<_>>();
+
+        let prelude = if dir.is_empty() {
+            quote! {}
+        } else {
+            quote! {
+                #[allow(unused_imports)]
+                use super::*;
+            }
+        };
+
+        let mod_rs_index = files.len();
+        let mut decls = vec![];
+
+        for item in items {
+            match item {
+                Item::Type(ident, cfg, token_stream) => {
+                    let local = ident.1.rsplit("::").next().unwrap();
+                    let ty = format_ident!("{}", local.to_upper_camel_case());
+                    let mut name = local.to_snake_case();
+
+                    // e.g. a type `Ids` and the shared `ids` module.
+                    while names.contains(&name) {
+                        name.push_str("_ty");
+                    }
+
+                    names.push(name.clone());
+
+                    let mod_ident = Ident(ident.0, name.clone()).to_field_name();
+
+                    decls.push(quote! {
+                        #cfg
+                        mod #mod_ident;
+                        // not a glob, which would be ambiguous with the `use super::*` of namespaces.
+                        #cfg
+                        pub use #mod_ident::#ty;
+                    });
+
+                    files.push((
+                        format!("{}{}.rs", dir, name),
+                        quote! {
+                            #[allow(unused_imports)]
+                            use super::*;
+
+                            #token_stream
+                        },
+                    ));
+                }
+                Item::Mod(node, items) => {
+                    decls.push(node.gen_mod_decl());
+
+                    let dir = format!("{}{}/", dir, node.ident.1.to_snake_case());
+
+                    Self::write_files(&dir, items, vec![], files);
+                }
+            }
         }
+
+        files.insert(
+            mod_rs_index,
+            (
+                format!("{}mod.rs", dir),
+                quote! {
+                    #prelude
+                    #(#decls)*
+                    #(#shared)*
+                },
+            ),
+        );
     }
 }
 
 /// opcode module code generator.
 #[derive(Default)]
 struct CodeGen {
-    /// collection of data types.
-    data_types: Vec<TokenStream>,
-    /// collection of attr types
-    attr_types: Vec<TokenStream>,
-    /// collection of el types
-    el_types: Vec<TokenStream>,
-    /// collection of leaf node types.
-    leaf_types: Vec<TokenStream>,
+    /// collection of data types, with their `#[cfg(...)]` attributes.
+    data_types: Vec<(Ident, TokenStream)>,
+    /// collection of attr types, with their `#[cfg(...)]` attributes and type ids.
+    attr_types: Vec<(Ident, TokenStream, usize)>,
+    /// collection of el types, with their `#[cfg(...)]` attributes and type ids.
+    el_types: Vec<(Ident, TokenStream, usize)>,
+    /// collection of leaf node types, with their `#[cfg(...)]` attributes and type ids.
+    leaf_types: Vec<(Ident, TokenStream, usize)>,
+    /// collection of type id constants.
+    ids: Vec<TokenStream>,
+    /// type mapping options.
+    options: MappingOptions,
+    /// the schema version.
+    version: Option<String>,
+    /// the vocabulary metadata.
+    meta: Option<Meta>,
+    /// target elements and leaves with their attrs, merged from `apply ... to` stats.
+    applies: Vec<(Ident, Vec<Ident>)>,
+    /// parent elements with their children, merged from `children ... of` stats.
+    children: Vec<(Ident, Vec<Ident>)>,
 }
 
 impl CodeGen {
-    fn codegen(mut self, stats: &[Stat]) -> TokenStream {
-        let mut token_streams = vec![];
+    /// Generate the definitions of every type, and collect the types and links used by the shared definitions.
+    fn collect<'a>(&mut self, stats: &'a [Stat]) -> Vec<Item<'a>> {
+        let mut items = vec![];
+        // enclosing `mod` blocks, with the items of their parent module.
+        let mut mods = vec![];
+
+        for (index, opcode) in stats.iter().enumerate() {
+            let type_id = opcode.type_id(index);
 
-        for opcode in stats {
             match opcode {
                 Stat::Element(node) => {
-                    token_streams.push(node.codegen());
-                    self.el_types.push(node.to_ident());
+                    let definition = node.codegen(TypeKind::Element, &self.options);
+                    let default = node.gen_default(TypeKind::Element, &self.options);
+                    let builder = node.gen_builder(&self.options);
+
+                    items.push(Item::Type(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { #definition #default #builder },
+                    ));
+                    self.el_types
+                        .push((node.ident.clone(), node.to_cfg(), type_id));
+                    self.push_id(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { mlang_rs::rt::opcode::TypeId },
+                        type_id,
+                    );
                 }
                 Stat::Leaf(node) => {
-                    token_streams.push(node.codegen());
-                    self.leaf_types.push(node.to_ident());
+                    let definition = node.codegen(TypeKind::Leaf, &self.options);
+                    let default = node.gen_default(TypeKind::Leaf, &self.options);
+                    let builder = node.gen_builder(&self.options);
+
+                    items.push(Item::Type(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { #definition #default #builder },
+                    ));
+                    self.leaf_types
+                        .push((node.ident.clone(), node.to_cfg(), type_id));
+                    self.push_id(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { mlang_rs::rt::opcode::TypeId },
+                        type_id,
+                    );
                 }
                 Stat::Attr(node) => {
-                    token_streams.push(node.codegen());
-                    self.attr_types.push(node.to_ident());
+                    let definition = node.codegen(TypeKind::Attr, &self.options);
+                    let default = node.gen_default(TypeKind::Attr, &self.options);
+                    let builder = node.gen_builder(&self.options);
+
+                    items.push(Item::Type(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { #definition #default #builder },
+                    ));
+                    self.attr_types
+                        .push((node.ident.clone(), node.to_cfg(), type_id));
+                    self.push_id(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { mlang_rs::rt::opcode::AttrId },
+                        type_id,
+                    );
                 }
                 Stat::Data(node) => {
-                    token_streams.push(node.codegen());
-                    self.data_types.push(node.to_ident());
+                    let definition = node.codegen(TypeKind::Data, &self.options);
+                    let default = node.gen_default(TypeKind::Data, &self.options);
+                    let constructor = node.gen_const_constructor(&self.options);
+
+                    items.push(Item::Type(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { #definition #default #constructor },
+                    ));
+                    self.data_types.push((node.ident.clone(), node.to_cfg()));
+                    self.push_id(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { mlang_rs::rt::opcode::TypeId },
+                        type_id,
+                    );
                 }
                 Stat::Enum(node) => {
-                    token_streams.push(node.codegen());
-                    self.data_types.push(node.to_ident());
+                    items.push(Item::Type(
+                        &node.ident,
+                        node.to_cfg(),
+                        node.codegen(TypeKind::Enum, &self.options),
+                    ));
+                    self.data_types.push((node.ident.clone(), node.to_cfg()));
+                    self.push_id(
+                        &node.ident,
+                        node.to_cfg(),
+                        quote! { mlang_rs::rt::opcode::TypeId },
+                        type_id,
+                    );
+                }
+                Stat::Mod(node) => {
+                    mods.push((node.as_ref(), std::mem::take(&mut items)));
+                }
+                Stat::Version(version) => {
+                    self.version = Some(version.version.1.clone());
+                }
+                Stat::Meta(meta) => {
+                    self.meta = Some(meta.as_ref().clone());
+                }
+                Stat::ApplyTo(node) => {
+                    merge_links(&mut self.applies, &node.from, &node.to);
+                }
+                Stat::ChildrenOf(node) => {
+                    merge_links(&mut self.children, &node.from, &node.to);
+                }
+                Stat::ModEnd(_) => {
+                    let (node, parent) = mods.pop().expect("unbalanced mod block");
+                    let children = std::mem::replace(&mut items, parent);
+
+                    items.push(Item::Mod(node, children));
                 }
                 _ => {}
             }
         }
 
-        token_streams.push(self.gen_data_definition());
-        token_streams.push(self.gen_attr_definition());
-        token_streams.push(self.gen_el_definition());
-        token_streams.push(self.gen_leaf_definition());
-        token_streams.push(self.gen_opcode_definition());
-        token_streams.push(self.gen_variable_definition());
+        items
+    }
+
+    /// Generate the definitions shared by all types, which follow the types in the module.
+    fn gen_shared(&self, stats: &[Stat]) -> Vec<TokenStream> {
+        let mut token_streams = vec![
+            self.options.to_alloc_prelude(),
+            self.gen_data_definition(),
+            self.gen_attr_definition(),
+            self.gen_el_definition(),
+            self.gen_leaf_definition(),
+            self.gen_opcode_definition(),
+            self.gen_node_definition(),
+            self.gen_variable_definition(),
+            self.gen_ids_definition(),
+        ];
+
+        token_streams.extend(self.gen_version_definition());
+        token_streams.extend(self.gen_meta_definition());
+        token_streams.push(Self::gen_fingerprint_definition(stats));
+
+        token_streams
+    }
+
+    fn codegen(mut self, stats: &[Stat]) -> TokenStream {
+        let items = self.collect(stats);
+        let shared = self.gen_shared(stats);
+
+        let items = Item::flatten(items);
 
         quote! {
-            #(#token_streams)*
+            #(#items)*
+            #(#shared)*
+        }
+    }
+
+    fn codegen_files(mut self, stats: &[Stat]) -> Vec<(String, TokenStream)> {
+        let items = self.collect(stats);
+        let shared = self.gen_shared(stats);
+
+        let mut files = vec![];
+
+        Item::write_files("", items, shared, &mut files);
+
+        files
+    }
+
+    fn push_id(&mut self, ident: &Ident, cfg: TokenStream, ty: TokenStream, type_id: usize) {
+        let name = format_ident!("{}", ident.1.to_shouty_snake_case());
+
+        self.ids.push(quote! {
+            #cfg
+            pub const #name: #ty = #ty(#type_id);
+        });
+    }
+
+    fn gen_version_definition(&self) -> Option<TokenStream> {
+        let version = self.version.as_ref()?;
+
+        Some(quote! {
+            /// The version of the schema this module is generated from.
+            pub const SCHEMA_VERSION: &str = #version;
+        })
+    }
+
+    fn gen_meta_definition(&self) -> Option<TokenStream> {
+        let meta = self.meta.as_ref()?;
+
+        let name = meta.get("name")?;
+
+        let optional = |key: &str| match meta.get(key) {
+            Some(value) => quote! { Some(#value) },
+            None => quote! { None },
+        };
+
+        let version = optional("version");
+        let namespace = optional("namespace");
+
+        Some(quote! {
+            /// The metadata of the vocabulary this module is generated from.
+            pub const SCHEMA_META: mlang_rs::rt::opcode::SchemaMeta = mlang_rs::rt::opcode::SchemaMeta {
+                name: #name,
+                version: #version,
+                namespace: #namespace,
+            };
+        })
+    }
+
+    fn gen_fingerprint_definition(stats: &[Stat]) -> TokenStream {
+        let fingerprint = fingerprint(stats);
+
+        quote! {
+            /// The `mlang_rs::lang::fingerprint` of the schema this module is generated from,
+            /// the schema hash of [`Fragment`](mlang_rs::rt::fragment::Fragment)s.
+            pub const SCHEMA_FINGERPRINT: [u8; 32] = [#(#fingerprint),*];
+        }
+    }
+
+    fn gen_ids_definition(&self) -> TokenStream {
+        let ids = &self.ids;
+
+        quote! {
+            /// Stable type identifiers, the same values passed as `type_id` to the serializer/deserializer.
+            pub mod ids {
+                #(#ids)*
+            }
         }
     }
     fn gen_variable_definition(&self) -> TokenStream {
@@ -145,9 +756,26 @@ impl CodeGen {
         let mut fields = vec![];
         let mut froms = vec![];
 
+        let lifetime = self.options.to_lifetime_params("Data");
+        let ref_lifetimes = if self.options.is_borrowed("Data") {
+            let lifetime = self.options.to_lifetime();
+
+            quote! { <'a, #lifetime> }
+        } else {
+            quote! { <'a> }
+        };
+
         let builtin_types = vec![
             ("bool", "bool"),
-            ("string", "String"),
+            ("char", "char"),
+            (
+                "string",
+                match (self.options.cow_string, self.options.no_std) {
+                    (true, false) => "std::borrow::Cow<'static, str>",
+                    (true, true) => "alloc::borrow::Cow<'static, str>",
+                    (false, _) => "String",
+                },
+            ),
             ("byte", "i8"),
             ("ubyte", "u8"),
             ("short", "i16"),
@@ -156,8 +784,18 @@ impl CodeGen {
             ("uint", "u32"),
             ("long", "i64"),
             ("ulong", "u64"),
+            ("int128", "i128"),
+            ("uint128", "u128"),
             ("float", "f32"),
             ("double", "f64"),
+            ("pathdata", "mlang_rs::rt::value::PathData"),
+            ("transform", "mlang_rs::rt::value::Transform"),
+            ("rect", "mlang_rs::rt::value::Rect"),
+            ("viewbox", "mlang_rs::rt::value::ViewBox"),
+            ("duration", "mlang_rs::rt::value::Duration"),
+            ("datetime", "mlang_rs::rt::value::DateTime"),
+            ("uuid", "mlang_rs::rt::value::Uuid"),
+            ("iri", "mlang_rs::rt::value::Iri"),
         ];
 
         for (ident, ty) in builtin_types {
@@ -172,16 +810,16 @@ impl CodeGen {
             });
 
             froms.push(quote! {
-                impl From<#ty> for Data {
+                impl #lifetime From<#ty> for Data #lifetime {
                     fn from(value: #ty) -> Self {
                         Data::#ident(value)
                     }
                 }
 
-                impl<'a> TryFrom<&'a Data> for &'a #ty {
+                impl #ref_lifetimes TryFrom<&'a Data #lifetime> for &'a #ty {
                     type Error = ();
 
-                    fn try_from(value: &'a Data) -> Result<Self,Self::Error> {
+                    fn try_from(value: &'a Data #lifetime) -> Result<Self,Self::Error> {
                         match value {
                             Data::#ident(v) => Ok(v),
                             _ => Err(())
@@ -189,16 +827,16 @@ impl CodeGen {
                     }
                 }
 
-                impl From<Vec<#ty>> for Data {
+                impl #lifetime From<Vec<#ty>> for Data #lifetime {
                     fn from(value: Vec<#ty>) -> Self {
                         Data::#list_ident(Box::new(value))
                     }
                 }
 
-                impl<'a> TryFrom<&'a Data> for &'a Vec<#ty> {
+                impl #ref_lifetimes TryFrom<&'a Data #lifetime> for &'a Vec<#ty> {
                     type Error = ();
 
-                    fn try_from(value: &'a Data) -> Result<Self,Self::Error> {
+                    fn try_from(value: &'a Data #lifetime) -> Result<Self,Self::Error> {
                         match value {
                             Data::#list_ident(v) => Ok(v),
                             _ => Err(())
@@ -208,41 +846,52 @@ impl CodeGen {
             });
         }
 
-        for ident in &self.data_types {
-            let list_ident = format_ident!("ListOf{}", ident.to_string());
+        for (ident, cfg) in &self.data_types {
+            let variant = ident.to_variant_name();
+            let list_ident = format_ident!("ListOf{}", variant.to_string());
+            let ident = {
+                let lifetime = self.options.to_lifetime_params(&ident.1);
+                let ident = ident.to_type_name();
+
+                quote! { #ident #lifetime }
+            };
 
             fields.push(quote! {
-                #ident(Box<#ident>),#list_ident(Box<Vec<#ident>>)
+                #cfg #variant(Box<#ident>), #cfg #list_ident(Box<Vec<#ident>>)
             });
 
             froms.push(quote! {
-                impl From<#ident> for Data {
+                #cfg
+                impl #lifetime From<#ident> for Data #lifetime {
                     fn from(value: #ident) -> Self {
-                        Data::#ident(Box::new(value))
+                        Data::#variant(Box::new(value))
                     }
                 }
 
-                impl<'a> TryFrom<&'a Data> for &'a #ident {
+                #cfg
+                impl #ref_lifetimes TryFrom<&'a Data #lifetime> for &'a #ident {
                     type Error = ();
 
-                    fn try_from(value: &'a Data) -> Result<Self,Self::Error> {
+                    fn try_from(value: &'a Data #lifetime) -> Result<Self,Self::Error> {
                         match value {
-                            Data::#ident(v) => Ok(v),
+                            Data::#variant(v) => Ok(v),
                             _ => Err(())
                         }
                     }
                 }
 
-                impl From<Vec<#ident>> for Data {
+                #cfg
+                impl #lifetime From<Vec<#ident>> for Data #lifetime {
                     fn from(value: Vec<#ident>) -> Self {
                         Data::#list_ident(Box::new(value))
                     }
                 }
 
-                impl<'a> TryFrom<&'a Data> for &'a Vec<#ident> {
+                #cfg
+                impl #ref_lifetimes TryFrom<&'a Data #lifetime> for &'a Vec<#ident> {
                     type Error = ();
 
-                    fn try_from(value: &'a Data) -> Result<Self,Self::Error> {
+                    fn try_from(value: &'a Data #lifetime) -> Result<Self,Self::Error> {
                         match value {
                             Data::#list_ident(v) => Ok(v),
                             _ => Err(())
@@ -252,10 +901,13 @@ impl CodeGen {
             });
         }
 
+        let json_schema = self.options.to_json_schema_derive();
+
         quote! {
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum Data {
+            #json_schema
+            pub enum Data #lifetime {
                 #(#fields),*
             }
 
@@ -266,29 +918,57 @@ impl CodeGen {
     fn gen_el_definition(&self) -> TokenStream {
         let mut fields = vec![];
         let mut froms = vec![];
+        let mut type_ids = vec![];
+
+        let lifetime = self.options.to_lifetime_params("Element");
+
+        for (el, cfg, type_id) in &self.el_types {
+            let variant = el.to_variant_name();
+            let el = {
+                let lifetime = self.options.to_lifetime_params(&el.1);
+                let el = el.to_type_name();
+
+                quote! { #el #lifetime }
+            };
 
-        for el in &self.el_types {
             fields.push(quote! {
-                #el(Box<#el>)
+                #cfg #variant(Box<#el>)
+            });
+
+            type_ids.push(quote! {
+                #cfg Self::#variant(_) => mlang_rs::rt::opcode::TypeId(#type_id)
             });
 
             froms.push(quote! {
-                impl From<#el> for Element {
+                #cfg
+                impl #lifetime From<#el> for Element #lifetime {
                     fn from(value: #el) -> Self {
-                        Self::#el(Box::new(value))
+                        Self::#variant(Box::new(value))
                     }
                 }
             });
         }
 
+        let type_id_body = gen_match_self(&type_ids);
+
+        let json_schema = self.options.to_json_schema_derive();
+
         quote! {
 
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum Element {
+            #json_schema
+            pub enum Element #lifetime {
                 #(#fields),*
             }
 
+            impl #lifetime Element #lifetime {
+                /// Returns the stable type id of the wrapped type, the same value as its [`ids`] constant.
+                pub fn type_id(&self) -> mlang_rs::rt::opcode::TypeId {
+                    #type_id_body
+                }
+            }
+
             #(#froms)*
         }
     }
@@ -296,29 +976,57 @@ impl CodeGen {
     fn gen_leaf_definition(&self) -> TokenStream {
         let mut fields = vec![];
         let mut froms = vec![];
+        let mut type_ids = vec![];
+
+        let lifetime = self.options.to_lifetime_params("Leaf");
+
+        for (el, cfg, type_id) in &self.leaf_types {
+            let variant = el.to_variant_name();
+            let el = {
+                let lifetime = self.options.to_lifetime_params(&el.1);
+                let el = el.to_type_name();
+
+                quote! { #el #lifetime }
+            };
 
-        for el in &self.leaf_types {
             fields.push(quote! {
-                #el(Box<#el>)
+                #cfg #variant(Box<#el>)
+            });
+
+            type_ids.push(quote! {
+                #cfg Self::#variant(_) => mlang_rs::rt::opcode::TypeId(#type_id)
             });
 
             froms.push(quote! {
-                impl From<#el> for Leaf {
+                #cfg
+                impl #lifetime From<#el> for Leaf #lifetime {
                     fn from(value: #el) -> Self {
-                        Self::#el(Box::new(value))
+                        Self::#variant(Box::new(value))
                     }
                 }
             });
         }
 
+        let type_id_body = gen_match_self(&type_ids);
+
+        let json_schema = self.options.to_json_schema_derive();
+
         quote! {
 
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum Leaf {
+            #json_schema
+            pub enum Leaf #lifetime {
                 #(#fields),*
             }
 
+            impl #lifetime Leaf #lifetime {
+                /// Returns the stable type id of the wrapped type, the same value as its [`ids`] constant.
+                pub fn type_id(&self) -> mlang_rs::rt::opcode::TypeId {
+                    #type_id_body
+                }
+            }
+
             #(#froms)*
         }
     }
@@ -326,66 +1034,623 @@ impl CodeGen {
     pub(super) fn gen_attr_definition(&self) -> TokenStream {
         let mut fields = vec![];
         let mut froms = vec![];
+        let mut type_ids = vec![];
+
+        let lifetime = self.options.to_lifetime_params("Attr");
+
+        for (el, cfg, type_id) in &self.attr_types {
+            let variant = el.to_variant_name();
+            let el = {
+                let lifetime = self.options.to_lifetime_params(&el.1);
+                let el = el.to_type_name();
+
+                quote! { #el #lifetime }
+            };
 
-        for el in &self.attr_types {
             fields.push(quote! {
-                #el(Box<#el>)
+                #cfg #variant(Box<#el>)
+            });
+
+            type_ids.push(quote! {
+                #cfg Self::#variant(_) => mlang_rs::rt::opcode::AttrId(#type_id)
             });
 
             froms.push(quote! {
-                impl From<#el> for Attr {
+                #cfg
+                impl #lifetime From<#el> for Attr #lifetime {
                     fn from(value: #el) -> Self {
-                        Self::#el(Box::new(value))
+                        Self::#variant(Box::new(value))
                     }
                 }
             });
         }
 
+        let type_id_body = gen_match_self(&type_ids);
+
+        let json_schema = self.options.to_json_schema_derive();
+
         quote! {
 
             #[derive(Debug, PartialEq, PartialOrd, Clone)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum Attr {
+            #json_schema
+            pub enum Attr #lifetime {
                 #(#fields),*
             }
 
+            impl #lifetime Attr #lifetime {
+                /// Returns the stable type id of the wrapped type, the same value as its [`ids`] constant.
+                pub fn type_id(&self) -> mlang_rs::rt::opcode::AttrId {
+                    #type_id_body
+                }
+            }
+
             #(#froms)*
         }
     }
 
+    /// Generate the `XxxNode` of every target of `apply ... to` stats and parent of `children ... of` stats,
+    /// with the `XxxApplicableAttr` enum of its attrs and the `XxxChild` enum of its children.
+    ///
+    /// Links to types that aren't attrs, elements or leaves are ignored.
+    fn gen_node_definition(&self) -> TokenStream {
+        let find = |types: &[(Ident, TokenStream, usize)], ident: &Ident| {
+            types
+                .iter()
+                .find(|(ty, _, _)| ty.1 == ident.1)
+                .map(|(_, cfg, _)| cfg.clone())
+        };
+
+        let links_of = |links: &[(Ident, Vec<Ident>)], ident: &Ident| {
+            links
+                .iter()
+                .find(|(target, _)| target.1 == ident.1)
+                .map(|(_, from)| from.clone())
+        };
+
+        let has_node = |ident: &Ident| {
+            links_of(&self.applies, ident).is_some() || links_of(&self.children, ident).is_some()
+        };
+
+        let node_ident =
+            |ident: &Ident| format_ident!("{}Node", ident.to_variant_name().to_string());
+
+        // the attrs and children of a target, which are declared attrs, elements and leaves.
+        let attrs_of = |target: &Ident| {
+            links_of(&self.applies, target)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|attr| find(&self.attr_types, attr).is_some())
+                .collect::<Vec<_>>()
+        };
+
+        let children_of = |target: &Ident| {
+            links_of(&self.children, target)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|child| {
+                    find(&self.el_types, child).is_some() || find(&self.leaf_types, child).is_some()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // a node borrows from the deserializer input if its value, an applicable attr or a child does,
+        // children are nodes themselves, so it's a fixed point.
+        let mut borrowed_nodes = std::collections::BTreeSet::new();
+
+        loop {
+            let len = borrowed_nodes.len();
+
+            for (target, _, _) in self.el_types.iter().chain(&self.leaf_types) {
+                let borrowed = |ident: &Ident| {
+                    self.options.is_borrowed(&ident.1) || borrowed_nodes.contains(&ident.1)
+                };
+
+                if has_node(target)
+                    && (borrowed(target)
+                        || attrs_of(target).iter().any(borrowed)
+                        || children_of(target).iter().any(borrowed))
+                {
+                    borrowed_nodes.insert(target.1.clone());
+                }
+            }
+
+            if borrowed_nodes.len() == len {
+                break;
+            }
+        }
+
+        let lifetime_params = |borrowed: bool| {
+            if borrowed {
+                let lifetime = self.options.to_lifetime();
+
+                quote! { <#lifetime> }
+            } else {
+                quote! {}
+            }
+        };
+
+        let type_lifetime = |ident: &Ident| self.options.to_lifetime_params(&ident.1);
+
+        let node_lifetime = |ident: &Ident| lifetime_params(borrowed_nodes.contains(&ident.1));
+
+        let opcode_lifetime = self.options.to_lifetime_params("Opcode");
+        let attr_lifetime = self.options.to_lifetime_params("Attr");
+
+        let mut token_streams = vec![];
+
+        let targets = self
+            .el_types
+            .iter()
+            .map(|(ident, cfg, _)| (ident, cfg, true))
+            .chain(
+                self.leaf_types
+                    .iter()
+                    .map(|(ident, cfg, _)| (ident, cfg, false)),
+            );
+
+        for (target, cfg, is_element) in targets {
+            if !has_node(target) {
+                continue;
+            }
+
+            let ty = target.to_type_name();
+            let variant = target.to_variant_name();
+            let node = node_ident(target);
+            let doc_ty = ty.to_string().replace(' ', "");
+
+            let ty_lifetime = type_lifetime(target);
+            let lifetime = node_lifetime(target);
+
+            let mut fields = vec![];
+            let mut inits = vec![];
+            let mut methods = vec![];
+            let mut push_opcodes = vec![];
+
+            if links_of(&self.applies, target).is_some() {
+                let attrs = attrs_of(target);
+                let attr_ident = format_ident!("{}ApplicableAttr", variant.to_string());
+                let attr_ident_lifetime =
+                    lifetime_params(attrs.iter().any(|attr| self.options.is_borrowed(&attr.1)));
+
+                let mut variants = vec![];
+                let mut froms = vec![];
+                let mut arms = vec![];
+
+                for attr in attrs {
+                    let attr_cfg = find(&self.attr_types, &attr).expect("attrs_of");
+
+                    let attr_variant = attr.to_variant_name();
+                    let attr_ty = {
+                        let lifetime = type_lifetime(&attr);
+                        let attr_ty = attr.to_type_name();
+
+                        quote! { #attr_ty #lifetime }
+                    };
+
+                    variants.push(quote! { #attr_cfg #attr_variant(Box<#attr_ty>) });
+
+                    froms.push(quote! {
+                        #attr_cfg
+                        impl #attr_ident_lifetime From<#attr_ty> for #attr_ident #attr_ident_lifetime {
+                            fn from(value: #attr_ty) -> Self {
+                                Self::#attr_variant(Box::new(value))
+                            }
+                        }
+                    });
+
+                    arms.push(quote! {
+                        #attr_cfg
+                        #attr_ident::#attr_variant(value) => Attr::#attr_variant(value)
+                    });
+                }
+
+                let doc = format!(
+                    " An attr applicable to [`{}`], as declared by `apply ... to` statements.",
+                    doc_ty
+                );
+
+                let attr_ident = quote! { #attr_ident #attr_ident_lifetime };
+
+                token_streams.push(quote! {
+                    #cfg
+                    #[doc = #doc]
+                    #[derive(Debug, PartialEq, PartialOrd, Clone)]
+                    pub enum #attr_ident {
+                        #(#variants),*
+                    }
+
+                    #(#froms)*
+
+                    #cfg
+                    impl #attr_lifetime From<#attr_ident> for Attr #attr_lifetime {
+                        fn from(value: #attr_ident) -> Self {
+                            match value {
+                                #(#arms,)*
+                            }
+                        }
+                    }
+
+                    #cfg
+                    impl #lifetime #ty #ty_lifetime {
+                        /// Apply `attr` to this value, returns the node of this value with the attr.
+                        pub fn apply(self, attr: impl Into<#attr_ident>) -> #node #lifetime {
+                            #node::new(self).apply(attr)
+                        }
+                    }
+                });
+
+                fields.push(quote! {
+                    /// The attrs applied to the value, in document order.
+                    pub attrs: Vec<#attr_ident>
+                });
+
+                inits.push(quote! { attrs: vec![] });
+
+                methods.push(quote! {
+                    /// Apply `attr` to the value.
+                    pub fn apply(mut self, attr: impl Into<#attr_ident>) -> Self {
+                        self.attrs.push(attr.into());
+                        self
+                    }
+                });
+
+                push_opcodes.push(quote! {
+                    for attr in self.attrs {
+                        opcodes.push(Opcode::Apply(Attr::from(attr)));
+                    }
+                });
+            }
+
+            if is_element {
+                push_opcodes.push(quote! {
+                    opcodes.push(Opcode::Element(Element::from(self.value)));
+                });
+            } else {
+                push_opcodes.push(quote! {
+                    opcodes.push(Opcode::Leaf(Leaf::from(self.value)));
+                });
+            }
+
+            if links_of(&self.children, target).is_some() && is_element {
+                let children = children_of(target);
+                let child_path = format_ident!("{}Child", variant.to_string());
+                let child_ident_lifetime = lifetime_params(children.iter().any(|child| {
+                    self.options.is_borrowed(&child.1) || borrowed_nodes.contains(&child.1)
+                }));
+                let child_ident = quote! { #child_path #child_ident_lifetime };
+
+                let mut variants = vec![];
+                let mut froms = vec![];
+                let mut arms = vec![];
+
+                for child in children {
+                    let child_variant = child.to_variant_name();
+                    let child_ty = {
+                        let lifetime = type_lifetime(&child);
+                        let child_ty = child.to_type_name();
+
+                        quote! { #child_ty #lifetime }
+                    };
+
+                    let (child_cfg, is_child_element) = match find(&self.el_types, &child) {
+                        Some(child_cfg) => (child_cfg, true),
+                        None => (find(&self.leaf_types, &child).expect("children_of"), false),
+                    };
+
+                    if has_node(&child) {
+                        let child_node = {
+                            let lifetime = node_lifetime(&child);
+                            let child_node = node_ident(&child);
+
+                            quote! { #child_node #lifetime }
+                        };
+
+                        variants.push(quote! { #child_cfg #child_variant(Box<#child_node>) });
+
+                        let child_node_ident = node_ident(&child);
+
+                        froms.push(quote! {
+                            #child_cfg
+                            impl #child_ident_lifetime From<#child_node> for #child_ident {
+                                fn from(value: #child_node) -> Self {
+                                    Self::#child_variant(Box::new(value))
+                                }
+                            }
+
+                            #child_cfg
+                            impl #child_ident_lifetime From<#child_ty> for #child_ident {
+                                fn from(value: #child_ty) -> Self {
+                                    Self::#child_variant(Box::new(#child_node_ident::new(value)))
+                                }
+                            }
+                        });
+
+                        arms.push(quote! {
+                            #child_cfg
+                            #child_path::#child_variant(node) => node.push_opcodes(opcodes)
+                        });
+
+                        continue;
+                    }
+
+                    variants.push(quote! { #child_cfg #child_variant(Box<#child_ty>) });
+
+                    froms.push(quote! {
+                        #child_cfg
+                        impl #child_ident_lifetime From<#child_ty> for #child_ident {
+                            fn from(value: #child_ty) -> Self {
+                                Self::#child_variant(Box::new(value))
+                            }
+                        }
+                    });
+
+                    if is_child_element {
+                        arms.push(quote! {
+                            #child_cfg
+                            #child_path::#child_variant(value) => {
+                                opcodes.push(Opcode::Element(Element::#child_variant(value)));
+                                opcodes.push(Opcode::Pop);
+                            }
+                        });
+                    } else {
+                        arms.push(quote! {
+                            #child_cfg
+                            #child_path::#child_variant(value) => opcodes.push(Opcode::Leaf(Leaf::#child_variant(value)))
+                        });
+                    }
+                }
+
+                let doc = format!(
+                    " A child of a [`{}`] element, as declared by `children ... of` statements.",
+                    doc_ty
+                );
+
+                token_streams.push(quote! {
+                    #cfg
+                    #[doc = #doc]
+                    #[derive(Debug, PartialEq, PartialOrd, Clone)]
+                    pub enum #child_ident {
+                        #(#variants),*
+                    }
+
+                    #(#froms)*
+                });
+
+                fields.push(quote! {
+                    /// The children of the element, in document order.
+                    pub children: Vec<#child_ident>
+                });
+
+                inits.push(quote! { children: vec![] });
+
+                methods.push(quote! {
+                    /// Append `child` to the children of the element.
+                    pub fn append_child(&mut self, child: impl Into<#child_ident>) {
+                        self.children.push(child.into());
+                    }
+
+                    /// Append `child` to the children of the element.
+                    pub fn with_child(mut self, child: impl Into<#child_ident>) -> Self {
+                        self.append_child(child);
+                        self
+                    }
+                });
+
+                if !arms.is_empty() {
+                    push_opcodes.push(quote! {
+                        for child in self.children {
+                            match child {
+                                #(#arms,)*
+                            }
+                        }
+                    });
+                }
+            }
+
+            let (doc, into_opcodes_doc) = if is_element {
+                push_opcodes.push(quote! { opcodes.push(Opcode::Pop); });
+
+                (
+                    format!(" A [`{}`] element with its attrs and children.", doc_ty),
+                    " Returns the opcodes of the attrs, the element and its children, closed by a [`Pop`](Opcode::Pop).",
+                )
+            } else {
+                (
+                    format!(" A [`{}`] leaf with its attrs.", doc_ty),
+                    " Returns the opcodes of the attrs and the leaf.",
+                )
+            };
+
+            // the opcodes of a node borrow if any opcode does, which is a superset of the node lifetime.
+            token_streams.push(quote! {
+                #cfg
+                #[doc = #doc]
+                #[derive(Debug, PartialEq, PartialOrd, Clone)]
+                pub struct #node #lifetime {
+                    /// The value of the node.
+                    pub value: #ty #ty_lifetime,
+                    #(#fields,)*
+                }
+
+                #cfg
+                impl #opcode_lifetime #node #lifetime {
+                    /// Create a new node of `value`.
+                    pub fn new(value: #ty #ty_lifetime) -> Self {
+                        Self {
+                            value,
+                            #(#inits,)*
+                        }
+                    }
+
+                    #(#methods)*
+
+                    #[doc = #into_opcodes_doc]
+                    pub fn into_opcodes(self) -> Vec<Opcode #opcode_lifetime> {
+                        let mut opcodes = vec![];
+                        self.push_opcodes(&mut opcodes);
+                        opcodes
+                    }
+
+                    fn push_opcodes(self, opcodes: &mut Vec<Opcode #opcode_lifetime>) {
+                        #(#push_opcodes)*
+                    }
+                }
+
+                #cfg
+                impl #lifetime From<#ty #ty_lifetime> for #node #lifetime {
+                    fn from(value: #ty #ty_lifetime) -> Self {
+                        Self::new(value)
+                    }
+                }
+            });
+        }
+
+        quote! {
+            #(#token_streams)*
+        }
+    }
+
     fn gen_opcode_definition(&self) -> TokenStream {
+        let json_schema = self.options.to_json_schema_derive();
+
+        let lifetime = self.options.to_lifetime_params("Opcode");
+        let attr = self.options.to_lifetime_params("Attr");
+        let el = self.options.to_lifetime_params("Element");
+        let leaf = self.options.to_lifetime_params("Leaf");
+
         quote! {
             #[derive(Debug, Clone, PartialEq)]
             #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-            pub enum Opcode {
-                Apply(Attr),
-                Element(Element),
+            #json_schema
+            pub enum Opcode #lifetime {
+                Apply(Attr #attr),
+                Element(Element #el),
                 Pop,
-                Leaf(Leaf),
+                Leaf(Leaf #leaf),
             }
 
-            impl From<Attr> for Opcode {
-                fn from(value: Attr) -> Self {
+            impl #lifetime Opcode #lifetime {
+                /// Returns the stable id of the element, leaf or attr, `None` for [`Pop`](Self::Pop).
+                ///
+                /// Ids follow the order of the [`ids`] constants, so sorting by them is deterministic.
+                pub fn type_id(&self) -> Option<mlang_rs::rt::opcode::Id> {
+                    match self {
+                        Self::Apply(attr) => Some(mlang_rs::rt::opcode::Id::Attr(attr.type_id())),
+                        Self::Element(el) => Some(mlang_rs::rt::opcode::Id::Type(el.type_id())),
+                        Self::Pop => None,
+                        Self::Leaf(leaf) => Some(mlang_rs::rt::opcode::Id::Type(leaf.type_id())),
+                    }
+                }
+            }
+
+            impl #lifetime From<Attr #attr> for Opcode #lifetime {
+                fn from(value: Attr #attr) -> Self {
                     Self::Apply(value)
                 }
             }
 
-            impl From<Element> for Opcode {
-                fn from(value: Element) -> Self {
+            impl #lifetime From<Element #el> for Opcode #lifetime {
+                fn from(value: Element #el) -> Self {
                     Self::Element(value)
                 }
             }
 
-            impl From<Leaf> for Opcode {
-                fn from(value: Leaf) -> Self {
+            impl #lifetime From<Leaf #leaf> for Opcode #lifetime {
+                fn from(value: Leaf #leaf) -> Self {
                     Self::Leaf(value)
                 }
             }
+
+            impl #lifetime mlang_rs::rt::dom::Opcode for Opcode #lifetime {
+                type Attr = Attr #attr;
+                type Element = Element #el;
+                type Leaf = Leaf #leaf;
+
+                fn into_op(self) -> mlang_rs::rt::dom::Op<Attr #attr, Element #el, Leaf #leaf> {
+                    match self {
+                        Self::Apply(attr) => mlang_rs::rt::dom::Op::Apply(attr),
+                        Self::Element(el) => mlang_rs::rt::dom::Op::Element(el),
+                        Self::Pop => mlang_rs::rt::dom::Op::Pop,
+                        Self::Leaf(leaf) => mlang_rs::rt::dom::Op::Leaf(leaf),
+                    }
+                }
+
+                fn from_op(op: mlang_rs::rt::dom::Op<Attr #attr, Element #el, Leaf #leaf>) -> Self {
+                    match op {
+                        mlang_rs::rt::dom::Op::Apply(attr) => Self::Apply(attr),
+                        mlang_rs::rt::dom::Op::Element(el) => Self::Element(el),
+                        mlang_rs::rt::dom::Op::Pop => Self::Pop,
+                        mlang_rs::rt::dom::Op::Leaf(leaf) => Self::Leaf(leaf),
+                    }
+                }
+
+                fn type_id(data: &mlang_rs::rt::dom::Data<Element #el, Leaf #leaf>) -> mlang_rs::rt::opcode::TypeId {
+                    match data {
+                        mlang_rs::rt::dom::Data::Element(el) => el.type_id(),
+                        mlang_rs::rt::dom::Data::Leaf(leaf) => leaf.type_id(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Merge the link `from` of each `to` into `links`, a list of targets with their deduplicated `from`s.
+fn merge_links(links: &mut Vec<(Ident, Vec<Ident>)>, from: &[Ident], to: &[Ident]) {
+    for target in to {
+        let index = match links.iter().position(|(ident, _)| ident.1 == target.1) {
+            Some(index) => index,
+            None => {
+                links.push((target.clone(), vec![]));
+                links.len() - 1
+            }
+        };
+
+        let links = &mut links[index].1;
+
+        for ident in from {
+            if !links.iter().any(|link| link.1 == ident.1) {
+                links.push(ident.clone());
+            }
         }
     }
 }
 
+/// Generate a `match self` over `clauses`, `match *self {}` if there are none.
+fn gen_match_self(clauses: &[TokenStream]) -> TokenStream {
+    if clauses.is_empty() {
+        quote! { match *self {} }
+    } else {
+        quote! {
+            match self {
+                #(#clauses,)*
+            }
+        }
+    }
+}
+
+/// Generate opcode module from [`stats`](Stat) as one file per type, returns the path of every file relative to
+/// the module directory with its token stream, `mod.rs` first.
+///
+/// `mod.rs` declares and re-exports the type files and holds the definitions shared by all types,
+/// namespaces are written to sub-directories with their own `mod.rs`.
+pub fn gen_opcode_files(
+    stats: impl AsRef<[Stat]>,
+    options: &MappingOptions,
+) -> Vec<(String, TokenStream)> {
+    CodeGen {
+        options: options.with_borrows(stats.as_ref()),
+        ..Default::default()
+    }
+    .codegen_files(stats.as_ref())
+}
+
 /// Generate opcode module from [`stats`](Stat).
-pub fn gen_opcode_mod(stats: impl AsRef<[Stat]>) -> TokenStream {
-    CodeGen::default().codegen(stats.as_ref())
+pub fn gen_opcode_mod(stats: impl AsRef<[Stat]>, options: &MappingOptions) -> TokenStream {
+    CodeGen {
+        options: options.with_borrows(stats.as_ref()),
+        ..Default::default()
+    }
+    .codegen(stats.as_ref())
 }
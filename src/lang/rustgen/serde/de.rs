@@ -4,7 +4,7 @@ use quote::quote;
 use crate::lang::{
     ir::{Enum, Node},
     rustgen::{
-        mapping::{ComplexTypeMapping, FieldMapping},
+        mapping::{CfgMapping, ComplexTypeMapping, FieldMapping, MappingOptions, VariantMapping},
         serde::SerdeDisplayName,
     },
 };
@@ -13,6 +13,7 @@ pub(super) trait DeserializeCodeGen {
     fn gen_deserialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         deserialize_fn: TokenStream,
         idx: usize,
     ) -> TokenStream;
@@ -22,59 +23,78 @@ impl DeserializeCodeGen for Node {
     fn gen_deserialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         deserialize_fn: TokenStream,
         type_id: usize,
     ) -> TokenStream {
         let ident = self.to_ident();
 
-        let ty_name = self.display_name().unwrap();
+        let ty_name = self.to_serde_name(options);
 
         let mut clauses = vec![];
 
         for (idx, field) in self.fields.iter().enumerate() {
-            let name = if let Some(name) = field.display_name() {
-                quote! { Some(#name)}
+            let name = field.to_serde_field_name(options);
+            let path = field.to_serde_path_name(idx, options);
+
+            let ty = field.to_deserialize_type(&quote! {}, options);
+
+            // a field with a default value can only be missing from the input of self-describing formats,
+            // non self-describing formats are driven by the schema and read every field in declaration order.
+            let value = if let Some(default) = field.to_default_value(options) {
+                quote! {
+                    if self.self_describing {
+                        data.deserialize_field::<Option<#ty>>(#ty_name, #idx, #name)
+                            .map_err(|err| err.field_context(#ty_name, #path))?
+                            .unwrap_or_else(|| #default)
+                    } else {
+                        data.deserialize_field::<#ty>(#ty_name, #idx, #name)
+                            .map_err(|err| err.field_context(#ty_name, #path))?
+                    }
+                }
             } else {
-                quote! { None}
+                quote! {
+                    data.deserialize_field::<#ty>(#ty_name, #idx, #name)
+                        .map_err(|err| err.field_context(#ty_name, #path))?
+                }
             };
 
-            let ty = field.to_type_definition(&quote! {});
-
-            clauses.push(
-                field.to_init_clause(
-                    &quote! {data.deserialize_field::<#ty>(#ty_name, #idx, #name)?},
-                ),
-            );
+            clauses.push(field.to_init_clause(&value));
         }
 
         let body = self.to_struct_body(clauses);
 
-        let name = self.display_name().unwrap();
+        let name = self.to_serde_name(options);
+
+        let cfg = self.to_cfg();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
 
         quote! {
-            impl mlang_rs::rt::serde::de::Deserialize for #opcode_mod #ident {
+            #cfg
+            impl<'de> mlang_rs::rt::serde::de::Deserialize<'de> for #opcode_mod #ident #lifetime {
 
-                type Value = #opcode_mod #ident;
+                type Value = #opcode_mod #ident #lifetime;
 
                 fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where
-                    D: mlang_rs::rt::serde::de::Deserializer
+                    D: mlang_rs::rt::serde::de::Deserializer<'de>
                 {
                     use mlang_rs::rt::serde::de::*;
 
-                    let _ = deserializer;
-
-                    struct V;
+                    struct V {
+                        self_describing: bool,
+                    }
 
-                    impl Visitor for V {
-                        type Value = #opcode_mod #ident;
+                    impl<'de> Visitor<'de> for V {
+                        type Value = #opcode_mod #ident #lifetime;
 
                         #[allow(unused_mut)]
                         fn visit_node<A>(self, mut data: A) -> Result<Self::Value, A::Error>
                         where
-                            A: NodeAccess,
+                            A: NodeAccess<'de>,
                         {
-                            let _ = data;
+                            let _ = (self.self_describing, &data);
 
                             use #opcode_mod *;
 
@@ -84,7 +104,9 @@ impl DeserializeCodeGen for Node {
                         }
                     }
 
-                    deserializer.#deserialize_fn(#type_id, #name, V)
+                    let self_describing = deserializer.is_self_describing();
+
+                    deserializer.#deserialize_fn(#type_id, #name, V { self_describing })
                 }
             }
         }
@@ -95,6 +117,7 @@ impl DeserializeCodeGen for Enum {
     fn gen_deserialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         _: TokenStream,
         type_id: usize,
     ) -> TokenStream {
@@ -105,26 +128,24 @@ impl DeserializeCodeGen for Enum {
 
         for (idx, node) in self.fields.iter().enumerate() {
             let mut clauses = vec![];
-            let ty_name = node.display_name().unwrap();
+            let ty_name = node.to_serde_name(options);
 
             for (idx, field) in node.fields.iter().enumerate() {
-                let name = if let Some(name) = field.display_name() {
-                    quote! { Some(#name)}
-                } else {
-                    quote! { None}
-                };
+                let name = field.to_serde_field_name(options);
+                let path = field.to_serde_path_name(idx, options);
 
-                let ty = field.to_type_definition(opcode_mod);
+                let ty = node.to_field_deserialize_type(&field, opcode_mod, options);
 
-                clauses.push(field.to_init_clause(
-                    &quote! {node.deserialize_field::<#ty>(#ty_name, #idx, #name)?},
-                ));
+                clauses.push(field.to_init_clause(&quote! {
+                    node.deserialize_field::<#ty>(#ty_name, #idx, #name)
+                        .map_err(|err| err.field_context(#ty_name, #path))?
+                }));
             }
 
             let field = node.to_ident();
             let body = node.to_struct_body(clauses);
 
-            let name = node.display_name().unwrap();
+            let name = node.to_serde_name(options);
             idxs.push(quote! {
                 #idx => Ok(#opcode_mod #ty::#field #body)
             });
@@ -134,16 +155,41 @@ impl DeserializeCodeGen for Enum {
             });
         }
 
-        let name = self.display_name().unwrap();
+        let name = self.to_serde_name(options);
+
+        // with stripped names, variants are only visited by index.
+        let visit_enum_with = if options.strip_names {
+            quote! {}
+        } else {
+            quote! {
+                /// Visit enum field.
+                #[allow(unused_mut)]
+                fn visit_enum_with<A>(self, variant: &str, mut node: A) -> Result<Self::Value, A::Error>
+                where
+                    A: NodeAccess<'de>,
+                {
+                    let _ = node;
+                    match variant {
+                        #(#names,)*
+                        _ => Err(Error::UnknownVariant(#name.to_string(),variant.to_string()).into())
+                    }
+                }
+            }
+        };
+
+        let cfg = self.to_cfg();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
 
         quote! {
-            impl mlang_rs::rt::serde::de::Deserialize for #opcode_mod #ty {
+            #cfg
+            impl<'de> mlang_rs::rt::serde::de::Deserialize<'de> for #opcode_mod #ty #lifetime {
 
-                type Value = #opcode_mod #ty;
+                type Value = #opcode_mod #ty #lifetime;
 
                 fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where
-                    D: mlang_rs::rt::serde::de::Deserializer
+                    D: mlang_rs::rt::serde::de::Deserializer<'de>
                 {
                     use mlang_rs::rt::serde::de::*;
 
@@ -151,14 +197,14 @@ impl DeserializeCodeGen for Enum {
 
                     struct V;
 
-                    impl Visitor for V {
-                        type Value = #opcode_mod #ty;
+                    impl<'de> Visitor<'de> for V {
+                        type Value = #opcode_mod #ty #lifetime;
 
                         /// Visit enum field.
                         #[allow(unused_mut)]
                         fn visit_enum<A>(self, variant_index: usize, mut node: A) -> Result<Self::Value, A::Error>
                         where
-                            A: NodeAccess,
+                            A: NodeAccess<'de>,
                         {
                             let _ = node;
                             match variant_index {
@@ -167,18 +213,7 @@ impl DeserializeCodeGen for Enum {
                             }
                         }
 
-                        /// Visit enum field.
-                        #[allow(unused_mut)]
-                        fn visit_enum_with<A>(self, variant: &str, mut node: A) -> Result<Self::Value, A::Error>
-                        where
-                            A: NodeAccess,
-                        {
-                            let _ = node;
-                            match variant {
-                                #(#names,)*
-                                _ => Err(Error::UnknownVariant(#name.to_string(),variant.to_string()).into())
-                            }
-                        }
+                        #visit_enum_with
                     }
 
                     deserializer.deserialize_enum(#type_id, #name, V)
@@ -1,7 +1,7 @@
 mod de;
 mod ser;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use de::DeserializeCodeGen;
 use heck::ToLowerCamelCase;
@@ -11,11 +11,36 @@ use ser::SerializeCodeGen;
 
 use crate::lang::{
     ir::{Enum, Field, Node, Stat},
-    rustgen::mapping::ComplexTypeMapping,
+    rustgen::mapping::{CfgMapping, ComplexTypeMapping, IdentMapping, MappingOptions},
 };
 
 trait SerdeDisplayName {
     fn display_name(&self) -> Option<String>;
+
+    /// Returns the name passed to the serializer/deserializer, empty if names are stripped.
+    fn to_serde_name(&self, options: &MappingOptions) -> String {
+        if options.strip_names {
+            String::new()
+        } else {
+            self.display_name().unwrap_or_default()
+        }
+    }
+
+    /// Returns the `Option<&str>` field name passed to the serializer/deserializer, `None` if names are stripped.
+    fn to_serde_field_name(&self, options: &MappingOptions) -> TokenStream {
+        match self.display_name() {
+            Some(name) if !options.strip_names => quote! { Some(#name) },
+            _ => quote! { None },
+        }
+    }
+
+    /// Returns the name of a field in the path of deserialization errors, the `index` if names are stripped.
+    fn to_serde_path_name(&self, index: usize, options: &MappingOptions) -> String {
+        match self.display_name() {
+            Some(name) if !options.strip_names => name,
+            _ => index.to_string(),
+        }
+    }
 }
 
 impl SerdeDisplayName for Node {
@@ -48,60 +73,77 @@ impl<'a> SerdeDisplayName for Field<'a> {
     }
 }
 
-struct CodeGen(TokenStream);
+struct CodeGen(TokenStream, MappingOptions);
 
 impl CodeGen {
     /// Create new sexpr mode generator
-    pub fn new(opcode_mod: impl AsRef<str>) -> Self {
-        Self(opcode_mod.as_ref().parse().unwrap())
+    pub fn new(opcode_mod: impl AsRef<str>, options: &MappingOptions) -> Self {
+        Self(opcode_mod.as_ref().parse().unwrap(), options.clone())
     }
 
     /// Generate sexpr mod
     pub fn codegen(self, stats: &[Stat]) -> TokenStream {
         let opcode_mod = &self.0;
+        let options = &self.1;
 
         let mut impls: Vec<TokenStream> = vec![];
 
-        let mut attr_fields: HashMap<String, Vec<String>> = Default::default();
-        let mut apply_attrs: HashMap<String, HashSet<String>> = Default::default();
-        let mut display_names: HashMap<String, String> = Default::default();
+        // ordered maps, so that the generated code is identical between builds.
+        let mut attr_fields: BTreeMap<String, Vec<String>> = Default::default();
+        let mut apply_attrs: BTreeMap<String, BTreeSet<String>> = Default::default();
+        let mut display_names: BTreeMap<String, String> = Default::default();
+        let mut cfgs: BTreeMap<String, TokenStream> = Default::default();
+
+        for (index, stat) in stats.iter().enumerate() {
+            let idx = stat.type_id(index);
 
-        for (idx, stat) in stats.iter().enumerate() {
             match stat {
                 Stat::Element(node) => {
-                    impls.push(node.gen_serialize_trait(opcode_mod, quote! { serialize_el }, idx));
+                    impls.push(node.gen_serialize_trait(
+                        opcode_mod,
+                        options,
+                        quote! { serialize_el },
+                        idx,
+                    ));
                     impls.push(node.gen_deserialize_trait(
                         opcode_mod,
+                        options,
                         quote! { deserialize_element },
                         idx,
                     ));
 
                     display_names.insert(node.ident.1.clone(), node.display_name().unwrap());
+                    cfgs.insert(node.ident.1.clone(), node.to_cfg());
                 }
                 Stat::Leaf(node) => {
                     impls.push(node.gen_serialize_trait(
                         opcode_mod,
+                        options,
                         quote! { serialize_leaf },
                         idx,
                     ));
 
                     impls.push(node.gen_deserialize_trait(
                         opcode_mod,
+                        options,
                         quote! { deserialize_leaf },
                         idx,
                     ));
 
                     display_names.insert(node.ident.1.clone(), node.display_name().unwrap());
+                    cfgs.insert(node.ident.1.clone(), node.to_cfg());
                 }
                 Stat::Attr(node) => {
                     impls.push(node.gen_serialize_trait(
                         opcode_mod,
+                        options,
                         quote! { serialize_attr },
                         idx,
                     ));
 
                     impls.push(node.gen_deserialize_trait(
                         opcode_mod,
+                        options,
                         quote! { deserialize_attr },
                         idx,
                     ));
@@ -115,16 +157,19 @@ impl CodeGen {
                     );
 
                     display_names.insert(node.ident.1.clone(), node.display_name().unwrap());
+                    cfgs.insert(node.ident.1.clone(), node.to_cfg());
                 }
                 Stat::Data(node) => {
                     impls.push(node.gen_serialize_trait(
                         opcode_mod,
+                        options,
                         quote! { serialize_data },
                         idx,
                     ));
 
                     impls.push(node.gen_deserialize_trait(
                         opcode_mod,
+                        options,
                         quote! { deserialize_data },
                         idx,
                     ));
@@ -132,15 +177,22 @@ impl CodeGen {
                 Stat::Enum(node) => {
                     impls.push(node.gen_serialize_trait(
                         opcode_mod,
+                        options,
                         quote! { serialize_enum },
                         idx,
                     ));
 
                     impls.push(node.gen_deserialize_trait(
                         opcode_mod,
+                        options,
                         quote! { deserialize_enum },
                         idx,
                     ));
+
+                    // binary-only builds don't carry the display names.
+                    if !options.strip_names {
+                        impls.extend(self.gen_enum_display(node));
+                    }
                 }
                 Stat::ApplyTo(apply_to) => {
                     for from in &apply_to.from {
@@ -156,29 +208,154 @@ impl CodeGen {
             }
         }
 
-        let fileds_to_attrs = self.gen_fields_to_attrs(&apply_attrs, attr_fields, display_names);
+        let fileds_to_attrs =
+            self.gen_fields_to_attrs(&apply_attrs, attr_fields, display_names, cfgs);
 
+        // binary-only builds don't carry the display names.
+        if !options.strip_names {
+            impls.push(self.gen_name_tables(stats));
+        }
+
+        impls.extend(self.gen_check_schema_version(stats));
         impls.push(self.gen_opcode_serialize_trait(stats));
         impls.push(self.gen_opcode_deserialize_trait(fileds_to_attrs, stats));
 
+        let prelude = options.to_alloc_prelude();
+
         quote! {
+            #prelude
             #(#impls)*
         }
     }
 
+    /// Generate the `pub static` name tables, which are shared by the deserializer and other runtime consumers.
+    fn gen_name_tables(&self, stats: &[Stat]) -> TokenStream {
+        let mut element_names = vec![];
+        let mut leaf_names = vec![];
+        let mut attr_names = vec![];
+        let mut enum_names = vec![];
+
+        for stat in stats {
+            match stat {
+                Stat::Element(node) | Stat::Leaf(node) | Stat::Attr(node) => {
+                    let (cfg, name) = (node.to_cfg(), node.display_name().unwrap());
+
+                    let names = match stat {
+                        Stat::Element(_) => &mut element_names,
+                        Stat::Leaf(_) => &mut leaf_names,
+                        _ => &mut attr_names,
+                    };
+
+                    names.push(quote! { #cfg #name });
+                }
+                Stat::Enum(node) => {
+                    let (cfg, name) = (node.to_cfg(), node.display_name().unwrap());
+
+                    let variants = node
+                        .fields
+                        .iter()
+                        .map(|variant| variant.display_name().unwrap());
+
+                    enum_names.push(quote! { #cfg (#name, &[#(#variants),*]) });
+                }
+                _ => {}
+            }
+        }
+
+        quote! {
+            /// Display names of the elements, in declaration order.
+            pub static ELEMENT_NAMES: &[&str] = &[#(#element_names),*];
+
+            /// Display names of the leaves, in declaration order.
+            pub static LEAF_NAMES: &[&str] = &[#(#leaf_names),*];
+
+            /// Display names of the attrs, in declaration order.
+            pub static ATTR_NAMES: &[&str] = &[#(#attr_names),*];
+
+            /// Display names of the enums, with the display names of their variants in declaration order.
+            pub static ENUM_NAMES: &[(&str, &[&str])] = &[#(#enum_names),*];
+        }
+    }
+
+    /// Generate `Display` and `FromStr` impls of a unit-like enum, which convert variants from/to their display names.
+    fn gen_enum_display(&self, node: &Enum) -> Option<TokenStream> {
+        if !node.is_unit_like() || node.fields.is_empty() {
+            return None;
+        }
+
+        let opcode_mod = &self.0;
+        let ident = node.to_ident();
+        let cfg = node.to_cfg();
+        let name = node.display_name().unwrap();
+
+        let mut to_names = vec![];
+        let mut from_names = vec![];
+
+        for variant in node.fields.iter() {
+            let variant_ident = variant.to_ident();
+            let variant_name = variant.display_name().unwrap();
+
+            to_names.push(quote! { Self::#variant_ident => #variant_name });
+            from_names.push(quote! { #variant_name => Ok(Self::#variant_ident) });
+        }
+
+        Some(quote! {
+            #cfg
+            impl core::fmt::Display for #opcode_mod #ident {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.write_str(match self {
+                        #(#to_names,)*
+                    })
+                }
+            }
+
+            #cfg
+            impl core::str::FromStr for #opcode_mod #ident {
+                type Err = mlang_rs::rt::serde::de::Error;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    match s {
+                        #(#from_names,)*
+                        _ => Err(mlang_rs::rt::serde::de::Error::UnknownVariant(#name.to_string(), s.to_string())),
+                    }
+                }
+            }
+        })
+    }
+
+    fn gen_check_schema_version(&self, stats: &[Stat]) -> Option<TokenStream> {
+        let opcode_mod = &self.0;
+
+        stats
+            .iter()
+            .any(|stat| matches!(stat, Stat::Version(_)))
+            .then(|| {
+                quote! {
+                    /// Check if a document written with schema version `document` can be read by this schema.
+                    pub fn check_schema_version(document: &str) -> Result<(), mlang_rs::rt::serde::de::Error> {
+                        mlang_rs::rt::serde::de::check_schema_version(#opcode_mod SCHEMA_VERSION, document)
+                    }
+                }
+            })
+    }
+
     fn gen_fields_to_attrs(
         &self,
-        apply_attrs: &HashMap<String, HashSet<String>>,
-        attr_fields: HashMap<String, Vec<String>>,
-        display_names: HashMap<String, String>,
+        apply_attrs: &BTreeMap<String, BTreeSet<String>>,
+        attr_fields: BTreeMap<String, Vec<String>>,
+        display_names: BTreeMap<String, String>,
+        cfgs: BTreeMap<String, TokenStream>,
     ) -> TokenStream {
-        let mut clauses = vec![];
-
-        let mut keys = apply_attrs.keys().collect::<Vec<_>>();
+        if apply_attrs.is_empty() {
+            // no `apply ... to` stats, keeps the generated loop free of unused warnings.
+            return quote! {
+                let _ = (attr_name, &mut attrs);
+            };
+        }
 
-        keys.sort();
+        let mut clauses = vec![];
 
-        for to in keys {
+        for (to, attrs) in apply_attrs {
             let ty = display_names.get(to).expect(&format!(
                 "apply to node({})'s display name is not found",
                 to
@@ -186,31 +363,34 @@ impl CodeGen {
 
             let mut fields_clauses = vec![];
 
-            let mut attrs = apply_attrs[to].iter().collect::<Vec<_>>();
-
-            attrs.sort();
-
             for attr in attrs {
                 let name = display_names
                     .get(attr)
                     .expect(&format!("attr({})'s display name is not found", attr));
 
+                let cfg = &cfgs[attr];
+
                 fields_clauses.push(quote! {
+                    #cfg
                     #[allow(unreachable_patterns)]
-                    #name => { attrs.insert(#name); },
+                    #name => { if !attrs.contains(&#name) { attrs.push(#name); } },
                 });
 
                 if let Some(fields) = attr_fields.get(attr) {
                     fields_clauses.push(quote! {
                         #(
+                            #cfg
                             #[allow(unreachable_patterns)]
-                            #fields => { attrs.insert(#name); },
+                            #fields => { if !attrs.contains(&#name) { attrs.push(#name); } },
                         )*
                     });
                 }
             }
 
+            let cfg = &cfgs[to];
+
             clauses.push(quote! {
+                #cfg
                 #ty => {
                     match attr_name {
                         #(#fields_clauses)*
@@ -237,8 +417,10 @@ impl CodeGen {
         from: impl FnOnce(TokenStream) -> TokenStream,
     ) -> TokenStream {
         let ident = node.to_ident();
+        let cfg = node.to_cfg();
         let state = from(quote! { #ident::deserialize(deserializer)? });
         quote! {
+            #cfg
             #type_id => #state
         }
     }
@@ -250,8 +432,10 @@ impl CodeGen {
     ) -> TokenStream {
         let ident = node.to_ident();
         let name = node.display_name().unwrap();
+        let cfg = node.to_cfg();
         let state = from(quote! { #ident::deserialize(deserializer)? });
         quote! {
+            #cfg
             #name => #state
         }
     }
@@ -266,13 +450,12 @@ impl CodeGen {
 
         let mut visit_opcode_clauses = vec![];
         let mut visit_opcode_with_clauses = vec![];
-        let mut element_names = vec![];
-        let mut leaf_names = vec![];
 
-        for (type_id, state) in stats.iter().enumerate() {
+        for (index, state) in stats.iter().enumerate() {
+            let type_id = state.type_id(index);
+
             match state {
                 Stat::Element(node) => {
-                    element_names.push(node.display_name().unwrap());
                     visit_opcode_clauses.push(self.gen_visit_opcode_clause(
                         type_id,
                         node,
@@ -292,7 +475,6 @@ impl CodeGen {
                     ));
                 }
                 Stat::Leaf(node) => {
-                    leaf_names.push(node.display_name().unwrap());
                     visit_opcode_clauses.push(self.gen_visit_opcode_clause(
                         type_id,
                         node,
@@ -334,13 +516,90 @@ impl CodeGen {
             }
         }
 
+        // a schema without elements, leaves and attrs has no opcode to visit.
+        let unused_deserializer = visit_opcode_clauses
+            .is_empty()
+            .then(|| quote! { let _ = deserializer; });
+
+        // with stripped names, only the `type_id` dispatch is generated, the named paths fall back to the
+        // default visitor methods, which fail with a type error.
+        let named = if self.1.strip_names {
+            quote! {}
+        } else {
+            quote! {
+                fn is_element(&self, name: &str) -> bool
+                {
+                    ELEMENT_NAMES.contains(&name)
+                }
+
+                fn is_leaf(&self, name: &str) -> bool
+                {
+                    LEAF_NAMES.contains(&name)
+                }
+
+                fn visit_opcode_with<D>(self, name: &str, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    #[allow(unused_imports)]
+                    use #opcode_mod *;
+
+                    #unused_deserializer
+
+                    match name {
+                        #(#visit_opcode_with_clauses,)*
+                        _ => {
+                            return Err(mlang_rs::rt::serde::de::Error::UnknownTypeName(name.to_string()).into());
+                        }
+                    }
+                }
+
+                fn visit_opcode_with_attrs<D>(
+                    self,
+                    name: &str,
+                    mut deserializer: D,
+                ) -> Result<Vec<Self::Value>, D::Error>
+                where
+                    D: AttrsNodeAccess<'de>,
+                {
+                    let _ = name;
+                    let _ = deserializer;
+
+                    let shadowed = deserializer.duplicate_attrs().shadowed(deserializer.attrs())?;
+
+                    for index in shadowed {
+                        deserializer.remove_attr(index);
+                    }
+
+                    // applied attrs, in the document order of their first attribute.
+                    let mut attrs: Vec<&str> = vec![];
+
+                    for attr_name in deserializer.attrs() {
+                        #fileds_to_attrs
+                    }
+
+                    let mut opcodes = vec![];
+
+                    for attr in attrs {
+                        opcodes.push(deserializer.deserialize_attr(attr,Self)?);
+                    }
+
+                    opcodes.push(deserializer.deserialize_attr(name,Self)?);
+
+                    Ok(opcodes)
+                }
+            }
+        };
+
+        let lifetime = self.1.to_lifetime_params("Opcode");
+
         quote! {
-            impl mlang_rs::rt::serde::de::Deserialize for #opcode_mod Opcode {
-                type Value = Option<Vec<#opcode_mod Opcode>>;
+            impl<'de> mlang_rs::rt::serde::de::Deserialize<'de> for #opcode_mod Opcode #lifetime {
+                type Value = Option<Vec<#opcode_mod Opcode #lifetime>>;
 
                 fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
                 where
-                    D: mlang_rs::rt::serde::de::Deserializer
+                    D: mlang_rs::rt::serde::de::Deserializer<'de>
 
                 {
                     use mlang_rs::rt::serde::de::*;
@@ -349,32 +608,17 @@ impl CodeGen {
 
                     struct V;
 
-                    impl Visitor for V {
-                        type Value = #opcode_mod Opcode;
-
-                        fn is_element(&self, name: &str) -> bool
-                        {
-                            match name {
-                                #(#element_names)|* => true,
-                                _ => false
-                            }
-
-                        }
-
-                        fn is_leaf(&self, name: &str) -> bool
-                        {
-                            match name {
-                                #(#leaf_names)|* => true,
-                                _ => false
-                            }
-
-                        }
+                    impl<'de> Visitor<'de> for V {
+                        type Value = #opcode_mod Opcode #lifetime;
 
                         fn visit_opcode<D>(self, type_id: usize, deserializer: D) -> Result<Self::Value, D::Error>
                         where
-                            D: Deserializer,
+                            D: Deserializer<'de>,
                         {
-                            use crate::opcode::*;
+                            #[allow(unused_imports)]
+                            use #opcode_mod *;
+
+                            #unused_deserializer
 
                             match type_id {
                                 #(#visit_opcode_clauses,)*
@@ -384,51 +628,7 @@ impl CodeGen {
                             }
                         }
 
-                        fn visit_opcode_with<D>(self, name: &str, deserializer: D) -> Result<Self::Value, D::Error>
-                        where
-                            D: Deserializer,
-                        {
-                            use crate::opcode::*;
-
-                            match name {
-                                #(#visit_opcode_with_clauses,)*
-                                _ => {
-                                    return Err(mlang_rs::rt::serde::de::Error::UnknownTypeName(name.to_string()).into());
-                                }
-                            }
-                        }
-
-                        fn visit_opcode_with_attrs<D>(
-                            self,
-                            name: &str,
-                            mut deserializer: D,
-                        ) -> Result<Vec<Self::Value>, D::Error>
-                        where
-                            D: AttrsNodeAccess,
-                        {
-                            let _ = name;
-                            let _ = deserializer;
-
-                            let mut attrs = std::collections::HashSet::new();
-
-                            for attr_name in deserializer.attrs() {
-                                #fileds_to_attrs
-                            }
-
-                            let mut opcodes = vec![];
-
-                            let mut attrs = attrs.into_iter().collect::<Vec<_>>();
-
-                            attrs.sort();
-
-                            for attr in attrs {
-                                opcodes.push(deserializer.deserialize_attr(attr,Self)?);
-                            }
-
-                            opcodes.push(deserializer.deserialize_attr(name,Self)?);
-
-                            Ok(opcodes)
-                        }
+                        #named
 
                         fn visit_pop<E>(self) -> Result<Self::Value, E>
                         where
@@ -454,23 +654,32 @@ impl CodeGen {
         for stat in stats.iter() {
             match stat {
                 Stat::Element(node) => {
-                    let ident = node.to_ident();
+                    let ident = node.ident.to_variant_name();
+
+                    let cfg = node.to_cfg();
 
                     ser_els.push(quote! {
+                        #cfg
                         #opcode_mod Element::#ident(value) => value.serialize(serializer)
                     });
                 }
                 Stat::Leaf(node) => {
-                    let ident = node.to_ident();
+                    let ident = node.ident.to_variant_name();
+
+                    let cfg = node.to_cfg();
 
                     ser_leaves.push(quote! {
+                        #cfg
                         #opcode_mod Leaf::#ident(value) => value.serialize(serializer)
                     });
                 }
                 Stat::Attr(node) => {
-                    let ident = node.to_ident();
+                    let ident = node.ident.to_variant_name();
+
+                    let cfg = node.to_cfg();
 
                     ser_attrs.push(quote! {
+                        #cfg
                         #opcode_mod Attr::#ident(value) => value.serialize(serializer)
                     });
                 }
@@ -478,27 +687,35 @@ impl CodeGen {
             }
         }
 
+        // `match v {}` doesn't compile for a reference to an empty enum.
+        let match_v = |arms: Vec<TokenStream>| {
+            if arms.is_empty() {
+                quote! { match *v {} }
+            } else {
+                quote! { match v { #(#arms),* } }
+            }
+        };
+
+        let (ser_attrs, ser_els, ser_leaves) =
+            (match_v(ser_attrs), match_v(ser_els), match_v(ser_leaves));
+
+        let lifetime = self.1.to_lifetime_params("Opcode");
+
         quote! {
-            impl mlang_rs::rt::serde::ser::Serialize for #opcode_mod Opcode {
+            impl #lifetime mlang_rs::rt::serde::ser::Serialize for #opcode_mod Opcode #lifetime {
                 fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
                 where
                     S: mlang_rs::rt::serde::ser::Serializer
                 {
                     match self {
                         Self::Apply(v) => {
-                            match v {
-                                #(#ser_attrs),*
-                            }
+                            #ser_attrs
                         },
                         Self::Element(v) => {
-                            match v {
-                                #(#ser_els),*
-                            }
+                            #ser_els
                         },
                         Self::Leaf(v) => {
-                            match v {
-                                #(#ser_leaves),*
-                            }
+                            #ser_leaves
                         }
                         Self::Pop => {
                             serializer.serialize_pop()
@@ -512,6 +729,17 @@ impl CodeGen {
 }
 
 /// Generate serde module from [`stats`](Stat).
-pub fn gen_serde_mod(stats: impl AsRef<[Stat]>, opcode_mod: impl AsRef<str>) -> TokenStream {
-    CodeGen::new(opcode_mod).codegen(stats.as_ref())
+pub fn gen_serde_mod(
+    stats: impl AsRef<[Stat]>,
+    opcode_mod: impl AsRef<str>,
+    options: &MappingOptions,
+) -> TokenStream {
+    let stats = stats.as_ref();
+
+    // generated impls borrow from the input of the deserializer.
+    CodeGen::new(
+        opcode_mod,
+        &options.with_borrows(stats).with_lifetime("'de"),
+    )
+    .codegen(stats)
 }
@@ -4,7 +4,7 @@ use quote::quote;
 use crate::lang::{
     ir::{Enum, Node},
     rustgen::{
-        mapping::{ComplexTypeMapping, FieldMapping},
+        mapping::{CfgMapping, ComplexTypeMapping, FieldMapping, MappingOptions},
         serde::SerdeDisplayName,
     },
 };
@@ -13,6 +13,7 @@ pub(super) trait SerializeCodeGen {
     fn gen_serialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         serialize_fn: TokenStream,
         idx: usize,
     ) -> TokenStream;
@@ -22,11 +23,12 @@ impl SerializeCodeGen for Node {
     fn gen_serialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         serialize_fn: TokenStream,
         idx: usize,
     ) -> TokenStream {
         let ident = self.to_ident();
-        let name = self.display_name().unwrap();
+        let name = self.to_serde_name(options);
 
         let mut stats = vec![];
 
@@ -37,14 +39,12 @@ impl SerializeCodeGen for Node {
                 format!("self.{}", idx).parse::<TokenStream>().unwrap()
             };
 
-            let name = if let Some(name) = field.display_name() {
-                quote! { Some(#name) }
-            } else {
-                quote! { None }
-            };
+            let name = field.to_serde_field_name(options);
+
+            let value = field.to_serialize_value(&quote! { &#value });
 
             stats.push(quote! {
-                serializer.serialize_field(#idx, #name, &#value)?
+                serializer.serialize_field(#idx, #name, #value)?
             });
         }
 
@@ -56,8 +56,13 @@ impl SerializeCodeGen for Node {
             quote! {mut}
         };
 
+        let cfg = self.to_cfg();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
         quote! {
-            impl mlang_rs::rt::serde::ser::Serialize for #opcode_mod #ident {
+            #cfg
+            impl #lifetime mlang_rs::rt::serde::ser::Serialize for #opcode_mod #ident #lifetime {
                 fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
                 where
                     S: mlang_rs::rt::serde::ser::Serializer
@@ -77,11 +82,12 @@ impl SerializeCodeGen for Enum {
     fn gen_serialize_trait(
         &self,
         opcode_mod: &TokenStream,
+        options: &MappingOptions,
         serialize_fn: TokenStream,
         type_id: usize,
     ) -> TokenStream {
         let mut stats = vec![];
-        let enum_name = self.display_name().unwrap();
+        let enum_name = self.to_serde_name(options);
 
         for (idx, node) in self.fields.iter().enumerate() {
             let ident = node.to_ident();
@@ -91,15 +97,22 @@ impl SerializeCodeGen for Enum {
 
             for (idx, field) in node.fields.iter().enumerate() {
                 if let Some(ident) = field.to_ident() {
-                    let name = ident.to_string();
+                    let name = if options.strip_names {
+                        quote! { None }
+                    } else {
+                        let name = ident.to_string();
+                        quote! { Some(#name) }
+                    };
+                    let value = field.to_serialize_value(&ident);
                     node_stats.push(quote! {
-                        serializer.serialize_field(#idx, Some(#name), #ident)?;
+                        serializer.serialize_field(#idx, #name, #value)?;
                     });
                     fields.push(ident);
                 } else {
                     let ident = format!("p{}", idx).parse::<TokenStream>().unwrap();
+                    let value = field.to_serialize_value(&ident);
                     node_stats.push(quote! {
-                        serializer.serialize_field(#idx, None, #ident)?;
+                        serializer.serialize_field(#idx, None, #value)?;
                     });
                     fields.push(ident);
                 }
@@ -109,7 +122,7 @@ impl SerializeCodeGen for Enum {
 
             let body = node.to_struct_body(fields);
 
-            let variant = node.display_name().unwrap();
+            let variant = node.to_serde_name(options);
 
             let mut_token = if node_stats.is_empty() {
                 quote! {}
@@ -130,8 +143,13 @@ impl SerializeCodeGen for Enum {
 
         let ident = self.to_ident();
 
+        let cfg = self.to_cfg();
+
+        let lifetime = options.to_lifetime_params(&self.ident.1);
+
         quote! {
-            impl mlang_rs::rt::serde::ser::Serialize for #opcode_mod #ident {
+            #cfg
+            impl #lifetime mlang_rs::rt::serde::ser::Serialize for #opcode_mod #ident #lifetime {
                 fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
                 where
                     S: mlang_rs::rt::serde::ser::Serializer
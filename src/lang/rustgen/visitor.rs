@@ -0,0 +1,110 @@
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::lang::{
+    ir::{Node, Stat},
+    rustgen::mapping::{CfgMapping, ComplexTypeMapping, IdentMapping},
+};
+
+/// Generate the `visit_xxx` method of `node` and the `match` arm that dispatches to it from the `wrapper` enum,
+/// `Element`, `Leaf` or `Attr`.
+fn gen_visit_method(
+    opcode_mod: &TokenStream,
+    wrapper: &str,
+    node: &Node,
+) -> (TokenStream, TokenStream) {
+    let ident = node.to_ident();
+    let variant = node.ident.to_variant_name();
+    let cfg = node.to_cfg();
+
+    let method = format_ident!("visit_{}", variant.to_string().to_snake_case());
+    let doc = format!(
+        " Visit a `{}` {}.",
+        ident.to_string().replace(' ', ""),
+        wrapper.to_lowercase()
+    );
+    let wrapper: TokenStream = wrapper.parse().unwrap();
+
+    let method_def = quote! {
+        #cfg
+        #[doc = #doc]
+        fn #method(&mut self, value: &#opcode_mod #ident) {
+            let _ = value;
+        }
+    };
+
+    let arm = quote! {
+        #cfg
+        #opcode_mod #wrapper::#variant(value) => visitor.#method(value)
+    };
+
+    (method_def, arm)
+}
+
+/// Generate the visitor module from [`stats`](Stat): an `OpcodeVisitor` trait with a `visit_xxx` method of every
+/// element, leaf and attr, and the `visit`/`walk` drivers that dispatch opcodes to them.
+pub fn gen_visitor_mod(stats: impl AsRef<[Stat]>, opcode_mod: impl AsRef<str>) -> TokenStream {
+    let opcode_mod: TokenStream = opcode_mod.as_ref().parse().unwrap();
+
+    let mut methods = vec![];
+    let mut el_arms = vec![];
+    let mut leaf_arms = vec![];
+    let mut attr_arms = vec![];
+
+    for stat in stats.as_ref() {
+        let (kind, arms, node) = match stat {
+            Stat::Element(node) => ("Element", &mut el_arms, node),
+            Stat::Leaf(node) => ("Leaf", &mut leaf_arms, node),
+            Stat::Attr(node) => ("Attr", &mut attr_arms, node),
+            _ => continue,
+        };
+
+        let (method, arm) = gen_visit_method(&opcode_mod, kind, node);
+
+        methods.push(method);
+        arms.push(arm);
+    }
+
+    // `match v {}` doesn't compile for a reference to an empty enum.
+    let match_v = |arms: Vec<TokenStream>| {
+        if arms.is_empty() {
+            quote! { match *v {} }
+        } else {
+            quote! { match v { #(#arms),* } }
+        }
+    };
+
+    let (el_arms, leaf_arms, attr_arms) =
+        (match_v(el_arms), match_v(leaf_arms), match_v(attr_arms));
+
+    quote! {
+        /// A visitor of opcodes, every method does nothing by default.
+        ///
+        /// Opcodes are visited in stream order: `Apply` attrs precede the element or leaf they apply to,
+        /// and the children of an element follow it up to the matching [`visit_pop`](Self::visit_pop).
+        pub trait OpcodeVisitor {
+            #(#methods)*
+
+            /// Visit the `Pop` that closes the current element.
+            fn visit_pop(&mut self) {}
+        }
+
+        /// Dispatch `opcode` to the `visit_xxx` method of `visitor`.
+        pub fn visit<V: OpcodeVisitor + ?Sized>(opcode: &#opcode_mod Opcode, visitor: &mut V) {
+            match opcode {
+                #opcode_mod Opcode::Apply(v) => #attr_arms,
+                #opcode_mod Opcode::Element(v) => #el_arms,
+                #opcode_mod Opcode::Leaf(v) => #leaf_arms,
+                #opcode_mod Opcode::Pop => visitor.visit_pop(),
+            }
+        }
+
+        /// Dispatch every opcode of `opcodes` to `visitor`, in order.
+        pub fn walk<V: OpcodeVisitor + ?Sized>(opcodes: &[#opcode_mod Opcode], visitor: &mut V) {
+            for opcode in opcodes {
+                visit(opcode, visitor);
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Create a new vocabulary crate, the `mlangc new <path>` command:
+//!
+//! ```no_run
+//! mlang_rs::lang::scaffold::new_crate("my-vocab").unwrap();
+//! ```
+//!
+//! The crate contains a sample `schema.ml`, a `build.rs` that compiles it with [`MlangBuild`](super::build::MlangBuild),
+//! a `lib.rs` that includes the generated modules, a `convert` binary, a round-trip test and a `README.md`.
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// The sample schema, `{name}` is replaced by the crate name.
+const SCHEMA: &str = r#"/// The vocabulary of `{name}`, replace the sample declarations with your own.
+version "0.1.0";
+meta { name: "{name}", version: "0.1.0" }
+
+/// Unit of a length.
+enum Unit { Px, Em, Percent }
+
+/// A length with its unit.
+data Length { value: float, unit: Unit }
+
+/// A rectangle.
+el Rect {
+    x: Length,
+    y: Length,
+    width: Length,
+    height: Length,
+    /// The corner radius.
+    #[option]
+    radius: Length,
+}
+
+/// A group of shapes.
+el Group;
+
+/// A text run.
+leaf Text(string);
+
+/// The fill color of a shape.
+attr Fill(string);
+
+apply Fill to (Rect, Group);
+children (Rect, Text, Group) of Group;
+"#;
+
+const BUILD_RS: &str = r#"use mlang_rs::lang::build::MlangBuild;
+
+fn main() {
+    MlangBuild::new()
+        .file("schema.ml")
+        .out_dir_env()
+        .compile()
+        .expect("compile schema.ml");
+}
+"#;
+
+const LIB_RS: &str = r#"//! The `{name}` vocabulary, generated from `schema.ml` by the build script.
+
+include!(concat!(env!("OUT_DIR"), "/mlang.rs"));
+"#;
+
+const CONVERT_RS: &str = r#"//! Convert documents of the `{name}` vocabulary between formats, also run by `mlangc convert`:
+//!
+//! ```sh
+//! cargo run --bin convert -- input.xml --to binary -o out.mlb
+//! ```
+
+fn main() -> std::process::ExitCode {
+    mlang_rs::rt::serde::convert::main::<{crate_name}::opcode::Opcode>()
+}
+"#;
+
+const ROUND_TRIP_RS: &str = r#"use {crate_name}::opcode::Unit;
+
+#[test]
+fn test_unit_round_trip() {
+    for unit in Unit::iter() {
+        assert_eq!(unit.to_string().parse::<Unit>().unwrap(), unit);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_rect_round_trip() {
+    use {crate_name}::opcode::{Length, Rect};
+
+    let px = |value| Length {
+        value,
+        unit: Unit::Px,
+    };
+
+    let rect = Rect::new(px(0.0), px(0.0), px(100.0), px(50.0)).with_radius(px(4.0));
+
+    let json = serde_json::to_string(&rect).unwrap();
+
+    assert_eq!(serde_json::from_str::<Rect>(&json).unwrap(), rect);
+}
+"#;
+
+const README: &str = r#"# {name}
+
+A vocabulary defined in [`schema.ml`](schema.ml) with [mlang](https://github.com/styles-lab/mlang).
+
+The build script compiles the schema into the `opcode` and `serde` modules of this crate, edit the schema
+and run `cargo build` to regenerate them. Errors and warnings of the schema are reported by the build script.
+
+```sh
+cargo test
+```
+
+The `convert` binary converts documents between the json, xml, s-expression and binary formats:
+
+```sh
+cargo run --bin convert -- input.xml --to binary -o out.mlb
+```
+
+The `serde` feature, on by default, derives `serde::Serialize`/`serde::Deserialize` for the generated types.
+"#;
+
+/// Returns the `Cargo.toml` of the crate `name`.
+fn manifest(name: &str) -> String {
+    format!(
+        r#"[package]
+edition = "2024"
+name = "{name}"
+version = "0.1.0"
+
+[dependencies]
+mlang-rs = {{version = "{version}", default-features = false, features = ["std", "rt", "rt_serde"]}}
+serde = {{version = "^1", features = ["derive"], optional = true}}
+
+[build-dependencies]
+mlang-rs = "{version}"
+
+[dev-dependencies]
+serde_json = "^1"
+
+[features]
+default = ["serde"]
+serde = ["dep:serde", "mlang-rs/serde"]
+"#,
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+/// Create a new vocabulary crate at `path`, the crate is named after the last component of `path`.
+///
+/// Fails if `path` exists and isn't an empty directory.
+pub fn new_crate(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid crate name of path {:?}", path),
+            )
+        })?;
+
+    if path.exists() && path.read_dir()?.next().is_some() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("destination {:?} already exists and isn't empty", path),
+        ));
+    }
+
+    let crate_name = name.replace('-', "_");
+
+    let files = [
+        ("Cargo.toml", manifest(name)),
+        (".gitignore", "/target\n".to_string()),
+        ("README.md", README.replace("{name}", name)),
+        ("schema.ml", SCHEMA.replace("{name}", name)),
+        ("build.rs", BUILD_RS.to_string()),
+        ("src/lib.rs", LIB_RS.replace("{name}", name)),
+        (
+            "src/bin/convert.rs",
+            CONVERT_RS
+                .replace("{name}", name)
+                .replace("{crate_name}", &crate_name),
+        ),
+        (
+            "tests/round_trip.rs",
+            ROUND_TRIP_RS.replace("{crate_name}", &crate_name),
+        ),
+    ];
+
+    for (file, content) in files {
+        let file = path.join(file);
+
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(file, content)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{compile_to_tokens, rustgen::CodeGen};
+
+    use super::{SCHEMA, new_crate};
+
+    #[test]
+    fn test_new_crate() {
+        assert!(
+            compile_to_tokens(SCHEMA.replace("{name}", "my-vocab"), &CodeGen::default()).is_ok()
+        );
+
+        let path = std::env::temp_dir()
+            .join(format!("mlang-scaffold-{}", std::process::id()))
+            .join("my-vocab");
+
+        new_crate(&path).unwrap();
+
+        let manifest = std::fs::read_to_string(path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains(r#"name = "my-vocab""#));
+
+        let test = std::fs::read_to_string(path.join("tests/round_trip.rs")).unwrap();
+        assert!(test.starts_with("use my_vocab::opcode::Unit;"));
+
+        let convert = std::fs::read_to_string(path.join("src/bin/convert.rs")).unwrap();
+        assert!(convert.contains("convert::main::<my_vocab::opcode::Opcode>()"));
+
+        assert!(new_crate(&path).is_err());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
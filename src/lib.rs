@@ -1,6 +1,12 @@
 //! `mlang` is a document type definition language, originally developed for the contruction of `vglang`.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod prelude;
 
 #[cfg(feature = "lang")]
 #[cfg_attr(docsrs, doc(cfg(feature = "lang")))]
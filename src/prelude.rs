@@ -0,0 +1,25 @@
+//! The commonly used traits, types and entry points of `mlang`:
+//!
+//! ```
+//! use mlang_rs::prelude::*;
+//! ```
+
+#[cfg(feature = "rt")]
+pub use crate::rt::{
+    fragment::Fragment,
+    opcode::{AttrId, SchemaMeta, TypeId, Variable},
+    resource::Resource,
+};
+
+#[cfg(feature = "rt_serde")]
+pub use crate::rt::serde::{
+    de::{Deserialize, Deserializer, Visitor},
+    ser::{Serialize, Serializer},
+};
+
+#[cfg(feature = "lang")]
+pub use crate::lang::{
+    build::MlangBuild,
+    compile, compile_file, compile_to_tokens,
+    rustgen::{CodeGen, TypeKind},
+};
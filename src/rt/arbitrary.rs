@@ -0,0 +1,359 @@
+//! [`Arbitrary`] impls of the runtime types and helpers of the generated `arbitrary` module.
+//!
+//! Generated values are valid schema values: floats are finite, decimals fit their precision and scale,
+//! and value types can be printed and parsed back.
+
+pub use ::arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use crate::rt::{
+    opcode::{Path, Target, Variable},
+    resource::Resource,
+    value::{
+        DateTime, Duration, Iri, Matrix, PathCommand, PathData, Rect, Transform, TransformFunction,
+        Uuid, ViewBox,
+    },
+};
+
+/// Generate a finite `float`.
+pub fn float(u: &mut Unstructured<'_>) -> Result<f32> {
+    let value: f32 = u.arbitrary()?;
+
+    Ok(if value.is_finite() { value } else { 0.0 })
+}
+
+/// Generate a finite `double`.
+pub fn double(u: &mut Unstructured<'_>) -> Result<f64> {
+    let value: f64 = u.arbitrary()?;
+
+    Ok(if value.is_finite() { value } else { 0.0 })
+}
+
+/// Generate a `decimal(precision, scale)` value.
+#[cfg(feature = "decimal")]
+pub fn decimal(
+    u: &mut Unstructured<'_>,
+    precision: u32,
+    scale: u32,
+) -> Result<crate::rt::value::Decimal> {
+    let max = 10i128.pow(precision) - 1;
+
+    Ok(crate::rt::value::Decimal::from_i128_with_scale(
+        u.int_in_range(-max..=max)?,
+        scale,
+    ))
+}
+
+/// Returns true if another item of a `vec` or `map` should be generated, false once the data is exhausted.
+fn more(u: &mut Unstructured<'_>) -> Result<bool> {
+    Ok(!u.is_empty() && u.arbitrary()?)
+}
+
+/// Generate a `vec[...]` value, the items are generated by `f`.
+pub fn vec<'a, T>(
+    u: &mut Unstructured<'a>,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut items = vec![];
+
+    while more(u)? {
+        items.push(f(u)?);
+    }
+
+    Ok(items)
+}
+
+/// Generate an array value, the items are generated by `f`.
+pub fn array<'a, T, const N: usize>(
+    u: &mut Unstructured<'a>,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<[T; N]> {
+    let items = (0..N).map(|_| f(u)).collect::<Result<Vec<_>>>()?;
+
+    Ok(items.try_into().ok().expect("array length"))
+}
+
+/// Generate a `map[...]` value, the keys and values are generated by `key` and `value`.
+pub fn map<'a, K: Ord, V>(
+    u: &mut Unstructured<'a>,
+    mut key: impl FnMut(&mut Unstructured<'a>) -> Result<K>,
+    mut value: impl FnMut(&mut Unstructured<'a>) -> Result<V>,
+) -> Result<std::collections::BTreeMap<K, V>> {
+    let mut items = std::collections::BTreeMap::new();
+
+    while more(u)? {
+        items.insert(key(u)?, value(u)?);
+    }
+
+    Ok(items)
+}
+
+/// Generate an `#[option]` field, the value is generated by `f`.
+pub fn option<'a, T>(
+    u: &mut Unstructured<'a>,
+    f: impl FnOnce(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Option<T>> {
+    Ok(if u.arbitrary()? { Some(f(u)?) } else { None })
+}
+
+/// Generate a `#[variable]` field, the constant value is generated by `f`.
+pub fn variable<'a, T>(
+    u: &mut Unstructured<'a>,
+    f: impl FnOnce(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Variable<T>> {
+    Ok(if u.ratio(1u8, 4u8)? {
+        Variable::Reference {
+            path: u.arbitrary()?,
+            target: u.arbitrary()?,
+        }
+    } else {
+        Variable::Constant(f(u)?)
+    })
+}
+
+impl<'a> Arbitrary<'a> for Path {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Path::Named(u.arbitrary()?)
+        } else {
+            Path::Index(u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Target {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[
+            Target::Register,
+            Target::ForeachItem,
+            Target::ForeachIndex,
+            Target::Range,
+        ])?)
+    }
+}
+
+impl<'a, T: Arbitrary<'a>> Arbitrary<'a> for Variable<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        variable(u, |u| u.arbitrary())
+    }
+}
+
+impl<'a, T> Arbitrary<'a> for Resource<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Resource::new(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Iri {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        const CHARS: &[u8] =
+            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-._~/#";
+
+        let mut text = if u.arbitrary()? {
+            "https://example.com/".to_string()
+        } else {
+            String::new()
+        };
+
+        for _ in 0..u.arbitrary_len::<u8>()? {
+            text.push(*u.choose(CHARS)? as char);
+        }
+
+        text.parse().map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Uuid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Uuid::from_bytes(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for DateTime {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // 0001-01-02 to 9999-12-30, so the local time of any offset has a four digits year.
+        let seconds = u.int_in_range(-62135510400..=253402214399)?;
+        let nanos = u.int_in_range(0..=999_999_999)?;
+        let offset = u.int_in_range(-1439..=1439)?;
+
+        DateTime::from_unix(seconds, nanos, offset).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Duration {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let value = float(u)?.abs();
+
+        Ok(if u.arbitrary()? {
+            Duration::Time(value)
+        } else {
+            Duration::Percent(value)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Rect {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Rect::new(float(u)?, float(u)?, float(u)?, float(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ViewBox {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ViewBox(Rect::new(
+            float(u)?,
+            float(u)?,
+            float(u)?.abs(),
+            float(u)?.abs(),
+        )))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Matrix {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Matrix::new(
+            float(u)?,
+            float(u)?,
+            float(u)?,
+            float(u)?,
+            float(u)?,
+            float(u)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for TransformFunction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.choose_index(6)? {
+            0 => TransformFunction::Matrix(u.arbitrary()?),
+            1 => TransformFunction::Translate {
+                x: float(u)?,
+                y: float(u)?,
+            },
+            2 => TransformFunction::Scale {
+                x: float(u)?,
+                y: float(u)?,
+            },
+            3 => TransformFunction::Rotate {
+                angle: float(u)?,
+                cx: float(u)?,
+                cy: float(u)?,
+            },
+            4 => TransformFunction::SkewX(float(u)?),
+            _ => TransformFunction::SkewY(float(u)?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Transform {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Transform(vec(u, |u| u.arbitrary())?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for PathCommand {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let abs = u.arbitrary()?;
+
+        Ok(match u.choose_index(10)? {
+            0 => PathCommand::MoveTo {
+                abs,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            1 => PathCommand::LineTo {
+                abs,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            2 => PathCommand::HorizontalLineTo { abs, x: float(u)? },
+            3 => PathCommand::VerticalLineTo { abs, y: float(u)? },
+            4 => PathCommand::CubicTo {
+                abs,
+                x1: float(u)?,
+                y1: float(u)?,
+                x2: float(u)?,
+                y2: float(u)?,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            5 => PathCommand::SmoothCubicTo {
+                abs,
+                x2: float(u)?,
+                y2: float(u)?,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            6 => PathCommand::QuadraticTo {
+                abs,
+                x1: float(u)?,
+                y1: float(u)?,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            7 => PathCommand::SmoothQuadraticTo {
+                abs,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            8 => PathCommand::ArcTo {
+                abs,
+                rx: float(u)?.abs(),
+                ry: float(u)?.abs(),
+                x_axis_rotation: float(u)?,
+                large_arc: u.arbitrary()?,
+                sweep: u.arbitrary()?,
+                x: float(u)?,
+                y: float(u)?,
+            },
+            _ => PathCommand::Close,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for PathData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut commands = vec(u, |u| u.arbitrary())?;
+
+        // path data starts with a `moveto`.
+        if let Some(first) = commands.first_mut()
+            && !matches!(first, PathCommand::MoveTo { .. })
+        {
+            *first = PathCommand::MoveTo {
+                abs: true,
+                x: 0.0,
+                y: 0.0,
+            };
+        }
+
+        Ok(PathData(commands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arbitrary, Unstructured};
+    use crate::rt::value::{DateTime, Iri, PathData, Transform, Uuid};
+
+    #[test]
+    fn test_round_trip() {
+        let data = (0..=255u8).cycle().take(4096).collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+
+        for _ in 0..16 {
+            let datetime = DateTime::arbitrary(&mut u).unwrap();
+            assert_eq!(datetime.to_string().parse::<DateTime>(), Ok(datetime));
+
+            let iri = Iri::arbitrary(&mut u).unwrap();
+            assert_eq!(iri.as_str().parse::<Iri>(), Ok(iri));
+
+            let uuid = Uuid::arbitrary(&mut u).unwrap();
+            assert_eq!(uuid.to_string().parse::<Uuid>(), Ok(uuid));
+
+            let path = PathData::arbitrary(&mut u).unwrap();
+            assert!(path.to_string().parse::<PathData>().is_ok());
+
+            let transform = Transform::arbitrary(&mut u).unwrap();
+            assert!(transform.to_string().parse::<Transform>().is_ok());
+        }
+    }
+}
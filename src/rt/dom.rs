@@ -0,0 +1,1494 @@
+//! A tree of nodes built from an opcode stream.
+//!
+//! Generated `Opcode` enums are flat stack-machine streams: attrs are applied to the next element or leaf, and the
+//! children of an element are closed by a `Pop`. A [`Document`] nests them instead, so consumers can walk and edit
+//! the tree and write it back as opcodes:
+//!
+//! ```ignore
+//! let mut document = Document::<Opcode>::from_opcodes(opcodes)?.with_type_index();
+//!
+//! let text = document.append(None, Node::leaf(Leaf::from(Text("hello".into())))).unwrap();
+//!
+//! assert_eq!(document.parent(text), None);
+//! assert!(document.by_type(ids::TEXT.into()).any(|handle| handle == text));
+//!
+//! let opcodes = document.into_opcodes();
+//! ```
+//!
+//! The nodes of a document are addressed by [`Handle`]s, which link them to their parent and siblings, so walking
+//! up the tree is as cheap as walking down. The optional indexes of nodes by type and by id are updated by every
+//! edit of the document.
+//!
+//! Documents are copied on write, a [`Snapshot`] is an immutable view that a render thread can walk while the
+//! document is edited:
+//!
+//! ```ignore
+//! let snapshot = document.snapshot();
+//!
+//! std::thread::spawn(move || render(&snapshot));
+//!
+//! document.remove(text);
+//! ```
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{fmt, iter, mem, ops::Deref};
+
+use crate::rt::opcode::TypeId;
+
+/// An opcode of a stream, seen by the tree builder.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op<A, E, L> {
+    /// Apply an attr to the next element or leaf.
+    Apply(A),
+    /// Open an element, the following nodes are its children until the matching `Pop`.
+    Element(E),
+    /// Close the last open element.
+    Pop,
+    /// A leaf node.
+    Leaf(L),
+}
+
+/// An opcode type whose streams can be built into a [`Document`], implemented by the generated `Opcode` enums.
+pub trait Opcode: Sized {
+    /// The attrs applied to nodes.
+    type Attr;
+    /// The elements, nodes with children.
+    type Element;
+    /// The leaves, nodes without children.
+    type Leaf;
+
+    /// Convert the opcode into an [`Op`].
+    fn into_op(self) -> Op<Self::Attr, Self::Element, Self::Leaf>;
+
+    /// Create an opcode from an [`Op`].
+    fn from_op(op: Op<Self::Attr, Self::Element, Self::Leaf>) -> Self;
+
+    /// Returns the type id of an element or a leaf, the key of [`Snapshot::by_type`].
+    fn type_id(data: &Data<Self::Element, Self::Leaf>) -> TypeId;
+}
+
+/// The element or the leaf of a node of a [`Document`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Data<E, L> {
+    Element(E),
+    Leaf(L),
+}
+
+/// Error returns by [`Document::from_opcodes`], positions are the indexes of the opcodes.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error("`Pop` at {0} without an open element.")]
+    Pop(usize),
+
+    #[error("Attrs before {0} aren't applied to an element or a leaf.")]
+    Attrs(usize),
+
+    #[error("{0} elements aren't closed by a `Pop`.")]
+    Unclosed(usize),
+}
+
+/// An element or a leaf with the attrs applied to it.
+pub enum Node<O>
+where
+    O: Opcode,
+{
+    Element {
+        attrs: Vec<O::Attr>,
+        element: O::Element,
+        children: Vec<Node<O>>,
+    },
+    Leaf {
+        attrs: Vec<O::Attr>,
+        leaf: O::Leaf,
+    },
+}
+
+// derives would bound `O` instead of its node types.
+impl<O> fmt::Debug for Node<O>
+where
+    O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Element {
+                attrs,
+                element,
+                children,
+            } => f
+                .debug_struct("Element")
+                .field("attrs", attrs)
+                .field("element", element)
+                .field("children", children)
+                .finish(),
+            Self::Leaf { attrs, leaf } => f
+                .debug_struct("Leaf")
+                .field("attrs", attrs)
+                .field("leaf", leaf)
+                .finish(),
+        }
+    }
+}
+
+impl<O> Clone for Node<O>
+where
+    O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Element {
+                attrs,
+                element,
+                children,
+            } => Self::Element {
+                attrs: attrs.clone(),
+                element: element.clone(),
+                children: children.clone(),
+            },
+            Self::Leaf { attrs, leaf } => Self::Leaf {
+                attrs: attrs.clone(),
+                leaf: leaf.clone(),
+            },
+        }
+    }
+}
+
+impl<O> PartialEq for Node<O>
+where
+    O: Opcode<Attr: PartialEq, Element: PartialEq, Leaf: PartialEq>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Element {
+                    attrs,
+                    element,
+                    children,
+                },
+                Self::Element {
+                    attrs: other_attrs,
+                    element: other_element,
+                    children: other_children,
+                },
+            ) => attrs == other_attrs && element == other_element && children == other_children,
+            (
+                Self::Leaf { attrs, leaf },
+                Self::Leaf {
+                    attrs: other_attrs,
+                    leaf: other_leaf,
+                },
+            ) => attrs == other_attrs && leaf == other_leaf,
+            _ => false,
+        }
+    }
+}
+
+impl<O> Node<O>
+where
+    O: Opcode,
+{
+    /// Create an element node without attrs and children.
+    pub fn element(element: O::Element) -> Self {
+        Self::Element {
+            attrs: vec![],
+            element,
+            children: vec![],
+        }
+    }
+
+    /// Create a leaf node without attrs.
+    pub fn leaf(leaf: O::Leaf) -> Self {
+        Self::Leaf {
+            attrs: vec![],
+            leaf,
+        }
+    }
+
+    /// Apply `attr` to the node.
+    pub fn with_attr(mut self, attr: O::Attr) -> Self {
+        self.attrs_mut().push(attr);
+        self
+    }
+
+    /// Append `child` to the node, leaves have no children so `child` is dropped.
+    pub fn with_child(mut self, child: Node<O>) -> Self {
+        if let Some(children) = self.children_mut() {
+            children.push(child);
+        }
+
+        self
+    }
+
+    /// Returns the attrs applied to the node, in stream order.
+    pub fn attrs(&self) -> &[O::Attr] {
+        match self {
+            Self::Element { attrs, .. } | Self::Leaf { attrs, .. } => attrs,
+        }
+    }
+
+    /// Returns the mutable attrs applied to the node.
+    pub fn attrs_mut(&mut self) -> &mut Vec<O::Attr> {
+        match self {
+            Self::Element { attrs, .. } | Self::Leaf { attrs, .. } => attrs,
+        }
+    }
+
+    /// Returns the children of an element, leaves have none.
+    pub fn children(&self) -> &[Node<O>] {
+        match self {
+            Self::Element { children, .. } => children,
+            Self::Leaf { .. } => &[],
+        }
+    }
+
+    /// Returns the mutable children of an element, `None` for leaves.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<O>>> {
+        match self {
+            Self::Element { children, .. } => Some(children),
+            Self::Leaf { .. } => None,
+        }
+    }
+}
+
+/// A handle of a node of a [`Document`], valid until the node is removed.
+///
+/// Handles of removed nodes aren't reused, so a stale handle doesn't address another node. The handles of a
+/// document are valid in its snapshots, and the other way around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle(usize);
+
+/// The key of the id index of a [`Document`], see [`Document::with_id_index`].
+pub type IdKey<E, L> = for<'a> fn(&'a Data<E, L>) -> Option<&'a str>;
+
+/// A node of a document, linked to its parent, its siblings and its children.
+struct Slot<O>
+where
+    O: Opcode,
+{
+    attrs: Vec<O::Attr>,
+    data: Data<O::Element, O::Leaf>,
+    parent: Option<Handle>,
+    prev: Option<Handle>,
+    next: Option<Handle>,
+    /// The first child of an element.
+    first: Option<Handle>,
+    /// The last child of an element.
+    last: Option<Handle>,
+}
+
+impl<O> Clone for Slot<O>
+where
+    O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            attrs: self.attrs.clone(),
+            data: self.data.clone(),
+            parent: self.parent,
+            prev: self.prev,
+            next: self.next,
+            first: self.first,
+            last: self.last,
+        }
+    }
+}
+
+/// A step of a walk of the nodes of a document, in stream order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Node(Handle),
+    /// The end of the children of an element.
+    Pop,
+}
+
+/// The key of an id index and the nodes by id.
+type Ids<E, L> = (IdKey<E, L>, BTreeMap<String, BTreeSet<Handle>>);
+
+/// The nodes and the indexes of a document, shared by its snapshots.
+struct Tree<O>
+where
+    O: Opcode,
+{
+    slots: Vec<Option<Slot<O>>>,
+    /// The first top-level node.
+    first: Option<Handle>,
+    /// The last top-level node.
+    last: Option<Handle>,
+    /// The nodes by type id, if indexed.
+    types: Option<BTreeMap<TypeId, BTreeSet<Handle>>>,
+    /// The key of the id index and the nodes by id, if indexed.
+    ids: Option<Ids<O::Element, O::Leaf>>,
+}
+
+impl<O> Clone for Tree<O>
+where
+    O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            first: self.first,
+            last: self.last,
+            types: self.types.clone(),
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+impl<O> Tree<O>
+where
+    O: Opcode,
+{
+    fn new() -> Self {
+        Self {
+            slots: vec![],
+            first: None,
+            last: None,
+            types: None,
+            ids: None,
+        }
+    }
+
+    fn slot(&self, handle: Handle) -> Option<&Slot<O>> {
+        self.slots.get(handle.0)?.as_ref()
+    }
+
+    fn slot_mut(&mut self, handle: Handle) -> Option<&mut Slot<O>> {
+        self.slots.get_mut(handle.0)?.as_mut()
+    }
+
+    /// Returns the top-level nodes.
+    fn roots(&self) -> Children<'_, O> {
+        Children {
+            tree: self,
+            next: self.first,
+        }
+    }
+
+    /// Returns the children of an element, leaves have none.
+    fn children(&self, handle: Handle) -> Children<'_, O> {
+        Children {
+            tree: self,
+            next: self.slot(handle).and_then(|slot| slot.first),
+        }
+    }
+
+    /// Returns the nodes of the document, in stream order.
+    fn steps(&self) -> impl Iterator<Item = Step> + '_ {
+        self.roots().flat_map(|root| self.walk(root))
+    }
+
+    /// Returns the node `root` and its descendants, in stream order.
+    fn walk(&self, root: Handle) -> impl Iterator<Item = Step> + '_ {
+        /// The next step of a walk.
+        enum State {
+            Enter(Handle),
+            /// Close an element.
+            Leave(Handle),
+            Done,
+        }
+
+        // the step after the node `handle` and its descendants.
+        let after = move |handle: Handle| match self.slot(handle) {
+            Some(slot) if handle != root => match (slot.next, slot.parent) {
+                (Some(next), _) => State::Enter(next),
+                (None, Some(parent)) => State::Leave(parent),
+                (None, None) => State::Done,
+            },
+            _ => State::Done,
+        };
+
+        let mut state = State::Enter(root);
+
+        iter::from_fn(move || match state {
+            State::Enter(handle) => {
+                let slot = self.slot(handle)?;
+
+                state = match (&slot.data, slot.first) {
+                    (Data::Element(_), Some(first)) => State::Enter(first),
+                    (Data::Element(_), None) => State::Leave(handle),
+                    (Data::Leaf(_), _) => after(handle),
+                };
+
+                Some(Step::Node(handle))
+            }
+            State::Leave(handle) => {
+                state = after(handle);
+
+                Some(Step::Pop)
+            }
+            State::Done => None,
+        })
+    }
+
+    /// Returns the opcodes of a step, cloned from the nodes.
+    fn opcodes(&self, step: Step) -> impl Iterator<Item = O> + '_
+    where
+        O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+    {
+        let (attrs, op) = match step {
+            Step::Node(handle) => match self.slot(handle) {
+                Some(slot) => (
+                    slot.attrs.as_slice(),
+                    Some(match &slot.data {
+                        Data::Element(element) => Op::Element(element.clone()),
+                        Data::Leaf(leaf) => Op::Leaf(leaf.clone()),
+                    }),
+                ),
+                None => (&[][..], None),
+            },
+            Step::Pop => (&[][..], Some(Op::Pop)),
+        };
+
+        attrs
+            .iter()
+            .map(|attr| O::from_op(Op::Apply(attr.clone())))
+            .chain(op.map(O::from_op))
+    }
+
+    /// Add an unlinked node to the document.
+    fn alloc(&mut self, attrs: Vec<O::Attr>, data: Data<O::Element, O::Leaf>) -> Handle {
+        let handle = Handle(self.slots.len());
+
+        self.slots.push(Some(Slot {
+            attrs,
+            data,
+            parent: None,
+            prev: None,
+            next: None,
+            first: None,
+            last: None,
+        }));
+
+        self.index(handle);
+
+        handle
+    }
+
+    /// Remove an unlinked node from the document, its children are left in place.
+    fn free(&mut self, handle: Handle) -> Option<Slot<O>> {
+        self.unindex(handle);
+        self.slots.get_mut(handle.0)?.take()
+    }
+
+    /// Insert `node` and its descendants before `before`, or as the last child of `parent`.
+    fn insert(&mut self, parent: Option<Handle>, before: Option<Handle>, node: Node<O>) -> Handle {
+        let (root, children) = self.alloc_node(node);
+
+        self.link(root, parent, before);
+
+        // the elements being inserted, with their children not inserted yet.
+        let mut stack = vec![(root, children.into_iter())];
+
+        while let Some((parent, children)) = stack.last_mut() {
+            let parent = *parent;
+
+            match children.next() {
+                Some(child) => {
+                    let (handle, children) = self.alloc_node(child);
+
+                    self.link(handle, Some(parent), None);
+                    stack.push((handle, children.into_iter()));
+                }
+                None => _ = stack.pop(),
+            }
+        }
+
+        root
+    }
+
+    /// Add an unlinked node, returns its handle and its children.
+    fn alloc_node(&mut self, node: Node<O>) -> (Handle, Vec<Node<O>>) {
+        match node {
+            Node::Element {
+                attrs,
+                element,
+                children,
+            } => (self.alloc(attrs, Data::Element(element)), children),
+            Node::Leaf { attrs, leaf } => (self.alloc(attrs, Data::Leaf(leaf)), vec![]),
+        }
+    }
+
+    /// Remove a node and its descendants, returns them as a [`Node`].
+    fn remove(&mut self, handle: Handle) -> Option<Node<O>> {
+        self.unlink(handle);
+
+        // the nodes being removed, with their children removed so far.
+        let mut stack: Vec<(Slot<O>, Vec<Node<O>>)> = vec![];
+        let mut next = Some(handle);
+
+        loop {
+            if let Some(slot) = next.and_then(|handle| self.free(handle)) {
+                next = slot.first;
+                stack.push((slot, vec![]));
+                continue;
+            }
+
+            // the last node has no more children.
+            let (slot, children) = stack.pop()?;
+
+            next = slot.next;
+
+            let node = match slot.data {
+                Data::Element(element) => Node::Element {
+                    attrs: slot.attrs,
+                    element,
+                    children,
+                },
+                Data::Leaf(leaf) => Node::Leaf {
+                    attrs: slot.attrs,
+                    leaf,
+                },
+            };
+
+            match stack.last_mut() {
+                Some((_, siblings)) => siblings.push(node),
+                None => return Some(node),
+            }
+        }
+    }
+
+    /// Link an unlinked node before `before`, or as the last child of `parent`.
+    fn link(&mut self, handle: Handle, parent: Option<Handle>, before: Option<Handle>) {
+        let prev = match before {
+            Some(before) => self.slot(before).and_then(|slot| slot.prev),
+            None => match parent {
+                Some(parent) => self.slot(parent).and_then(|slot| slot.last),
+                None => self.last,
+            },
+        };
+
+        if let Some(slot) = self.slot_mut(handle) {
+            slot.parent = parent;
+            slot.prev = prev;
+            slot.next = before;
+        }
+
+        match prev.and_then(|prev| self.slot_mut(prev)) {
+            Some(slot) => slot.next = Some(handle),
+            None => match parent.and_then(|parent| self.slot_mut(parent)) {
+                Some(slot) => slot.first = Some(handle),
+                None => self.first = Some(handle),
+            },
+        }
+
+        match before.and_then(|before| self.slot_mut(before)) {
+            Some(slot) => slot.prev = Some(handle),
+            None => match parent.and_then(|parent| self.slot_mut(parent)) {
+                Some(slot) => slot.last = Some(handle),
+                None => self.last = Some(handle),
+            },
+        }
+    }
+
+    /// Unlink a node from its parent and siblings.
+    fn unlink(&mut self, handle: Handle) {
+        let Some(slot) = self.slot_mut(handle) else {
+            return;
+        };
+
+        let (parent, prev, next) = (slot.parent.take(), slot.prev.take(), slot.next.take());
+
+        match prev.and_then(|prev| self.slot_mut(prev)) {
+            Some(slot) => slot.next = next,
+            None => match parent.and_then(|parent| self.slot_mut(parent)) {
+                Some(slot) => slot.first = next,
+                None => self.first = next,
+            },
+        }
+
+        match next.and_then(|next| self.slot_mut(next)) {
+            Some(slot) => slot.prev = prev,
+            None => match parent.and_then(|parent| self.slot_mut(parent)) {
+                Some(slot) => slot.last = prev,
+                None => self.last = prev,
+            },
+        }
+    }
+
+    /// Rebuild the indexes from the nodes.
+    fn reindex(&mut self) {
+        for handle in (0..self.slots.len()).map(Handle) {
+            self.index(handle);
+        }
+    }
+
+    /// Add a node to the indexes.
+    fn index(&mut self, handle: Handle) {
+        let Some(slot) = self.slots.get(handle.0).and_then(Option::as_ref) else {
+            return;
+        };
+
+        if let Some(types) = &mut self.types {
+            types
+                .entry(O::type_id(&slot.data))
+                .or_default()
+                .insert(handle);
+        }
+
+        let Some((key, ids)) = &mut self.ids else {
+            return;
+        };
+
+        if let Some(id) = key(&slot.data) {
+            ids.entry(id.to_string()).or_default().insert(handle);
+        }
+    }
+
+    /// Remove a node from the indexes.
+    fn unindex(&mut self, handle: Handle) {
+        let Some(slot) = self.slots.get(handle.0).and_then(Option::as_ref) else {
+            return;
+        };
+
+        if let Some(types) = &mut self.types {
+            let type_id = O::type_id(&slot.data);
+
+            if let Some(handles) = types.get_mut(&type_id) {
+                handles.remove(&handle);
+
+                if handles.is_empty() {
+                    types.remove(&type_id);
+                }
+            }
+        }
+
+        let Some((key, ids)) = &mut self.ids else {
+            return;
+        };
+
+        let Some(id) = key(&slot.data) else {
+            return;
+        };
+
+        if let Some(handles) = ids.get_mut(id) {
+            handles.remove(&handle);
+
+            if handles.is_empty() {
+                ids.remove(id);
+            }
+        }
+    }
+}
+
+/// An immutable view of a [`Document`], see [`Document::snapshot`].
+///
+/// A snapshot shares the nodes of its document until the document is edited, it is `Send + Sync` when the node
+/// types are, so a render thread can walk it while the document is edited on another thread. All the read
+/// methods of a document are the methods of its snapshot.
+pub struct Snapshot<O>
+where
+    O: Opcode,
+{
+    tree: Arc<Tree<O>>,
+}
+
+impl<O> Clone for Snapshot<O>
+where
+    O: Opcode,
+{
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl<O> fmt::Debug for Snapshot<O>
+where
+    O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.debug("Snapshot", f)
+    }
+}
+
+// snapshots are equal if they have the same nodes, regardless of their handles.
+impl<O> PartialEq for Snapshot<O>
+where
+    O: Opcode<Attr: PartialEq, Element: PartialEq, Leaf: PartialEq>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let mut steps = self.tree.steps();
+        let mut other_steps = other.tree.steps();
+
+        loop {
+            match (steps.next(), other_steps.next()) {
+                (None, None) => return true,
+                (Some(Step::Pop), Some(Step::Pop)) => {}
+                (Some(Step::Node(handle)), Some(Step::Node(other_handle))) => {
+                    match (self.tree.slot(handle), other.tree.slot(other_handle)) {
+                        (Some(slot), Some(other_slot))
+                            if slot.attrs == other_slot.attrs && slot.data == other_slot.data => {}
+                        _ => return false,
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl<O> Snapshot<O>
+where
+    O: Opcode,
+{
+    /// Write a copy of the nodes as an opcode stream.
+    pub fn to_opcodes(&self) -> Vec<O>
+    where
+        O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+    {
+        self.tree
+            .steps()
+            .flat_map(|step| self.tree.opcodes(step))
+            .collect()
+    }
+
+    /// Returns true if `handle` is a node of the document.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.tree.slot(handle).is_some()
+    }
+
+    /// Returns the element or the leaf of a node.
+    pub fn data(&self, handle: Handle) -> Option<&Data<O::Element, O::Leaf>> {
+        self.tree.slot(handle).map(|slot| &slot.data)
+    }
+
+    /// Returns the attrs applied to a node, in stream order.
+    pub fn attrs(&self, handle: Handle) -> Option<&[O::Attr]> {
+        self.tree.slot(handle).map(|slot| slot.attrs.as_slice())
+    }
+
+    /// Returns the parent element of a node, `None` for top-level nodes.
+    pub fn parent(&self, handle: Handle) -> Option<Handle> {
+        self.tree.slot(handle)?.parent
+    }
+
+    /// Returns the previous sibling of a node.
+    pub fn prev_sibling(&self, handle: Handle) -> Option<Handle> {
+        self.tree.slot(handle)?.prev
+    }
+
+    /// Returns the next sibling of a node.
+    pub fn next_sibling(&self, handle: Handle) -> Option<Handle> {
+        self.tree.slot(handle)?.next
+    }
+
+    /// Returns the first child of an element.
+    pub fn first_child(&self, handle: Handle) -> Option<Handle> {
+        self.tree.slot(handle)?.first
+    }
+
+    /// Returns the last child of an element.
+    pub fn last_child(&self, handle: Handle) -> Option<Handle> {
+        self.tree.slot(handle)?.last
+    }
+
+    /// Returns the top-level nodes.
+    pub fn roots(&self) -> Children<'_, O> {
+        self.tree.roots()
+    }
+
+    /// Returns the children of an element, leaves have none.
+    pub fn children(&self, handle: Handle) -> Children<'_, O> {
+        self.tree.children(handle)
+    }
+
+    /// Returns the nodes of type `type_id`, in creation order, empty if the document isn't indexed by type.
+    pub fn by_type(&self, type_id: TypeId) -> impl Iterator<Item = Handle> + '_ {
+        self.tree
+            .types
+            .as_ref()
+            .and_then(|types| types.get(&type_id))
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    /// Returns the first created node whose id is `id`, `None` if the document isn't indexed by id.
+    pub fn by_id(&self, id: &str) -> Option<Handle> {
+        self.tree
+            .ids
+            .as_ref()?
+            .1
+            .get(id)
+            .and_then(|handles| handles.first())
+            .copied()
+    }
+
+    /// Serialize the node `handle`, its attrs and its descendants as a standalone opcode stream, e.g. to copy an
+    /// element:
+    ///
+    /// ```ignore
+    /// let mut serializer = json::Serializer::new();
+    ///
+    /// document.serialize_subtree(handle, &mut serializer)?;
+    ///
+    /// let json = serializer.finish()?;
+    /// ```
+    ///
+    /// Returns false and serializes nothing if `handle` isn't a node of the document.
+    #[cfg(feature = "rt_serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rt_serde")))]
+    pub fn serialize_subtree<S, E>(&self, handle: Handle, serializer: &mut S) -> Result<bool, E>
+    where
+        O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone> + crate::rt::serde::ser::Serialize,
+        for<'a> &'a mut S: crate::rt::serde::ser::Serializer<Error = E>,
+    {
+        if !self.contains(handle) {
+            return Ok(false);
+        }
+
+        for step in self.tree.walk(handle) {
+            for opcode in self.tree.opcodes(step) {
+                opcode.serialize(&mut *serializer)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Format the nodes as a struct `name`.
+    fn debug(&self, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    where
+        O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+    {
+        /// Formats a node as a [`Node`].
+        struct DebugNode<'a, O>(&'a Tree<O>, Handle)
+        where
+            O: Opcode;
+
+        impl<O> fmt::Debug for DebugNode<'_, O>
+        where
+            O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let DebugNode(tree, handle) = *self;
+
+                let Some(slot) = tree.slot(handle) else {
+                    return Ok(());
+                };
+
+                match &slot.data {
+                    Data::Element(element) => f
+                        .debug_struct("Element")
+                        .field("attrs", &slot.attrs)
+                        .field("element", element)
+                        .field("children", &DebugNodes(tree.children(handle)))
+                        .finish(),
+                    Data::Leaf(leaf) => f
+                        .debug_struct("Leaf")
+                        .field("attrs", &slot.attrs)
+                        .field("leaf", leaf)
+                        .finish(),
+                }
+            }
+        }
+
+        /// Formats nodes as a list.
+        struct DebugNodes<'a, O>(Children<'a, O>)
+        where
+            O: Opcode;
+
+        impl<O> fmt::Debug for DebugNodes<'_, O>
+        where
+            O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list()
+                    .entries(self.0.clone().map(|handle| DebugNode(self.0.tree, handle)))
+                    .finish()
+            }
+        }
+
+        f.debug_struct(name)
+            .field("nodes", &DebugNodes(self.roots()))
+            .finish()
+    }
+}
+
+/// A document, the top-level nodes of an opcode stream.
+///
+/// The nodes are copied on write: clones and [`snapshot`](Self::snapshot)s of a document share its nodes until
+/// one of them is edited. The read methods are the methods of [`Snapshot`].
+pub struct Document<O>
+where
+    O: Opcode,
+{
+    snapshot: Snapshot<O>,
+}
+
+impl<O> fmt::Debug for Document<O>
+where
+    O: Opcode<Attr: fmt::Debug, Element: fmt::Debug, Leaf: fmt::Debug>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.snapshot.debug("Document", f)
+    }
+}
+
+impl<O> Clone for Document<O>
+where
+    O: Opcode,
+{
+    fn clone(&self) -> Self {
+        Self {
+            snapshot: self.snapshot.clone(),
+        }
+    }
+}
+
+impl<O> PartialEq for Document<O>
+where
+    O: Opcode<Attr: PartialEq, Element: PartialEq, Leaf: PartialEq>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.snapshot == other.snapshot
+    }
+}
+
+impl<O> Default for Document<O>
+where
+    O: Opcode,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Deref for Document<O>
+where
+    O: Opcode,
+{
+    type Target = Snapshot<O>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.snapshot
+    }
+}
+
+impl<O> Document<O>
+where
+    O: Opcode,
+{
+    /// Create an empty document.
+    pub fn new() -> Self {
+        Self::from_tree(Tree::new())
+    }
+
+    /// Build the tree of an opcode stream.
+    pub fn from_opcodes(opcodes: impl IntoIterator<Item = O>) -> Result<Self, Error> {
+        let mut tree = Tree::new();
+        // the open elements.
+        let mut stack = vec![];
+        let mut attrs = vec![];
+        let mut len = 0;
+
+        for (index, opcode) in opcodes.into_iter().enumerate() {
+            len = index + 1;
+
+            match opcode.into_op() {
+                Op::Apply(attr) => attrs.push(attr),
+                Op::Element(element) => {
+                    let parent = stack.last().copied();
+                    let handle = tree.alloc(mem::take(&mut attrs), Data::Element(element));
+
+                    tree.link(handle, parent, None);
+                    stack.push(handle);
+                }
+                Op::Leaf(leaf) => {
+                    let parent = stack.last().copied();
+                    let handle = tree.alloc(mem::take(&mut attrs), Data::Leaf(leaf));
+
+                    tree.link(handle, parent, None);
+                }
+                Op::Pop => {
+                    if !attrs.is_empty() {
+                        return Err(Error::Attrs(index));
+                    }
+
+                    if stack.pop().is_none() {
+                        return Err(Error::Pop(index));
+                    }
+                }
+            }
+        }
+
+        if !attrs.is_empty() {
+            return Err(Error::Attrs(len));
+        }
+
+        if !stack.is_empty() {
+            return Err(Error::Unclosed(stack.len()));
+        }
+
+        Ok(Self::from_tree(tree))
+    }
+
+    /// Create a document of the top-level `nodes`.
+    pub fn from_nodes(nodes: impl IntoIterator<Item = Node<O>>) -> Self {
+        let mut tree = Tree::new();
+
+        for node in nodes {
+            tree.insert(None, None, node);
+        }
+
+        Self::from_tree(tree)
+    }
+
+    /// Returns an immutable view of the document as it is now, later edits of the document don't change it.
+    pub fn snapshot(&self) -> Snapshot<O> {
+        self.snapshot.clone()
+    }
+
+    fn from_tree(tree: Tree<O>) -> Self {
+        Self {
+            snapshot: Snapshot {
+                tree: Arc::new(tree),
+            },
+        }
+    }
+}
+
+impl<O> Document<O>
+where
+    O: Opcode<Attr: Clone, Element: Clone, Leaf: Clone>,
+{
+    /// Index the nodes by their type id, see [`by_type`](Snapshot::by_type).
+    pub fn with_type_index(mut self) -> Self {
+        let tree = self.tree_mut();
+
+        tree.types = Some(BTreeMap::new());
+        tree.reindex();
+        self
+    }
+
+    /// Index the nodes by the id `key` returns for them, e.g. the `id` field of elements, see
+    /// [`by_id`](Snapshot::by_id).
+    pub fn with_id_index(mut self, key: IdKey<O::Element, O::Leaf>) -> Self {
+        let tree = self.tree_mut();
+
+        tree.ids = Some((key, BTreeMap::new()));
+        tree.reindex();
+        self
+    }
+
+    /// Write the document as an opcode stream, the nodes are only copied if a snapshot shares them.
+    pub fn into_opcodes(self) -> Vec<O> {
+        let mut tree = Arc::unwrap_or_clone(self.snapshot.tree);
+        let steps = tree.steps().collect::<Vec<_>>();
+        let mut opcodes = vec![];
+
+        for step in steps {
+            let Step::Node(handle) = step else {
+                opcodes.push(O::from_op(Op::Pop));
+                continue;
+            };
+
+            if let Some(slot) = tree.slots[handle.0].take() {
+                opcodes.extend(
+                    slot.attrs
+                        .into_iter()
+                        .map(|attr| O::from_op(Op::Apply(attr))),
+                );
+                opcodes.push(O::from_op(match slot.data {
+                    Data::Element(element) => Op::Element(element),
+                    Data::Leaf(leaf) => Op::Leaf(leaf),
+                }));
+            }
+        }
+
+        opcodes
+    }
+
+    /// Returns the mutable attrs applied to a node.
+    pub fn attrs_mut(&mut self, handle: Handle) -> Option<&mut Vec<O::Attr>> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        self.tree_mut().slot_mut(handle).map(|slot| &mut slot.attrs)
+    }
+
+    /// Append `node` to the children of `parent`, or to the top-level nodes if `parent` is `None`.
+    ///
+    /// Returns the handle of the node, `None` if `parent` isn't an element of the document.
+    pub fn append(&mut self, parent: Option<Handle>, node: Node<O>) -> Option<Handle> {
+        if parent.is_some_and(|parent| !matches!(self.data(parent), Some(Data::Element(_)))) {
+            return None;
+        }
+
+        Some(self.tree_mut().insert(parent, None, node))
+    }
+
+    /// Insert `node` before `sibling`, returns the handle of the node, `None` if `sibling` isn't in the document.
+    pub fn insert_before(&mut self, sibling: Handle, node: Node<O>) -> Option<Handle> {
+        if !self.contains(sibling) {
+            return None;
+        }
+
+        let parent = self.parent(sibling);
+
+        Some(self.tree_mut().insert(parent, Some(sibling), node))
+    }
+
+    /// Remove a node and its descendants from the document, returns them as a [`Node`].
+    pub fn remove(&mut self, handle: Handle) -> Option<Node<O>> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        self.tree_mut().remove(handle)
+    }
+
+    /// Replace the element or the leaf of a node by `data` of the same kind, the indexes are updated.
+    ///
+    /// Returns the previous data, `None` if `handle` isn't in the document or `data` is of another kind.
+    pub fn set_data(
+        &mut self,
+        handle: Handle,
+        data: Data<O::Element, O::Leaf>,
+    ) -> Option<Data<O::Element, O::Leaf>> {
+        match (self.data(handle)?, &data) {
+            (Data::Element(_), Data::Element(_)) | (Data::Leaf(_), Data::Leaf(_)) => {}
+            _ => return None,
+        }
+
+        let tree = self.tree_mut();
+
+        tree.unindex(handle);
+
+        let data = tree
+            .slot_mut(handle)
+            .map(|slot| mem::replace(&mut slot.data, data));
+
+        tree.index(handle);
+
+        data
+    }
+
+    /// Returns the nodes to edit, copied first if a snapshot shares them.
+    fn tree_mut(&mut self) -> &mut Tree<O> {
+        Arc::make_mut(&mut self.snapshot.tree)
+    }
+}
+
+/// An iterator of the children of an element or of the top-level nodes of a [`Document`].
+pub struct Children<'a, O>
+where
+    O: Opcode,
+{
+    tree: &'a Tree<O>,
+    next: Option<Handle>,
+}
+
+impl<O> Clone for Children<'_, O>
+where
+    O: Opcode,
+{
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree,
+            next: self.next,
+        }
+    }
+}
+
+impl<O> Iterator for Children<'_, O>
+where
+    O: Opcode,
+{
+    type Item = Handle;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.next?;
+
+        self.next = self.tree.slot(handle).and_then(|slot| slot.next);
+
+        Some(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::{Data, Document, Error, Node, Op, Opcode, Snapshot};
+    use crate::rt::opcode::TypeId;
+
+    /// Attrs are `char`s, elements `u8`s and leaves `u16`s.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Test(Op<char, u8, u16>);
+
+    impl Opcode for Test {
+        type Attr = char;
+        type Element = u8;
+        type Leaf = u16;
+
+        fn into_op(self) -> Op<char, u8, u16> {
+            self.0
+        }
+
+        fn from_op(op: Op<char, u8, u16>) -> Self {
+            Self(op)
+        }
+
+        fn type_id(data: &Data<u8, u16>) -> TypeId {
+            match data {
+                Data::Element(element) => TypeId(*element as usize),
+                Data::Leaf(leaf) => TypeId(*leaf as usize),
+            }
+        }
+    }
+
+    fn opcodes(ops: Vec<Op<char, u8, u16>>) -> Vec<Test> {
+        ops.into_iter().map(Test).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ops = opcodes(vec![
+            Op::Apply('a'),
+            Op::Element(1),
+            Op::Leaf(10),
+            Op::Element(2),
+            Op::Apply('b'),
+            Op::Apply('c'),
+            Op::Leaf(20),
+            Op::Pop,
+            Op::Pop,
+            Op::Leaf(30),
+        ]);
+
+        let document = Document::from_opcodes(ops.clone()).unwrap();
+
+        assert_eq!(
+            document,
+            Document::from_nodes([
+                Node::element(1)
+                    .with_attr('a')
+                    .with_child(Node::leaf(10))
+                    .with_child(
+                        Node::element(2).with_child(Node::leaf(20).with_attr('b').with_attr('c'))
+                    ),
+                Node::leaf(30),
+            ])
+        );
+        assert_eq!(document.to_opcodes(), ops);
+        assert_eq!(document.into_opcodes(), ops);
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut document = Document::<Test>::from_opcodes(opcodes(vec![
+            Op::Element(1),
+            Op::Leaf(10),
+            Op::Element(2),
+            Op::Leaf(20),
+            Op::Pop,
+            Op::Pop,
+            Op::Leaf(30),
+        ]))
+        .unwrap();
+
+        let roots = document.roots().collect::<Vec<_>>();
+        let children = document.children(roots[0]).collect::<Vec<_>>();
+
+        assert_eq!(roots.len(), 2);
+        assert_eq!(children.len(), 2);
+        assert_eq!(document.parent(children[1]), Some(roots[0]));
+        assert_eq!(document.parent(roots[0]), None);
+        assert_eq!(document.next_sibling(children[0]), Some(children[1]));
+        assert_eq!(document.prev_sibling(children[1]), Some(children[0]));
+        assert_eq!(document.prev_sibling(roots[1]), Some(roots[0]));
+        assert_eq!(document.last_child(roots[0]), Some(children[1]));
+        assert_eq!(document.data(roots[1]), Some(&Data::Leaf(30)));
+
+        let leaf = document.first_child(children[1]).unwrap();
+
+        assert_eq!(
+            document.parent(document.parent(leaf).unwrap()),
+            Some(roots[0])
+        );
+
+        // edits keep the links.
+        let inserted = document
+            .insert_before(children[1], Node::leaf(15).with_attr('a'))
+            .unwrap();
+
+        assert_eq!(document.next_sibling(children[0]), Some(inserted));
+        assert_eq!(document.prev_sibling(children[1]), Some(inserted));
+        assert_eq!(document.append(Some(roots[1]), Node::leaf(40)), None);
+
+        assert_eq!(
+            document.remove(children[1]),
+            Some(Node::element(2).with_child(Node::leaf(20)))
+        );
+        assert!(!document.contains(leaf));
+        assert_eq!(document.remove(leaf), None);
+        assert_eq!(document.last_child(roots[0]), Some(inserted));
+        assert_eq!(document.next_sibling(inserted), None);
+
+        let appended = document.append(None, Node::element(3)).unwrap();
+
+        assert_eq!(document.prev_sibling(appended), Some(roots[1]));
+        assert_eq!(
+            document.into_opcodes(),
+            opcodes(vec![
+                Op::Element(1),
+                Op::Leaf(10),
+                Op::Apply('a'),
+                Op::Leaf(15),
+                Op::Pop,
+                Op::Leaf(30),
+                Op::Element(3),
+                Op::Pop,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_indexes() {
+        // leaves `1x` have the id `"ten"`.
+        fn id(data: &Data<u8, u16>) -> Option<&str> {
+            match data {
+                Data::Leaf(10..20) => Some("ten"),
+                _ => None,
+            }
+        }
+
+        let mut document = Document::<Test>::from_opcodes(opcodes(vec![
+            Op::Element(1),
+            Op::Leaf(10),
+            Op::Leaf(20),
+            Op::Pop,
+        ]))
+        .unwrap()
+        .with_type_index()
+        .with_id_index(id);
+
+        let element = document.roots().next().unwrap();
+        let leaf = document.first_child(element).unwrap();
+
+        assert_eq!(document.by_type(TypeId(1)).collect::<Vec<_>>(), [element]);
+        assert_eq!(document.by_id("ten"), Some(leaf));
+
+        // the indexes follow the edits.
+        let appended = document.append(Some(element), Node::leaf(11)).unwrap();
+
+        assert_eq!(
+            document.set_data(leaf, Data::Leaf(20)),
+            Some(Data::Leaf(10))
+        );
+        assert_eq!(document.set_data(leaf, Data::Element(2)), None);
+        assert_eq!(document.by_id("ten"), Some(appended));
+        assert_eq!(document.by_type(TypeId(20)).count(), 2);
+
+        document.remove(element);
+
+        assert_eq!(document.by_id("ten"), None);
+        assert_eq!(document.by_type(TypeId(20)).count(), 0);
+        assert_eq!(Document::<Test>::new().by_id("ten"), None);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let ops = opcodes(vec![Op::Element(1), Op::Leaf(10), Op::Pop]);
+        let mut document = Document::<Test>::from_opcodes(ops.clone())
+            .unwrap()
+            .with_type_index();
+        let snapshot = document.snapshot();
+        let element = snapshot.roots().next().unwrap();
+
+        // edits don't change the snapshot.
+        let leaf = document.append(Some(element), Node::leaf(20)).unwrap();
+
+        document.attrs_mut(element).unwrap().push('a');
+
+        assert!(!snapshot.contains(leaf));
+        assert_eq!(snapshot.attrs(element), Some(&[][..]));
+        assert_eq!(snapshot.by_type(TypeId(20)).count(), 0);
+        assert_eq!(snapshot.to_opcodes(), ops);
+        assert_eq!(
+            document.snapshot().by_type(TypeId(20)).collect::<Vec<_>>(),
+            [leaf]
+        );
+        assert_eq!(
+            document.into_opcodes(),
+            opcodes(vec![
+                Op::Apply('a'),
+                Op::Element(1),
+                Op::Leaf(10),
+                Op::Leaf(20),
+                Op::Pop,
+            ])
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_snapshots() {
+        use std::{sync::mpsc, thread};
+
+        fn send_sync<T: Send + Sync>() {}
+
+        send_sync::<Snapshot<Test>>();
+
+        let mut document = Document::<Test>::from_nodes([Node::element(1)]);
+        let element = document.roots().next().unwrap();
+        let (sender, receiver) = mpsc::channel::<Snapshot<Test>>();
+
+        // renders the snapshots while the document is edited.
+        let render = thread::spawn(move || {
+            receiver
+                .into_iter()
+                .map(|snapshot| {
+                    snapshot
+                        .children(element)
+                        .filter_map(|handle| match snapshot.data(handle) {
+                            Some(Data::Leaf(leaf)) => Some(*leaf),
+                            _ => None,
+                        })
+                        .sum::<u16>()
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for leaf in 1..=100 {
+            document.append(Some(element), Node::leaf(leaf));
+            sender.send(document.snapshot()).unwrap();
+        }
+
+        drop(sender);
+
+        assert_eq!(
+            render.join().unwrap(),
+            (1..=100).map(|n| n * (n + 1) / 2).collect::<Vec<u16>>()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Document::from_opcodes(opcodes(vec![Op::Leaf(1), Op::Pop])),
+            Err(Error::Pop(1))
+        );
+        assert_eq!(
+            Document::from_opcodes(opcodes(vec![Op::Element(1), Op::Apply('a'), Op::Pop])),
+            Err(Error::Attrs(2))
+        );
+        assert_eq!(
+            Document::from_opcodes(opcodes(vec![Op::Leaf(1), Op::Apply('a')])),
+            Err(Error::Attrs(2))
+        );
+        assert_eq!(
+            Document::from_opcodes(opcodes(vec![Op::Element(1), Op::Element(2), Op::Pop])),
+            Err(Error::Unclosed(1))
+        );
+    }
+}
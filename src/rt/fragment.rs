@@ -0,0 +1,250 @@
+//! A small envelope for document fragments exchanged via clipboard or between applications.
+//!
+//! Encoded layout (all integers are little-endian):
+//!
+//! | field          | size           |
+//! |----------------|----------------|
+//! | magic          | 4 bytes `MLFR` |
+//! | version        | 1 byte         |
+//! | schema hash    | 32 bytes       |
+//! | vocabulary len | u16            |
+//! | vocabulary     | vocabulary len |
+//! | format len     | u16            |
+//! | format         | format len     |
+//! | payload len    | u64            |
+//! | payload        | payload len    |
+//!
+//! Fragments of version 1 have no vocabulary fields.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Magic bytes of an encoded fragment.
+pub const MAGIC: &[u8; 4] = b"MLFR";
+
+/// Current version of the envelope layout.
+pub const VERSION: u8 = 2;
+
+/// Error returns by [`Fragment::decode`] and [`Fragment::verify`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error("Invalid fragment magic bytes.")]
+    Magic,
+
+    #[error("Unsupported fragment version({0}).")]
+    Version(u8),
+
+    #[error("Fragment is truncated.")]
+    Truncated,
+
+    #[error("Fragment format name is not valid utf8.")]
+    Format,
+
+    #[error("Fragment format name is too long({0}).")]
+    FormatTooLong(usize),
+
+    #[error("Fragment vocabulary name is not valid utf8.")]
+    Vocabulary,
+
+    #[error("Fragment vocabulary name is too long({0}).")]
+    VocabularyTooLong(usize),
+
+    #[error("Fragment schema hash mismatch.")]
+    SchemaMismatch,
+
+    #[error("Fragment has {0} trailing bytes after the payload.")]
+    Trailing(usize),
+}
+
+/// A serialized document fragment tagged with the hash of the schema it was produced with.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fragment {
+    /// Hash of the vocabulary schema the payload was serialized with.
+    pub schema_hash: [u8; 32],
+    /// Name of the vocabulary, the `name` of the schema's `meta` stat, empty if unknown.
+    pub vocabulary: String,
+    /// Name of the format used to serialize `payload`.
+    pub format: String,
+    /// Serialized opcodes.
+    pub payload: Vec<u8>,
+}
+
+impl Fragment {
+    /// Create a new fragment.
+    pub fn new(schema_hash: [u8; 32], format: impl Into<String>, payload: Vec<u8>) -> Self {
+        Self {
+            schema_hash,
+            vocabulary: String::new(),
+            format: format.into(),
+            payload,
+        }
+    }
+
+    /// Tag this fragment with the vocabulary name, usually `SCHEMA_META.name` of the generated module.
+    pub fn with_vocabulary(mut self, vocabulary: impl Into<String>) -> Self {
+        self.vocabulary = vocabulary.into();
+        self
+    }
+
+    /// Encode this fragment into bytes.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let format_len = u16::try_from(self.format.len())
+            .map_err(|_| Error::FormatTooLong(self.format.len()))?;
+
+        let vocabulary_len = u16::try_from(self.vocabulary.len())
+            .map_err(|_| Error::VocabularyTooLong(self.vocabulary.len()))?;
+
+        let mut buf = Vec::with_capacity(
+            MAGIC.len()
+                + 1
+                + 32
+                + 2
+                + self.vocabulary.len()
+                + 2
+                + self.format.len()
+                + 8
+                + self.payload.len(),
+        );
+
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        buf.extend_from_slice(&self.schema_hash);
+        buf.extend_from_slice(&vocabulary_len.to_le_bytes());
+        buf.extend_from_slice(self.vocabulary.as_bytes());
+        buf.extend_from_slice(&format_len.to_le_bytes());
+        buf.extend_from_slice(self.format.as_bytes());
+        buf.extend_from_slice(&(self.payload.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&self.payload);
+
+        Ok(buf)
+    }
+
+    /// Decode a fragment from bytes, the payload must end the bytes.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::Magic);
+        }
+
+        let version = reader.take(1)?[0];
+
+        if version == 0 || version > VERSION {
+            return Err(Error::Version(version));
+        }
+
+        let schema_hash = reader.take(32)?.try_into().unwrap();
+
+        let vocabulary = if version >= 2 {
+            let vocabulary_len = u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+
+            core::str::from_utf8(reader.take(vocabulary_len)?)
+                .map_err(|_| Error::Vocabulary)?
+                .to_string()
+        } else {
+            String::new()
+        };
+
+        let format_len = u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+
+        let format = core::str::from_utf8(reader.take(format_len)?)
+            .map_err(|_| Error::Format)?
+            .to_string();
+
+        let payload_len = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+
+        let payload_len = usize::try_from(payload_len).map_err(|_| Error::Truncated)?;
+
+        let payload = reader.take(payload_len)?.to_vec();
+
+        if !reader.0.is_empty() {
+            return Err(Error::Trailing(reader.0.len()));
+        }
+
+        Ok(Self {
+            schema_hash,
+            vocabulary,
+            format,
+            payload,
+        })
+    }
+
+    /// Check that this fragment was produced with the schema identified by `schema_hash`.
+    pub fn verify(&self, schema_hash: &[u8; 32]) -> Result<(), Error> {
+        if &self.schema_hash == schema_hash {
+            Ok(())
+        } else {
+            Err(Error::SchemaMismatch)
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Truncated);
+        }
+
+        let (head, tail) = self.0.split_at(len);
+
+        self.0 = tail;
+
+        Ok(head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::{Error, Fragment};
+
+    #[test]
+    fn test_round_trip() {
+        let fragment = Fragment::new([7; 32], "binary", vec![1, 2, 3]).with_vocabulary("svg");
+
+        let bytes = fragment.encode().unwrap();
+
+        assert_eq!(Fragment::decode(&bytes), Ok(fragment.clone()));
+        assert_eq!(fragment.verify(&[7; 32]), Ok(()));
+        assert_eq!(fragment.verify(&[0; 32]), Err(Error::SchemaMismatch));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = Fragment::new([0; 32], "json", vec![]).encode().unwrap();
+
+        assert_eq!(Fragment::decode(&bytes[1..]), Err(Error::Magic));
+        assert_eq!(
+            Fragment::decode(&bytes[..bytes.len() - 1]),
+            Err(Error::Truncated)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.extend_from_slice(&bytes);
+
+        assert_eq!(
+            Fragment::decode(&trailing),
+            Err(Error::Trailing(bytes.len()))
+        );
+
+        let mut bytes = bytes;
+        bytes[4] = 3;
+
+        assert_eq!(Fragment::decode(&bytes), Err(Error::Version(3)));
+
+        // version 1 has no vocabulary fields.
+        let mut v1 = bytes[..37].to_vec();
+        v1[4] = 1;
+        v1.extend_from_slice(&bytes[39..]);
+
+        assert_eq!(
+            Fragment::decode(&v1),
+            Ok(Fragment::new([0; 32], "json", vec![]))
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Size and alignment information of generated types, see `CodeGen::with_layout_report`.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+/// Size of one enum variant's payload.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VariantLayout {
+    /// Variant name.
+    pub name: &'static str,
+    /// Estimated payload size, the `size_of` a tuple of the variant's field types.
+    pub size: usize,
+}
+
+/// Size and alignment of one generated type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TypeLayout {
+    /// Type name.
+    pub name: &'static str,
+    /// `size_of` of the type.
+    pub size: usize,
+    /// `align_of` of the type.
+    pub align: usize,
+    /// Variant payloads, empty for structs.
+    pub variants: &'static [VariantLayout],
+}
+
+impl TypeLayout {
+    /// Returns the largest variant, if it is at least `threshold` bytes larger than the second-largest one.
+    pub fn large_variant(&self, threshold: usize) -> Option<&VariantLayout> {
+        let mut sizes = self.variants.iter().collect::<Vec<_>>();
+
+        sizes.sort_by_key(|variant| core::cmp::Reverse(variant.size));
+
+        match sizes.as_slice() {
+            [largest, second, ..] if largest.size >= second.size + threshold => Some(largest),
+            _ => None,
+        }
+    }
+}
+
+/// Render a human readable report of `layouts`, sorted by size.
+///
+/// Enum variants at least `threshold` bytes larger than the second-largest variant are flagged,
+/// they are candidates for boxing.
+pub fn report(layouts: &[TypeLayout], threshold: usize) -> String {
+    let mut layouts = layouts.iter().collect::<Vec<_>>();
+
+    layouts.sort_by_key(|layout| core::cmp::Reverse(layout.size));
+
+    let mut report = String::new();
+
+    for layout in layouts {
+        writeln!(
+            report,
+            "{}: size = {}, align = {}",
+            layout.name, layout.size, layout.align
+        )
+        .unwrap();
+
+        if let Some(variant) = layout.large_variant(threshold) {
+            writeln!(
+                report,
+                "    warning: variant `{}` is large ({} bytes), consider boxing it.",
+                variant.name, variant.size
+            )
+            .unwrap();
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TypeLayout, VariantLayout};
+
+    #[test]
+    fn test_large_variant() {
+        let layout = TypeLayout {
+            name: "Paint",
+            size: 264,
+            align: 8,
+            variants: &[
+                VariantLayout {
+                    name: "None",
+                    size: 0,
+                },
+                VariantLayout {
+                    name: "Color",
+                    size: 256,
+                },
+                VariantLayout {
+                    name: "Named",
+                    size: 24,
+                },
+            ],
+        };
+
+        assert_eq!(layout.large_variant(200).map(|v| v.name), Some("Color"));
+        assert_eq!(layout.large_variant(300), None);
+    }
+}
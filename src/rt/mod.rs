@@ -1,6 +1,14 @@
 //! Rust runtime library for generation codes.
 
+#[cfg(feature = "arbitrary")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arbitrary")))]
+pub mod arbitrary;
+pub mod dom;
+pub mod fragment;
+pub mod layout;
 pub mod opcode;
+pub mod resource;
+pub mod value;
 
 #[cfg(feature = "rt_serde")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rt_serde")))]
@@ -1,6 +1,9 @@
+use alloc::{borrow::ToOwned, string::String};
+
 /// The path used by [`Variable`] is used to point to [`Target`].
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Path {
     /// Reference by name.
     Named(String),
@@ -29,6 +32,7 @@ impl From<usize> for Path {
 /// The type of variable pointed to by [`Path`].
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[repr(u8)]
 pub enum Target {
     /// Target is animation register.
@@ -44,6 +48,7 @@ pub enum Target {
 /// Variable used by property fields.
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Variable<T> {
     /// A literal constant value.
     Constant(T),
@@ -68,3 +73,81 @@ where
         Self::Constant(T::default())
     }
 }
+
+/// Stable identifier of a generated element, leaf, data or enum type.
+///
+/// The inner value is the `type_id` passed to the serializer/deserializer.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TypeId(pub usize);
+
+impl From<usize> for TypeId {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TypeId> for usize {
+    fn from(value: TypeId) -> Self {
+        value.0
+    }
+}
+
+/// Stable identifier of a generated attr type.
+///
+/// The inner value is the `type_id` passed to the serializer/deserializer.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AttrId(pub usize);
+
+impl From<usize> for AttrId {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+impl From<AttrId> for usize {
+    fn from(value: AttrId) -> Self {
+        value.0
+    }
+}
+
+/// Stable identifier of the node of an opcode, returns by the generated `Opcode::type_id`.
+///
+/// Element, leaf and attr type ids are numbered independently, so an attr id is never compared with a type id.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Id {
+    /// The id of an element or leaf.
+    Type(TypeId),
+    /// The id of an attr.
+    Attr(AttrId),
+}
+
+impl From<TypeId> for Id {
+    fn from(value: TypeId) -> Self {
+        Self::Type(value)
+    }
+}
+
+impl From<AttrId> for Id {
+    fn from(value: AttrId) -> Self {
+        Self::Attr(value)
+    }
+}
+
+/// The metadata of a vocabulary, declared by the `meta { ... }` stat of its schema.
+///
+/// Generated modules export it as `SCHEMA_META`, so documents and tools can identify their vocabulary.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct SchemaMeta {
+    /// The name of the vocabulary: `svg`.
+    pub name: &'static str,
+    /// The version of the vocabulary: `1.1`.
+    pub version: Option<&'static str>,
+    /// The namespace of the vocabulary: `http://www.w3.org/2000/svg`.
+    pub namespace: Option<&'static str>,
+}
@@ -0,0 +1,201 @@
+//! Lazily loaded external resources, the runtime type of `#[external]` fields.
+
+use core::future::Future;
+
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+// without `std` the content is loaded into a `OnceCell`, so resources aren't `Sync`.
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell as OnceLock;
+
+use alloc::string::String;
+
+use crate::rt::value::Iri;
+
+/// Error returned by [`ResourceLoader`]s.
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+    #[error("Resource `{0}` is not found.")]
+    NotFound(Iri),
+
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Load resource `{0}`: {1}")]
+    Other(Iri, String),
+}
+
+/// Loads the content of external resources synchronously.
+pub trait ResourceLoader<T> {
+    /// Load the content of the resource referenced by `iri`.
+    fn load(&self, iri: &Iri) -> Result<T, ResourceError>;
+}
+
+/// Loads the content of external resources asynchronously.
+pub trait AsyncResourceLoader<T> {
+    /// Load the content of the resource referenced by `iri`.
+    fn load(&self, iri: &Iri) -> impl Future<Output = Result<T, ResourceError>> + Send;
+}
+
+/// A reference to an external resource, whose content is loaded on first use by a [`ResourceLoader`].
+///
+/// Only the [`Iri`] is serialized, two resources are equal if they reference the same iri.
+#[derive(Debug, Default, Clone)]
+pub struct Resource<T> {
+    iri: Iri,
+    content: OnceLock<T>,
+}
+
+impl<T> From<Iri> for Resource<T> {
+    fn from(value: Iri) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Resource<T> {
+    /// Create a resource reference that is not loaded yet.
+    pub fn new(iri: Iri) -> Self {
+        Self {
+            iri,
+            content: OnceLock::new(),
+        }
+    }
+
+    /// Create a resource reference with its already loaded `content`.
+    pub fn with_content(iri: Iri, content: T) -> Self {
+        Self {
+            iri,
+            content: OnceLock::from(content),
+        }
+    }
+
+    /// Returns the referenced iri.
+    pub fn iri(&self) -> &Iri {
+        &self.iri
+    }
+
+    /// Returns the content if it has been loaded.
+    pub fn get(&self) -> Option<&T> {
+        self.content.get()
+    }
+
+    /// Returns true if the content has been loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.content.get().is_some()
+    }
+
+    /// Returns the content, loads it with `loader` on first call.
+    pub fn load<L>(&self, loader: &L) -> Result<&T, ResourceError>
+    where
+        L: ResourceLoader<T> + ?Sized,
+    {
+        if let Some(content) = self.content.get() {
+            return Ok(content);
+        }
+
+        let content = loader.load(&self.iri)?;
+
+        Ok(self.content.get_or_init(|| content))
+    }
+
+    /// Returns the content, loads it with the async `loader` on first call.
+    pub async fn load_async<L>(&self, loader: &L) -> Result<&T, ResourceError>
+    where
+        L: AsyncResourceLoader<T> + ?Sized,
+    {
+        if let Some(content) = self.content.get() {
+            return Ok(content);
+        }
+
+        let content = loader.load(&self.iri).await?;
+
+        Ok(self.content.get_or_init(|| content))
+    }
+}
+
+impl<T> PartialEq for Resource<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iri == other.iri
+    }
+}
+
+impl<T> PartialOrd for Resource<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.iri.partial_cmp(&other.iri)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Resource<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde::Serialize::serialize(&self.iri, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Resource<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <Iri as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T> schemars::JsonSchema for Resource<T> {
+    fn schema_name() -> alloc::borrow::Cow<'static, str> {
+        Iri::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        Iri::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use core::cell::Cell;
+
+    use super::{Resource, ResourceError, ResourceLoader};
+    use crate::rt::value::Iri;
+
+    struct Loader(Cell<usize>);
+
+    impl ResourceLoader<Vec<u8>> for Loader {
+        fn load(&self, iri: &Iri) -> Result<Vec<u8>, ResourceError> {
+            self.0.set(self.0.get() + 1);
+
+            match iri.as_str() {
+                "a.png" => Ok(vec![1, 2, 3]),
+                _ => Err(ResourceError::NotFound(iri.clone())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resource() {
+        let loader = Loader(Cell::new(0));
+
+        let resource = Resource::<Vec<u8>>::new("a.png".parse().unwrap());
+
+        assert!(!resource.is_loaded());
+        assert_eq!(resource.load(&loader).unwrap(), &[1, 2, 3]);
+        assert_eq!(resource.load(&loader).unwrap(), &[1, 2, 3]);
+        assert_eq!(loader.0.get(), 1);
+
+        let missing = Resource::<Vec<u8>>::new("b.png".parse().unwrap());
+
+        assert!(matches!(
+            missing.load(&loader),
+            Err(ResourceError::NotFound(_))
+        ));
+        assert!(!missing.is_loaded());
+    }
+}
@@ -0,0 +1,105 @@
+//! Standard base64 encoding (RFC 4648, with padding) used by the text form of `bytes` values.
+
+use alloc::{string::String, vec::Vec};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Error returned by [`decode`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Base64Error {
+    #[error("Invalid base64 character `{1}` at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Invalid base64 length {0}, expect a multiple of 4.")]
+    Length(usize),
+}
+
+/// Encode `input` as base64 text.
+pub fn encode(input: &[u8]) -> String {
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+
+    for chunk in input.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, byte)| {
+            bits | (*byte as u32) << (16 - idx * 8)
+        });
+
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                output.push(ALPHABET[(bits >> (18 - idx * 6)) as usize & 0x3f] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
+
+/// Decode base64 text, whitespaces are ignored.
+pub fn decode(input: &str) -> Result<Vec<u8>, Base64Error> {
+    let mut output = Vec::with_capacity(input.len() / 4 * 3);
+
+    let mut bits = 0u32;
+    let mut count = 0usize;
+    let mut padding = 0usize;
+
+    for (offset, c) in input.char_indices() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            '=' if count % 4 >= 2 && padding < 2 => {
+                padding += 1;
+                0
+            }
+            c if c.is_ascii_whitespace() => continue,
+            _ => return Err(Base64Error::Unexpect(offset, c)),
+        };
+
+        if padding > 0 && c != '=' {
+            return Err(Base64Error::Unexpect(offset, c));
+        }
+
+        bits = bits << 6 | value;
+        count += 1;
+
+        if count.is_multiple_of(4) {
+            let bytes = bits.to_be_bytes();
+            output.extend_from_slice(&bytes[1..4 - padding]);
+            bits = 0;
+        }
+    }
+
+    if !count.is_multiple_of(4) {
+        return Err(Base64Error::Length(count));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Base64Error, decode, encode};
+
+    #[test]
+    fn test_base64() {
+        for (raw, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\xff\x00\xfe", "/wD+"),
+        ] {
+            assert_eq!(encode(raw), text);
+            assert_eq!(decode(text).unwrap(), raw);
+        }
+
+        assert_eq!(decode("Zm9v\n YmFy").unwrap(), b"foobar");
+        assert_eq!(decode("Zm9"), Err(Base64Error::Length(3)));
+        assert_eq!(decode("Zg=a"), Err(Base64Error::Unexpect(3, 'a')));
+        assert_eq!(decode("Z==="), Err(Base64Error::Unexpect(1, '=')));
+    }
+}
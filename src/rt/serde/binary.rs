@@ -0,0 +1,1579 @@
+//! A compact binary format of opcode streams.
+//!
+//! A document starts with the header `MLB` and the format version, followed by the opcodes. Version 2 headers also
+//! contain the schema version of the document as a string, set by [`Serializer::with_schema_version`] and checked
+//! by [`Deserializer::with_schema_check`], documents without a schema version are written as version 1. Every opcode is a
+//! varint tag, `0` for `Pop` and `type_id + 1` for elements, leaves and attrs, followed by the fields of the node in
+//! declaration order, including the fields with default values.
+//!
+//! Values are written as:
+//!
+//! | value                          | binary                                                     |
+//! |--------------------------------|------------------------------------------------------------|
+//! | `bool`, `byte`, `ubyte`        | one byte                                                   |
+//! | `int128`, `uint128`            | 16 little-endian bytes                                     |
+//! | other integers                 | LEB128 varints, zigzag encoded if signed                   |
+//! | `char`                         | varint unicode scalar value                                |
+//! | `float`, `double`              | little-endian IEEE 754                                     |
+//! | strings, `bytes`               | varint length and the utf-8 text or the bytes              |
+//! | `vec[T]`, `[T;N]`, tuples      | varint length and the items                                |
+//! | `[K -> V]`                     | varint length and the keys and values                      |
+//! | data                           | the fields                                                 |
+//! | enums                          | varint variant index and the fields                        |
+//! | options                        | `0` for `None`, `1` and the value for `Some`               |
+//! | variables                      | `0` and the constant, or `1`, the path and the target      |
+//! | `datetime`                     | zigzag seconds, varint nanoseconds and zigzag utc offset   |
+//! | `uuid`                         | the 16 bytes                                               |
+//! | other types                    | their text forms                                           |
+//!
+//! The format doesn't use names, so it also works with code generated by `CodeGen::strip_names`, but the
+//! `type_id`s and the field order must match the schema that wrote the document.
+//!
+//! With the `std` feature, documents can be read incrementally from a [`std::io::Read`] by [`IoDeserializer`] or
+//! [`from_reader`], without loading them into memory first.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Deref;
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        de::{self, Deserialize, FieldContext, MapAccess, NodeAccess, SeqAccess, Visitor},
+        float::{FloatError, NonFinite},
+        ser::{self, Serialize},
+    },
+    value::{DateTime, Uuid},
+};
+
+/// The magic bytes of a document.
+const MAGIC: &[u8; 3] = b"MLB";
+
+/// The version of the format.
+pub(crate) const VERSION: u8 = 2;
+
+/// The version of headers without the schema version.
+const VERSION_1: u8 = 1;
+
+/// The tag of the `Pop` opcode.
+const POP: u64 = 0;
+
+/// Max nesting depth of data, enums, vectors and maps.
+const MAX_DEPTH: usize = 256;
+
+/// Error returns by the binary [`Serializer`] and [`Deserializer`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    De(#[from] de::Error),
+
+    #[error(transparent)]
+    Float(#[from] FloatError),
+
+    #[error("Invalid binary header, expect `MLB`.")]
+    Magic,
+
+    #[error("Unsupported binary format version {0}, expect at most {VERSION}.")]
+    Version(u8),
+
+    #[error("Unexpected end of input at {0}.")]
+    Eof(usize),
+
+    #[error("Invalid varint at {0}.")]
+    Varint(usize),
+
+    #[error("Invalid utf-8 string at {0}.")]
+    Utf8(usize),
+
+    #[error("Invalid unicode scalar value at {0}.")]
+    Char(usize),
+
+    #[error("Invalid tag {1} at {0}.")]
+    Tag(usize, u8),
+
+    #[error("Expect node type_id {0}, got {1}.")]
+    TypeId(usize, usize),
+
+    #[error("Trailing bytes at {0}.")]
+    Trailing(usize),
+
+    #[error("Values are nested too deeply at {0}.")]
+    Depth(usize),
+
+    #[cfg(feature = "std")]
+    #[error("I/O error at {0}: {1}.")]
+    Io(usize, std::io::ErrorKind),
+}
+
+impl FieldContext for Error {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::De(err) => Error::De(err.field_context(ty, field)),
+            // syntax errors already carry their position.
+            err => err,
+        }
+    }
+}
+
+/// Serialize `opcodes` as a binary document.
+pub fn to_bytes<'a, T>(opcodes: impl IntoIterator<Item = &'a T>) -> Result<Vec<u8>, Error>
+where
+    T: Serialize + 'a,
+{
+    let mut serializer = Serializer::new();
+
+    for opcode in opcodes {
+        opcode.serialize(&mut serializer)?;
+    }
+
+    Ok(serializer.finish())
+}
+
+/// Deserialize the opcodes of a binary document.
+pub fn from_bytes<'de, T>(bytes: &'de [u8]) -> Result<Vec<T>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>>,
+{
+    let mut deserializer = Deserializer::new(bytes)?;
+    let mut opcodes = vec![];
+
+    while let Some(batch) = T::deserialize(&mut deserializer)? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Serialize a single value, e.g. a data or an enum, as a binary document.
+pub fn value_to_bytes<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new();
+
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.finish())
+}
+
+/// Deserialize a single value, e.g. a data or an enum, from a binary document.
+pub fn value_from_bytes<'de, T>(bytes: &'de [u8]) -> Result<T::Value, Error>
+where
+    T: Deserialize<'de>,
+{
+    Deserializer::new(bytes)?.finish_value::<T>()
+}
+
+/// Deserialize the opcodes of a binary document read from `reader`.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn from_reader<T, R>(reader: R) -> Result<Vec<T>, Error>
+where
+    T: de::DeserializeOwned<Owned = Option<Vec<T>>>,
+    R: std::io::Read,
+{
+    let mut deserializer = IoDeserializer::from_reader(reader)?;
+    let mut opcodes = vec![];
+
+    while let Some(batch) = T::deserialize(&mut deserializer)? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Deserialize a single value, e.g. a data or an enum, from a binary document read from `reader`.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn value_from_reader<T, R>(reader: R) -> Result<T::Owned, Error>
+where
+    T: de::DeserializeOwned,
+    R: std::io::Read,
+{
+    IoDeserializer::from_reader(reader)?.finish_value::<T>()
+}
+
+pub(crate) fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Returns the number of bytes of the varint of `value`.
+pub(crate) fn varint_len(value: u64) -> usize {
+    (u64::BITS - (value | 1).leading_zeros()).div_ceil(7) as usize
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// A serializer of binary documents.
+///
+/// ```ignore
+/// let mut serializer = Serializer::new();
+///
+/// for opcode in &opcodes {
+///     opcode.serialize(&mut serializer)?;
+/// }
+///
+/// let bytes = serializer.finish();
+/// ```
+pub struct Serializer {
+    output: Vec<u8>,
+    non_finite: NonFinite,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer {
+    /// Create a serializer, the header is written at once.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Create a serializer with room for `len` bytes of values after the header, e.g. the length computed by
+    /// [`SizeHint`](ser::SizeHint).
+    pub fn with_capacity(len: usize) -> Self {
+        let mut output = Vec::with_capacity(MAGIC.len() + 1 + len);
+        output.extend_from_slice(MAGIC);
+        output.push(VERSION_1);
+
+        Self {
+            output,
+            non_finite: NonFinite::default(),
+        }
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Write the schema version of the document to the header, e.g. the `SCHEMA_VERSION` constant of the generated
+    /// `opcode` module, see [`Deserializer::with_schema_check`].
+    ///
+    /// The header is rewritten, so it's set before any value is serialized.
+    pub fn with_schema_version(mut self, version: &str) -> Self {
+        self.output.truncate(MAGIC.len());
+        self.output.push(VERSION);
+        self.write_bytes(version.as_bytes());
+        self
+    }
+
+    /// Returns the binary document.
+    pub fn finish(self) -> Vec<u8> {
+        self.output
+    }
+
+    /// Returns the bytes written since the last call, used to stream a document.
+    #[cfg(feature = "async")]
+    pub(crate) fn take(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.output)
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.output.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+
+        self.output.push(value as u8);
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write_varint(len as u64);
+    }
+
+    fn write_bytes(&mut self, value: &[u8]) {
+        self.write_len(value.len());
+        self.output.extend_from_slice(value);
+    }
+
+    /// Write the tag of a node opcode.
+    fn write_node(&mut self, type_id: usize) {
+        self.write_varint(type_id as u64 + 1);
+    }
+}
+
+/// Returns by [`Serializer`] to write the fields of a node, an item of a sequence or an entry of a map.
+pub struct NodeSerializer<'a> {
+    serializer: &'a mut Serializer,
+}
+
+impl ser::SerializeNode for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = (index, name);
+
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        key.serialize(&mut *self.serializer)?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Error = Error;
+    type SerializeNode = NodeSerializer<'a>;
+    type SerializeSeq = NodeSerializer<'a>;
+    type SerializeMap = NodeSerializer<'a>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.write_node(type_id);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.write_node(type_id);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.write_node(type_id);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, fields);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant, fields);
+
+        self.write_len(variant_index);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        self.write_len(len);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        self.write_len(len);
+
+        Ok(NodeSerializer { serializer: self })
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        self.output.push(value as u8);
+        Ok(())
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        self.write_bytes(value.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        self.output.push(value as u8);
+        Ok(())
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        self.output.push(value);
+        Ok(())
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        self.write_varint(zigzag(value.into()));
+        Ok(())
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        self.write_varint(value.into());
+        Ok(())
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        self.write_varint(zigzag(value.into()));
+        Ok(())
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        self.write_varint(value.into());
+        Ok(())
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        self.write_varint(zigzag(value));
+        Ok(())
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        self.write_varint(value);
+        Ok(())
+    }
+
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error> {
+        self.output.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error> {
+        self.output.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Self::Error> {
+        self.write_varint(value.into());
+        Ok(())
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f32(value)?;
+
+        self.output.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f64(value)?;
+
+        self.output.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Self::Error> {
+        self.write_bytes(value);
+        Ok(())
+    }
+
+    fn serialize_datetime(self, value: &DateTime) -> Result<(), Self::Error> {
+        self.write_varint(zigzag(value.unix_timestamp()));
+        self.write_varint(value.nanos().into());
+        self.write_varint(zigzag(value.offset().into()));
+        Ok(())
+    }
+
+    fn serialize_uuid(self, value: &Uuid) -> Result<(), Self::Error> {
+        self.output.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.output.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.output.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_constant<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.output.push(0);
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        self.output.push(1);
+
+        match path {
+            Path::Named(name) => {
+                self.output.push(0);
+                self.write_bytes(name.as_bytes());
+            }
+            Path::Index(index) => {
+                self.output.push(1);
+                self.write_len(*index);
+            }
+        }
+
+        self.output.push(*target as u8);
+
+        Ok(())
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        self.write_varint(POP);
+        Ok(())
+    }
+}
+
+/// A part of the input returned by [`Input::read_slice`].
+pub enum Slice<'de, 'a, T: ?Sized = [u8]> {
+    /// The bytes are borrowed from the document and live as long as it.
+    Borrowed(&'de T),
+    /// The bytes are copied into a buffer of the input and only live until the next read.
+    Copied(&'a T),
+}
+
+impl<T: ?Sized> Deref for Slice<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Slice::Borrowed(value) => value,
+            Slice::Copied(value) => value,
+        }
+    }
+}
+
+/// The source of a binary document read by [`Deserializer`], strings of a `Slice::Borrowed` are
+/// deserialized without a copy.
+pub trait Input<'de> {
+    /// Returns the next `len` bytes, `None` if the input ends before.
+    fn read_slice(&mut self, len: usize) -> Result<Option<Slice<'de, '_>>, Error>;
+
+    /// Returns true if all bytes of the input are read.
+    fn is_end(&mut self) -> Result<bool, Error>;
+}
+
+impl<'de> Input<'de> for &'de [u8] {
+    fn read_slice(&mut self, len: usize) -> Result<Option<Slice<'de, '_>>, Error> {
+        if self.len() < len {
+            return Ok(None);
+        }
+
+        let (slice, rest) = self.split_at(len);
+
+        *self = rest;
+
+        Ok(Some(Slice::Borrowed(slice)))
+    }
+
+    fn is_end(&mut self) -> Result<bool, Error> {
+        Ok(self.is_empty())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, R> Input<'de> for de::IoReader<R>
+where
+    R: std::io::Read,
+{
+    fn read_slice(&mut self, len: usize) -> Result<Option<Slice<'de, '_>>, Error> {
+        let position = self.position();
+
+        de::IoReader::read_slice(self, len)
+            .map(|slice| slice.map(Slice::Copied))
+            .map_err(|err| Error::Io(position, err.kind()))
+    }
+
+    fn is_end(&mut self) -> Result<bool, Error> {
+        let position = self.position();
+
+        de::IoReader::is_end(self).map_err(|err| Error::Io(position, err.kind()))
+    }
+}
+
+/// A deserializer of binary documents, opcodes are read until the end of the input.
+pub struct Deserializer<I> {
+    input: I,
+    pos: usize,
+    /// The `type_id` of the opcode whose tag is read by `deserialize_opcode`.
+    pending: Option<usize>,
+    /// The nesting depth of the value being read.
+    depth: usize,
+    non_finite: NonFinite,
+    schema_version: Option<String>,
+}
+
+/// A deserializer of binary documents read incrementally from a [`std::io::Read`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub type IoDeserializer<R> = Deserializer<de::IoReader<R>>;
+
+impl<'a> Deserializer<&'a [u8]> {
+    /// Create a deserializer of the binary document `bytes`, fails if the header is invalid.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::with_input(bytes)
+    }
+}
+
+/// An in-memory input whose strings are copied, e.g. a buffer that is reused after the value is read.
+#[cfg(feature = "async")]
+pub(crate) struct Copied<'a>(&'a [u8]);
+
+#[cfg(feature = "async")]
+impl<'de> Input<'de> for Copied<'_> {
+    fn read_slice(&mut self, len: usize) -> Result<Option<Slice<'de, '_>>, Error> {
+        if self.0.len() < len {
+            return Ok(None);
+        }
+
+        let (slice, rest) = self.0.split_at(len);
+
+        self.0 = rest;
+
+        Ok(Some(Slice::Copied(slice)))
+    }
+
+    fn is_end(&mut self) -> Result<bool, Error> {
+        Ok(self.0.is_empty())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> Deserializer<Copied<'a>> {
+    /// Create a deserializer of `bytes`, a part of a document after the header that starts at `pos`.
+    pub(crate) fn resume(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            input: Copied(bytes),
+            pos,
+            pending: None,
+            depth: 0,
+            non_finite: NonFinite::default(),
+            schema_version: None,
+        }
+    }
+
+    /// Returns the position in the document of the next byte to read.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Deserializer<de::IoReader<R>>
+where
+    R: std::io::Read,
+{
+    /// Create a deserializer of the binary document read from `reader`, fails if the header is invalid.
+    pub fn from_reader(reader: R) -> Result<Self, Error> {
+        Self::with_input(de::IoReader::new(reader))
+    }
+}
+
+impl<'de, I> Deserializer<I>
+where
+    I: Input<'de>,
+{
+    /// Create a deserializer of the binary document read from `input`, fails if the header is invalid.
+    pub fn with_input(input: I) -> Result<Self, Error> {
+        let mut deserializer = Self {
+            input,
+            pos: 0,
+            pending: None,
+            depth: 0,
+            non_finite: NonFinite::default(),
+            schema_version: None,
+        };
+
+        match deserializer.read_slice(MAGIC.len()) {
+            Ok(magic) if *magic == MAGIC[..] => {}
+            Ok(_) | Err(Error::Eof(_)) => return Err(Error::Magic),
+            Err(err) => return Err(err),
+        }
+
+        match deserializer.read_u8()? {
+            VERSION_1 => {}
+            VERSION => deserializer.schema_version = Some(deserializer.read_str()?.to_string()),
+            version => return Err(Error::Version(version)),
+        }
+
+        Ok(deserializer)
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Returns the schema version written to the header, `None` if the document doesn't have one.
+    pub fn schema_version(&self) -> Option<&str> {
+        self.schema_version.as_deref()
+    }
+
+    /// Check the schema version of the document with `check`, e.g. the generated `serde::check_schema_version`,
+    /// documents without a schema version are accepted.
+    pub fn with_schema_check<F>(self, check: F) -> Result<Self, Error>
+    where
+        F: FnOnce(&str) -> Result<(), de::Error>,
+    {
+        if let Some(version) = &self.schema_version {
+            check(version)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Deserialize a single value, fails if the input doesn't end after it.
+    fn finish_value<T>(mut self) -> Result<T::Value, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let value = T::deserialize(&mut self)?;
+
+        if !self.input.is_end()? {
+            return Err(Error::Trailing(self.pos));
+        }
+
+        Ok(value)
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<Slice<'de, '_>, Error> {
+        let pos = self.pos;
+
+        match self.input.read_slice(len)? {
+            Some(slice) => {
+                self.pos += len;
+                Ok(slice)
+            }
+            None => Err(Error::Eof(pos)),
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        // `read_slice` returns exactly `N` bytes.
+        Ok((*self.read_slice(N)?).try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u64, Error> {
+        let start = self.pos;
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+
+            // the 10th byte only has one significant bit.
+            if shift == 63 && byte > 1 {
+                return Err(Error::Varint(start));
+            }
+
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(Error::Varint(start))
+    }
+
+    /// Read a varint and convert it to a narrower integer.
+    fn read_uint<T>(&mut self) -> Result<T, Error>
+    where
+        T: TryFrom<u64>,
+    {
+        let start = self.pos;
+
+        self.read_varint()?
+            .try_into()
+            .map_err(|_| Error::Varint(start))
+    }
+
+    /// Read a zigzag varint and convert it to a narrower integer.
+    fn read_int<T>(&mut self) -> Result<T, Error>
+    where
+        T: TryFrom<i64>,
+    {
+        let start = self.pos;
+
+        unzigzag(self.read_varint()?)
+            .try_into()
+            .map_err(|_| Error::Varint(start))
+    }
+
+    fn read_bytes(&mut self) -> Result<Slice<'de, '_>, Error> {
+        let len = self.read_uint()?;
+
+        self.read_slice(len)
+    }
+
+    fn read_str(&mut self) -> Result<Slice<'de, '_, str>, Error> {
+        let start = self.pos;
+        let utf8 = |_| Error::Utf8(start);
+
+        match self.read_bytes()? {
+            Slice::Borrowed(bytes) => core::str::from_utf8(bytes).map(Slice::Borrowed),
+            Slice::Copied(bytes) => core::str::from_utf8(bytes).map(Slice::Copied),
+        }
+        .map_err(utf8)
+    }
+
+    /// Read the tag of a node opcode and check its `type_id`.
+    fn read_node(&mut self, type_id: usize) -> Result<(), Error> {
+        let found = match self.pending.take() {
+            Some(found) => found,
+            None => {
+                let start = self.pos;
+
+                match self.read_uint::<usize>()? {
+                    0 => return Err(Error::Tag(start, 0)),
+                    tag => tag - 1,
+                }
+            }
+        };
+
+        if found != type_id {
+            return Err(Error::TypeId(type_id, found));
+        }
+
+        Ok(())
+    }
+
+    /// Read a nested value with `read`, values deeper than [`MAX_DEPTH`] are rejected.
+    fn nested<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::Depth(self.pos));
+        }
+
+        self.depth += 1;
+
+        let value = read(self);
+
+        self.depth -= 1;
+
+        value
+    }
+
+    /// Read a one byte tag less than `max`.
+    fn read_tag(&mut self, max: u8) -> Result<u8, Error> {
+        let start = self.pos;
+
+        match self.read_u8()? {
+            tag if tag < max => Ok(tag),
+            tag => Err(Error::Tag(start, tag)),
+        }
+    }
+}
+
+impl<'de, I> de::Deserializer<'de> for &mut Deserializer<I>
+where
+    I: Input<'de>,
+{
+    type Error = Error;
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn is_self_describing(&self) -> bool {
+        false
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.input.is_end()? {
+            return Ok(None);
+        }
+
+        let start = self.pos;
+        let tag = self.read_varint()?;
+
+        if tag == POP {
+            return Ok(Some(vec![visitor.visit_pop::<Error>()?]));
+        }
+
+        let type_id = usize::try_from(tag - 1).map_err(|_| Error::Varint(start))?;
+
+        // the tag is checked by `deserialize_element`, `deserialize_leaf` or `deserialize_attr`.
+        self.pending = Some(type_id);
+
+        Ok(Some(vec![visitor.visit_opcode(type_id, self)?]))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_node(type_id)?;
+
+        visitor.visit_node(FieldsAccess(self))
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_node(type_id)?;
+
+        visitor.visit_node(FieldsAccess(self))
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_node(type_id)?;
+
+        visitor.visit_node(FieldsAccess(self))
+    }
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.nested(|this| visitor.visit_node(FieldsAccess(this)))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        let variant_index = self.read_uint()?;
+
+        self.nested(|this| visitor.visit_enum(variant_index, FieldsAccess(this)))
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining = self.read_uint()?;
+
+        self.nested(|this| {
+            visitor.visit_seq(ItemsAccess {
+                deserializer: this,
+                remaining,
+            })
+        })
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining = self.read_uint()?;
+
+        self.nested(|this| {
+            visitor.visit_map(ItemsAccess {
+                deserializer: this,
+                remaining,
+            })
+        })
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.read_tag(2)? {
+            0 => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.read_tag(2)? == 0 {
+            return visitor.visit_constant(self);
+        }
+
+        let path = match self.read_tag(2)? {
+            0 => Path::Named((*self.read_str()?).into()),
+            _ => Path::Index(self.read_uint()?),
+        };
+
+        let target = match self.read_tag(4)? {
+            0 => Target::Register,
+            1 => Target::ForeachItem,
+            2 => Target::ForeachIndex,
+            _ => Target::Range,
+        };
+
+        visitor.visit_variable(path, target)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.read_str()? {
+            Slice::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Slice::Copied(value) => visitor.visit_string(value),
+        }
+    }
+
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_raw(&self.read_str()?)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.read_bytes()? {
+            Slice::Borrowed(value) => visitor.visit_borrowed_bytes(value),
+            Slice::Copied(value) => visitor.visit_bytes(value),
+        }
+    }
+
+    fn deserialize_datetime<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let seconds = self.read_int()?;
+        let nanos = self.read_uint()?;
+        let offset = self.read_int()?;
+
+        let value = DateTime::from_unix(seconds, nanos, offset).map_err(de::Error::DateTime)?;
+
+        visitor.visit_datetime(value)
+    }
+
+    fn deserialize_uuid<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_uuid(Uuid::from_bytes(self.read_array()?))
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(self.read_tag(2)? == 1)
+    }
+
+    fn deserialize_byte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_byte(self.read_u8()? as i8)
+    }
+
+    fn deserialize_ubyte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_ubyte(self.read_u8()?)
+    }
+
+    fn deserialize_short<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_short(self.read_int()?)
+    }
+
+    fn deserialize_ushort<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_ushort(self.read_uint()?)
+    }
+
+    fn deserialize_int<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_int(self.read_int()?)
+    }
+
+    fn deserialize_uint<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_uint(self.read_uint()?)
+    }
+
+    fn deserialize_long<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_long(self.read_int()?)
+    }
+
+    fn deserialize_ulong<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_ulong(self.read_varint()?)
+    }
+
+    fn deserialize_float<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_float(f32::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_double<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_double(f64::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_int128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_int128(i128::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_uint128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_uint128(u128::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let start = self.pos;
+        let value = char::from_u32(self.read_uint()?).ok_or(Error::Char(start))?;
+
+        visitor.visit_char(value)
+    }
+}
+
+/// Access to the fields of a node, data or enum variant, in declaration order.
+struct FieldsAccess<'a, I>(&'a mut Deserializer<I>);
+
+impl<'de, I> NodeAccess<'de> for FieldsAccess<'_, I>
+where
+    I: Input<'de>,
+{
+    type Error = Error;
+
+    fn deserialize_field<T>(
+        &mut self,
+        ty: &str,
+        index: usize,
+        field_name: Option<&str>,
+    ) -> Result<T::Value, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let _ = (ty, index, field_name);
+
+        T::deserialize(&mut *self.0)
+    }
+}
+
+/// Access to the items of a sequence or the entries of a map.
+struct ItemsAccess<'a, I> {
+    deserializer: &'a mut Deserializer<I>,
+    remaining: usize,
+}
+
+impl<'de, I> SeqAccess<'de> for ItemsAccess<'_, I>
+where
+    I: Input<'de>,
+{
+    type Error = Error;
+
+    fn next_item<T>(&mut self) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        T::deserialize(&mut *self.deserializer).map(Some)
+    }
+}
+
+impl<'de, I> MapAccess<'de> for ItemsAccess<'_, I>
+where
+    I: Input<'de>,
+{
+    type Error = Error;
+
+    fn next_key<K>(&mut self) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: Deserialize<'de>,
+    {
+        self.next_item::<K>()
+    }
+
+    fn next_value<V>(&mut self) -> Result<V::Value, Self::Error>
+    where
+        V: Deserialize<'de>,
+    {
+        V::deserialize(&mut *self.deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, collections::BTreeMap, string::ToString, vec, vec::Vec};
+
+    use super::{Deserializer, Error, Serializer, value_from_bytes, value_to_bytes};
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::{
+            de::{self, Borrow},
+            ser::Serialize,
+        },
+        value::{DateTime, Uuid},
+    };
+
+    /// Strip the header of `bytes`.
+    fn body(bytes: Vec<u8>) -> Vec<u8> {
+        bytes[4..].to_vec()
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(body(value_to_bytes(&-1i32).unwrap()), [1]);
+        assert_eq!(body(value_to_bytes(&300u32).unwrap()), [0xac, 0x02]);
+        assert_eq!(
+            body(value_to_bytes(&"hé".to_string()).unwrap()),
+            [3, b'h', 0xc3, 0xa9]
+        );
+
+        for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+            let bytes = value_to_bytes(&value).unwrap();
+
+            assert_eq!(value_from_bytes::<i64>(&bytes), Ok(value));
+        }
+
+        let map = BTreeMap::from([(1u32, vec![Some(1.5f32), None]), (2, vec![])]);
+        let bytes = value_to_bytes(&map).unwrap();
+
+        assert_eq!(
+            value_from_bytes::<BTreeMap<u32, Vec<Option<f32>>>>(&bytes),
+            Ok(map)
+        );
+
+        let value = (
+            DateTime::from_unix(-86_400, 5, -480).unwrap(),
+            Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8),
+            f64::NAN.to_bits(),
+        );
+        let bytes = value_to_bytes(&value).unwrap();
+
+        assert_eq!(value_from_bytes::<(DateTime, Uuid, u64)>(&bytes), Ok(value));
+
+        assert_eq!(body(value_to_bytes(&'é').unwrap()), [0xe9, 0x01]);
+
+        let value = ('😀', i128::MIN, u128::MAX);
+        let bytes = value_to_bytes(&value).unwrap();
+
+        assert_eq!(value_from_bytes::<(char, i128, u128)>(&bytes), Ok(value));
+    }
+
+    #[test]
+    fn test_nested_option() {
+        for value in [None, Some(None), Some(Some(None)), Some(Some(Some(7)))] {
+            let bytes = value_to_bytes(&value).unwrap();
+
+            assert_eq!(
+                value_from_bytes::<Option<Option<Option<i32>>>>(&bytes),
+                Ok(value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_variable() {
+        for value in [
+            Variable::Constant(5),
+            Variable::Reference {
+                path: Path::Index(3),
+                target: Target::ForeachItem,
+            },
+            Variable::Reference {
+                path: Path::Named("w".to_string()),
+                target: Target::Range,
+            },
+        ] {
+            let bytes = value_to_bytes(&value).unwrap();
+
+            assert_eq!(value_from_bytes::<Variable<i32>>(&bytes), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let bytes = value_to_bytes(&"abc".to_string()).unwrap();
+
+        let value = value_from_bytes::<Borrow<Cow<str>>>(&bytes).unwrap();
+
+        assert!(matches!(value, Cow::Borrowed("abc")));
+        assert_eq!(value_from_bytes::<&str>(&bytes), Ok("abc"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reader() {
+        use super::{value_from_reader, value_to_bytes};
+        use crate::rt::serde::de::IoReader;
+
+        /// A reader that returns at most 3 bytes per read.
+        struct Chunks<'a>(&'a [u8]);
+
+        impl std::io::Read for Chunks<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(self.0.len()).min(3);
+
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+
+                Ok(len)
+            }
+        }
+
+        let value = vec!["a".repeat(100), "hello".to_string(), "".to_string()];
+        let bytes = value_to_bytes(&value).unwrap();
+
+        assert_eq!(
+            value_from_reader::<Vec<alloc::string::String>, _>(Chunks(&bytes)),
+            Ok(value)
+        );
+        assert_eq!(
+            value_from_reader::<Vec<alloc::string::String>, _>(Chunks(&bytes[..50])),
+            Err(Error::Eof(6))
+        );
+
+        let mut bytes = value_to_bytes(&7u8).unwrap();
+
+        bytes.push(0);
+
+        assert_eq!(
+            value_from_reader::<u8, _>(Chunks(&bytes)),
+            Err(Error::Trailing(5))
+        );
+
+        let mut reader = IoReader::with_capacity(2, Chunks(b"abcdef"));
+
+        assert_eq!(reader.read_slice(4).unwrap(), Some(&b"abcd"[..]));
+        assert_eq!(reader.read_slice(3).unwrap(), None);
+        assert_eq!(reader.read_slice(2).unwrap(), Some(&b"ef"[..]));
+        assert_eq!(reader.position(), 6);
+        assert!(reader.is_end().unwrap());
+    }
+
+    #[test]
+    fn test_hints() {
+        use crate::rt::serde::de::Deserializer as _;
+
+        let mut deserializer = Deserializer::new(b"MLB\x01").unwrap();
+
+        assert!(!(&mut deserializer).is_human_readable());
+        assert!(!(&mut deserializer).is_self_describing());
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let mut serializer = Serializer::new().with_schema_version("1.2.0");
+
+        1u8.serialize(&mut serializer).unwrap();
+
+        let bytes = serializer.finish();
+
+        assert_eq!(bytes, b"MLB\x02\x051.2.0\x01");
+
+        let deserializer = Deserializer::new(&bytes).unwrap();
+
+        assert_eq!(deserializer.schema_version(), Some("1.2.0"));
+
+        assert!(
+            deserializer
+                .with_schema_check(|document| de::check_schema_version("1.3.0", document))
+                .is_ok()
+        );
+
+        assert_eq!(
+            Deserializer::new(&bytes)
+                .unwrap()
+                .with_schema_check(|document| de::check_schema_version("2.0.0", document))
+                .err(),
+            Some(Error::De(de::Error::SchemaVersion(
+                "1.2.0".to_string(),
+                "2.0.0".to_string()
+            )))
+        );
+
+        // documents without a schema version are accepted.
+        let bytes = value_to_bytes(&1u8).unwrap();
+
+        assert_eq!(&bytes[..4], b"MLB\x01");
+        assert!(
+            Deserializer::new(&bytes)
+                .unwrap()
+                .with_schema_check(|_| Err(de::Error::UnknownType(0)))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Deserializer::new(b"MLX\x01").err(), Some(Error::Magic));
+        assert_eq!(Deserializer::new(b"MLB\x03").err(), Some(Error::Version(3)));
+        assert_eq!(
+            Deserializer::new(b"MLB\x02\x03a").err(),
+            Some(Error::Eof(5))
+        );
+        assert_eq!(Deserializer::new(b"ML").err(), Some(Error::Magic));
+
+        assert_eq!(
+            value_from_bytes::<Vec<u8>>(b"MLB\x01\x03\x01"),
+            Err(Error::Eof(6))
+        );
+        assert_eq!(
+            value_from_bytes::<u8>(b"MLB\x01\x01\x02"),
+            Err(Error::Trailing(5))
+        );
+        assert_eq!(
+            value_from_bytes::<u16>(b"MLB\x01\xff\xff\x04"),
+            Err(Error::Varint(4))
+        );
+        assert_eq!(
+            value_from_bytes::<u64>(b"MLB\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02"),
+            Err(Error::Varint(4))
+        );
+        assert_eq!(
+            value_from_bytes::<Option<u8>>(b"MLB\x01\x02"),
+            Err(Error::Tag(4, 2))
+        );
+        assert_eq!(
+            value_from_bytes::<alloc::string::String>(b"MLB\x01\x01\xff"),
+            Err(Error::Utf8(4))
+        );
+        assert_eq!(
+            value_from_bytes::<char>(b"MLB\x01\x80\xb0\x03"),
+            Err(Error::Char(4))
+        );
+    }
+}
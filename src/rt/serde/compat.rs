@@ -0,0 +1,1360 @@
+//! Adapters between `mlang`'s serialization traits and the [`serde`] crate.
+//!
+//! [`AsSerde`] implements [`serde::Serialize`] for any [`Serialize`] value and [`FromSerde`] implements
+//! [`serde::Deserialize`] for any [`Deserialize`] value, so the generated code can be written and read by
+//! `serde_json`, `bincode`, `rmp-serde` or any other serde format.
+//!
+//! ```ignore
+//! let json = serde_json::to_string(&AsSerde(&opcodes))?;
+//!
+//! let opcodes: Vec<Opcode> = serde_json::from_str::<FromSerde<Vec<Opcode>>>(&json)?
+//!     .into_inner()
+//!     .into_iter()
+//!     .flatten()
+//!     .flatten()
+//!     .collect();
+//! ```
+//!
+//! Values are mapped to the serde data model as:
+//!
+//! | value                     | serde                                                             |
+//! |---------------------------|-------------------------------------------------------------------|
+//! | elements, leaves, attrs   | a seq of `type_id + 1` and the fields                             |
+//! | `Pop`                     | a seq of `0`                                                      |
+//! | data                      | a seq of the fields                                               |
+//! | enums                     | a seq of the variant index and the fields                         |
+//! | `vec[T]`, `[T;N]`, tuples | a seq                                                             |
+//! | `[K -> V]`                | a map                                                             |
+//! | options                   | an option                                                         |
+//! | variables                 | the newtype variants `Constant` or `Reference`, like [`Variable`] |
+//! | `bytes`                   | bytes                                                             |
+//! | scalars, `char`           | the scalars of the same width                                     |
+//! | other types               | their text forms                                                  |
+//!
+//! Nodes don't carry names, so the mapping works with non self-describing formats and with code generated by
+//! `CodeGen::strip_names`, but the `type_id`s and the field order must match the schema that wrote the document.
+//!
+//! [`Variable`]: crate::rt::opcode::Variable
+
+use alloc::{string::String, vec, vec::Vec};
+use core::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{DeserializeSeed, EnumAccess, Error as _, VariantAccess},
+    ser::{Error as _, SerializeSeq as _},
+};
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        de::{
+            self, Deserialize, DeserializeOwned, FieldContext, Kind, MapAccess, NodeAccess,
+            SeqAccess, Visitor,
+        },
+        ser::{self, Serialize},
+    },
+};
+
+/// A wrapper that implements [`serde::Serialize`] for a [`Serialize`] value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsSerde<T>(pub T);
+
+impl<T> serde::Serialize for AsSerde<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Ref(&self.0).serialize(serializer)
+    }
+}
+
+/// A wrapper that implements [`serde::Deserialize`] for a [`DeserializeOwned`] value.
+pub struct FromSerde<T>(pub T::Owned)
+where
+    T: DeserializeOwned;
+
+impl<T> FromSerde<T>
+where
+    T: DeserializeOwned,
+{
+    /// Returns the deserialized value.
+    pub fn into_inner(self) -> T::Owned {
+        self.0
+    }
+}
+
+impl<'de, T> serde::Deserialize<'de> for FromSerde<T>
+where
+    T: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <T as Deserialize<'de>>::deserialize(Deserializer::new(deserializer))
+            .map(FromSerde)
+            .map_err(Error::into_serde)
+    }
+}
+
+/// The payload of the `Reference` variant of a variable.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Reference<P, T> {
+    path: P,
+    target: T,
+}
+
+/// The variants of a variable.
+#[derive(serde::Deserialize)]
+#[serde(variant_identifier)]
+enum VariableTag {
+    Constant,
+    Reference,
+}
+
+const VARIABLE: &str = "Variable";
+
+const VARIABLE_VARIANTS: &[&str] = &["Constant", "Reference"];
+
+/// A [`serde::Serialize`] view of a borrowed [`Serialize`] value.
+struct Ref<'a, T: ?Sized>(&'a T);
+
+impl<T> serde::Serialize for Ref<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut ok = None;
+
+        self.0.serialize(Serializer {
+            serializer,
+            ok: &mut ok,
+        })?;
+
+        ok.ok_or_else(|| S::Error::custom("value didn't serialize anything"))
+    }
+}
+
+/// Adapts a [`serde::Serializer`] to [`ser::Serializer`], the result of the serde serializer is stored in `ok`.
+struct Serializer<'a, S>
+where
+    S: serde::Serializer,
+{
+    serializer: S,
+    ok: &'a mut Option<S::Ok>,
+}
+
+/// Returns by [`Serializer`] to write the fields of a node, the items of a sequence or the entries of a map.
+struct Compound<'a, C, O> {
+    compound: C,
+    ok: &'a mut Option<O>,
+}
+
+impl<C> ser::SerializeNode for Compound<'_, C, C::Ok>
+where
+    C: serde::ser::SerializeSeq,
+{
+    type Error = C::Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = (index, name);
+
+        self.compound.serialize_element(&Ref(value))
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        *self.ok = Some(self.compound.end()?);
+        Ok(())
+    }
+}
+
+impl<C> ser::SerializeSeq for Compound<'_, C, C::Ok>
+where
+    C: serde::ser::SerializeSeq,
+{
+    type Error = C::Error;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.compound.serialize_element(&Ref(value))
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        *self.ok = Some(self.compound.end()?);
+        Ok(())
+    }
+}
+
+impl<C> ser::SerializeMap for Compound<'_, C, C::Ok>
+where
+    C: serde::ser::SerializeMap,
+{
+    type Error = C::Error;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.compound.serialize_entry(&Ref(key), &Ref(value))
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        *self.ok = Some(self.compound.end()?);
+        Ok(())
+    }
+}
+
+impl<'a, S> Serializer<'a, S>
+where
+    S: serde::Serializer,
+{
+    /// Start a seq of `len` items, with `tag` as the first item.
+    fn tagged(
+        self,
+        tag: usize,
+        len: usize,
+    ) -> Result<Compound<'a, S::SerializeSeq, S::Ok>, S::Error> {
+        let mut compound = self.serializer.serialize_seq(Some(len + 1))?;
+
+        compound.serialize_element(&tag)?;
+
+        Ok(Compound {
+            compound,
+            ok: self.ok,
+        })
+    }
+}
+
+impl<'a, S> ser::Serializer for Serializer<'a, S>
+where
+    S: serde::Serializer,
+{
+    type Error = S::Error;
+    type SerializeNode = Compound<'a, S::SerializeSeq, S::Ok>;
+    type SerializeSeq = Compound<'a, S::SerializeSeq, S::Ok>;
+    type SerializeMap = Compound<'a, S::SerializeMap, S::Ok>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = name;
+
+        self.tagged(type_id + 1, fields)
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = name;
+
+        self.tagged(type_id + 1, fields)
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = name;
+
+        self.tagged(type_id + 1, fields)
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name);
+
+        self.serialize_seq(fields)
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant);
+
+        self.tagged(variant_index, fields)
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(Compound {
+            compound: self.serializer.serialize_seq(Some(len))?,
+            ok: self.ok,
+        })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(Compound {
+            compound: self.serializer.serialize_map(Some(len))?,
+            ok: self.ok,
+        })
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_bool(value)?);
+        Ok(())
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_str(value)?);
+        Ok(())
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_i8(value)?);
+        Ok(())
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_u8(value)?);
+        Ok(())
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_i16(value)?);
+        Ok(())
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_u16(value)?);
+        Ok(())
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_i32(value)?);
+        Ok(())
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_u32(value)?);
+        Ok(())
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_i64(value)?);
+        Ok(())
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_u64(value)?);
+        Ok(())
+    }
+
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_i128(value)?);
+        Ok(())
+    }
+
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_u128(value)?);
+        Ok(())
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_char(value)?);
+        Ok(())
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_f32(value)?);
+        Ok(())
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_f64(value)?);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_bytes(value)?);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_none()?);
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        *self.ok = Some(self.serializer.serialize_some(&Ref(value))?);
+        Ok(())
+    }
+
+    fn serialize_constant<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        *self.ok = Some(self.serializer.serialize_newtype_variant(
+            VARIABLE,
+            0,
+            VARIABLE_VARIANTS[0],
+            &Ref(value),
+        )?);
+        Ok(())
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        *self.ok = Some(self.serializer.serialize_newtype_variant(
+            VARIABLE,
+            1,
+            VARIABLE_VARIANTS[1],
+            &Reference { path, target },
+        )?);
+        Ok(())
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        self.tagged(0, 0).and_then(ser::SerializeNode::finish)
+    }
+}
+
+/// Error of the deserializer adapters, converted to the error of the serde deserializer by [`Error::into_serde`].
+enum Error<E> {
+    Serde(E),
+    De(de::Error),
+}
+
+impl<E> From<de::Error> for Error<E> {
+    fn from(value: de::Error) -> Self {
+        Self::De(value)
+    }
+}
+
+impl<E> FieldContext for Error<E> {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::De(err) => Error::De(err.field_context(ty, field)),
+            // the errors of the serde deserializer have their own context.
+            err => err,
+        }
+    }
+}
+
+impl<E> Error<E>
+where
+    E: serde::de::Error,
+{
+    fn into_serde(self) -> E {
+        match self {
+            Error::Serde(err) => err,
+            Error::De(err) => E::custom(err),
+        }
+    }
+}
+
+/// A [`DeserializeSeed`] that reads a [`Deserialize`] value.
+struct Seed<T>(PhantomData<T>);
+
+impl<T> Seed<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for Seed<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(Deserializer::new(deserializer)).map_err(Error::into_serde)
+    }
+}
+
+/// Adapts a [`serde::Deserializer`] to [`de::Deserializer`].
+struct Deserializer<'de, D> {
+    deserializer: D,
+    marker: PhantomData<&'de ()>,
+}
+
+impl<'de, D> Deserializer<'de, D>
+where
+    D: serde::Deserializer<'de>,
+{
+    fn new(deserializer: D) -> Self {
+        Self {
+            deserializer,
+            marker: PhantomData,
+        }
+    }
+
+    /// Read a serde value and pass it to `visit`.
+    fn read<T, R>(
+        self,
+        visit: impl FnOnce(T) -> Result<R, Error<D::Error>>,
+    ) -> Result<R, Error<D::Error>>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        visit(T::deserialize(self.deserializer).map_err(Error::Serde)?)
+    }
+
+    fn read_seq<V>(self, visitor: V, kind: SeqKind) -> Result<V::Value, Error<D::Error>>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_seq(SeqVisitor { visitor, kind })
+            .map_err(Error::Serde)
+    }
+}
+
+impl<'de, D> de::Deserializer<'de> for Deserializer<'de, D>
+where
+    D: serde::Deserializer<'de>,
+{
+    type Error = Error<D::Error>;
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+
+    fn is_self_describing(&self) -> bool {
+        false
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Ok(Some(vec![self.read_seq(visitor, SeqKind::Opcode)?]))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_seq(visitor, SeqKind::Node(type_id))
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_seq(visitor, SeqKind::Node(type_id))
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.read_seq(visitor, SeqKind::Node(type_id))
+    }
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.read_seq(visitor, SeqKind::Data)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.read_seq(visitor, SeqKind::Enum)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read_seq(visitor, SeqKind::Items)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_map(MapVisitor(visitor))
+            .map_err(Error::Serde)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_option(OptionVisitor(visitor))
+            .map_err(Error::Serde)
+    }
+
+    fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_enum(VARIABLE, VARIABLE_VARIANTS, VariableVisitor(visitor))
+            .map_err(Error::Serde)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_str(StrVisitor(visitor))
+            .map_err(Error::Serde)
+    }
+
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value: String| visitor.visit_raw(&value))
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer
+            .deserialize_bytes(BytesVisitor(visitor))
+            .map_err(Error::Serde)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_bool(value))
+    }
+
+    fn deserialize_byte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_byte(value))
+    }
+
+    fn deserialize_ubyte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_ubyte(value))
+    }
+
+    fn deserialize_short<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_short(value))
+    }
+
+    fn deserialize_ushort<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_ushort(value))
+    }
+
+    fn deserialize_int<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_int(value))
+    }
+
+    fn deserialize_uint<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_uint(value))
+    }
+
+    fn deserialize_long<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_long(value))
+    }
+
+    fn deserialize_ulong<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_ulong(value))
+    }
+
+    fn deserialize_int128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_int128(value))
+    }
+
+    fn deserialize_uint128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_uint128(value))
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_char(value))
+    }
+
+    fn deserialize_float<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_float(value))
+    }
+
+    fn deserialize_double<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.read(|value| visitor.visit_double(value))
+    }
+}
+
+/// The kinds of values read from a serde seq.
+#[derive(Clone, Copy)]
+enum SeqKind {
+    /// Any opcode, the tag selects the node type or `Pop`.
+    Opcode,
+    /// An element, leaf or attr with the `type_id`.
+    Node(usize),
+    Data,
+    Enum,
+    Items,
+}
+
+/// A serde visitor of the seqs written for nodes, data, enums and sequences.
+struct SeqVisitor<V> {
+    visitor: V,
+    kind: SeqKind,
+}
+
+impl<'de, V> serde::de::Visitor<'de> for SeqVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            SeqKind::Opcode | SeqKind::Node(_) => {
+                formatter.write_str("a seq of a tag and the node fields")
+            }
+            SeqKind::Data => formatter.write_str("a seq of the data fields"),
+            SeqKind::Enum => formatter.write_str("a seq of a variant index and the variant fields"),
+            SeqKind::Items => formatter.write_str("a seq"),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut fields = Fields::new(&mut seq);
+
+        let value = match self.kind {
+            SeqKind::Opcode => match fields.tag()? {
+                0 => self.visitor.visit_pop(),
+                tag => self.visitor.visit_opcode(
+                    tag - 1,
+                    OpcodeDeserializer {
+                        fields,
+                        type_id: tag - 1,
+                    },
+                ),
+            },
+            SeqKind::Node(type_id) => match fields.tag()? {
+                0 => Err(de::Error::Unexpect(Kind::Pop).into()),
+                tag if tag - 1 == type_id => self.visitor.visit_node(fields),
+                tag => Err(de::Error::Unexpect(Kind::Opcode(tag - 1)).into()),
+            },
+            SeqKind::Data => self.visitor.visit_node(fields),
+            SeqKind::Enum => {
+                let variant_index = fields.tag()?;
+
+                self.visitor.visit_enum(variant_index, fields)
+            }
+            SeqKind::Items => self.visitor.visit_seq(fields),
+        };
+
+        value.map_err(Error::into_serde)
+    }
+}
+
+/// Access to the items of a serde seq, i.e. the fields of a node, data or enum variant or the items of a sequence.
+struct Fields<'a, 'de, A> {
+    seq: &'a mut A,
+    marker: PhantomData<&'de ()>,
+}
+
+impl<'a, 'de, A> Fields<'a, 'de, A>
+where
+    A: serde::de::SeqAccess<'de>,
+{
+    fn new(seq: &'a mut A) -> Self {
+        Self {
+            seq,
+            marker: PhantomData,
+        }
+    }
+
+    /// Read the opcode tag or the variant index in front of the fields.
+    fn tag(&mut self) -> Result<usize, A::Error> {
+        match self.seq.next_element()? {
+            Some(tag) => Ok(tag),
+            None => Err(A::Error::invalid_length(0, &"a tag")),
+        }
+    }
+
+    fn next<T>(&mut self) -> Result<Option<T::Value>, Error<A::Error>>
+    where
+        T: Deserialize<'de>,
+    {
+        self.seq
+            .next_element_seed(Seed::<T>::new())
+            .map_err(Error::Serde)
+    }
+}
+
+impl<'de, A> NodeAccess<'de> for Fields<'_, 'de, A>
+where
+    A: serde::de::SeqAccess<'de>,
+{
+    type Error = Error<A::Error>;
+
+    fn deserialize_field<T>(
+        &mut self,
+        ty: &str,
+        index: usize,
+        field_name: Option<&str>,
+    ) -> Result<T::Value, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let _ = (ty, field_name);
+
+        match self.next::<T>()? {
+            Some(value) => Ok(value),
+            None => Err(Error::Serde(A::Error::invalid_length(
+                index,
+                &"more fields",
+            ))),
+        }
+    }
+}
+
+impl<'de, A> SeqAccess<'de> for Fields<'_, 'de, A>
+where
+    A: serde::de::SeqAccess<'de>,
+{
+    type Error = Error<A::Error>;
+
+    fn next_item<T>(&mut self) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.next::<T>()
+    }
+}
+
+/// Passed to [`Visitor::visit_opcode`] to read the fields of the opcode with the tag already read.
+struct OpcodeDeserializer<'a, 'de, A> {
+    fields: Fields<'a, 'de, A>,
+    type_id: usize,
+}
+
+impl<'de, A> OpcodeDeserializer<'_, 'de, A>
+where
+    A: serde::de::SeqAccess<'de>,
+{
+    fn visit_node<V>(self, type_id: usize, visitor: V) -> Result<V::Value, Error<A::Error>>
+    where
+        V: Visitor<'de>,
+    {
+        if type_id != self.type_id {
+            return Err(de::Error::Unexpect(Kind::Opcode(self.type_id)).into());
+        }
+
+        visitor.visit_node(self.fields)
+    }
+
+    fn reject<T>(self) -> Result<T, Error<A::Error>> {
+        Err(de::Error::Unexpect(Kind::Opcode(self.type_id)).into())
+    }
+}
+
+macro_rules! reject_deserialize {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let _ = visitor;
+
+                self.reject()
+            }
+        )+
+    };
+}
+
+impl<'de, A> de::Deserializer<'de> for OpcodeDeserializer<'_, 'de, A>
+where
+    A: serde::de::SeqAccess<'de>,
+{
+    type Error = Error<A::Error>;
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        self.reject()
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.visit_node(type_id, visitor)
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.visit_node(type_id, visitor)
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = name;
+
+        self.visit_node(type_id, visitor)
+    }
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name, visitor);
+
+        self.reject()
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name, visitor);
+
+        self.reject()
+    }
+
+    reject_deserialize!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// A serde visitor of maps.
+struct MapVisitor<V>(V);
+
+impl<'de, V> serde::de::Visitor<'de> for MapVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        self.0
+            .visit_map(Entries {
+                map: &mut map,
+                marker: PhantomData,
+            })
+            .map_err(Error::into_serde)
+    }
+}
+
+/// Access to the entries of a serde map.
+struct Entries<'a, 'de, A> {
+    map: &'a mut A,
+    marker: PhantomData<&'de ()>,
+}
+
+impl<'de, A> MapAccess<'de> for Entries<'_, 'de, A>
+where
+    A: serde::de::MapAccess<'de>,
+{
+    type Error = Error<A::Error>;
+
+    fn next_key<K>(&mut self) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: Deserialize<'de>,
+    {
+        self.map
+            .next_key_seed(Seed::<K>::new())
+            .map_err(Error::Serde)
+    }
+
+    fn next_value<V>(&mut self) -> Result<V::Value, Self::Error>
+    where
+        V: Deserialize<'de>,
+    {
+        self.map
+            .next_value_seed(Seed::<V>::new())
+            .map_err(Error::Serde)
+    }
+}
+
+/// A serde visitor of options.
+struct OptionVisitor<V>(V);
+
+impl<'de, V> serde::de::Visitor<'de> for OptionVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an option")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_none::<Error<E>>().map_err(Error::into_serde)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        self.0
+            .visit_some(Deserializer::new(deserializer))
+            .map_err(Error::into_serde)
+    }
+}
+
+/// A serde visitor of the `Constant` and `Reference` variants of variables.
+struct VariableVisitor<V>(V);
+
+impl<'de, V> serde::de::Visitor<'de> for VariableVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a variable")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        match data.variant()? {
+            (VariableTag::Constant, variant) => variant.newtype_variant_seed(ConstantSeed(self.0)),
+            (VariableTag::Reference, variant) => {
+                let Reference { path, target } = variant.newtype_variant()?;
+
+                self.0
+                    .visit_variable::<Error<A::Error>>(path, target)
+                    .map_err(Error::into_serde)
+            }
+        }
+    }
+}
+
+/// A [`DeserializeSeed`] that passes the constant of a variable to [`Visitor::visit_constant`].
+struct ConstantSeed<V>(V);
+
+impl<'de, V> DeserializeSeed<'de> for ConstantSeed<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        self.0
+            .visit_constant(Deserializer::new(deserializer))
+            .map_err(Error::into_serde)
+    }
+}
+
+/// A serde visitor of strings, borrowed strings are passed on without a copy.
+struct StrVisitor<V>(V);
+
+impl<'de, V> serde::de::Visitor<'de> for StrVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0
+            .visit_borrowed_str::<Error<E>>(value)
+            .map_err(Error::into_serde)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0
+            .visit_string::<Error<E>>(value)
+            .map_err(Error::into_serde)
+    }
+}
+
+/// A serde visitor of bytes, formats without a bytes type write them as a seq of `u8`.
+struct BytesVisitor<V>(V);
+
+impl<'de, V> serde::de::Visitor<'de> for BytesVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+
+    fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0
+            .visit_borrowed_bytes::<Error<E>>(value)
+            .map_err(Error::into_serde)
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0
+            .visit_bytes::<Error<E>>(value)
+            .map_err(Error::into_serde)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        self.visit_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        collections::BTreeMap,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{AsSerde, FromSerde};
+    use crate::rt::opcode::{Path, Target, Variable};
+
+    #[test]
+    fn test_values() {
+        let value = (7i32, "hello".to_string(), vec![Some(1u8), None]);
+        let json = serde_json::to_string(&AsSerde(&value)).unwrap();
+
+        assert_eq!(json, r#"[7,"hello",[1,null]]"#);
+        assert_eq!(
+            serde_json::from_str::<FromSerde<(i32, String, Vec<Option<u8>>)>>(&json)
+                .unwrap()
+                .into_inner(),
+            value
+        );
+
+        let map = BTreeMap::from([(1u32, -1i64), (2, i64::MIN)]);
+        let json = serde_json::to_string(&AsSerde(&map)).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<FromSerde<BTreeMap<u32, i64>>>(&json)
+                .unwrap()
+                .into_inner(),
+            map
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let variables = vec![
+            Variable::Constant(1.5f32),
+            Variable::Reference {
+                path: Path::Named("width".to_string()),
+                target: Target::Register,
+            },
+        ];
+
+        // the same representation as the serde derives of `Variable`.
+        let json = serde_json::to_string(&AsSerde(&variables)).unwrap();
+
+        assert_eq!(json, serde_json::to_string(&variables).unwrap());
+        assert_eq!(
+            serde_json::from_str::<FromSerde<Vec<Variable<f32>>>>(&json)
+                .unwrap()
+                .into_inner(),
+            variables
+        );
+    }
+}
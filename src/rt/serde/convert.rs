@@ -0,0 +1,292 @@
+//! Convert documents between the formats of `rt::serde`.
+//!
+//! The formats are read and written through the generated types, so a conversion is generic over the `Opcode` of
+//! a vocabulary:
+//!
+//! ```ignore
+//! let bytes = convert::<Opcode>(json.as_bytes(), Format::Json, Format::Binary)?;
+//! ```
+//!
+//! With the `std` feature, [`main`] is the `convert` command of a vocabulary crate, see `mlangc convert`:
+//!
+//! ```sh
+//! convert input.xml --to binary -o out.mlb
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::rt::serde::{binary, de::Deserialize, json, ser::Serialize, sexpr, xml};
+
+/// Error returns by [`convert`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    Json(#[from] json::Error),
+
+    #[error(transparent)]
+    Xml(#[from] xml::Error),
+
+    #[error(transparent)]
+    Sexpr(#[from] sexpr::Error),
+
+    #[error(transparent)]
+    Binary(#[from] binary::Error),
+
+    #[error("Invalid utf-8 {0} document at {1}.")]
+    Utf8(Format, usize),
+}
+
+/// A document format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Xml,
+    Sexpr,
+    Binary,
+}
+
+impl Format {
+    /// All the formats.
+    pub const ALL: [Format; 4] = [Format::Json, Format::Xml, Format::Sexpr, Format::Binary];
+
+    /// Returns the name of this format, parsed back by [`FromStr`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Xml => "xml",
+            Format::Sexpr => "sexpr",
+            Format::Binary => "binary",
+        }
+    }
+
+    /// Returns the format of a file extension, `json`, `xml`, `sexpr` or `mlb`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "json" => Some(Format::Json),
+            "xml" => Some(Format::Xml),
+            "sexpr" => Some(Format::Sexpr),
+            "mlb" => Some(Format::Binary),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| alloc::format!("unknown format `{}`", s))
+    }
+}
+
+/// Read the opcodes of the `from` document `input`.
+pub fn from_slice<'de, T>(input: &'de [u8], from: Format) -> Result<Vec<T>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>>,
+{
+    Ok(match from {
+        Format::Json => json::from_str(text(input, from)?)?,
+        Format::Xml => xml::from_str(text(input, from)?)?,
+        Format::Sexpr => sexpr::from_str(text(input, from)?)?,
+        Format::Binary => binary::from_bytes(input)?,
+    })
+}
+
+/// Returns the text of the `from` document `input`.
+fn text(input: &[u8], from: Format) -> Result<&str, Error> {
+    core::str::from_utf8(input).map_err(|err| Error::Utf8(from, err.valid_up_to()))
+}
+
+/// Write `opcodes` as a `to` document.
+pub fn to_vec<T>(opcodes: &[T], to: Format) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    Ok(match to {
+        Format::Json => json::to_string(opcodes)?.into_bytes(),
+        Format::Xml => xml::to_string(opcodes)?.into_bytes(),
+        Format::Sexpr => sexpr::to_string(opcodes)?.into_bytes(),
+        Format::Binary => binary::to_bytes(opcodes)?,
+    })
+}
+
+/// Convert the `from` document `input` to a `to` document.
+pub fn convert<'de, T>(input: &'de [u8], from: Format, to: Format) -> Result<Vec<u8>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>> + Serialize,
+{
+    to_vec(&from_slice::<T>(input, from)?, to)
+}
+
+/// The usage of [`main`].
+#[cfg(feature = "std")]
+pub const USAGE: &str = "usage: convert <input> [--from <format>] --to <format> [-o <output>]
+
+formats: json, xml, sexpr, binary; the input format defaults to the format of the input extension,
+`.json`, `.xml`, `.sexpr` or `.mlb`, the output is written to stdout without `-o`.";
+
+/// The options of [`main`].
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+struct Args {
+    input: String,
+    from: Format,
+    to: Format,
+    output: Option<String>,
+}
+
+#[cfg(feature = "std")]
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut input = None;
+        let mut from = None;
+        let mut to = None;
+        let mut output = None;
+
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .ok_or_else(|| alloc::format!("missing the value of `{}`", option))
+            };
+
+            match arg.as_str() {
+                "--from" => from = Some(value(&arg)?.parse()?),
+                "--to" => to = Some(value(&arg)?.parse()?),
+                "-o" | "--output" => output = Some(value(&arg)?),
+                _ if arg.starts_with('-') => {
+                    return Err(alloc::format!("unknown option `{}`", arg));
+                }
+                _ if input.is_none() => input = Some(arg),
+                _ => return Err(alloc::format!("unexpected argument `{}`", arg)),
+            }
+        }
+
+        let input = input.ok_or("missing the input file")?;
+
+        let from = match from {
+            Some(from) => from,
+            None => std::path::Path::new(&input)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(Format::from_extension)
+                .ok_or_else(|| alloc::format!("unknown format of `{}`, set `--from`", input))?,
+        };
+
+        Ok(Self {
+            input,
+            from,
+            to: to.ok_or("missing `--to`")?,
+            output,
+        })
+    }
+}
+
+/// The `convert` command of the vocabulary of `T`, reads the command line arguments, see [`USAGE`].
+///
+/// ```ignore
+/// fn main() -> std::process::ExitCode {
+///     mlang_rs::rt::serde::convert::main::<my_vocab::opcode::Opcode>()
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn main<T>() -> std::process::ExitCode
+where
+    T: for<'de> Deserialize<'de, Value = Option<Vec<T>>> + Serialize,
+{
+    use std::{io::Write, process::ExitCode};
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = std::fs::read(&args.input)
+        .map_err(|err| alloc::format!("read `{}`: {}", args.input, err))
+        .and_then(|input| {
+            convert::<T>(&input, args.from, args.to)
+                .map_err(|err| alloc::format!("convert `{}`: {}", args.input, err))
+        })
+        .and_then(|output| match &args.output {
+            Some(path) => std::fs::write(path, output)
+                .map_err(|err| alloc::format!("write `{}`: {}", path, err)),
+            None => std::io::stdout()
+                .write_all(&output)
+                .map_err(|err| alloc::format!("write stdout: {}", err)),
+        });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Error, Format, text};
+
+    #[test]
+    fn test_format() {
+        for format in Format::ALL {
+            assert_eq!(format.name().parse(), Ok(format));
+        }
+
+        assert!("yaml".parse::<Format>().is_err());
+        assert_eq!(Format::from_extension("mlb"), Some(Format::Binary));
+        assert_eq!(Format::from_extension("svg"), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_args() {
+        use super::Args;
+
+        let args = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(
+            args(&["in.xml", "--to", "binary", "-o", "out.mlb"]),
+            Ok(Args {
+                input: "in.xml".to_string(),
+                from: Format::Xml,
+                to: Format::Binary,
+                output: Some("out.mlb".to_string()),
+            })
+        );
+        assert_eq!(
+            args(&["in.svgx", "--from", "xml", "--to", "json"]).map(|args| args.from),
+            Ok(Format::Xml)
+        );
+        assert!(args(&["in.svgx", "--to", "json"]).is_err());
+        assert!(args(&["in.xml"]).is_err());
+        assert!(args(&["in.xml", "--to"]).is_err());
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(text(b"[1]", Format::Json), Ok("[1]"));
+        assert_eq!(
+            text(b"[\xff]", Format::Json),
+            Err(Error::Utf8(Format::Json, 1))
+        );
+    }
+}
@@ -1,9 +1,29 @@
-use std::{
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
     marker::PhantomData,
     num::{ParseFloatError, ParseIntError},
 };
 
-use crate::rt::opcode::{Path, Target, Variable};
+use crate::rt::{
+    opcode::{Path, Target, Variable},
+    resource::Resource,
+    serde::{
+        base64::Base64Error,
+        float::{FloatError, NonFinite},
+        number::NumberPolicy,
+    },
+    value::{
+        DateTime, DateTimeError, Duration, DurationError, Iri, IriError, PathData, PathDataError,
+        Rect, RectError, Transform, TransformError, Uuid, UuidError, ViewBox,
+    },
+};
 
 /// Error used by [`Visitor`]
 #[derive(Debug, thiserror::Error, PartialEq)]
@@ -31,6 +51,90 @@ pub enum Error {
 
     #[error("Unknown variant index({1}) of enum({0})")]
     UnknownVariantIndex(String, usize),
+
+    #[error("Duplicate attribute `{0}`")]
+    DuplicateAttr(String),
+
+    #[error("Document schema version `{0}` is not compatible with `{1}`")]
+    SchemaVersion(String, String),
+
+    #[error(transparent)]
+    PathData(#[from] PathDataError),
+
+    #[error(transparent)]
+    Transform(#[from] TransformError),
+
+    #[error(transparent)]
+    Rect(#[from] RectError),
+
+    #[error(transparent)]
+    Duration(#[from] DurationError),
+
+    #[error(transparent)]
+    Iri(#[from] IriError),
+
+    #[error(transparent)]
+    DateTime(#[from] DateTimeError),
+
+    #[error(transparent)]
+    Uuid(#[from] UuidError),
+
+    #[error(transparent)]
+    Base64(#[from] Base64Error),
+
+    #[error("Number `{0}` can't be converted to {1}")]
+    Coerce(String, Kind),
+
+    #[error(transparent)]
+    Float(#[from] FloatError),
+
+    #[error("Expect one character, got `{0}`")]
+    Char(String),
+
+    #[cfg(feature = "decimal")]
+    #[error(transparent)]
+    Decimal(#[from] rust_decimal::Error),
+
+    #[error("Decimal `{0}` doesn't fit in decimal({1}, {2})")]
+    Precision(String, u32, u32),
+
+    #[error("String `{0}` can't be borrowed from the input")]
+    Borrow(String),
+
+    /// The error of a nested field, `path` is the name of the outermost type followed by the field names,
+    /// e.g. `rect.stroke.width`.
+    #[error("{}: {source}", join_path(.path))]
+    Context {
+        path: Vec<String>,
+        source: Box<Error>,
+    },
+}
+
+/// Join the non-empty names of a [`Error::Context`] path, types and fields are unnamed with stripped names.
+fn join_path(path: &[String]) -> String {
+    path.iter()
+        .filter(|name| !name.is_empty())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+impl FieldContext for Error {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::Context { mut path, source } => {
+                // the inner path starts with the type of the field.
+                path[0] = field.to_string();
+                path.insert(0, ty.to_string());
+
+                Error::Context { path, source }
+            }
+            err => Error::Context {
+                path: vec![ty.to_string(), field.to_string()],
+                source: Box::new(err),
+            },
+        }
+    }
 }
 
 /// Unexpect kind .
@@ -56,6 +160,10 @@ pub enum Kind {
     Long,
     #[error("kind: ulong.")]
     Ulong,
+    #[error("kind: int128.")]
+    Int128,
+    #[error("kind: uint128.")]
+    Uint128,
     #[error("kind: float.")]
     Float,
     #[error("kind: double.")]
@@ -81,18 +189,28 @@ pub enum Kind {
     Some,
     #[error("kind: seq.")]
     Seq,
+    #[error("kind: map.")]
+    Map,
     #[error("kind: variable.")]
     Variable,
     #[error("kind: Variable::Constant.")]
     Constant,
     #[error("kind: pop.")]
     Pop,
+    #[error("kind: raw.")]
+    Raw,
+    #[error("kind: bytes.")]
+    Bytes,
+    #[error("kind: datetime.")]
+    DateTime,
+    #[error("kind: uuid.")]
+    Uuid,
 }
 
 /// This trait represents a visitor that walks through a deserializer.
-pub trait Visitor: Sized {
+pub trait Visitor<'de>: Sized {
     /// The value produced by this visitor.
-    type Value: 'static;
+    type Value;
 
     fn is_element(&self, name: &str) -> bool {
         let _ = name;
@@ -109,7 +227,7 @@ pub trait Visitor: Sized {
     /// The default implementation fails with a type error.
     fn visit_opcode<D>(self, type_id: usize, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         let _ = type_id;
         let _ = deserializer;
@@ -122,7 +240,7 @@ pub trait Visitor: Sized {
     /// The default implementation fails with a type error.
     fn visit_opcode_with<D>(self, name: &str, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         let _ = name;
         let _ = deserializer;
@@ -139,7 +257,7 @@ pub trait Visitor: Sized {
         deserializer: D,
     ) -> Result<Vec<Self::Value>, D::Error>
     where
-        D: AttrsNodeAccess,
+        D: AttrsNodeAccess<'de>,
     {
         let _ = name;
         let _ = deserializer;
@@ -155,10 +273,18 @@ pub trait Visitor: Sized {
         Err(Error::Unexpect(Kind::Pop).into())
     }
 
-    /// The input contains a element node.
+    /// The input contains a none value.
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        Err(Error::Unexpect(Kind::None).into())
+    }
+
+    /// The input contains a some value, the inner value is read from `deserializer`.
     fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         let _ = deserializer;
 
@@ -168,7 +294,7 @@ pub trait Visitor: Sized {
     /// The input contains a element node.
     fn visit_constant<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         let _ = deserializer;
 
@@ -189,7 +315,7 @@ pub trait Visitor: Sized {
     /// The input contains a data.
     fn visit_node<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: NodeAccess,
+        D: NodeAccess<'de>,
     {
         let _ = deserializer;
 
@@ -199,7 +325,7 @@ pub trait Visitor: Sized {
     /// The input contains a enum data.
     fn visit_enum<D>(self, variant_index: usize, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: NodeAccess,
+        D: NodeAccess<'de>,
     {
         let _ = variant_index;
         let _ = deserializer;
@@ -214,7 +340,7 @@ pub trait Visitor: Sized {
         deserializer: D,
     ) -> Result<Self::Value, D::Error>
     where
-        D: NodeAccess,
+        D: NodeAccess<'de>,
     {
         let _ = variant_name;
         let _ = deserializer;
@@ -225,13 +351,23 @@ pub trait Visitor: Sized {
     /// The input contains a enum data.
     fn visit_seq<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: SeqAccess,
+        D: SeqAccess<'de>,
     {
         let _ = deserializer;
 
         Err(Error::Unexpect(Kind::Seq).into())
     }
 
+    /// The input contains a map.
+    fn visit_map<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: MapAccess<'de>,
+    {
+        let _ = deserializer;
+
+        Err(Error::Unexpect(Kind::Map).into())
+    }
+
     /// The input contains a `string` value.
     fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
     where
@@ -242,6 +378,76 @@ pub trait Visitor: Sized {
         Err(Error::Unexpect(Kind::String).into())
     }
 
+    /// The input contains a `string` value that lives as long as the input.
+    ///
+    /// The default implementation forwards to [`visit_string`](Self::visit_string).
+    fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        self.visit_string(value)
+    }
+
+    /// The input contains a `char` value.
+    ///
+    /// The default implementation forwards a string of the character to [`visit_string`](Self::visit_string).
+    fn visit_char<E>(self, value: char) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        self.visit_string(value.encode_utf8(&mut [0; 4]))
+    }
+
+    /// The input contains the unparsed source text of a value.
+    fn visit_raw<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::Raw).into())
+    }
+
+    /// The input contains a `bytes` value.
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::Bytes).into())
+    }
+
+    /// The input contains a `bytes` value that lives as long as the input.
+    ///
+    /// The default implementation forwards to [`visit_bytes`](Self::visit_bytes).
+    fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        self.visit_bytes(value)
+    }
+
+    /// The input contains a datetime value.
+    fn visit_datetime<E>(self, value: DateTime) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::DateTime).into())
+    }
+
+    /// The input contains an uuid value.
+    fn visit_uuid<E>(self, value: Uuid) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::Uuid).into())
+    }
+
     /// The input contains a `bool` value.
     fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E>
     where
@@ -332,6 +538,26 @@ pub trait Visitor: Sized {
         Err(Error::Unexpect(Kind::Ulong).into())
     }
 
+    /// The input contains a `int128` value.
+    fn visit_int128<E>(self, value: i128) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::Int128).into())
+    }
+
+    /// The input contains a `uint128` value.
+    fn visit_uint128<E>(self, value: u128) -> Result<Self::Value, E>
+    where
+        E: From<Error>,
+    {
+        let _ = value;
+
+        Err(Error::Unexpect(Kind::Uint128).into())
+    }
+
     /// The input contains a `float` value.
     fn visit_float<E>(self, value: f32) -> Result<Self::Value, E>
     where
@@ -353,37 +579,311 @@ pub trait Visitor: Sized {
     }
 }
 
+/// Check if a document written with schema version `document` can be read by schema version `schema`.
+///
+/// Versions are `major.minor.patch` strings, a document is compatible if it has the same major version
+/// and is not newer than the schema.
+pub fn check_schema_version(schema: &str, document: &str) -> Result<(), Error> {
+    let parse = |version: &str| {
+        let parts = version
+            .split('.')
+            .map(|v| v.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        <[u64; 3]>::try_from(parts).ok()
+    };
+
+    match (parse(schema), parse(document)) {
+        (Some(schema), Some(document)) if schema[0] == document[0] && document <= schema => Ok(()),
+        _ => Err(Error::SchemaVersion(
+            document.to_string(),
+            schema.to_string(),
+        )),
+    }
+}
+
 /// Trait to access a sequence value.
-pub trait SeqAccess {
+pub trait SeqAccess<'de> {
     type Error: From<Error>;
 
     /// This returns Ok(Some(value)) for the next value in the sequence, or Ok(None) if there are no more remaining items.
     fn next_item<T>(&mut self) -> Result<Option<T::Value>, Self::Error>
     where
-        T: Deserialize;
+        T: Deserialize<'de>;
+}
+
+/// Trait to access a map value.
+pub trait MapAccess<'de> {
+    type Error: From<Error>;
+
+    /// This returns Ok(Some(key)) for the next entry in the map, or Ok(None) if there are no more remaining entries.
+    fn next_key<K>(&mut self) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: Deserialize<'de>;
+
+    /// Returns the value of the entry whose key was just returned by [`next_key`](MapAccess::next_key).
+    fn next_value<V>(&mut self) -> Result<V::Value, Self::Error>
+    where
+        V: Deserialize<'de>;
+}
+
+/// Policy for an attribute that occurs more than once on the same node.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum DuplicateAttrs {
+    /// Keep the first occurrence.
+    FirstWins,
+    /// Keep the last occurrence.
+    #[default]
+    LastWins,
+    /// Report [`Error::DuplicateAttr`].
+    Error,
+}
+
+impl DuplicateAttrs {
+    /// Returns the indexes of the attribute `names` shadowed by a duplicate, in descending order.
+    pub fn shadowed<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<usize>, Error> {
+        let mut seen = BTreeMap::new();
+        let mut shadowed = vec![];
+
+        for (index, name) in names.into_iter().enumerate() {
+            if let Some(prev) = seen.insert(name, index) {
+                match self {
+                    DuplicateAttrs::FirstWins => {
+                        seen.insert(name, prev);
+                        shadowed.push(index);
+                    }
+                    DuplicateAttrs::LastWins => shadowed.push(prev),
+                    DuplicateAttrs::Error => return Err(Error::DuplicateAttr(name.to_string())),
+                }
+            }
+        }
+
+        shadowed.sort_by(|a, b| b.cmp(a));
+
+        Ok(shadowed)
+    }
+}
+
+/// The default max nesting depth of elements of the json, xml and s-expression deserializers, see e.g.
+/// [`json::Deserializer::with_max_depth`](super::json::Deserializer::with_max_depth).
+///
+/// Elements are read with an explicit stack, so deep documents don't overflow the call stack and the limit only
+/// bounds the memory of a document. The binary format is read as a flat stream of opcodes, so it has no limit.
+/// Values are read recursively by the generated code, so their nesting depth has a fixed limit in each format.
+pub const MAX_ELEMENT_DEPTH: usize = 1 << 20;
+
+/// Policy for a node whose name isn't an element or a leaf of the schema, e.g. in a document written with a newer
+/// version of it.
+///
+/// Unknown attributes and fields are always ignored, only the formats that read nodes by name can skip a node.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum UnknownNames {
+    /// Report [`Error::UnknownTypeName`].
+    #[default]
+    Error,
+    /// Skip the node with its attributes and children.
+    Skip,
+}
+
+/// Policy for a number whose kind differs from the expected one.
+///
+/// Text formats can't distinguish integer widths, a `1` may be read as a `ubyte` or a `double`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NumericCoercion {
+    /// Only accept the expected kind.
+    #[default]
+    Strict,
+    /// Convert numbers of any kind, report [`Error::Coerce`] if the value is out of range
+    /// or is converted to an integer and has a fraction part.
+    Checked,
+}
+
+impl NumericCoercion {
+    fn coerce_int<T>(&self, kind: Kind, value: i128) -> Result<T, Error>
+    where
+        T: Number,
+    {
+        if *self == NumericCoercion::Strict && kind != T::KIND {
+            return Err(Error::Unexpect(kind));
+        }
+
+        T::from_int(value).ok_or_else(|| Error::Coerce(value.to_string(), T::KIND))
+    }
+
+    fn coerce_uint<T>(&self, kind: Kind, value: u128) -> Result<T, Error>
+    where
+        T: Number,
+    {
+        if *self == NumericCoercion::Strict && kind != T::KIND {
+            return Err(Error::Unexpect(kind));
+        }
+
+        T::from_uint(value).ok_or_else(|| Error::Coerce(value.to_string(), T::KIND))
+    }
+
+    fn coerce_float<T>(&self, kind: Kind, value: f64) -> Result<T, Error>
+    where
+        T: Number,
+    {
+        if *self == NumericCoercion::Strict && kind != T::KIND {
+            return Err(Error::Unexpect(kind));
+        }
+
+        T::from_float(value).ok_or_else(|| Error::Coerce(value.to_string(), T::KIND))
+    }
+}
+
+/// Numeric types converted by [`NumericCoercion`].
+trait Number: Sized {
+    const KIND: Kind;
+
+    fn from_int(value: i128) -> Option<Self>;
+
+    /// Convert a `uint128`, the only value that may not fit in an `i128`.
+    fn from_uint(value: u128) -> Option<Self>;
+
+    fn from_float(value: f64) -> Option<Self>;
+
+    /// Apply the [`NonFinite`] policy, integers are always finite.
+    fn check_finite(self, policy: NonFinite) -> Result<Self, FloatError> {
+        let _ = policy;
+
+        Ok(self)
+    }
+}
+
+macro_rules! impl_int_number {
+    ($ty:ident, $kind:ident) => {
+        impl Number for $ty {
+            const KIND: Kind = Kind::$kind;
+
+            fn from_int(value: i128) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn from_uint(value: u128) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn from_float(value: f64) -> Option<Self> {
+                // `MAX as f64 + 1.0` is exact, `MAX as f64` is rounded up for 64-bit integers.
+                // `value as $ty as f64 == value` checks the fraction, `f64::fract` isn't in `core`.
+                if value >= $ty::MIN as f64
+                    && value < $ty::MAX as f64 + 1.0
+                    && value as $ty as f64 == value
+                {
+                    Some(value as $ty)
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
+impl_int_number!(i8, Byte);
+impl_int_number!(u8, Ubyte);
+impl_int_number!(i16, Short);
+impl_int_number!(u16, Ushort);
+impl_int_number!(i32, Int);
+impl_int_number!(u32, Uint);
+impl_int_number!(i64, Long);
+impl_int_number!(u64, Ulong);
+impl_int_number!(i128, Int128);
+impl_int_number!(u128, Uint128);
+
+impl Number for f32 {
+    const KIND: Kind = Kind::Float;
+
+    fn from_int(value: i128) -> Option<Self> {
+        Some(value as f32)
+    }
+
+    fn from_uint(value: u128) -> Option<Self> {
+        Some(value as f32)
+    }
+
+    fn from_float(value: f64) -> Option<Self> {
+        if value.is_finite() && value.abs() > f32::MAX as f64 {
+            None
+        } else {
+            Some(value as f32)
+        }
+    }
+
+    fn check_finite(self, policy: NonFinite) -> Result<Self, FloatError> {
+        policy.check_f32(self)
+    }
+}
+
+impl Number for f64 {
+    const KIND: Kind = Kind::Double;
+
+    fn from_int(value: i128) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn from_uint(value: u128) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn from_float(value: f64) -> Option<Self> {
+        Some(value)
+    }
+
+    fn check_finite(self, policy: NonFinite) -> Result<Self, FloatError> {
+        policy.check_f64(self)
+    }
 }
 
 /// Trait to access applied attrs.
-pub trait AttrsNodeAccess {
+pub trait AttrsNodeAccess<'de> {
     type Error: From<Error>;
 
-    /// Returns a iterator over the attribute names.
-    fn attrs(&self) -> impl Iterator<Item = &str>;
+    /// Returns a iterator over the attribute names and their unparsed values, in document order.
+    fn attrs_with_values(&self) -> impl Iterator<Item = (&str, &str)>;
+
+    /// Returns a iterator over the attribute names, in document order.
+    fn attrs(&self) -> impl Iterator<Item = &str> {
+        self.attrs_with_values().map(|(name, _)| name)
+    }
+
+    /// Returns the policy for attributes that occur more than once, the default is [`DuplicateAttrs::LastWins`].
+    fn duplicate_attrs(&self) -> DuplicateAttrs {
+        DuplicateAttrs::default()
+    }
+
+    /// Remove the attribute at `index` of [`attrs`](Self::attrs), it is shadowed by a duplicate.
+    ///
+    /// Generated code calls this in descending `index` order before any [`deserialize_attr`](Self::deserialize_attr).
+    fn remove_attr(&mut self, index: usize);
 
     /// This returns Ok(Some(value)) for the next value in the sequence, or Ok(None) if there are no more remaining items.
     fn deserialize_attr<V>(&mut self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a element node.
     fn deserialize_node<V>(self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
+}
+
+/// Errors that record the path of the field that failed to deserialize, see [`Error::Context`].
+///
+/// The generated code calls [`field_context`](Self::field_context) on the errors returned by
+/// [`NodeAccess::deserialize_field`].
+pub trait FieldContext: From<Error> {
+    /// Record that the error occurred in the field `field` of the type `ty`.
+    fn field_context(self, ty: &str, field: &str) -> Self;
 }
 
 /// Trait to access a sequence value.
-pub trait NodeAccess {
-    type Error: From<Error>;
+pub trait NodeAccess<'de> {
+    type Error: FieldContext;
 
     /// Deserialize next filed.
     fn deserialize_field<T>(
@@ -393,18 +893,51 @@ pub trait NodeAccess {
         field_name: Option<&str>,
     ) -> Result<T::Value, Self::Error>
     where
-        T: Deserialize;
+        T: Deserialize<'de>;
 }
 
 /// A data format that can deserialize any data structure supported by `mlang`.
-pub trait Deserializer {
+pub trait Deserializer<'de> {
     /// Error type used by this `deserializer`.
-    type Error: From<Error> + 'static;
+    type Error: From<Error>;
+
+    /// Returns the policy for numbers of another kind than the expected one,
+    /// the default is [`NumericCoercion::Strict`].
+    fn numeric_coercion(&self) -> NumericCoercion {
+        NumericCoercion::default()
+    }
+
+    /// Returns the policy for `NaN` and infinite `float` and `double` values,
+    /// the default is [`NonFinite::Keep`].
+    fn non_finite(&self) -> NonFinite {
+        NonFinite::default()
+    }
 
-    /// derserialize a list of opcodes.
+    /// Returns the hook of numbers read from strings, the default is `None`.
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        None
+    }
+
+    /// Returns false if values are written in a compact form instead of text, e.g. by the binary format.
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    /// Returns false if the input doesn't tell the names and types of values, so they are read as the schema
+    /// drives them: the fields of nodes in declaration order, including the fields with default values.
+    fn is_self_describing(&self) -> bool {
+        true
+    }
+
+    /// Returns the policy for nodes with an unknown name, the default is [`UnknownNames::Error`].
+    fn unknown_names(&self) -> UnknownNames {
+        UnknownNames::default()
+    }
+
+    /// derserialize a list of opcodes, an empty list is returned for a skipped node.
     fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a element node.
     fn deserialize_element<V>(
@@ -414,7 +947,7 @@ pub trait Deserializer {
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a element node.
     fn deserialize_leaf<V>(
@@ -424,7 +957,7 @@ pub trait Deserializer {
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a element node.
     fn deserialize_attr<V>(
@@ -434,7 +967,7 @@ pub trait Deserializer {
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a element node.
     fn deserialize_data<V>(
@@ -444,7 +977,7 @@ pub trait Deserializer {
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a enum data.
     fn deserialize_enum<V>(
@@ -454,101 +987,334 @@ pub trait Deserializer {
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a sequence data.
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
-    /// derserialize a option value.
-    fn deserialize_option<V>(self, visitor: V) -> Result<Option<V::Value>, Self::Error>
+    /// derserialize a map data.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
+
+    /// derserialize a option value, calls [`visit_none`](Visitor::visit_none) or [`visit_some`](Visitor::visit_some).
+    ///
+    /// Formats must keep nested options apart, a `Some(None)` is not a `None`.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
 
     /// derserialize a variable value.
     fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a string value.
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
-    /// derserialize a bool value.
-    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// derserialize a value as its unparsed source text.
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
-    /// derserialize a byte value.
-    fn deserialize_byte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// derserialize a bytes value, text formats read base64 by default, binary formats should read raw bytes.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        struct Base64<V>(V);
 
-    /// derserialize a ubyte value.
-    fn deserialize_ubyte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
+        impl<'de, V> Visitor<'de> for Base64<V>
+        where
+            V: Visitor<'de>,
+        {
+            type Value = V::Value;
 
-    /// derserialize a short value.
-    fn deserialize_short<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                let value = super::base64::decode(value).map_err(Error::Base64)?;
 
-    /// derserialize a ushort value.
-    fn deserialize_ushort<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
+                self.0.visit_bytes(&value)
+            }
+        }
 
-    /// derserialize a int value.
-    fn deserialize_int<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
+        self.deserialize_string(Base64(visitor))
+    }
 
-    /// derserialize a uint value.
-    fn deserialize_uint<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
-    /// derserialize a long value.
-    fn deserialize_long<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// derserialize a datetime value, text formats read RFC3339 by default,
+    /// binary formats should read the unix timestamp, nanoseconds and utc offset.
+    fn deserialize_datetime<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        struct Rfc3339<V>(V);
 
-    /// derserialize a ulong value.
-    fn deserialize_ulong<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor;
+        impl<'de, V> Visitor<'de> for Rfc3339<V>
+        where
+            V: Visitor<'de>,
+        {
+            type Value = V::Value;
+
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                let value = value.parse().map_err(Error::DateTime)?;
+
+                self.0.visit_datetime(value)
+            }
+        }
+
+        self.deserialize_string(Rfc3339(visitor))
+    }
+
+    /// derserialize an uuid value, text formats read the hyphenated form by default,
+    /// binary formats should read the 16 bytes.
+    fn deserialize_uuid<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        struct Hyphenated<V>(V);
+
+        impl<'de, V> Visitor<'de> for Hyphenated<V>
+        where
+            V: Visitor<'de>,
+        {
+            type Value = V::Value;
+
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                let value = value.parse().map_err(Error::Uuid)?;
+
+                self.0.visit_uuid(value)
+            }
+        }
+
+        self.deserialize_string(Hyphenated(visitor))
+    }
+
+    /// derserialize a bool value.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a byte value.
+    fn deserialize_byte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a ubyte value.
+    fn deserialize_ubyte<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a short value.
+    fn deserialize_short<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a ushort value.
+    fn deserialize_ushort<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a int value.
+    fn deserialize_int<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a uint value.
+    fn deserialize_uint<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+    /// derserialize a long value.
+    fn deserialize_long<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a ulong value.
+    fn deserialize_ulong<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>;
+
+    /// derserialize a int128 value, formats read the decimal text by default.
+    fn deserialize_int128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    /// derserialize a uint128 value, formats read the decimal text by default.
+    fn deserialize_uint128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    /// derserialize a char value, formats read a string of the character by default.
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+        Self: Sized,
+    {
+        self.deserialize_string(visitor)
+    }
 
     /// derserialize a float value.
     fn deserialize_float<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 
     /// derserialize a double value.
     fn deserialize_double<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: Visitor;
+        V: Visitor<'de>;
 }
 
 /// Implement this trait to support derserializing from any data format.
-pub trait Deserialize: Sized {
-    type Value: 'static;
+///
+/// `'de` is the lifetime of the input, a value may borrow strings from it, see [`Borrow`].
+pub trait Deserialize<'de>: Sized {
+    type Value;
     /// Derserialize this value from given `derserializer`.
     fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer;
+        D: Deserializer<'de>;
+}
+
+/// A [`Deserialize`] type whose value doesn't borrow from the input, like serde's `DeserializeOwned`.
+///
+/// Deserializers that don't keep the input in memory, e.g. readers, only deserialize these types.
+pub trait DeserializeOwned:
+    for<'de> Deserialize<'de, Value = <Self as DeserializeOwned>::Owned>
+{
+    /// The value produced by this type, for any input lifetime.
+    type Owned;
+}
+
+impl<T, V> DeserializeOwned for T
+where
+    T: for<'de> Deserialize<'de, Value = V>,
+{
+    type Owned = V;
+}
+
+/// A buffered reader of a [`std::io::Read`] source, used by deserializers to read a document incrementally
+/// instead of loading it into memory first.
+///
+/// Only the bytes of the value being read are kept in the buffer, it grows if a single read is larger than the
+/// capacity.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub struct IoReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    start: usize,
+    position: usize,
+    capacity: usize,
 }
 
-impl Deserialize for String {
+#[cfg(feature = "std")]
+impl<R> IoReader<R>
+where
+    R: std::io::Read,
+{
+    /// Create a reader of `reader` with a buffer of 8 KiB.
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(8 * 1024, reader)
+    }
+
+    /// Create a reader of `reader` that reads at least `capacity` bytes at a time.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(capacity),
+            start: 0,
+            position: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns the number of bytes consumed from the source.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the source, the buffered bytes that are not consumed yet are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Returns the next `len` bytes, `None` if the source ends before.
+    pub fn read_slice(&mut self, len: usize) -> std::io::Result<Option<&[u8]>> {
+        if !self.fill(len)? {
+            return Ok(None);
+        }
+
+        let start = self.start;
+
+        self.start += len;
+        self.position += len;
+
+        Ok(Some(&self.buf[start..self.start]))
+    }
+
+    /// Returns true if all bytes of the source are consumed.
+    pub fn is_end(&mut self) -> std::io::Result<bool> {
+        Ok(!self.fill(1)?)
+    }
+
+    /// Buffer at least `len` bytes, returns false if the source ends before.
+    fn fill(&mut self, len: usize) -> std::io::Result<bool> {
+        while self.buf.len() - self.start < len {
+            self.buf.drain(..self.start);
+            self.start = 0;
+
+            let filled = self.buf.len();
+
+            self.buf.resize(filled + self.capacity.max(len - filled), 0);
+
+            let read = self.reader.read(&mut self.buf[filled..]);
+
+            self.buf
+                .truncate(filled + read.as_ref().map_or(0, |read| *read));
+
+            match read {
+                Ok(0) => return Ok(false),
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<'de> Deserialize<'de> for String {
     type Value = String;
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V;
 
-        impl Visitor for V {
+        impl<'de> Visitor<'de> for V {
             type Value = String;
 
             fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
@@ -563,15 +1329,391 @@ impl Deserialize for String {
     }
 }
 
-impl Deserialize for bool {
+impl<'de> Deserialize<'de> for Cow<'static, str> {
+    type Value = Cow<'static, str>;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Cow::Owned)
+    }
+}
+
+impl<'de> Deserialize<'de> for &'de str {
+    type Value = &'de str;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = &'de str;
+
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Err(Error::Borrow(value.to_string()).into())
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value)
+            }
+        }
+
+        deserializer.deserialize_string(V)
+    }
+}
+
+/// Deserialize type of `#[borrow]` fields, a `Cow<'de, str>` borrows the string from the input if it can,
+/// e.g. a string without escapes, and falls back to an owned copy, so does a `Cow<'de, [u8]>` with the bytes of
+/// binary documents, text documents encode them.
+pub struct Borrow<T>(PhantomData<T>);
+
+impl<'de> Deserialize<'de> for Borrow<Cow<'de, str>> {
+    type Value = Cow<'de, str>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Cow<'de, str>;
+
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(Cow::Owned(value.to_string()))
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(Cow::Borrowed(value))
+            }
+        }
+
+        deserializer.deserialize_string(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for Borrow<Cow<'de, [u8]>> {
+    type Value = Cow<'de, [u8]>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Cow<'de, [u8]>;
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(Cow::Owned(value.to_vec()))
+            }
+
+            fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(Cow::Borrowed(value))
+            }
+        }
+
+        deserializer.deserialize_bytes(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for PathData {
+    type Value = PathData;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::PathData(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Transform {
+    type Value = Transform;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::Transform(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rect {
+    type Value = Rect;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::Rect(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for ViewBox {
+    type Value = ViewBox;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::Rect(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    type Value = Duration;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::Duration(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Iri {
+    type Value = Iri;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value.parse().map_err(|err| Error::Iri(err).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTime {
+    type Value = DateTime;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = DateTime;
+
+            fn visit_datetime<E>(self, value: DateTime) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value)
+            }
+        }
+
+        deserializer.deserialize_datetime(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    type Value = Uuid;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Uuid;
+
+            fn visit_uuid<E>(self, value: Uuid) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value)
+            }
+        }
+
+        deserializer.deserialize_uuid(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for char {
+    type Value = char;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = char;
+
+            fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                let mut chars = value.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => Err(Error::Char(value.to_string()).into()),
+                }
+            }
+
+            fn visit_char<E>(self, value: char) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value)
+            }
+        }
+
+        deserializer.deserialize_char(V)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl<'de> Deserialize<'de> for rust_decimal::Decimal {
+    type Value = rust_decimal::Decimal;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        value
+            .trim()
+            .parse()
+            .map_err(|err| Error::Decimal(err).into())
+    }
+}
+
+/// Deserialize type of `decimal(P, S)` fields, rejects values with more than `S` fractional digits
+/// or more than `P - S` integral digits.
+pub struct Fixed<const P: u32, const S: u32>;
+
+#[cfg(feature = "decimal")]
+impl<'de, const P: u32, const S: u32> Deserialize<'de> for Fixed<P, S> {
+    type Value = rust_decimal::Decimal;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = <rust_decimal::Decimal as Deserialize<'de>>::deserialize(deserializer)?;
+
+        let normalized = value.normalize();
+        let digits = match normalized.mantissa().unsigned_abs() {
+            0 => 0,
+            mantissa => mantissa.ilog10() + 1,
+        };
+
+        if normalized.scale() > S || digits.saturating_sub(normalized.scale()) > P - S {
+            return Err(Error::Precision(value.to_string(), P, S).into());
+        }
+
+        Ok(value)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Resource<T>
+where
+    T: 'static,
+{
+    type Value = Resource<T>;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Iri::deserialize(deserializer).map(Resource::new)
+    }
+}
+
+/// Deserialize type of `#[raw]` fields, captures the unparsed source text of the value.
+pub struct Raw<T>(PhantomData<T>);
+
+impl<'de> Deserialize<'de> for Raw<String> {
+    type Value = String;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = String;
+
+            fn visit_raw<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value.to_string())
+            }
+        }
+
+        deserializer.deserialize_raw(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for Raw<Cow<'static, str>> {
+    type Value = Cow<'static, str>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Raw::<String>::deserialize(deserializer).map(Cow::Owned)
+    }
+}
+
+/// Deserialize type of `bytes` fields, a `Vec<u8>` is deserialized from a sequence of `ubyte` without it.
+pub struct Bytes<T>(PhantomData<T>);
+
+impl<'de> Deserialize<'de> for Bytes<Vec<u8>> {
+    type Value = Vec<u8>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Vec<u8>;
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(value.to_vec())
+            }
+        }
+
+        deserializer.deserialize_bytes(V)
+    }
+}
+
+impl<'de> Deserialize<'de> for bool {
     type Value = bool;
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V;
 
-        impl Visitor for V {
+        impl<'de> Visitor<'de> for V {
             type Value = bool;
 
             fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
@@ -593,75 +1735,112 @@ impl Deserialize for bool {
     }
 }
 
+macro_rules! visit_num {
+    ($visit:ident, $from:ty, $kind:ident, $coerce:ident, $as:ty) => {
+        fn $visit<E>(self, value: $from) -> Result<Self::Value, E>
+        where
+            E: From<Error>,
+        {
+            Ok(self.0.$coerce(Kind::$kind, <$as>::from(value))?)
+        }
+    };
+}
+
 macro_rules! impl_deserilaize_num {
-    ($ty:ident, $visit:ident, $deserialize:ident) => {
-        impl Deserialize for $ty {
+    ($ty:ident, $deserialize:ident) => {
+        impl<'de> Deserialize<'de> for $ty {
             type Value = $ty;
 
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
             where
-                D: Deserializer,
+                D: Deserializer<'de>,
             {
-                struct V;
+                struct V(NumericCoercion, Option<&'static dyn NumberPolicy>);
 
-                impl Visitor for V {
+                impl<'de> Visitor<'de> for V {
                     type Value = $ty;
 
                     fn visit_string<E>(self, value: &str) -> Result<Self::Value, E>
                     where
                         E: From<Error>,
                     {
+                        let value = match self.1 {
+                            Some(policy) => policy.parse(<$ty as Number>::KIND, value),
+                            None => Cow::Borrowed(value),
+                        };
+
                         let value = value.parse::<$ty>().map_err(|err| Error::from(err))?;
                         Ok(value)
                     }
 
-                    fn $visit<E>(self, value: $ty) -> Result<Self::Value, E>
-                    where
-                        E: From<Error>,
-                    {
-                        Ok(value)
-                    }
+                    visit_num!(visit_byte, i8, Byte, coerce_int, i128);
+                    visit_num!(visit_ubyte, u8, Ubyte, coerce_int, i128);
+                    visit_num!(visit_short, i16, Short, coerce_int, i128);
+                    visit_num!(visit_ushort, u16, Ushort, coerce_int, i128);
+                    visit_num!(visit_int, i32, Int, coerce_int, i128);
+                    visit_num!(visit_uint, u32, Uint, coerce_int, i128);
+                    visit_num!(visit_long, i64, Long, coerce_int, i128);
+                    visit_num!(visit_ulong, u64, Ulong, coerce_int, i128);
+                    visit_num!(visit_int128, i128, Int128, coerce_int, i128);
+                    visit_num!(visit_uint128, u128, Uint128, coerce_uint, u128);
+                    visit_num!(visit_float, f32, Float, coerce_float, f64);
+                    visit_num!(visit_double, f64, Double, coerce_float, f64);
                 }
 
-                deserializer.$deserialize(V)
+                let coercion = deserializer.numeric_coercion();
+                let non_finite = deserializer.non_finite();
+                let policy = deserializer.number_policy();
+
+                let value = deserializer.$deserialize(V(coercion, policy))?;
+
+                Ok(value.check_finite(non_finite).map_err(Error::from)?)
             }
         }
     };
 }
 
-impl_deserilaize_num!(i8, visit_byte, deserialize_byte);
-impl_deserilaize_num!(u8, visit_ubyte, deserialize_ubyte);
-impl_deserilaize_num!(i16, visit_short, deserialize_short);
-impl_deserilaize_num!(u16, visit_ushort, deserialize_ushort);
-impl_deserilaize_num!(i32, visit_int, deserialize_int);
-impl_deserilaize_num!(u32, visit_uint, deserialize_uint);
-impl_deserilaize_num!(i64, visit_long, deserialize_long);
-impl_deserilaize_num!(u64, visit_ulong, deserialize_ulong);
-impl_deserilaize_num!(f32, visit_float, deserialize_float);
-impl_deserilaize_num!(f64, visit_double, deserialize_double);
-
-impl<'de, T> Deserialize for Option<T>
+impl_deserilaize_num!(i8, deserialize_byte);
+impl_deserilaize_num!(u8, deserialize_ubyte);
+impl_deserilaize_num!(i16, deserialize_short);
+impl_deserilaize_num!(u16, deserialize_ushort);
+impl_deserilaize_num!(i32, deserialize_int);
+impl_deserilaize_num!(u32, deserialize_uint);
+impl_deserilaize_num!(i64, deserialize_long);
+impl_deserilaize_num!(u64, deserialize_ulong);
+impl_deserilaize_num!(i128, deserialize_int128);
+impl_deserilaize_num!(u128, deserialize_uint128);
+impl_deserilaize_num!(f32, deserialize_float);
+impl_deserilaize_num!(f64, deserialize_double);
+
+impl<'de, T> Deserialize<'de> for Option<T>
 where
-    T: Deserialize,
+    T: Deserialize<'de>,
 {
     type Value = Option<T::Value>;
     fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V<T>(PhantomData<T>);
 
-        impl<'de, T> Visitor for V<T>
+        impl<'de, T> Visitor<'de> for V<T>
         where
-            T: Deserialize,
+            T: Deserialize<'de>,
         {
-            type Value = T::Value;
+            type Value = Option<T::Value>;
+
+            fn visit_none<E>(self) -> Result<Self::Value, E>
+            where
+                E: From<Error>,
+            {
+                Ok(None)
+            }
 
             fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
             where
-                D: Deserializer,
+                D: Deserializer<'de>,
             {
-                T::deserialize(deserializer)
+                T::deserialize(deserializer).map(Some)
             }
         }
 
@@ -669,20 +1848,33 @@ where
     }
 }
 
-impl<'de, T> Deserialize for Variable<T>
+impl<'de, T> Deserialize<'de> for Box<T>
 where
-    T: Deserialize,
+    T: Deserialize<'de>,
+{
+    type Value = Box<T::Value>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Box::new)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Variable<T>
+where
+    T: Deserialize<'de>,
 {
     type Value = Variable<T::Value>;
     fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V<T>(PhantomData<T>);
 
-        impl<'de, T> Visitor for V<T>
+        impl<'de, T> Visitor<'de> for V<T>
         where
-            T: Deserialize,
+            T: Deserialize<'de>,
         {
             type Value = Variable<T::Value>;
 
@@ -695,7 +1887,7 @@ where
 
             fn visit_constant<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
             where
-                D: Deserializer,
+                D: Deserializer<'de>,
             {
                 Ok(Variable::Constant(T::deserialize(deserializer)?))
             }
@@ -705,26 +1897,26 @@ where
     }
 }
 
-impl<'de, T> Deserialize for Vec<T>
+impl<'de, T> Deserialize<'de> for Vec<T>
 where
-    T: Deserialize,
+    T: Deserialize<'de>,
 {
     type Value = Vec<T::Value>;
     fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V<T>(PhantomData<T>);
 
-        impl<'de, T> Visitor for V<T>
+        impl<'de, T> Visitor<'de> for V<T>
         where
-            T: Deserialize,
+            T: Deserialize<'de>,
         {
             type Value = Vec<T::Value>;
 
             fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
             where
-                S: SeqAccess,
+                S: SeqAccess<'de>,
             {
                 let mut values = vec![];
 
@@ -740,26 +1932,26 @@ where
     }
 }
 
-impl<'de, T, const N: usize> Deserialize for [T; N]
+impl<'de, T, const N: usize> Deserialize<'de> for [T; N]
 where
-    T: Deserialize,
+    T: Deserialize<'de>,
 {
     type Value = [T::Value; N];
     fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
     where
-        D: Deserializer,
+        D: Deserializer<'de>,
     {
         struct V<T>(PhantomData<T>);
 
-        impl<'de, T> Visitor for V<T>
+        impl<'de, T> Visitor<'de> for V<T>
         where
-            T: Deserialize,
+            T: Deserialize<'de>,
         {
             type Value = Vec<T::Value>;
 
             fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
             where
-                S: SeqAccess,
+                S: SeqAccess<'de>,
             {
                 let mut values = vec![];
 
@@ -778,3 +1970,180 @@ where
             .map_err(|err: Vec<T::Value>| Error::OutOfRange(err.len(), N))?)
     }
 }
+
+macro_rules! impl_deserialize_tuple {
+    ($len: literal => $($name: ident $idx: tt)+) => {
+        impl<'de, $($name),+> Deserialize<'de> for ($($name,)+)
+        where
+            $($name: Deserialize<'de>),+
+        {
+            type Value = ($($name::Value,)+);
+            fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct V<$($name),+>(PhantomData<($($name,)+)>);
+
+                impl<'de, $($name),+> Visitor<'de> for V<$($name),+>
+                where
+                    $($name: Deserialize<'de>),+
+                {
+                    type Value = ($($name::Value,)+);
+
+                    fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+                    where
+                        S: SeqAccess<'de>,
+                    {
+                        Ok(($(
+                            seq.next_item::<$name>()?
+                                .ok_or(Error::OutOfRange($idx, $len))?,
+                        )+))
+                    }
+                }
+
+                deserializer.deserialize_seq(V::<$($name),+>(PhantomData))
+            }
+        }
+    };
+}
+
+impl_deserialize_tuple!(1 => T0 0);
+impl_deserialize_tuple!(2 => T0 0 T1 1);
+impl_deserialize_tuple!(3 => T0 0 T1 1 T2 2);
+impl_deserialize_tuple!(4 => T0 0 T1 1 T2 2 T3 3);
+impl_deserialize_tuple!(5 => T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_deserialize_tuple!(6 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_deserialize_tuple!(7 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_deserialize_tuple!(8 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
+impl_deserialize_tuple!(9 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8);
+impl_deserialize_tuple!(10 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9);
+impl_deserialize_tuple!(11 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10);
+impl_deserialize_tuple!(12 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11);
+
+impl<'de, K, V> Deserialize<'de> for BTreeMap<K, V>
+where
+    K: Deserialize<'de>,
+    K::Value: Ord,
+    V: Deserialize<'de>,
+{
+    type Value = BTreeMap<K::Value, V::Value>;
+    fn deserialize<D>(deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Vis<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K, V> Visitor<'de> for Vis<K, V>
+        where
+            K: Deserialize<'de>,
+            K::Value: Ord,
+            V: Deserialize<'de>,
+        {
+            type Value = BTreeMap<K::Value, V::Value>;
+
+            fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut values = BTreeMap::new();
+
+                while let Some(key) = map.next_key::<K>()? {
+                    values.insert(key, map.next_value::<V>()?);
+                }
+
+                Ok(values)
+            }
+        }
+
+        deserializer.deserialize_map(Vis::<K, V>(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::{DuplicateAttrs, Error, FieldContext, Kind, NumericCoercion, check_schema_version};
+
+    const NAMES: [&str; 5] = ["fill", "stroke", "fill", "id", "fill"];
+
+    #[test]
+    fn test_duplicate_attrs() {
+        assert_eq!(DuplicateAttrs::FirstWins.shadowed(NAMES), Ok(vec![4, 2]));
+        assert_eq!(DuplicateAttrs::LastWins.shadowed(NAMES), Ok(vec![2, 0]));
+        assert_eq!(
+            DuplicateAttrs::Error.shadowed(NAMES),
+            Err(Error::DuplicateAttr("fill".to_string()))
+        );
+        assert_eq!(DuplicateAttrs::Error.shadowed(["fill", "id"]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_check_schema_version() {
+        assert_eq!(check_schema_version("1.2.0", "1.2.0"), Ok(()));
+        assert_eq!(check_schema_version("1.2.0", "1.1.9"), Ok(()));
+        assert!(check_schema_version("1.2.0", "1.3.0").is_err());
+        assert!(check_schema_version("2.0.0", "1.0.0").is_err());
+        assert!(check_schema_version("1.0.0", "1.0").is_err());
+    }
+
+    #[test]
+    fn test_field_context() {
+        let err = Error::from("1px".parse::<f32>().unwrap_err())
+            .field_context("stroke", "width")
+            .field_context("rect", "stroke");
+
+        assert_eq!(err.to_string(), "rect.stroke.width: invalid float literal");
+        assert!(matches!(
+            err,
+            Error::Context { path, source } if path == ["rect", "stroke", "width"]
+                && matches!(*source, Error::ParseFloatError(_))
+        ));
+
+        // stripped names.
+        let err = Error::Unexpect(Kind::Int).field_context("", "1");
+
+        assert!(err.to_string().starts_with("1: "));
+    }
+
+    #[test]
+    fn test_numeric_coercion() {
+        let checked = NumericCoercion::Checked;
+
+        assert_eq!(checked.coerce_int::<u8>(Kind::Int, 200), Ok(200));
+        assert_eq!(checked.coerce_int::<f32>(Kind::Int, -3), Ok(-3.0));
+        assert_eq!(checked.coerce_float::<i16>(Kind::Double, 12.0), Ok(12));
+        assert_eq!(checked.coerce_float::<f32>(Kind::Double, 0.5), Ok(0.5));
+        assert_eq!(
+            checked.coerce_int::<u8>(Kind::Int, 300),
+            Err(Error::Coerce("300".to_string(), Kind::Ubyte))
+        );
+        assert_eq!(
+            checked.coerce_float::<i32>(Kind::Double, 1.5),
+            Err(Error::Coerce("1.5".to_string(), Kind::Int))
+        );
+        assert!(
+            checked
+                .coerce_float::<u64>(Kind::Double, 2f64.powi(64))
+                .is_err()
+        );
+        assert!(checked.coerce_float::<f32>(Kind::Double, 1e300).is_err());
+        assert_eq!(
+            checked.coerce_uint::<u128>(Kind::Uint128, u128::MAX),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            checked.coerce_uint::<i128>(Kind::Uint128, u128::MAX),
+            Err(Error::Coerce(u128::MAX.to_string(), Kind::Int128))
+        );
+        assert_eq!(checked.coerce_int::<u128>(Kind::Long, 5), Ok(5));
+
+        let strict = NumericCoercion::Strict;
+
+        assert_eq!(strict.coerce_int::<u8>(Kind::Ubyte, 7), Ok(7));
+        assert_eq!(
+            strict.coerce_int::<u8>(Kind::Int, 7),
+            Err(Error::Unexpect(Kind::Int))
+        );
+    }
+}
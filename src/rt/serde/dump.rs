@@ -0,0 +1,513 @@
+//! A human-readable dump of opcode streams, for debugging serialize impls and golden tests.
+//!
+//! Every opcode starts a line, its fields follow on lines indented one level deeper, keyed by their names or
+//! indexes, and the children of an element are indented until its `pop`:
+//!
+//! ```text
+//! el group #2
+//!   id: None
+//!   attr fill #7
+//!     0: paint::named #3
+//!       name: "blue"
+//!   leaf text #4
+//!     0: "hello"
+//! pop
+//! ```
+//!
+//! Sequences and maps write their items on indented lines, `- item` and `key => value`. A node that writes another
+//! number of fields than it declares is marked by a `!! expect N fields, got M` line.
+
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        float::token,
+        json::target_name,
+        ser::{self, Serialize},
+    },
+};
+
+/// Dump `opcodes` as a string.
+pub fn to_string<'a, T>(opcodes: impl IntoIterator<Item = &'a T>) -> Result<String, fmt::Error>
+where
+    T: Serialize + 'a,
+{
+    let mut dumper = Dumper::new(String::new());
+
+    for opcode in opcodes {
+        opcode.serialize(&mut dumper)?;
+    }
+
+    Ok(dumper.into_inner())
+}
+
+/// A serializer that writes an indented tree of an opcode stream, or of a single value, to a [`fmt::Write`].
+///
+/// ```ignore
+/// let mut dumper = Dumper::new(String::new());
+///
+/// for opcode in &opcodes {
+///     opcode.serialize(&mut dumper)?;
+/// }
+///
+/// println!("{}", dumper.into_inner());
+/// ```
+pub struct Dumper<W> {
+    writer: W,
+    /// The indent level of the value being written.
+    depth: usize,
+    /// The number of open elements.
+    elements: usize,
+    /// Nothing is written yet.
+    first: bool,
+}
+
+impl<W> Dumper<W>
+where
+    W: Write,
+{
+    /// Create a dumper writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            depth: 0,
+            elements: 0,
+            first: true,
+        }
+    }
+
+    /// Returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Start a line at `depth`.
+    fn line(&mut self, depth: usize) -> fmt::Result {
+        if !self.first {
+            self.writer.write_char('\n')?;
+        }
+
+        self.first = false;
+
+        for _ in 0..depth {
+            self.writer.write_str("  ")?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the header of a node, `name #type_id`, `#type_id` if names are stripped.
+    fn header(&mut self, name: &str, type_id: usize) -> fmt::Result {
+        if name.is_empty() {
+            write!(self.writer, "#{}", type_id)
+        } else {
+            write!(self.writer, "{} #{}", name, type_id)
+        }
+    }
+
+    /// Start an opcode on its own line.
+    fn opcode(&mut self, kind: &str, name: &str, type_id: usize) -> fmt::Result {
+        self.line(self.elements)?;
+        self.writer.write_str(kind)?;
+        self.writer.write_char(' ')?;
+        self.header(name, type_id)
+    }
+
+    /// Returns the serializer of the fields of a node, whose header is written.
+    fn node(&mut self, depth: usize, fields: usize, element: bool) -> NodeSerializer<'_, W> {
+        // the fields start new lines, even after a value at the top level.
+        self.first = false;
+
+        NodeSerializer {
+            dumper: self,
+            depth,
+            fields,
+            written: 0,
+            element,
+        }
+    }
+
+    /// Returns the serializer of the items of a sequence or map, whose header is written.
+    fn items(&mut self) -> ItemsSerializer<'_, W> {
+        self.first = false;
+
+        ItemsSerializer {
+            depth: self.depth + 1,
+            dumper: self,
+        }
+    }
+
+    fn write_float(&mut self, value: f64, text: impl fmt::Debug) -> fmt::Result {
+        match token(value) {
+            Some(token) => self.writer.write_str(token),
+            None => write!(self.writer, "{:?}", text),
+        }
+    }
+}
+
+/// Returns by [`Dumper`] to write the fields of a node.
+pub struct NodeSerializer<'a, W> {
+    dumper: &'a mut Dumper<W>,
+    /// The indent level of the fields.
+    depth: usize,
+    fields: usize,
+    written: usize,
+    /// The node is an element, its children follow.
+    element: bool,
+}
+
+impl<W> ser::SerializeNode for NodeSerializer<'_, W>
+where
+    W: Write,
+{
+    type Error = fmt::Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.written += 1;
+        self.dumper.line(self.depth)?;
+
+        match name {
+            Some(name) if !name.is_empty() => write!(self.dumper.writer, "{}: ", name)?,
+            _ => write!(self.dumper.writer, "{}: ", index)?,
+        }
+
+        self.dumper.depth = self.depth;
+
+        value.serialize(&mut *self.dumper)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        if self.written != self.fields {
+            self.dumper.line(self.depth)?;
+            write!(
+                self.dumper.writer,
+                "!! expect {} fields, got {}",
+                self.fields, self.written
+            )?;
+        }
+
+        if self.element {
+            self.dumper.elements += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns by [`Dumper`] to write the items of a sequence or the entries of a map.
+pub struct ItemsSerializer<'a, W> {
+    dumper: &'a mut Dumper<W>,
+    /// The indent level of the items.
+    depth: usize,
+}
+
+impl<W> ser::SerializeSeq for ItemsSerializer<'_, W>
+where
+    W: Write,
+{
+    type Error = fmt::Error;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.dumper.line(self.depth)?;
+        self.dumper.writer.write_str("- ")?;
+        self.dumper.depth = self.depth;
+
+        value.serialize(&mut *self.dumper)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<W> ser::SerializeMap for ItemsSerializer<'_, W>
+where
+    W: Write,
+{
+    type Error = fmt::Error;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.dumper.line(self.depth)?;
+        self.dumper.depth = self.depth;
+
+        key.serialize(&mut *self.dumper)?;
+
+        self.dumper.writer.write_str(" => ")?;
+        self.dumper.depth = self.depth;
+
+        value.serialize(&mut *self.dumper)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W> ser::Serializer for &'a mut Dumper<W>
+where
+    W: Write,
+{
+    type Error = fmt::Error;
+    type SerializeNode = NodeSerializer<'a, W>;
+    type SerializeSeq = ItemsSerializer<'a, W>;
+    type SerializeMap = ItemsSerializer<'a, W>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        self.opcode("el", name, type_id)?;
+
+        Ok(self.node(self.elements + 1, fields, true))
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        self.opcode("leaf", name, type_id)?;
+
+        Ok(self.node(self.elements + 1, fields, false))
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        self.opcode("attr", name, type_id)?;
+
+        Ok(self.node(self.elements + 1, fields, false))
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        self.header(name, type_id)?;
+
+        Ok(self.node(self.depth + 1, fields, false))
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        if variant.is_empty() {
+            write!(self.writer, "{}::{} #{}", name, variant_index, type_id)?;
+        } else {
+            write!(self.writer, "{}::{} #{}", name, variant, type_id)?;
+        }
+
+        Ok(self.node(self.depth + 1, fields, false))
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        write!(self.writer, "[{}]", len)?;
+
+        Ok(self.items())
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        write!(self.writer, "{{{}}}", len)?;
+
+        Ok(self.items())
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        write!(self.writer, "{:?}", value)
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error> {
+        write!(self.writer, "{}", value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Self::Error> {
+        write!(self.writer, "{:?}", value)
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        self.write_float(f64::from(value), value)
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        self.write_float(value, value)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Self::Error> {
+        write!(self.writer, "bytes({})", value.len())?;
+
+        for (index, byte) in value.iter().enumerate() {
+            let sep = if index == 0 { ' ' } else { ':' };
+
+            write!(self.writer, "{}{:02x}", sep, byte)?;
+        }
+
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.writer.write_str("None")
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.writer.write_str("Some ")?;
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        match path {
+            Path::Named(name) => write!(self.writer, "var {:?}", name)?,
+            Path::Index(index) => write!(self.writer, "var {}", index)?,
+        }
+
+        write!(self.writer, " ({})", target_name(target))
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        // unbalanced pops are dumped as is, the dump is for debugging streams.
+        self.elements = self.elements.saturating_sub(1);
+        self.line(self.elements)?;
+        self.writer.write_str("pop")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeMap, string::String, vec};
+
+    use super::{Dumper, to_string};
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::ser::{Serialize, SerializeNode, Serializer},
+    };
+
+    /// An element with a named field, or a pop.
+    struct Node(Option<Option<i32>>);
+
+    impl Serialize for Node {
+        fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+        where
+            S: Serializer,
+        {
+            let Some(value) = &self.0 else {
+                return serializer.serialize_pop();
+            };
+
+            let mut node = serializer.serialize_el(2, "group", 2)?;
+
+            node.serialize_field(0, Some("id"), value)?;
+            node.finish()
+        }
+    }
+
+    #[test]
+    fn test_dump() {
+        let text = to_string(&[
+            Node(Some(Some(1))),
+            Node(Some(None)),
+            Node(None),
+            Node(None),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            text,
+            "el group #2\n  id: Some 1\n  !! expect 2 fields, got 1\n  el group #2\n    id: None\n    !! expect 2 fields, got 1\n  pop\npop"
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let mut dumper = Dumper::new(String::new());
+
+        let map = BTreeMap::from([(1u8, vec![f32::INFINITY, 0.5])]);
+
+        map.serialize(&mut dumper).unwrap();
+
+        assert_eq!(dumper.into_inner(), "{1}\n  1 => [2]\n    - INF\n    - 0.5");
+
+        let mut dumper = Dumper::new(String::new());
+
+        let value = Variable::<i32>::Reference {
+            path: Path::Named("w".into()),
+            target: Target::Register,
+        };
+
+        value.serialize(&mut dumper).unwrap();
+        (&mut dumper).serialize_bytes(&[1, 0xab]).unwrap();
+
+        assert_eq!(dumper.into_inner(), "var \"w\" (register)bytes(2) 01:ab");
+    }
+}
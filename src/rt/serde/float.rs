@@ -0,0 +1,136 @@
+//! Policies for the `float` and `double` values of text formats: the non-finite values, `NaN` and the infinities,
+//! and the number of written decimals.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Error returned by [`NonFinite::Error`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum FloatError {
+    #[error("Non-finite number `{0}` is not allowed.")]
+    NonFinite(&'static str),
+}
+
+/// Policy for `NaN` and infinite values of `float` and `double` fields.
+///
+/// Text backends write kept values as the tokens returned by [`token`], which are parsed back by `str::parse`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NonFinite {
+    /// Keep non-finite values.
+    #[default]
+    Keep,
+    /// Report [`FloatError::NonFinite`].
+    Error,
+    /// Clamp infinities to the largest finite values, `NaN` becomes `0`.
+    Clamp,
+}
+
+macro_rules! check_float {
+    ($name:ident, $ty:ident) => {
+        #[doc = concat!("Apply this policy to a `", stringify!($ty), "` value.")]
+        pub fn $name(&self, value: $ty) -> Result<$ty, FloatError> {
+            if value.is_finite() {
+                return Ok(value);
+            }
+
+            match self {
+                NonFinite::Keep => Ok(value),
+                NonFinite::Error => Err(FloatError::NonFinite(token(f64::from(value)).unwrap())),
+                NonFinite::Clamp if value.is_nan() => Ok(0.0),
+                NonFinite::Clamp => Ok(value.clamp($ty::MIN, $ty::MAX)),
+            }
+        }
+    };
+}
+
+impl NonFinite {
+    check_float!(check_f32, f32);
+    check_float!(check_f64, f64);
+}
+
+/// The decimals of the `float` and `double` values written by text formats.
+///
+/// `#[precision(n)]` fields are written with `n` decimals whatever the format of the serializer.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum FloatFormat {
+    /// The shortest text that is parsed back to the same value.
+    #[default]
+    Shortest,
+    /// Round to this number of decimals, trailing zeros are trimmed.
+    Decimals(u32),
+}
+
+/// Write a finite `value` rounded to `decimals` decimals, trailing zeros and a trailing `.` are trimmed.
+pub fn write_decimals(output: &mut String, value: f64, decimals: u32) {
+    let start = output.len();
+
+    _ = write!(output, "{:.*}", decimals as usize, value);
+
+    if output[start..].contains('.') {
+        let len = output[start..]
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .len();
+        output.truncate(start + len);
+    }
+
+    // small negative values are rounded to `-0`.
+    if output[start..] == *"-0" {
+        output.replace_range(start.., "0");
+    }
+}
+
+/// Returns the text token of a non-finite value: `INF`, `-INF` or `NaN`.
+pub fn token(value: f64) -> Option<&'static str> {
+    if value.is_nan() {
+        Some("NaN")
+    } else if value == f64::INFINITY {
+        Some("INF")
+    } else if value == f64::NEG_INFINITY {
+        Some("-INF")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{FloatError, NonFinite, token, write_decimals};
+
+    #[test]
+    fn test_non_finite() {
+        assert_eq!(NonFinite::Keep.check_f32(f32::INFINITY), Ok(f32::INFINITY));
+        assert_eq!(NonFinite::Error.check_f32(1.5), Ok(1.5));
+        assert_eq!(
+            NonFinite::Error.check_f64(f64::NEG_INFINITY),
+            Err(FloatError::NonFinite("-INF"))
+        );
+        assert_eq!(NonFinite::Clamp.check_f32(f32::INFINITY), Ok(f32::MAX));
+        assert_eq!(NonFinite::Clamp.check_f64(f64::NAN), Ok(0.0));
+
+        for value in [f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(token(value).unwrap().parse::<f64>(), Ok(value));
+        }
+
+        assert!(token(f64::NAN).unwrap().parse::<f32>().unwrap().is_nan());
+        assert_eq!(token(1.0), None);
+    }
+
+    #[test]
+    fn test_write_decimals() {
+        let decimals = |value: f64, decimals: u32| {
+            let mut output = String::new();
+            write_decimals(&mut output, value, decimals);
+            output
+        };
+
+        assert_eq!(decimals(1.23456, 3), "1.235");
+        assert_eq!(decimals(1.5, 3), "1.5");
+        assert_eq!(decimals(2.0, 3), "2");
+        assert_eq!(decimals(120.0, 0), "120");
+        assert_eq!(decimals(-0.0001, 2), "0");
+        assert_eq!(decimals(f64::from(0.1f32), 4), "0.1");
+    }
+}
@@ -0,0 +1,2309 @@
+//! A JSON format of opcode streams.
+//!
+//! A document is an array of nodes, every element or leaf is an object whose members are:
+//!
+//! - the element or leaf, keyed by its display name;
+//! - the attrs applied to it, keyed by their display names, in stream order;
+//! - for elements, the `children` array of the nodes between the element and its `Pop`.
+//!
+//! ```json
+//! [
+//!     {
+//!         "fill": { "named": { "name": "red" } },
+//!         "group": {},
+//!         "children": [
+//!             { "rect": { "x": 1.0, "y": 2.0, "label": null } },
+//!             { "text": "hello" }
+//!         ]
+//!     }
+//! ]
+//! ```
+//!
+//! The fields of nodes and data are written as:
+//!
+//! - an object of the named fields;
+//! - an array of the unnamed fields, a single unnamed field is written as is, unless it is an array;
+//! - `{}` if the node has no fields.
+//!
+//! Enum variants without fields are written as their names, other variants as an object with the variant name as
+//! the only key, e.g. `{ "named": { "name": "red" } }`.
+//!
+//! Other values are written as:
+//!
+//! | value                | json                                                    |
+//! |----------------------|---------------------------------------------------------|
+//! | numbers              | numbers, non-finite floats as the strings `NaN`, `INF` or `-INF` |
+//! | `vec[T]`, `[T;N]`, tuples | arrays                                             |
+//! | `[K -> V]`           | objects, number and bool keys are written as strings    |
+//! | `None`               | `null`                                                  |
+//! | `Some(v)`            | `v`, or `{ "$some": v }` if `v` is written as `null`, e.g. `Some(None)` |
+//! | variable references  | `{ "$var": "name", "target": "register" }`             |
+//! | other types          | their text forms, e.g. base64 for `bytes`               |
+//!
+//! The format uses display names, it doesn't work with code generated by `CodeGen::strip_names`.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt::Write, mem, slice};
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        de::{
+            self, AttrsNodeAccess, Deserialize, FieldContext, Kind, MapAccess, NodeAccess,
+            NumericCoercion, SeqAccess, UnknownNames, Visitor,
+        },
+        float::{FloatError, FloatFormat, NonFinite, token, write_decimals},
+        number::NumberPolicy,
+        ser::{self, Serialize},
+    },
+};
+
+/// The key of the children of an element.
+const CHILDREN: &str = "children";
+
+/// The key of the wrapper of a `Some` value written as `null`.
+pub(super) const SOME: &str = "$some";
+
+/// The key of the path of a variable reference.
+pub(super) const VAR: &str = "$var";
+
+/// The key of the target of a variable reference.
+const TARGET: &str = "target";
+
+/// Max nesting depth of the arrays and objects of a value.
+const MAX_DEPTH: usize = 256;
+
+/// Error returns by the json [`Serializer`] and [`Deserializer`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    De(#[from] de::Error),
+
+    #[error(transparent)]
+    Float(#[from] FloatError),
+
+    #[error("Invalid json at {0}, {1}.")]
+    Syntax(usize, &'static str),
+
+    #[error("Invalid opcode stream, {0}.")]
+    Stream(&'static str),
+
+    #[error("Invalid node, {0}.")]
+    Node(String),
+
+    #[error("Json requires display names, the names of the schema are stripped.")]
+    Unnamed,
+
+    #[error("Map key `{0}` is not a string, number or bool.")]
+    MapKey(String),
+
+    #[error("Missing field `{1}` of `{0}`.")]
+    MissingField(String, String),
+
+    #[error("Missing attr `{0}`.")]
+    MissingAttr(String),
+}
+
+impl FieldContext for Error {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::De(err) => Error::De(err.field_context(ty, field)),
+            // syntax errors already carry their position.
+            err => err,
+        }
+    }
+}
+
+/// Serialize `opcodes` as a json document.
+pub fn to_string<'a, T>(opcodes: impl IntoIterator<Item = &'a T>) -> Result<String, Error>
+where
+    T: Serialize + 'a,
+{
+    let mut serializer = Serializer::new();
+
+    for opcode in opcodes {
+        opcode.serialize(&mut serializer)?;
+    }
+
+    serializer.finish()
+}
+
+/// Deserialize the opcodes of a json document.
+pub fn from_str<'de, T>(text: &'de str) -> Result<Vec<T>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>>,
+{
+    let mut deserializer = Deserializer::new(text);
+    let mut opcodes = vec![];
+
+    while let Some(batch) = T::deserialize(&mut deserializer)? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Serialize a single value, e.g. a data or an enum, as json.
+pub fn value_to_string<T>(value: &T) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::value(NonFinite::default());
+
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.into_value())
+}
+
+/// Deserialize a single value, e.g. a data or an enum, from json.
+pub fn value_from_str<'de, T>(text: &'de str) -> Result<T::Value, Error>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(&mut Deserializer::new(text))
+}
+
+/// A json value, strings and numbers borrow the source text if possible.
+#[derive(Debug, PartialEq)]
+enum Value<'a> {
+    Null,
+    Bool(bool),
+    /// The source text of a number.
+    Number(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Array(Vec<Value<'a>>),
+    Object(Vec<Member<'a>>),
+}
+
+/// A member of a json object.
+#[derive(Debug, PartialEq)]
+struct Member<'a> {
+    key: Cow<'a, str>,
+    value: Value<'a>,
+    /// The source text of `value`.
+    raw: Cow<'a, str>,
+}
+
+static NULL: Value<'static> = Value::Null;
+
+impl Value<'_> {
+    fn kind(&self) -> Kind {
+        match self {
+            Value::Null => Kind::None,
+            Value::Bool(_) => Kind::Bool,
+            Value::Number(_) => Kind::Double,
+            Value::String(_) => Kind::String,
+            Value::Array(_) => Kind::Seq,
+            Value::Object(_) => Kind::Map,
+        }
+    }
+
+    /// Returns the value of the member `key` of an object, the last one if the key is duplicated.
+    fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .rev()
+                .find(|member| member.key == key)
+                .map(|member| &member.value),
+            _ => None,
+        }
+    }
+
+    /// Copy the borrowed text, so the value no longer borrows the source.
+    fn into_owned(self) -> Value<'static> {
+        let owned = |text: Cow<'_, str>| Cow::Owned(text.into_owned());
+
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(value) => Value::Bool(value),
+            Value::Number(text) => Value::Number(owned(text)),
+            Value::String(text) => Value::String(owned(text)),
+            Value::Array(items) => Value::Array(items.into_iter().map(Value::into_owned).collect()),
+            Value::Object(members) => Value::Object(
+                members
+                    .into_iter()
+                    .map(|member| Member {
+                        key: owned(member.key),
+                        value: member.value.into_owned(),
+                        raw: owned(member.raw),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// An item of the node arrays of a document, in stream order.
+#[derive(Debug)]
+enum Event<'a> {
+    /// The members of a node object without its `children` array, `true` if it has one, whose nodes follow
+    /// until the matching [`End`](Self::End).
+    Node(Vec<Member<'a>>, bool),
+    /// The end of a `children` array.
+    End,
+    /// An item that isn't an object.
+    Value(Value<'a>),
+}
+
+/// A frame of the stack of [`Parser::document`].
+enum Frame<'a> {
+    /// A node array, the document or the `children` of a node.
+    Array,
+    /// A node object, the index of its event, the members read so far and whether it has a `children` array.
+    Object(usize, Vec<Member<'a>>, bool),
+}
+
+/// A recursive descent json parser, the node arrays of documents are read with an explicit stack.
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(input: &'a str) -> Result<Value<'a>, Error> {
+        let mut parser = Self { input, offset: 0 };
+
+        let value = parser.value(0)?;
+
+        parser.skip_whitespaces();
+
+        if parser.offset != input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    /// Parse the node arrays of a document as a flat list of events, so nesting elements doesn't recurse.
+    ///
+    /// Node objects deeper than `max_depth` are rejected, the values of their members are parsed as is.
+    fn document(input: &'a str, max_depth: usize) -> Result<Vec<Event<'a>>, Error> {
+        let mut parser = Self { input, offset: 0 };
+
+        parser.skip_whitespaces();
+
+        if parser.peek() != Some(b'[') {
+            parser.value(0)?;
+            return Err(Error::Node("the document isn't an array".to_string()));
+        }
+
+        parser.offset += 1;
+
+        let mut events = vec![];
+        let mut stack = vec![Frame::Array];
+        // the last frame has just been opened.
+        let mut first = true;
+
+        while let Some(frame) = stack.last() {
+            let close = match frame {
+                Frame::Array => b']',
+                Frame::Object(..) => b'}',
+            };
+
+            parser.skip_whitespaces();
+
+            if parser.peek() == Some(close) {
+                parser.offset += 1;
+                first = false;
+
+                match stack.pop() {
+                    Some(Frame::Object(event, members, children)) => {
+                        events[event] = Event::Node(members, children);
+                    }
+                    // the end of the `children` of a node.
+                    _ if !stack.is_empty() => events.push(Event::End),
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            if !mem::take(&mut first) {
+                match parser.peek() {
+                    Some(b',') => parser.offset += 1,
+                    _ if close == b']' => return Err(parser.error("expect `,` or `]`")),
+                    _ => return Err(parser.error("expect `,` or `}`")),
+                }
+
+                parser.skip_whitespaces();
+            }
+
+            match stack.last_mut() {
+                Some(Frame::Array) if parser.peek() == Some(b'{') => {
+                    // frames alternate between node arrays and node objects.
+                    if stack.len().div_ceil(2) > max_depth {
+                        return Err(parser.error("elements are nested too deeply"));
+                    }
+
+                    parser.offset += 1;
+
+                    events.push(Event::End);
+                    stack.push(Frame::Object(events.len() - 1, vec![], false));
+                    first = true;
+                }
+                Some(Frame::Array) => events.push(Event::Value(parser.value(0)?)),
+                Some(Frame::Object(_, members, children)) => {
+                    if parser.peek() != Some(b'"') {
+                        return Err(parser.error("expect a key"));
+                    }
+
+                    let key = parser.string()?;
+
+                    parser.expect(b':', "expect `:`")?;
+                    parser.skip_whitespaces();
+
+                    if key == CHILDREN && parser.peek() == Some(b'[') && !*children {
+                        *children = true;
+                        parser.offset += 1;
+
+                        stack.push(Frame::Array);
+                        first = true;
+                    } else {
+                        let start = parser.offset;
+                        let value = parser.value(0)?;
+                        let raw = Cow::Borrowed(&input[start..parser.offset]);
+
+                        members.push(Member { key, value, raw });
+                    }
+                }
+                None => {}
+            }
+        }
+
+        parser.skip_whitespaces();
+
+        if parser.offset != input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        Ok(events)
+    }
+
+    fn error(&self, message: &'static str) -> Error {
+        Error::Syntax(self.offset, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.offset).copied()
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8, message: &'static str) -> Result<(), Error> {
+        self.skip_whitespaces();
+
+        if self.peek() == Some(c) {
+            self.offset += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, Error> {
+        self.skip_whitespaces();
+
+        match self.peek() {
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[' | b'{') if depth == MAX_DEPTH => Err(self.error("nesting is too deep")),
+            Some(b'[') => self.array(depth + 1),
+            Some(b'{') => self.object(depth + 1),
+            Some(_) => Err(self.error("expect a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, literal: &'static str, value: Value<'a>) -> Result<Value<'a>, Error> {
+        if self.input[self.offset..].starts_with(literal) {
+            self.offset += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("expect a value"))
+        }
+    }
+
+    fn digits(&mut self) -> Result<(), Error> {
+        let start = self.offset;
+
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.offset += 1;
+        }
+
+        if self.offset == start {
+            Err(self.error("expect digits"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn number(&mut self) -> Result<Value<'a>, Error> {
+        let start = self.offset;
+
+        if self.peek() == Some(b'-') {
+            self.offset += 1;
+        }
+
+        self.digits()?;
+
+        if self.peek() == Some(b'.') {
+            self.offset += 1;
+            self.digits()?;
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            self.offset += 1;
+
+            if let Some(b'+' | b'-') = self.peek() {
+                self.offset += 1;
+            }
+
+            self.digits()?;
+        }
+
+        Ok(Value::Number(Cow::Borrowed(
+            &self.input[start..self.offset],
+        )))
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .input
+            .get(self.offset..self.offset + 4)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+
+        let value =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid \\u escape"))?;
+
+        self.offset += 4;
+
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<Cow<'a, str>, Error> {
+        // skip the opening quote.
+        self.offset += 1;
+
+        let start = self.offset;
+        let mut owned: Option<String> = None;
+
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+
+            match c {
+                b'"' => {
+                    let value = match owned {
+                        Some(value) => Cow::Owned(value),
+                        None => Cow::Borrowed(&self.input[start..self.offset]),
+                    };
+
+                    self.offset += 1;
+
+                    return Ok(value);
+                }
+                b'\\' => {
+                    let value =
+                        owned.get_or_insert_with(|| self.input[start..self.offset].to_string());
+
+                    self.offset += 1;
+
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+
+                    self.offset += 1;
+
+                    match escape {
+                        b'"' => value.push('"'),
+                        b'\\' => value.push('\\'),
+                        b'/' => value.push('/'),
+                        b'b' => value.push('\u{8}'),
+                        b'f' => value.push('\u{c}'),
+                        b'n' => value.push('\n'),
+                        b'r' => value.push('\r'),
+                        b't' => value.push('\t'),
+                        b'u' => {
+                            let mut code = self.hex4()?;
+
+                            // a surrogate pair.
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.input[self.offset..].starts_with("\\u")
+                            {
+                                self.offset += 2;
+
+                                let low = self.hex4()?;
+
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("invalid surrogate pair"));
+                                }
+
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+
+                            value.push(
+                                char::from_u32(code)
+                                    .ok_or_else(|| self.error("invalid \\u escape"))?,
+                            );
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                0..0x20 => return Err(self.error("control character in string")),
+                _ => {
+                    let len = self.input[self.offset..]
+                        .chars()
+                        .next()
+                        .map_or(1, char::len_utf8);
+
+                    if let Some(value) = &mut owned {
+                        value.push_str(&self.input[self.offset..self.offset + len]);
+                    }
+
+                    self.offset += len;
+                }
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value<'a>, Error> {
+        // skip `[`.
+        self.offset += 1;
+
+        let mut items = vec![];
+
+        self.skip_whitespaces();
+
+        if self.peek() == Some(b']') {
+            self.offset += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.value(depth)?);
+
+            self.skip_whitespaces();
+
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b']') => {
+                    self.offset += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expect `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value<'a>, Error> {
+        // skip `{`.
+        self.offset += 1;
+
+        let mut members = vec![];
+
+        self.skip_whitespaces();
+
+        if self.peek() == Some(b'}') {
+            self.offset += 1;
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.skip_whitespaces();
+
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expect a key"));
+            }
+
+            let key = self.string()?;
+
+            self.expect(b':', "expect `:`")?;
+            self.skip_whitespaces();
+
+            let start = self.offset;
+            let value = self.value(depth)?;
+            let raw = Cow::Borrowed(&self.input[start..self.offset]);
+
+            members.push(Member { key, value, raw });
+
+            self.skip_whitespaces();
+
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b'}') => {
+                    self.offset += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("expect `,` or `}`")),
+            }
+        }
+    }
+}
+
+/// Returns the json name of a variable target.
+pub(super) fn target_name(target: &Target) -> &'static str {
+    match target {
+        Target::Register => "register",
+        Target::ForeachItem => "foreachItem",
+        Target::ForeachIndex => "foreachIndex",
+        Target::Range => "range",
+    }
+}
+
+/// A serializer of json documents.
+///
+/// Opcodes are written in stream order, the document is returned by [`finish`](Self::finish):
+///
+/// ```ignore
+/// let mut serializer = Serializer::new();
+///
+/// for opcode in &opcodes {
+///     opcode.serialize(&mut serializer)?;
+/// }
+///
+/// let json = serializer.finish()?;
+/// ```
+pub struct Serializer {
+    output: String,
+    /// The node arrays being written, the document and the `children` of open elements, `true` until their first node.
+    arrays: Vec<bool>,
+    /// The object of the next node is open, it has attrs.
+    node: bool,
+    /// `Some` wrappers of the next value, only written if the value is `null`.
+    some: usize,
+    non_finite: NonFinite,
+    float_format: FloatFormat,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer {
+    /// Create a serializer of an opcode stream.
+    pub fn new() -> Self {
+        Self {
+            output: "[".to_string(),
+            arrays: vec![true],
+            node: false,
+            some: 0,
+            non_finite: NonFinite::default(),
+            float_format: FloatFormat::default(),
+            number_policy: None,
+        }
+    }
+
+    /// A serializer of a single value, opcodes are not allowed.
+    pub(super) fn value(non_finite: NonFinite) -> Self {
+        Self {
+            output: String::new(),
+            arrays: vec![],
+            node: false,
+            some: 0,
+            non_finite,
+            float_format: FloatFormat::default(),
+            number_policy: None,
+        }
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Set the decimals of `float` and `double` values, the default is [`FloatFormat::Shortest`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Set the hook of the text of numbers, the numbers it rewrites are written as strings.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.number_policy = Some(policy);
+        self
+    }
+
+    /// Set the float format of the next values, returns the previous one.
+    pub(super) fn replace_float_format(&mut self, format: FloatFormat) -> FloatFormat {
+        mem::replace(&mut self.float_format, format)
+    }
+
+    /// Returns the json of the single value written by a [`value`](Self::value) serializer.
+    pub(super) fn into_value(self) -> String {
+        self.output
+    }
+
+    /// Returns the json document, fails if an attr isn't followed by its element or leaf, or an element isn't popped.
+    pub fn finish(mut self) -> Result<String, Error> {
+        if self.node {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        if self.arrays.len() != 1 {
+            return Err(Error::Stream("elements aren't popped"));
+        }
+
+        self.output.push(']');
+
+        Ok(self.output)
+    }
+
+    /// Start writing a value, pending `Some` wrappers are dropped.
+    fn begin_value(&mut self) {
+        self.some = 0;
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.output.push('"');
+
+        for c in value.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                // writing to a `String` never fails.
+                c if (c as u32) < 0x20 => _ = write!(self.output, "\\u{:04x}", c as u32),
+                c => self.output.push(c),
+            }
+        }
+
+        self.output.push('"');
+    }
+
+    fn write_key(&mut self, name: &str) -> Result<(), Error> {
+        if name.is_empty() {
+            return Err(Error::Unnamed);
+        }
+
+        self.write_string(name);
+        self.output.push(':');
+
+        Ok(())
+    }
+
+    fn write_number(&mut self, kind: Kind, value: impl core::fmt::Display) {
+        self.begin_value();
+
+        let start = self.output.len();
+
+        _ = write!(self.output, "{}", value);
+
+        self.print_number(kind, start);
+    }
+
+    /// Rewrite the number written from `start` with the number policy.
+    fn print_number(&mut self, kind: Kind, start: usize) {
+        let Some(text) = self
+            .number_policy
+            .and_then(|policy| policy.print(kind, &self.output[start..]))
+        else {
+            return;
+        };
+
+        self.output.truncate(start);
+        self.write_string(&text);
+    }
+
+    fn write_float(&mut self, kind: Kind, value: f64, text: impl core::fmt::Debug) {
+        self.begin_value();
+
+        if let Some(token) = token(value) {
+            self.write_string(token);
+            return;
+        }
+
+        let start = self.output.len();
+
+        match self.float_format {
+            FloatFormat::Shortest => _ = write!(self.output, "{:?}", text),
+            FloatFormat::Decimals(decimals) => write_decimals(&mut self.output, value, decimals),
+        }
+
+        self.print_number(kind, start);
+    }
+
+    /// Open the object of the next node, or continue it after an attr.
+    fn open_node(&mut self) -> Result<(), Error> {
+        if self.node {
+            self.output.push(',');
+            return Ok(());
+        }
+
+        let first = self
+            .arrays
+            .last_mut()
+            .ok_or(Error::Stream("opcodes can't be serialized as values"))?;
+
+        if !*first {
+            self.output.push(',');
+        }
+
+        *first = false;
+
+        self.output.push('{');
+        self.node = true;
+
+        Ok(())
+    }
+}
+
+/// What [`NodeSerializer::finish`] writes after the fields.
+enum End {
+    Value,
+    Enum,
+    Element,
+    Leaf,
+}
+
+/// How the fields of a node are written.
+#[derive(Clone, Copy)]
+enum Body {
+    /// No fields.
+    Unit,
+    Object,
+    Array,
+    /// A single unnamed field, which starts at the offset.
+    Newtype(usize),
+}
+
+/// Returns by [`Serializer`] to write the fields of a node.
+pub struct NodeSerializer<'a> {
+    serializer: &'a mut Serializer,
+    fields: usize,
+    body: Option<Body>,
+    end: End,
+    /// The `Some` wrappers of a data, its single unnamed field is written in its place so it gets them.
+    some: usize,
+}
+
+impl<'a> NodeSerializer<'a> {
+    fn new(serializer: &'a mut Serializer, fields: usize, end: End) -> Self {
+        let body = if fields == 0 {
+            serializer.output.push_str("{}");
+            Some(Body::Unit)
+        } else {
+            None
+        };
+
+        Self {
+            serializer,
+            fields,
+            body,
+            end,
+            some: 0,
+        }
+    }
+}
+
+impl ser::SerializeNode for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = index;
+
+        let output = &mut self.serializer.output;
+
+        match (self.body, name) {
+            (None, Some(name)) => {
+                output.push('{');
+                self.body = Some(Body::Object);
+                self.serializer.write_key(name)?;
+            }
+            (None, None) if self.fields == 1 => {
+                self.body = Some(Body::Newtype(output.len()));
+                self.serializer.some = self.some;
+            }
+            (None, None) => {
+                output.push('[');
+                self.body = Some(Body::Array);
+            }
+            (Some(Body::Object), Some(name)) => {
+                output.push(',');
+                self.serializer.write_key(name)?;
+            }
+            (Some(Body::Array), _) => output.push(','),
+            _ => return Err(Error::Unnamed),
+        }
+
+        value.serialize(&mut *self.serializer)?;
+
+        // a single unnamed field written as an array is wrapped, it would be read as an array of fields.
+        if let Some(Body::Newtype(start)) = self.body {
+            let output = &mut self.serializer.output;
+
+            if output[start..].starts_with('[') {
+                output.insert(start, '[');
+                output.push(']');
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        let serializer = self.serializer;
+
+        match self.body {
+            Some(Body::Object) => serializer.output.push('}'),
+            Some(Body::Array) => serializer.output.push(']'),
+            Some(Body::Unit | Body::Newtype(_)) => {}
+            None => serializer.output.push_str("{}"),
+        }
+
+        match self.end {
+            End::Value => {}
+            End::Enum => serializer.output.push('}'),
+            End::Element => {
+                serializer.output.push(',');
+                serializer.write_string(CHILDREN);
+                serializer.output.push_str(":[");
+                serializer.arrays.push(true);
+                serializer.node = false;
+            }
+            End::Leaf => {
+                serializer.output.push('}');
+                serializer.node = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns by [`Serializer`] to write the items of a sequence.
+pub struct SeqSerializer<'a> {
+    serializer: &'a mut Serializer,
+    first: bool,
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Error = Error;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.first {
+            self.serializer.output.push(',');
+        }
+
+        self.first = false;
+
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        self.serializer.output.push(']');
+        Ok(())
+    }
+}
+
+/// Returns by [`Serializer`] to write the entries of a map.
+pub struct MapSerializer<'a> {
+    serializer: &'a mut Serializer,
+    first: bool,
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Error = Error;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        let mut key_serializer = Serializer::value(self.serializer.non_finite);
+
+        key.serialize(&mut key_serializer)?;
+
+        let key = key_serializer.output;
+
+        let output = &mut self.serializer.output;
+
+        if !self.first {
+            output.push(',');
+        }
+
+        self.first = false;
+
+        if key.starts_with('"') {
+            output.push_str(&key);
+        } else if key == "true"
+            || key == "false"
+            || key.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+        {
+            output.push('"');
+            output.push_str(&key);
+            output.push('"');
+        } else {
+            return Err(Error::MapKey(key));
+        }
+
+        output.push(':');
+
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        self.serializer.output.push('}');
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Error = Error;
+    type SerializeNode = NodeSerializer<'a>;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.open_node()?;
+        self.write_key(name)?;
+
+        Ok(NodeSerializer::new(self, fields, End::Element))
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.open_node()?;
+        self.write_key(name)?;
+
+        Ok(NodeSerializer::new(self, fields, End::Leaf))
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.open_node()?;
+        self.write_key(name)?;
+
+        Ok(NodeSerializer::new(self, fields, End::Value))
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name);
+
+        let some = core::mem::take(&mut self.some);
+
+        Ok(NodeSerializer {
+            some,
+            ..NodeSerializer::new(self, fields, End::Value)
+        })
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant_index);
+
+        if variant.is_empty() {
+            return Err(Error::Unnamed);
+        }
+
+        self.begin_value();
+
+        if fields == 0 {
+            self.write_string(variant);
+
+            return Ok(NodeSerializer {
+                serializer: self,
+                fields,
+                body: Some(Body::Unit),
+                end: End::Value,
+                some: 0,
+            });
+        }
+
+        self.output.push('{');
+        self.write_key(variant)?;
+
+        Ok(NodeSerializer::new(self, fields, End::Enum))
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        let _ = len;
+
+        self.begin_value();
+        self.output.push('[');
+
+        Ok(SeqSerializer {
+            serializer: self,
+            first: true,
+        })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        let _ = len;
+
+        self.begin_value();
+        self.output.push('{');
+
+        Ok(MapSerializer {
+            serializer: self,
+            first: true,
+        })
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        self.begin_value();
+        _ = write!(self.output, "{}", value);
+        Ok(())
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        self.begin_value();
+        self.write_string(value);
+        Ok(())
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Byte, value);
+        Ok(())
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ubyte, value);
+        Ok(())
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Short, value);
+        Ok(())
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ushort, value);
+        Ok(())
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Int, value);
+        Ok(())
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Uint, value);
+        Ok(())
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Long, value);
+        Ok(())
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ulong, value);
+        Ok(())
+    }
+
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error> {
+        self.write_number(Kind::Int128, value);
+        Ok(())
+    }
+
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error> {
+        self.write_number(Kind::Uint128, value);
+        Ok(())
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f32(value)?;
+
+        self.write_float(Kind::Float, f64::from(value), value);
+        Ok(())
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f64(value)?;
+
+        self.write_float(Kind::Double, value, value);
+        Ok(())
+    }
+
+    fn serialize_with_precision<T>(self, decimals: u32, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let format = self.replace_float_format(FloatFormat::Decimals(decimals));
+        let result = value.serialize(&mut *self);
+
+        self.replace_float_format(format);
+
+        result
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        for _ in 0..self.some {
+            self.output.push('{');
+            self.write_key(SOME)?;
+        }
+
+        self.output.push_str("null");
+
+        for _ in 0..self.some {
+            self.output.push('}');
+        }
+
+        self.some = 0;
+
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.some += 1;
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        self.begin_value();
+        self.output.push('{');
+        self.write_key(VAR)?;
+
+        match path {
+            Path::Named(name) => self.write_string(name),
+            Path::Index(index) => _ = write!(self.output, "{}", index),
+        }
+
+        self.output.push(',');
+        self.write_key(TARGET)?;
+        self.write_string(target_name(target));
+        self.output.push('}');
+
+        Ok(())
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        if self.node {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        if self.arrays.len() < 2 {
+            return Err(Error::Stream("pop without an open element"));
+        }
+
+        self.arrays.pop();
+        self.output.push_str("]}");
+
+        Ok(())
+    }
+}
+
+/// Policies of the deserializer.
+#[derive(Clone, Copy, Default)]
+struct Options {
+    numeric_coercion: NumericCoercion,
+    non_finite: NonFinite,
+    unknown_names: UnknownNames,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+/// A deserializer of json documents.
+///
+/// Opcodes are read in stream order, [`deserialize_opcode`](de::Deserializer::deserialize_opcode) returns the attrs
+/// and the node of an object, or the `Pop` of an element at the end of its children.
+pub struct Deserializer<'a> {
+    input: &'a str,
+    /// The parsed document, until opcodes are read.
+    document: Option<Value<'a>>,
+    /// The events of the node arrays, once opcodes are read.
+    events: Option<vec::IntoIter<Event<'a>>>,
+    /// The last element has no `children`, its `Pop` is the next opcode.
+    pop: bool,
+    max_depth: usize,
+    started: bool,
+    options: Options,
+}
+
+impl<'a> Deserializer<'a> {
+    /// Create a deserializer of the json `text`.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            input: text,
+            document: None,
+            events: None,
+            pop: false,
+            max_depth: de::MAX_ELEMENT_DEPTH,
+            started: false,
+            options: Options::default(),
+        }
+    }
+
+    /// Set the policy for numbers of another kind than the expected one, the default is [`NumericCoercion::Strict`].
+    pub fn with_numeric_coercion(mut self, policy: NumericCoercion) -> Self {
+        self.options.numeric_coercion = policy;
+        self
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.options.non_finite = policy;
+        self
+    }
+
+    /// Set the policy for nodes with an unknown name, the default is [`UnknownNames::Error`].
+    pub fn with_unknown_names(mut self, policy: UnknownNames) -> Self {
+        self.options.unknown_names = policy;
+        self
+    }
+
+    /// Set the hook of numbers read from strings.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.options.number_policy = Some(policy);
+        self
+    }
+
+    /// Set the max nesting depth of elements, the default is [`MAX_ELEMENT_DEPTH`](de::MAX_ELEMENT_DEPTH).
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Create a deserializer of the json `text` whose strings are copied, so values can be read from a
+    /// temporary `text`, e.g. json decoded from an xml attribute.
+    pub(crate) fn owned(text: &str) -> Result<Deserializer<'static>, Error> {
+        let mut deserializer = Deserializer::new("");
+
+        deserializer.document = Some(Parser::parse(text)?.into_owned());
+
+        Ok(deserializer)
+    }
+
+    /// Returns the deserializer of the document as a single value.
+    fn document(&mut self) -> Result<ValueDeserializer<'a, '_>, Error> {
+        if self.started {
+            return Err(Error::Stream("values can't be read from an opcode stream"));
+        }
+
+        if self.document.is_none() {
+            self.document = Some(Parser::parse(self.input)?);
+        }
+
+        Ok(ValueDeserializer {
+            value: self.document.as_ref().unwrap(),
+            options: self.options,
+        })
+    }
+}
+
+macro_rules! forward_to_document {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.document()?.$method(visitor)
+            }
+        )+
+    };
+}
+
+macro_rules! forward_node_to_document {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, type_id: usize, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.document()?.$method(type_id, name, visitor)
+            }
+        )+
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn numeric_coercion(&self) -> NumericCoercion {
+        self.options.numeric_coercion
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.options.number_policy
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.options.non_finite
+    }
+
+    fn unknown_names(&self) -> UnknownNames {
+        self.options.unknown_names
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.started {
+            if self.document.is_some() {
+                return Err(Error::Stream("opcodes can't be read from a value"));
+            }
+
+            self.events = Some(Parser::document(self.input, self.max_depth)?.into_iter());
+            self.started = true;
+        }
+
+        if mem::take(&mut self.pop) {
+            return Ok(Some(vec![visitor.visit_pop::<Error>()?]));
+        }
+
+        let Some(events) = self.events.as_mut() else {
+            return Ok(None);
+        };
+
+        let (mut members, children) = match events.next() {
+            Some(Event::Node(members, children)) => (members, children),
+            Some(Event::End) => return Ok(Some(vec![visitor.visit_pop::<Error>()?])),
+            Some(Event::Value(node)) => {
+                return Err(Error::Node(format!(
+                    "expect an object, got {}",
+                    node.kind()
+                )));
+            }
+            None => {
+                self.events = None;
+                return Ok(None);
+            }
+        };
+
+        // a `children` member that isn't read as a node array.
+        let invalid = members
+            .iter()
+            .position(|member| member.key == CHILDREN)
+            .map(|index| members.remove(index).value);
+
+        let Some(index) = members
+            .iter()
+            .position(|member| visitor.is_element(&member.key) || visitor.is_leaf(&member.key))
+        else {
+            if self.options.unknown_names == UnknownNames::Skip {
+                if children {
+                    // skip the nodes of the children, with their own children.
+                    let mut depth = 1;
+
+                    while depth > 0 {
+                        match events.next() {
+                            Some(Event::Node(_, true)) => depth += 1,
+                            Some(Event::End) => depth -= 1,
+                            Some(_) => {}
+                            None => break,
+                        }
+                    }
+                }
+
+                return Ok(Some(vec![]));
+            }
+
+            let keys = members
+                .iter()
+                .map(|member| member.key.as_ref())
+                .collect::<Vec<_>>();
+
+            return Err(Error::Node(format!(
+                "no element or leaf in {{{}}}",
+                keys.join(", ")
+            )));
+        };
+
+        let name = &members[index].key;
+        let element = visitor.is_element(name);
+
+        let attrs = NodeAttrs {
+            name,
+            node: &members[index].value,
+            attrs: members
+                .iter()
+                .enumerate()
+                .filter(|(attr, _)| *attr != index)
+                .map(|(_, member)| member)
+                .collect(),
+            options: self.options,
+        };
+
+        let opcodes = visitor.visit_opcode_with_attrs(name, attrs)?;
+
+        match (element, children || invalid.is_some()) {
+            (true, _) if invalid.is_some() => {
+                return Err(Error::Node(format!(
+                    "the children of `{}` isn't an array",
+                    name
+                )));
+            }
+            (true, false) => self.pop = true,
+            (false, true) => {
+                return Err(Error::Node(format!("leaf `{}` can't have children", name)));
+            }
+            _ => {}
+        }
+
+        Ok(Some(opcodes))
+    }
+
+    forward_node_to_document!(
+        deserialize_element,
+        deserialize_leaf,
+        deserialize_attr,
+        deserialize_data,
+        deserialize_enum
+    );
+
+    forward_to_document!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// The attrs and the node of a node object.
+struct NodeAttrs<'a, 'b> {
+    name: &'b str,
+    node: &'b Value<'a>,
+    attrs: Vec<&'b Member<'a>>,
+    options: Options,
+}
+
+impl<'de> AttrsNodeAccess<'de> for NodeAttrs<'de, '_> {
+    type Error = Error;
+
+    fn attrs_with_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attrs
+            .iter()
+            .map(|member| (member.key.as_ref(), member.raw.as_ref()))
+    }
+
+    fn remove_attr(&mut self, index: usize) {
+        self.attrs.remove(index);
+    }
+
+    fn deserialize_attr<V>(&mut self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // the generated code reads the node itself as the last attr.
+        let value = if name == self.name {
+            self.node
+        } else {
+            self.attrs
+                .iter()
+                .find(|member| member.key == name)
+                .map(|member| &member.value)
+                .ok_or_else(|| Error::MissingAttr(name.to_string()))?
+        };
+
+        visitor.visit_opcode_with(name, ValueDeserializer::new(value, self.options))
+    }
+
+    fn deserialize_node<V>(self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_opcode_with(name, ValueDeserializer::new(self.node, self.options))
+    }
+}
+
+/// A deserializer of a parsed json value.
+#[derive(Clone, Copy)]
+struct ValueDeserializer<'a, 'b> {
+    value: &'b Value<'a>,
+    options: Options,
+}
+
+impl<'de, 'b> ValueDeserializer<'de, 'b> {
+    fn new(value: &'b Value<'de>, options: Options) -> Self {
+        Self { value, options }
+    }
+
+    fn unexpect<T>(&self) -> Result<T, Error> {
+        Err(de::Error::Unexpect(self.value.kind()).into())
+    }
+
+    fn visit_node<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_node(FieldsAccess {
+            value: self.value,
+            options: self.options,
+        })
+    }
+}
+
+macro_rules! deserialize_int {
+    ($method:ident, $ty:ty, $visit:ident) => {
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.value {
+                Value::Number(text) if !text.contains(['.', 'e', 'E']) => {
+                    visitor.$visit(text.parse::<$ty>().map_err(de::Error::from)?)
+                }
+                Value::Number(text) => {
+                    visitor.visit_double(text.parse::<f64>().map_err(de::Error::from)?)
+                }
+                Value::String(text) => visitor.visit_string(text),
+                _ => self.unexpect(),
+            }
+        }
+    };
+}
+
+macro_rules! deserialize_float {
+    ($method:ident, $ty:ty, $visit:ident) => {
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.value {
+                Value::Number(text) => {
+                    visitor.$visit(text.parse::<$ty>().map_err(de::Error::from)?)
+                }
+                // non-finite tokens, or rewritten by a number policy.
+                Value::String(text) => visitor.visit_string(text),
+                _ => self.unexpect(),
+            }
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn numeric_coercion(&self) -> NumericCoercion {
+        self.options.numeric_coercion
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.options.number_policy
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.options.non_finite
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        Err(Error::Stream("opcodes can't be read from a value"))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        match self.value {
+            Value::String(variant) => visitor.visit_enum_with(
+                variant,
+                FieldsAccess {
+                    value: &NULL,
+                    options: self.options,
+                },
+            ),
+            Value::Object(members) if members.len() == 1 => visitor.visit_enum_with(
+                &members[0].key,
+                FieldsAccess {
+                    value: &members[0].value,
+                    options: self.options,
+                },
+            ),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Array(items) => visitor.visit_seq(ItemsAccess {
+                items: items.iter(),
+                options: self.options,
+            }),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Object(members) => visitor.visit_map(MembersAccess {
+                members: members.iter(),
+                value: None,
+                options: self.options,
+            }),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            Value::Object(members) if members.len() == 1 && members[0].key == SOME => {
+                visitor.visit_some(Self::new(&members[0].value, self.options))
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let Some(path) = self.value.get(VAR) else {
+            return visitor.visit_constant(self);
+        };
+
+        let path = match path {
+            Value::String(name) => Path::Named(name.to_string()),
+            Value::Number(index) => Path::Index(index.parse().map_err(de::Error::from)?),
+            _ => return Err(de::Error::Unexpect(Kind::Variable).into()),
+        };
+
+        let target = match self.value.get(TARGET) {
+            Some(Value::String(target)) => [
+                Target::Register,
+                Target::ForeachItem,
+                Target::ForeachIndex,
+                Target::Range,
+            ]
+            .into_iter()
+            .find(|value| target_name(value) == target)
+            .ok_or(de::Error::Unexpect(Kind::Variable))?,
+            _ => return Err(de::Error::Unexpect(Kind::Variable).into()),
+        };
+
+        visitor.visit_variable(path, target)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(Cow::Borrowed(value)) => visitor.visit_borrowed_str(value),
+            Value::String(value) => visitor.visit_string(value),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(value) => visitor.visit_raw(value),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Bool(value) => visitor.visit_bool(*value),
+            // map keys.
+            Value::String(value) if value == "true" => visitor.visit_bool(true),
+            Value::String(value) if value == "false" => visitor.visit_bool(false),
+            _ => self.unexpect(),
+        }
+    }
+
+    deserialize_int!(deserialize_byte, i8, visit_byte);
+    deserialize_int!(deserialize_ubyte, u8, visit_ubyte);
+    deserialize_int!(deserialize_short, i16, visit_short);
+    deserialize_int!(deserialize_ushort, u16, visit_ushort);
+    deserialize_int!(deserialize_int, i32, visit_int);
+    deserialize_int!(deserialize_uint, u32, visit_uint);
+    deserialize_int!(deserialize_long, i64, visit_long);
+    deserialize_int!(deserialize_ulong, u64, visit_ulong);
+    deserialize_int!(deserialize_int128, i128, visit_int128);
+    deserialize_int!(deserialize_uint128, u128, visit_uint128);
+    deserialize_float!(deserialize_float, f32, visit_float);
+    deserialize_float!(deserialize_double, f64, visit_double);
+}
+
+/// Access to the fields of a node, see the [module docs](self) for their layouts.
+struct FieldsAccess<'a, 'b> {
+    value: &'b Value<'a>,
+    options: Options,
+}
+
+impl<'de> NodeAccess<'de> for FieldsAccess<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_field<T>(
+        &mut self,
+        ty: &str,
+        index: usize,
+        field_name: Option<&str>,
+    ) -> Result<T::Value, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let value = match (self.value, field_name) {
+            (Value::Object(_), Some(name)) => self.value.get(name),
+            (Value::Array(items), _) => items.get(index),
+            (value, None) if index == 0 => Some(value),
+            _ => None,
+        };
+
+        match value {
+            Some(value) => T::deserialize(ValueDeserializer::new(value, self.options)),
+            // missing fields are read as `null`, so optional fields can be omitted.
+            None => T::deserialize(ValueDeserializer::new(&NULL, self.options)).map_err(|_| {
+                Error::MissingField(
+                    ty.to_string(),
+                    field_name.map_or_else(|| index.to_string(), ToString::to_string),
+                )
+            }),
+        }
+    }
+}
+
+/// Access to the items of an array.
+struct ItemsAccess<'a, 'b> {
+    items: slice::Iter<'b, Value<'a>>,
+    options: Options,
+}
+
+impl<'de> SeqAccess<'de> for ItemsAccess<'de, '_> {
+    type Error = Error;
+
+    fn next_item<T>(&mut self) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.items
+            .next()
+            .map(|item| T::deserialize(ValueDeserializer::new(item, self.options)))
+            .transpose()
+    }
+}
+
+/// Access to the members of an object.
+struct MembersAccess<'a, 'b> {
+    members: slice::Iter<'b, Member<'a>>,
+    /// The value of the last key.
+    value: Option<&'b Value<'a>>,
+    options: Options,
+}
+
+impl<'de> MapAccess<'de> for MembersAccess<'de, '_> {
+    type Error = Error;
+
+    fn next_key<K>(&mut self) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: Deserialize<'de>,
+    {
+        let Some(member) = self.members.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some(&member.value);
+
+        let key = Value::String(member.key.clone());
+
+        K::deserialize(ValueDeserializer::new(&key, self.options)).map(Some)
+    }
+
+    fn next_value<V>(&mut self) -> Result<V::Value, Self::Error>
+    where
+        V: Deserialize<'de>,
+    {
+        let value = self.value.take().ok_or(de::Error::Unexpect(Kind::Map))?;
+
+        V::deserialize(ValueDeserializer::new(value, self.options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        borrow::Cow,
+        collections::BTreeMap,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{Deserializer, Error, Serializer, value_from_str, value_to_string};
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::{
+            de::{
+                self, AttrsNodeAccess, Borrow, Deserialize, Deserializer as _, Kind, UnknownNames,
+                Visitor,
+            },
+            float::{FloatFormat, NonFinite},
+            number::NumberPolicy,
+            ser::{Precision, Serialize, Serializer as _},
+        },
+    };
+
+    #[test]
+    fn test_values() {
+        let map = BTreeMap::from([(1u32, vec![Some(1.5f32), None]), (2, vec![])]);
+        let json = value_to_string(&map).unwrap();
+
+        assert_eq!(json, r#"{"1":[1.5,null],"2":[]}"#);
+        assert_eq!(
+            value_from_str::<BTreeMap<u32, Vec<Option<f32>>>>(&json),
+            Ok(map)
+        );
+
+        let text = "a \"b\"\n\u{1}é";
+        let json = value_to_string(&text.to_string()).unwrap();
+
+        assert_eq!(json, r#""a \"b\"\n\u0001é""#);
+        assert_eq!(value_from_str::<String>(&json), Ok(text.to_string()));
+        assert_eq!(value_from_str::<String>(r#""😀\/""#), Ok("😀/".to_string()));
+
+        assert_eq!(value_to_string(&f64::NEG_INFINITY).unwrap(), r#""-INF""#);
+        assert_eq!(value_from_str::<f64>(r#""-INF""#), Ok(f64::NEG_INFINITY));
+
+        let value = ('é', i128::MIN, u128::MAX);
+        let json = value_to_string(&value).unwrap();
+
+        assert_eq!(
+            json,
+            r#"["é",-170141183460469231731687303715884105728,340282366920938463463374607431768211455]"#
+        );
+        assert_eq!(value_from_str::<(char, i128, u128)>(&json), Ok(value));
+    }
+
+    #[test]
+    fn test_nested_option() {
+        for value in [None, Some(None), Some(Some(None)), Some(Some(Some(7)))] {
+            let json = value_to_string(&value).unwrap();
+
+            assert_eq!(
+                value_from_str::<Option<Option<Option<i32>>>>(&json),
+                Ok(value)
+            );
+        }
+
+        assert_eq!(
+            value_to_string(&Some(Option::<i32>::None)).unwrap(),
+            r#"{"$some":null}"#
+        );
+    }
+
+    #[test]
+    fn test_variable() {
+        let value = Variable::<i32>::Reference {
+            path: Path::Index(3),
+            target: Target::ForeachItem,
+        };
+
+        let json = value_to_string(&value).unwrap();
+
+        assert_eq!(json, r#"{"$var":3,"target":"foreachItem"}"#);
+        assert_eq!(value_from_str::<Variable<i32>>(&json), Ok(value));
+        assert_eq!(
+            value_from_str::<Variable<i32>>("5"),
+            Ok(Variable::Constant(5))
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        struct Comma;
+
+        impl NumberPolicy for Comma {
+            fn parse<'a>(&self, kind: Kind, text: &'a str) -> Cow<'a, str> {
+                let _ = kind;
+                Cow::Owned(text.trim_end_matches("px").replace(',', "."))
+            }
+
+            fn print(&self, kind: Kind, text: &str) -> Option<String> {
+                matches!(kind, Kind::Float | Kind::Double).then(|| text.replace('.', ","))
+            }
+        }
+
+        let mut serializer = Serializer::value(NonFinite::default())
+            .with_float_format(FloatFormat::Decimals(2))
+            .with_number_policy(&Comma);
+
+        (vec![1.2345f64, 2.0], 3u8)
+            .serialize(&mut serializer)
+            .unwrap();
+
+        assert_eq!(serializer.output, r#"[["1,23","2"],3]"#);
+
+        let mut deserializer =
+            Deserializer::new(r#"[["1,23","2px"],"3px"]"#).with_number_policy(&Comma);
+
+        assert_eq!(
+            <(Vec<f64>, u8)>::deserialize(&mut deserializer),
+            Ok((vec![1.23, 2.0], 3))
+        );
+
+        assert_eq!(
+            value_to_string(&(Precision(1, &vec![1.26f32]), 1.26f32)).unwrap(),
+            "[[1.3],1.26]"
+        );
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let text = r#"["abc","a\nb"]"#;
+
+        let values = value_from_str::<Vec<Borrow<Cow<str>>>>(text).unwrap();
+
+        assert!(matches!(values[0], Cow::Borrowed("abc")));
+        assert!(matches!(&values[1], Cow::Owned(value) if value == "a\nb"));
+
+        assert_eq!(value_from_str::<&str>(r#""abc""#), Ok("abc"));
+        assert_eq!(
+            value_from_str::<&str>(r#""a\nb""#),
+            Err(Error::De(de::Error::Borrow("a\nb".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            value_from_str::<Vec<i32>>("[1, 2"),
+            Err(Error::Syntax(5, "expect `,` or `]`"))
+        );
+        assert_eq!(
+            value_from_str::<i32>("1 2"),
+            Err(Error::Syntax(2, "trailing characters"))
+        );
+        assert!(value_from_str::<u8>("256").is_err());
+        assert!(value_to_string(&BTreeMap::from([(vec![1], 1)])).is_err());
+
+        struct Pop;
+
+        impl Serialize for Pop {
+            fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+            where
+                S: crate::rt::serde::ser::Serializer,
+            {
+                serializer.serialize_pop()
+            }
+        }
+
+        assert_eq!(
+            Pop.serialize(&mut Serializer::new()),
+            Err(Error::Stream("pop without an open element"))
+        );
+        assert_eq!(Serializer::new().finish(), Ok("[]".to_string()));
+        assert!(
+            (&mut Serializer::value(Default::default()))
+                .serialize_pop()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        /// Reads the names of `group` elements and `rect` leaves.
+        struct Names;
+
+        impl<'de> Visitor<'de> for Names {
+            type Value = String;
+
+            fn is_element(&self, name: &str) -> bool {
+                name == "group"
+            }
+
+            fn is_leaf(&self, name: &str) -> bool {
+                name == "rect"
+            }
+
+            fn visit_opcode_with_attrs<D>(self, name: &str, _: D) -> Result<Vec<String>, D::Error>
+            where
+                D: AttrsNodeAccess<'de>,
+            {
+                Ok(vec![name.to_string()])
+            }
+
+            fn visit_pop<E>(self) -> Result<String, E>
+            where
+                E: From<de::Error>,
+            {
+                Ok("pop".to_string())
+            }
+        }
+
+        fn read(mut deserializer: Deserializer<'_>) -> Result<Vec<String>, Error> {
+            let mut names = vec![];
+
+            while let Some(batch) = (&mut deserializer).deserialize_opcode(Names)? {
+                names.extend(batch);
+            }
+
+            Ok(names)
+        }
+
+        let text = r#"[
+            { "group": {}, "children": [{ "rect": {} }, { "path": {}, "children": [{ "rect": {} }] }] },
+            { "rect": {}, "hidden": {} }
+        ]"#;
+
+        assert_eq!(
+            read(Deserializer::new(text)),
+            Err(Error::Node("no element or leaf in {path}".to_string()))
+        );
+        assert_eq!(
+            read(Deserializer::new(text).with_unknown_names(UnknownNames::Skip)),
+            Ok(vec![
+                "group".to_string(),
+                "rect".to_string(),
+                "pop".to_string(),
+                "rect".to_string()
+            ])
+        );
+    }
+}
@@ -1,2 +1,426 @@
+pub mod base64;
+pub mod binary;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod compat;
+pub mod convert;
 pub mod de;
+pub mod dump;
+pub mod float;
+pub mod json;
+pub mod number;
 pub mod ser;
+pub mod sexpr;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub mod stream;
+pub mod xml;
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        collections::VecDeque,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{
+        de::{Deserialize, Deserializer, Error, Visitor},
+        ser::{Serialize, SerializeMap, SerializeNode, SerializeSeq, Serializer},
+    };
+    use crate::rt::opcode::{Path, Target};
+
+    /// A format that only supports `int`, `string` and options.
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        None,
+        Some,
+        Int(i32),
+        String(String),
+    }
+
+    struct Unsupported;
+
+    impl SerializeNode for Unsupported {
+        type Error = Error;
+
+        fn serialize_field<T>(&mut self, _: usize, _: Option<&str>, _: &T) -> Result<(), Error>
+        where
+            T: ?Sized + Serialize,
+        {
+            unimplemented!()
+        }
+
+        fn finish(self) -> Result<(), Error> {
+            unimplemented!()
+        }
+    }
+
+    impl SerializeSeq for Unsupported {
+        type Error = Error;
+
+        fn next_item<T>(&mut self, _: &T) -> Result<(), Error>
+        where
+            T: ?Sized + Serialize,
+        {
+            unimplemented!()
+        }
+
+        fn finish(self) -> Result<(), Error> {
+            unimplemented!()
+        }
+    }
+
+    impl SerializeMap for Unsupported {
+        type Error = Error;
+
+        fn next_entry<K, V>(&mut self, _: &K, _: &V) -> Result<(), Error>
+        where
+            K: ?Sized + Serialize,
+            V: ?Sized + Serialize,
+        {
+            unimplemented!()
+        }
+
+        fn finish(self) -> Result<(), Error> {
+            unimplemented!()
+        }
+    }
+
+    impl Serializer for &mut Vec<Token> {
+        type Error = Error;
+        type SerializeNode = Unsupported;
+        type SerializeSeq = Unsupported;
+        type SerializeMap = Unsupported;
+
+        fn serialize_el(self, _: usize, _: &str, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_leaf(self, _: usize, _: &str, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_attr(self, _: usize, _: &str, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_data(self, _: usize, _: &str, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_enum(
+            self,
+            _: usize,
+            _: &str,
+            _: &str,
+            _: usize,
+            _: usize,
+        ) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_seq(self, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_map(self, _: usize) -> Result<Unsupported, Error> {
+            unimplemented!()
+        }
+
+        fn serialize_bool(self, _: bool) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_string(self, value: &str) -> Result<(), Error> {
+            self.push(Token::String(value.to_string()));
+            Ok(())
+        }
+
+        fn serialize_byte(self, _: i8) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_ubyte(self, _: u8) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_short(self, _: i16) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_ushort(self, _: u16) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_int(self, value: i32) -> Result<(), Error> {
+            self.push(Token::Int(value));
+            Ok(())
+        }
+
+        fn serialize_uint(self, _: u32) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_long(self, _: i64) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_ulong(self, _: u64) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_float(self, _: f32) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_double(self, _: f64) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_none(self) -> Result<(), Error> {
+            self.push(Token::None);
+            Ok(())
+        }
+
+        fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+        where
+            T: ?Sized + Serialize,
+        {
+            self.push(Token::Some);
+            value.serialize(self)
+        }
+
+        fn serialize_variable(self, _: &Path, _: &Target) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn serialize_pop(self) -> Result<(), Error> {
+            unimplemented!()
+        }
+    }
+
+    impl<'de> Deserializer<'de> for &mut VecDeque<Token> {
+        type Error = Error;
+
+        fn deserialize_opcode<V>(self, _: V) -> Result<Option<Vec<V::Value>>, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_element<V>(self, _: usize, _: &str, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_leaf<V>(self, _: usize, _: &str, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_attr<V>(self, _: usize, _: &str, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_data<V>(self, _: usize, _: &str, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_enum<V>(self, _: usize, _: &str, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_seq<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_map<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.pop_front() {
+                Some(Token::None) => visitor.visit_none(),
+                Some(Token::Some) => visitor.visit_some(self),
+                token => panic!("expect option, got {:?}", token),
+            }
+        }
+
+        fn deserialize_variable<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.pop_front() {
+                Some(Token::String(value)) => visitor.visit_string(&value),
+                token => panic!("expect string, got {:?}", token),
+            }
+        }
+
+        fn deserialize_raw<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_bool<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_byte<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_ubyte<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_short<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_ushort<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_int<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.pop_front() {
+                Some(Token::Int(value)) => visitor.visit_int(value),
+                token => panic!("expect int, got {:?}", token),
+            }
+        }
+
+        fn deserialize_uint<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_long<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_ulong<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_float<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+
+        fn deserialize_double<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_nested_option() {
+        for value in [None, Some(None), Some(Some(7))] {
+            let mut tokens = vec![];
+
+            value.serialize(&mut tokens).unwrap();
+
+            let mut tokens = VecDeque::from(tokens);
+
+            assert_eq!(<Option<Option<i32>>>::deserialize(&mut tokens), Ok(value));
+            assert!(tokens.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_char() {
+        let mut tokens = vec![];
+
+        'é'.serialize(&mut tokens).unwrap();
+
+        assert_eq!(tokens, [Token::String("é".to_string())]);
+        assert_eq!(char::deserialize(&mut VecDeque::from(tokens)), Ok('é'));
+
+        assert_eq!(
+            char::deserialize(&mut VecDeque::from([Token::String("ab".to_string())])),
+            Err(Error::Char("ab".to_string()))
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_fixed() {
+        use super::de::Fixed;
+
+        let decimal = |text: &str| VecDeque::from([Token::String(text.to_string())]);
+
+        assert_eq!(
+            Fixed::<5, 2>::deserialize(&mut decimal("123.40")).map(|v| v.to_string()),
+            Ok("123.40".to_string())
+        );
+        assert!(Fixed::<2, 2>::deserialize(&mut decimal("0")).is_ok());
+        assert_eq!(
+            Fixed::<5, 2>::deserialize(&mut decimal("1.234")),
+            Err(Error::Precision("1.234".to_string(), 5, 2))
+        );
+        assert!(Fixed::<5, 2>::deserialize(&mut decimal("1234")).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! A hook for the text of numbers in text formats, e.g. for legacy documents with `,` decimal separators or
+//! unit suffixes.
+//!
+//! ```ignore
+//! struct Comma;
+//!
+//! impl NumberPolicy for Comma {
+//!     fn parse<'a>(&self, kind: Kind, text: &'a str) -> Cow<'a, str> {
+//!         let _ = kind;
+//!         Cow::Owned(text.trim_end_matches("px").replace(',', "."))
+//!     }
+//!
+//!     fn print(&self, kind: Kind, text: &str) -> Option<String> {
+//!         matches!(kind, Kind::Float | Kind::Double).then(|| text.replace('.', ","))
+//!     }
+//! }
+//!
+//! let opcodes = xml::Deserializer::new(text).with_number_policy(&Comma);
+//! ```
+
+use alloc::{borrow::Cow, string::String};
+
+use crate::rt::serde::de::Kind;
+
+/// Parse and print hook of the numeric primitives of the json, xml and s-expression formats.
+///
+/// Numbers are passed to the hook as the text of their [`Kind`], `bool`, `char` and `decimal` are not numbers here.
+pub trait NumberPolicy {
+    /// Returns the text of a number read from a string of the input, in the form parsed by `str::parse`.
+    ///
+    /// Numbers written as number tokens of json and s-expressions are not passed to the hook. The default
+    /// returns `text` unchanged.
+    fn parse<'a>(&self, kind: Kind, text: &'a str) -> Cow<'a, str> {
+        let _ = kind;
+
+        Cow::Borrowed(text)
+    }
+
+    /// Returns the text of a finite number to write instead of `text`, its default form, or `None` to keep it.
+    ///
+    /// Json and s-expressions write the returned text as a string. The default returns `None`.
+    fn print(&self, kind: Kind, text: &str) -> Option<String> {
+        let _ = (kind, text);
+
+        None
+    }
+}
@@ -1,4 +1,18 @@
-use crate::rt::opcode::{Path, Target, Variable};
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{convert::Infallible, marker::PhantomData};
+
+use crate::rt::{
+    opcode::{Path, Target, Variable},
+    resource::Resource,
+    serde::{binary, float::NonFinite},
+    value::{DateTime, Duration, Iri, PathData, Rect, Transform, Uuid, ViewBox},
+};
 
 /// Serializer for Opcodes.
 pub trait Serializer {
@@ -11,6 +25,9 @@ pub trait Serializer {
     /// Returns by [`serialize_seq`](Serializer::serialize_seq) to help serializing array of vector.
     type SerializeSeq: SerializeSeq<Error = Self::Error>;
 
+    /// Returns by [`serialize_map`](Serializer::serialize_map) to help serializing map.
+    type SerializeMap: SerializeMap<Error = Self::Error>;
+
     /// Serialize a element node.
     fn serialize_el(
         self,
@@ -56,6 +73,9 @@ pub trait Serializer {
     /// Serialize vglang `vec[T]` or `[T;N]`
     fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error>;
 
+    /// Serialize vglang `[K -> V]`
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error>;
+
     /// Serialize vglang `string`.
     fn serialize_bool(self, value: bool) -> Result<(), Self::Error>;
 
@@ -86,15 +106,106 @@ pub trait Serializer {
     /// Serialize vglang `ulong`.
     fn serialize_ulong(self, value: u64) -> Result<(), Self::Error>;
 
-    /// Serialize vglang `long`.
+    /// Serialize vglang `int128`, formats write the decimal text by default.
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(&value.to_string())
+    }
+
+    /// Serialize vglang `uint128`, formats write the decimal text by default.
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(&value.to_string())
+    }
+
+    /// Serialize vglang `char`, formats write a string of the character by default.
+    fn serialize_char(self, value: char) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(value.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Returns the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    ///
+    /// Implementations of [`serialize_float`](Self::serialize_float) and [`serialize_double`](Self::serialize_double)
+    /// apply it with [`NonFinite::check_f32`] and [`NonFinite::check_f64`].
+    fn non_finite(&self) -> NonFinite {
+        NonFinite::default()
+    }
+
+    /// Serialize vglang `float`.
     fn serialize_float(self, value: f32) -> Result<(), Self::Error>;
 
     /// Serialize vglang `double`.
     fn serialize_double(self, value: f64) -> Result<(), Self::Error>;
 
+    /// Serialize a `#[precision(n)]` field, its `float` and `double` values are written with `decimals` decimals.
+    ///
+    /// Text formats override this, the precision is ignored by default.
+    fn serialize_with_precision<T>(self, decimals: u32, value: &T) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+        T: ?Sized + Serialize,
+    {
+        let _ = decimals;
+
+        value.serialize(self)
+    }
+
+    /// Serialize vglang `bytes`, text formats write base64 by default, binary formats should write raw bytes.
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(&super::base64::encode(value))
+    }
+
+    /// Serialize vglang `datetime`, text formats write RFC3339 by default,
+    /// binary formats should write the unix timestamp, nanoseconds and utc offset.
+    fn serialize_datetime(self, value: &DateTime) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(&value.to_string())
+    }
+
+    /// Serialize vglang `uuid`, text formats write the hyphenated form by default,
+    /// binary formats should write the 16 bytes.
+    fn serialize_uuid(self, value: &Uuid) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.serialize_string(&value.to_string())
+    }
+
     /// Serialize a none value.
     fn serialize_none(self) -> Result<(), Self::Error>;
 
+    /// Serialize a some value, formats that write nested options override this
+    /// to keep `Some(None)` apart from `None`.
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    /// Serialize the constant of a variable, formats that can't tell a constant from a reference by its value
+    /// override this to tag it.
+    fn serialize_constant<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
     /// Serialize a none value.
     fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error>;
 
@@ -114,6 +225,20 @@ pub trait SerializeSeq {
     fn finish(self) -> Result<(), Self::Error>;
 }
 
+/// A trait to help serialzing a map.
+pub trait SerializeMap {
+    type Error;
+
+    /// Serialize next entry.
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize;
+
+    /// Finish serializing a map.
+    fn finish(self) -> Result<(), Self::Error>;
+}
+
 /// A trait to help serialzing a node.
 pub trait SerializeNode {
     /// Error type returns by this trait.
@@ -133,6 +258,59 @@ pub trait SerializeNode {
     fn finish(self) -> Result<(), Self::Error>;
 }
 
+/// The `SerializeNode`, `SerializeSeq` and `SerializeMap` of serializers that don't support them,
+/// it can't be instantiated.
+pub struct Impossible<E> {
+    void: Infallible,
+    marker: PhantomData<E>,
+}
+
+impl<E> SerializeNode for Impossible<E> {
+    type Error = E;
+
+    fn serialize_field<T>(&mut self, _: usize, _: Option<&str>, _: &T) -> Result<(), E>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.void {}
+    }
+
+    fn finish(self) -> Result<(), E> {
+        match self.void {}
+    }
+}
+
+impl<E> SerializeSeq for Impossible<E> {
+    type Error = E;
+
+    fn next_item<T>(&mut self, _: &T) -> Result<(), E>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.void {}
+    }
+
+    fn finish(self) -> Result<(), E> {
+        match self.void {}
+    }
+}
+
+impl<E> SerializeMap for Impossible<E> {
+    type Error = E;
+
+    fn next_entry<K, V>(&mut self, _: &K, _: &V) -> Result<(), E>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        match self.void {}
+    }
+
+    fn finish(self) -> Result<(), E> {
+        match self.void {}
+    }
+}
+
 /// A node/enum must implement this trait to support serde framework.
 pub trait Serialize {
     /// serialize self with `serializer`.
@@ -150,6 +328,43 @@ impl Serialize for bool {
     }
 }
 
+impl Serialize for DateTime {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_datetime(self)
+    }
+}
+
+impl Serialize for Uuid {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_uuid(self)
+    }
+}
+
+impl Serialize for char {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_char(*self)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl Serialize for rust_decimal::Decimal {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
 impl Serialize for String {
     fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
     where
@@ -159,6 +374,78 @@ impl Serialize for String {
     }
 }
 
+impl Serialize for Cow<'_, str> {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(self)
+    }
+}
+
+impl Serialize for PathData {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
+impl Serialize for Transform {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
+impl Serialize for Rect {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
+impl Serialize for ViewBox {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(&self.to_string())
+    }
+}
+
+impl Serialize for Iri {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_string(self.as_str())
+    }
+}
+
+impl<T> Serialize for Resource<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        self.iri().serialize(serializer)
+    }
+}
+
 impl Serialize for i8 {
     fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
     where
@@ -231,6 +518,24 @@ impl Serialize for u64 {
     }
 }
 
+impl Serialize for i128 {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_int128(*self)
+    }
+}
+
+impl Serialize for u128 {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_uint128(*self)
+    }
+}
+
 impl Serialize for f32 {
     fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
     where
@@ -258,12 +563,36 @@ where
         S: Serializer,
     {
         match self {
-            Some(v) => v.serialize(serializer),
+            Some(v) => serializer.serialize_some(v),
             None => serializer.serialize_none(),
         }
     }
 }
 
+impl<T> Serialize for Box<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<T> Serialize for &T
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
 impl<T> Serialize for Variable<T>
 where
     T: Serialize,
@@ -273,7 +602,85 @@ where
         S: Serializer,
     {
         match self {
-            Variable::Constant(v) => v.serialize(serializer),
+            Variable::Constant(v) => serializer.serialize_constant(v),
+            Variable::Reference { path, target } => serializer.serialize_variable(path, target),
+        }
+    }
+}
+
+/// Serialize wrapper of `#[precision(n)]` fields, see [`Serializer::serialize_with_precision`].
+pub struct Precision<'a, T: ?Sized>(pub u32, pub &'a T);
+
+impl<T> Serialize for Precision<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_with_precision(self.0, self.1)
+    }
+}
+
+/// Serialize wrapper of `bytes` fields, a `Vec<u8>` is serialized as a sequence of `ubyte` without it.
+pub struct Bytes<'a, T>(pub &'a T);
+
+impl Serialize for Bytes<'_, Vec<u8>> {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl Serialize for Bytes<'_, Cow<'_, [u8]>> {
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<T> Serialize for Bytes<'_, Box<T>>
+where
+    for<'a> Bytes<'a, T>: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        Bytes(&**self.0).serialize(serializer)
+    }
+}
+
+impl<T> Serialize for Bytes<'_, Option<T>>
+where
+    for<'a> Bytes<'a, T>: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(v) => serializer.serialize_some(&Bytes(v)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<T> Serialize for Bytes<'_, Variable<T>>
+where
+    for<'a> Bytes<'a, T>: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Variable::Constant(v) => serializer.serialize_constant(&Bytes(v)),
             Variable::Reference { path, target } => serializer.serialize_variable(path, target),
         }
     }
@@ -314,3 +721,411 @@ where
         seq.finish()
     }
 }
+
+macro_rules! impl_serialize_tuple {
+    ($len: literal => $($name: ident $idx: tt)+) => {
+        impl<$($name),+> Serialize for ($($name,)+)
+        where
+            $($name: Serialize),+
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+            where
+                S: Serializer,
+            {
+                let mut seq = serializer.serialize_seq($len)?;
+
+                $(seq.next_item(&self.$idx)?;)+
+
+                seq.finish()
+            }
+        }
+    };
+}
+
+impl_serialize_tuple!(1 => T0 0);
+impl_serialize_tuple!(2 => T0 0 T1 1);
+impl_serialize_tuple!(3 => T0 0 T1 1 T2 2);
+impl_serialize_tuple!(4 => T0 0 T1 1 T2 2 T3 3);
+impl_serialize_tuple!(5 => T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_serialize_tuple!(6 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_serialize_tuple!(7 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_serialize_tuple!(8 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
+impl_serialize_tuple!(9 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8);
+impl_serialize_tuple!(10 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9);
+impl_serialize_tuple!(11 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10);
+impl_serialize_tuple!(12 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11);
+
+impl<K, V> Serialize for BTreeMap<K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(self.len())?;
+
+        for (key, value) in self.iter() {
+            map.next_entry(key, value)?;
+        }
+
+        map.finish()
+    }
+}
+
+/// A serializer that computes the length of the [`binary`] encoding of values without writing them, so the
+/// buffer of the real serialization can be allocated at once.
+///
+/// ```ignore
+/// let mut size = SizeHint::new();
+///
+/// for opcode in &opcodes {
+///     let Ok(()) = opcode.serialize(&mut size);
+/// }
+///
+/// let mut serializer = binary::Serializer::with_capacity(size.len());
+/// ```
+///
+/// The length doesn't include the header of the document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizeHint {
+    len: usize,
+}
+
+impl SizeHint {
+    /// Create a size hint of zero bytes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bytes of the values serialized so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no byte is counted.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn add_varint(&mut self, value: u64) {
+        self.len += binary::varint_len(value);
+    }
+
+    fn add_bytes(&mut self, len: usize) {
+        self.add_varint(len as u64);
+        self.len += len;
+    }
+}
+
+impl SerializeNode for &mut SizeHint {
+    type Error = Infallible;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = (index, name);
+
+        value.serialize(&mut **self)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl SerializeSeq for &mut SizeHint {
+    type Error = Infallible;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl SerializeMap for &mut SizeHint {
+    type Error = Infallible;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        key.serialize(&mut **self)?;
+        value.serialize(&mut **self)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Serializer for &mut SizeHint {
+    type Error = Infallible;
+    type SerializeNode = Self;
+    type SerializeSeq = Self;
+    type SerializeMap = Self;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.add_varint(type_id as u64 + 1);
+
+        Ok(self)
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.add_varint(type_id as u64 + 1);
+
+        Ok(self)
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (name, fields);
+
+        self.add_varint(type_id as u64 + 1);
+
+        Ok(self)
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, fields);
+
+        Ok(self)
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant, fields);
+
+        self.add_varint(variant_index as u64);
+
+        Ok(self)
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        self.add_varint(len as u64);
+
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        self.add_varint(len as u64);
+
+        Ok(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<(), Self::Error> {
+        self.len += 1;
+        Ok(())
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        self.add_bytes(value.len());
+        Ok(())
+    }
+
+    fn serialize_byte(self, _: i8) -> Result<(), Self::Error> {
+        self.len += 1;
+        Ok(())
+    }
+
+    fn serialize_ubyte(self, _: u8) -> Result<(), Self::Error> {
+        self.len += 1;
+        Ok(())
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        self.add_varint(binary::zigzag(value.into()));
+        Ok(())
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        self.add_varint(value.into());
+        Ok(())
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        self.add_varint(binary::zigzag(value.into()));
+        Ok(())
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        self.add_varint(value.into());
+        Ok(())
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        self.add_varint(binary::zigzag(value));
+        Ok(())
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        self.add_varint(value);
+        Ok(())
+    }
+
+    fn serialize_int128(self, _: i128) -> Result<(), Self::Error> {
+        self.len += size_of::<i128>();
+        Ok(())
+    }
+
+    fn serialize_uint128(self, _: u128) -> Result<(), Self::Error> {
+        self.len += size_of::<u128>();
+        Ok(())
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Self::Error> {
+        self.add_varint(value.into());
+        Ok(())
+    }
+
+    fn serialize_float(self, _: f32) -> Result<(), Self::Error> {
+        self.len += size_of::<f32>();
+        Ok(())
+    }
+
+    fn serialize_double(self, _: f64) -> Result<(), Self::Error> {
+        self.len += size_of::<f64>();
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Self::Error> {
+        self.add_bytes(value.len());
+        Ok(())
+    }
+
+    fn serialize_datetime(self, value: &DateTime) -> Result<(), Self::Error> {
+        self.add_varint(binary::zigzag(value.unix_timestamp()));
+        self.add_varint(value.nanos().into());
+        self.add_varint(binary::zigzag(value.offset().into()));
+        Ok(())
+    }
+
+    fn serialize_uuid(self, value: &Uuid) -> Result<(), Self::Error> {
+        self.len += value.as_bytes().len();
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.len += 1;
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.len += 1;
+        value.serialize(self)
+    }
+
+    fn serialize_constant<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.len += 1;
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, _: &Target) -> Result<(), Self::Error> {
+        // the variable tag, the path tag and the target.
+        self.len += 3;
+
+        match path {
+            Path::Named(name) => self.add_bytes(name.len()),
+            Path::Index(index) => self.add_varint(*index as u64),
+        }
+
+        Ok(())
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        self.add_varint(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeMap, string::ToString, vec};
+
+    use super::{Serialize, SizeHint};
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::binary,
+    };
+
+    fn check<T>(value: &T)
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut size = SizeHint::new();
+        let Ok(()) = value.serialize(&mut size);
+
+        let mut serializer = binary::Serializer::with_capacity(size.len());
+        value.serialize(&mut serializer).unwrap();
+        let bytes = serializer.finish();
+
+        assert_eq!(bytes.len(), bytes.capacity());
+        assert_eq!(bytes, binary::value_to_bytes(value).unwrap());
+    }
+
+    #[test]
+    fn test_size_hint() {
+        check(&0i32);
+        check(&-65i64);
+        check(&u64::MAX);
+        check(&(true, 1.5f32, f64::NAN, -3i8));
+        check(&('a', 'é', i128::MIN, u128::MAX));
+        check(&vec!["a".repeat(200), "".to_string()]);
+        check(&Some(Some(300u16)));
+        check(&BTreeMap::from([(1u32, 2.5f64), (128, -1.0)]));
+        check(&Variable::<u8>::Constant(5));
+        check(&Variable::<u8>::Reference {
+            path: Path::Named("x".repeat(130)),
+            target: Target::Range,
+        });
+    }
+}
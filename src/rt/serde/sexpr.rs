@@ -0,0 +1,2376 @@
+//! An s-expression format of opcode streams.
+//!
+//! A document is a sequence of lists, one for every element or leaf, headed by its display name. The children of an
+//! element follow its fields and attrs, so `Pop` is implied:
+//!
+//! ```text
+//! (group
+//!   (rect :id "r" :x 1.5 :y INF :h 3.0 @fill none @stroke (:paint (color (:r 1 :g 2 :b 3)) :dashes [1.0 2.0]))
+//!   (text "hello" @fill (named :name "red")))
+//! ```
+//!
+//! The items of a node list are:
+//!
+//! - named fields, keyed by `:name`;
+//! - unnamed fields, positional for leaves; the positional items of an element are its children, so the unnamed
+//!   fields of elements are keyed by their index, `:0`, `:1`, ...;
+//! - the attrs applied to the node, `@name` followed by the fields of the attr.
+//!
+//! The fields of attrs and data are written as:
+//!
+//! - a list of the named fields, `(:r 1 :g 2 :b 3)`;
+//! - a vector of the unnamed fields, a single unnamed field is written as is, unless it is a vector;
+//! - `()` if the node has no fields.
+//!
+//! Enum variants without fields are written as their names, other variants as a list headed by the variant name,
+//! with the fields as the items of a leaf, e.g. `(named :name "red")`.
+//!
+//! Other values are written as:
+//!
+//! | value                     | s-expression                                             |
+//! |---------------------------|----------------------------------------------------------|
+//! | numbers and `bool`        | the number or `true`/`false`, non-finite floats as `NaN`, `INF` or `-INF` |
+//! | `vec[T]`, `[T;N]`, tuples | vectors, `[1 2 3]`                                       |
+//! | `[K -> V]`                | maps of keys and values, `{"a" 1 "b" 2}`                 |
+//! | `None`                    | `nil`, keyed fields are omitted                          |
+//! | `Some(v)`                 | `v`, or `($some v)` if `v` is written as `nil`, e.g. `Some(None)` |
+//! | variable references       | `($var "name" register)`                                 |
+//! | other types               | their text forms as strings, e.g. base64 for `bytes`     |
+//!
+//! Comments start with `;` and run to the end of the line.
+//!
+//! The format uses display names, it doesn't work with code generated by `CodeGen::strip_names`.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt::Write, iter, mem, slice};
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        de::{
+            self, AttrsNodeAccess, Deserialize, FieldContext, Kind, MapAccess, NodeAccess,
+            NumericCoercion, SeqAccess, UnknownNames, Visitor,
+        },
+        float::{FloatError, FloatFormat, NonFinite, token, write_decimals},
+        json::target_name,
+        number::NumberPolicy,
+        ser::{self, Serialize},
+    },
+};
+
+/// The head of the wrapper of a `Some` value written as `nil`.
+const SOME: &str = "$some";
+
+/// The head of a variable reference.
+const VAR: &str = "$var";
+
+/// The symbol of `None`.
+const NIL: &str = "nil";
+
+/// Max nesting depth of lists, vectors and maps.
+const MAX_DEPTH: usize = 256;
+
+/// Error returns by the s-expression [`Serializer`] and [`Deserializer`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    De(#[from] de::Error),
+
+    #[error(transparent)]
+    Float(#[from] FloatError),
+
+    #[error("Invalid s-expression at {0}, {1}.")]
+    Syntax(usize, &'static str),
+
+    #[error("Invalid opcode stream, {0}.")]
+    Stream(&'static str),
+
+    #[error("Invalid node, {0}.")]
+    Node(String),
+
+    #[error("S-expressions require display names, the names of the schema are stripped.")]
+    Unnamed,
+
+    #[error("Missing field `{1}` of `{0}`.")]
+    MissingField(String, String),
+
+    #[error("Missing attr `{0}`.")]
+    MissingAttr(String),
+}
+
+impl FieldContext for Error {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::De(err) => Error::De(err.field_context(ty, field)),
+            // syntax errors already carry their position.
+            err => err,
+        }
+    }
+}
+
+/// Serialize `opcodes` as an s-expression document.
+pub fn to_string<'a, T>(opcodes: impl IntoIterator<Item = &'a T>) -> Result<String, Error>
+where
+    T: Serialize + 'a,
+{
+    let mut serializer = Serializer::new();
+
+    for opcode in opcodes {
+        opcode.serialize(&mut serializer)?;
+    }
+
+    serializer.finish()
+}
+
+/// Deserialize the opcodes of an s-expression document.
+pub fn from_str<'de, T>(text: &'de str) -> Result<Vec<T>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>>,
+{
+    let mut deserializer = Deserializer::new(text);
+    let mut opcodes = vec![];
+
+    while let Some(batch) = T::deserialize(&mut deserializer)? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Serialize a single value, e.g. a data or an enum, as an s-expression.
+pub fn value_to_string<T>(value: &T) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::value(NonFinite::default());
+
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.output)
+}
+
+/// Deserialize a single value, e.g. a data or an enum, from an s-expression.
+pub fn value_from_str<'de, T>(text: &'de str) -> Result<T::Value, Error>
+where
+    T: Deserialize<'de>,
+{
+    let value = Parser::parse_value(text)?;
+
+    T::deserialize(ValueDeserializer::new(&value, Options::default()))
+}
+
+/// An s-expression, strings borrow the source text if possible.
+#[derive(Debug, PartialEq)]
+enum Value<'a> {
+    /// Names, `nil`, `true`, `false` and the non-finite tokens.
+    Symbol(&'a str),
+    /// A `:name` key, without the `:`.
+    Keyword(&'a str),
+    /// An `@name` attr, without the `@`.
+    Attr(&'a str),
+    /// The source text of a number.
+    Number(&'a str),
+    String(Cow<'a, str>),
+    List(Vec<Item<'a>>),
+    Vector(Vec<Item<'a>>),
+    /// Keys and values, alternately.
+    Map(Vec<Item<'a>>),
+}
+
+/// An item of a list, vector or map.
+#[derive(Debug, PartialEq)]
+struct Item<'a> {
+    value: Value<'a>,
+    /// The source text of `value`.
+    raw: &'a str,
+}
+
+static NIL_VALUE: Value<'static> = Value::Symbol(NIL);
+
+impl Value<'_> {
+    fn kind(&self) -> Kind {
+        match self {
+            Value::Symbol(NIL) => Kind::None,
+            Value::Symbol("true" | "false") => Kind::Bool,
+            Value::Symbol(_) => Kind::Enum,
+            Value::Keyword(_) | Value::Attr(_) | Value::String(_) => Kind::String,
+            Value::Number(_) => Kind::Double,
+            Value::List(_) => Kind::Data,
+            Value::Vector(_) => Kind::Seq,
+            Value::Map(_) => Kind::Map,
+        }
+    }
+}
+
+/// Returns the value keyed by `:key` in a list of keys and values, the last one if the key is duplicated.
+fn keyed<'a, 'b>(items: &'b [Item<'a>], key: &str) -> Option<&'b Value<'a>> {
+    items
+        .chunks(2)
+        .rev()
+        .find(|pair| matches!(pair[0].value, Value::Keyword(name) if name == key))
+        .and_then(|pair| pair.get(1))
+        .map(|item| &item.value)
+}
+
+/// The items of a node or variant list, after its name.
+struct Items<'a, 'b> {
+    keyed: Vec<(&'a str, &'b Value<'a>)>,
+    positional: Vec<&'b Value<'a>>,
+    attrs: Vec<(&'a str, &'b Item<'a>)>,
+}
+
+impl<'a, 'b> Items<'a, 'b> {
+    /// Split `items`, the positional items of elements are their children and are skipped.
+    fn new(items: &'b [Item<'a>], element: bool) -> Result<Self, Error> {
+        let mut this = Self {
+            keyed: vec![],
+            positional: vec![],
+            attrs: vec![],
+        };
+
+        let mut iter = items.iter();
+
+        while let Some(item) = iter.next() {
+            match item.value {
+                Value::Keyword(key) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| Error::Node(format!("`:{}` has no value", key)))?;
+
+                    this.keyed.push((key, &value.value));
+                }
+                Value::Attr(name) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| Error::Node(format!("`@{}` has no value", name)))?;
+
+                    this.attrs.push((name, value));
+                }
+                _ if element => {}
+                _ => this.positional.push(&item.value),
+            }
+        }
+
+        Ok(this)
+    }
+
+    /// Returns the first positional item of a node list, the positional lists of a node are read as child nodes.
+    fn positional(items: &'b [Item<'a>]) -> Option<&'b Item<'a>> {
+        let mut iter = items.iter();
+
+        while let Some(item) = iter.next() {
+            match item.value {
+                Value::Keyword(_) | Value::Attr(_) => _ = iter.next(),
+                _ => return Some(item),
+            }
+        }
+
+        None
+    }
+
+    fn get(&self, key: &str) -> Option<&'b Value<'a>> {
+        self.keyed
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// A node list whose positional lists are read as nodes.
+#[derive(Debug)]
+struct Node<'a> {
+    /// The source text of the list.
+    raw: &'a str,
+    /// The items of the list, but its positional lists.
+    items: Vec<Item<'a>>,
+    /// The indexes of the positional lists in the list, whose nodes follow the event of the list.
+    lists: Vec<usize>,
+}
+
+impl<'a> Node<'a> {
+    /// Returns the items of the list, its positional lists are read back from the nodes of `events`, e.g. the
+    /// fields of a leaf.
+    fn into_items(self, events: &mut vec::IntoIter<Event<'a>>) -> Result<Vec<Item<'a>>, Error> {
+        // the lists being read back, with their positional lists read so far.
+        let mut stack = vec![(self, vec![])];
+
+        for event in events {
+            match event {
+                Event::Node(_) if stack.len() == MAX_DEPTH => {
+                    return Err(Error::Node(
+                        "the fields of a leaf are nested too deeply".to_string(),
+                    ));
+                }
+                Event::Node(node) => stack.push((node, vec![])),
+                Event::End => {
+                    let Some((node, lists)) = stack.pop() else {
+                        break;
+                    };
+
+                    let raw = node.raw;
+                    let items = node.merge(lists);
+
+                    match stack.last_mut() {
+                        Some((_, lists)) => lists.push(Item {
+                            value: Value::List(items),
+                            raw,
+                        }),
+                        None => return Ok(items),
+                    }
+                }
+                Event::Value(_) => {}
+            }
+        }
+
+        Err(Error::Node("unterminated node list".to_string()))
+    }
+
+    /// Returns the items of the list with its positional `lists`.
+    fn merge(self, lists: Vec<Item<'a>>) -> Vec<Item<'a>> {
+        let len = self.items.len() + self.lists.len();
+        let mut items = self.items.into_iter();
+        let mut lists = lists.into_iter();
+
+        (0..len)
+            .map_while(|index| match self.lists.binary_search(&index) {
+                Ok(_) => lists.next(),
+                Err(_) => items.next(),
+            })
+            .collect()
+    }
+}
+
+/// An item of the document or the children of a node list, in stream order.
+#[derive(Debug)]
+enum Event<'a> {
+    /// A node list, whose positional lists follow until the matching [`End`](Self::End).
+    Node(Node<'a>),
+    /// The end of a node list.
+    End,
+    /// An item that isn't a list.
+    Value(Item<'a>),
+}
+
+/// A recursive descent s-expression parser, the node lists of documents are read with an explicit stack.
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse the node lists of a document as a flat list of events, so nesting elements doesn't recurse.
+    ///
+    /// The positional lists of a node list are read as nodes, which are rejected deeper than `max_depth`; keyed
+    /// values and other items are parsed as is.
+    fn document(input: &'a str, max_depth: usize) -> Result<Vec<Event<'a>>, Error> {
+        let mut parser = Self { input, offset: 0 };
+        let mut events = vec![];
+        // the open node lists, the index of their event, their start and their node.
+        let mut stack: Vec<(usize, usize, Node<'a>)> = vec![];
+
+        loop {
+            parser.skip_whitespaces();
+
+            match (stack.last_mut(), parser.peek()) {
+                (None, None) => return Ok(events),
+                (Some(_), None) => return Err(parser.error("unterminated list")),
+                (Some(_), Some(b')')) => {
+                    parser.offset += 1;
+
+                    if let Some((event, start, mut node)) = stack.pop() {
+                        node.raw = &input[start..parser.offset];
+                        events[event] = Event::Node(node);
+                        events.push(Event::End);
+                    }
+                }
+                (None, Some(b')' | b']' | b'}')) => {
+                    return Err(parser.error("unexpected closing bracket"));
+                }
+                (Some(_), Some(b']' | b'}')) => {
+                    return Err(parser.error("mismatched closing bracket"));
+                }
+                (node, Some(b'(')) => {
+                    if let Some((_, _, node)) = node {
+                        node.lists.push(node.items.len() + node.lists.len());
+                    }
+
+                    if stack.len() == max_depth {
+                        return Err(parser.error("elements are nested too deeply"));
+                    }
+
+                    let start = parser.offset;
+
+                    parser.offset += 1;
+                    parser.skip_whitespaces();
+
+                    // the name of the node.
+                    let mut items = vec![];
+
+                    if !matches!(parser.peek(), Some(b')') | None) {
+                        items.push(parser.item(0)?);
+                    }
+
+                    events.push(Event::End);
+                    stack.push((
+                        events.len() - 1,
+                        start,
+                        Node {
+                            raw: "",
+                            items,
+                            lists: vec![],
+                        },
+                    ));
+                }
+                (None, Some(_)) => events.push(Event::Value(parser.item(0)?)),
+                (Some((_, _, node)), Some(_)) => {
+                    let item = parser.item(0)?;
+                    let keyed = matches!(item.value, Value::Keyword(_) | Value::Attr(_));
+
+                    node.items.push(item);
+
+                    parser.skip_whitespaces();
+
+                    if keyed && !matches!(parser.peek(), Some(b')' | b']' | b'}') | None) {
+                        node.items.push(parser.item(0)?);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse a document of a single value.
+    fn parse_value(input: &'a str) -> Result<Value<'a>, Error> {
+        let mut parser = Self { input, offset: 0 };
+
+        let value = parser.value(0)?;
+
+        parser.skip_whitespaces();
+
+        if parser.offset != input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    fn error(&self, message: &'static str) -> Error {
+        Error::Syntax(self.offset, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.offset).copied()
+    }
+
+    /// Skip whitespaces and comments.
+    fn skip_whitespaces(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => self.offset += 1,
+                Some(b';') => {
+                    self.offset = self.input[self.offset..]
+                        .find('\n')
+                        .map_or(self.input.len(), |len| self.offset + len);
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Item<'a>, Error> {
+        self.skip_whitespaces();
+
+        let start = self.offset;
+        let value = self.value(depth)?;
+
+        Ok(Item {
+            value,
+            raw: &self.input[start..self.offset],
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, Error> {
+        self.skip_whitespaces();
+
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'(' | b'[' | b'{') if depth == MAX_DEPTH => {
+                Err(self.error("nesting is too deep"))
+            }
+            Some(b'(') => self.items(b')', depth + 1).map(Value::List),
+            Some(b'[') => self.items(b']', depth + 1).map(Value::Vector),
+            Some(b'{') => {
+                let items = self.items(b'}', depth + 1)?;
+
+                if items.len() % 2 != 0 {
+                    return Err(Error::Syntax(self.offset - 1, "a map key has no value"));
+                }
+
+                Ok(Value::Map(items))
+            }
+            Some(b')' | b']' | b'}') => Err(self.error("unexpected closing bracket")),
+            Some(_) => self.atom(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn items(&mut self, close: u8, depth: usize) -> Result<Vec<Item<'a>>, Error> {
+        // skip the opening bracket.
+        self.offset += 1;
+
+        let mut items = vec![];
+
+        loop {
+            self.skip_whitespaces();
+
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.offset += 1;
+                    return Ok(items);
+                }
+                Some(b')' | b']' | b'}') => return Err(self.error("mismatched closing bracket")),
+                Some(_) => items.push(self.item(depth)?),
+                None => return Err(self.error("unterminated list")),
+            }
+        }
+    }
+
+    /// Parse a symbol, keyword, attr or number.
+    fn atom(&mut self) -> Result<Value<'a>, Error> {
+        let start = self.offset;
+        let rest = &self.input[start..];
+        let len = rest
+            .find(|c: char| c.is_ascii_whitespace() || "()[]{}\";".contains(c))
+            .unwrap_or(rest.len());
+
+        let token = &rest[..len];
+
+        self.offset += len;
+
+        let named = |name: &'a str| {
+            if name.is_empty() {
+                Err(Error::Syntax(start, "expect a name"))
+            } else {
+                Ok(name)
+            }
+        };
+
+        if let Some(name) = token.strip_prefix(':') {
+            return named(name).map(Value::Keyword);
+        }
+
+        if let Some(name) = token.strip_prefix('@') {
+            return named(name).map(Value::Attr);
+        }
+
+        let digits = token.strip_prefix(['-', '+']).unwrap_or(token);
+
+        if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            Ok(Value::Number(token))
+        } else {
+            Ok(Value::Symbol(token))
+        }
+    }
+
+    fn string(&mut self) -> Result<Cow<'a, str>, Error> {
+        // skip the opening quote.
+        self.offset += 1;
+
+        let start = self.offset;
+        let mut owned: Option<String> = None;
+
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+
+            match c {
+                b'"' => {
+                    let value = match owned {
+                        Some(value) => Cow::Owned(value),
+                        None => Cow::Borrowed(&self.input[start..self.offset]),
+                    };
+
+                    self.offset += 1;
+
+                    return Ok(value);
+                }
+                b'\\' => {
+                    let value =
+                        owned.get_or_insert_with(|| self.input[start..self.offset].to_string());
+
+                    self.offset += 1;
+
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+
+                    self.offset += 1;
+
+                    match escape {
+                        b'"' => value.push('"'),
+                        b'\\' => value.push('\\'),
+                        b'n' => value.push('\n'),
+                        b'r' => value.push('\r'),
+                        b't' => value.push('\t'),
+                        b'u' => {
+                            let code = self.input[self.offset..]
+                                .strip_prefix('{')
+                                .and_then(|rest| rest.split_once('}'))
+                                .map(|(hex, _)| hex)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+
+                            let c = u32::from_str_radix(code, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+
+                            value.push(c);
+                            self.offset += code.len() + 2;
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                _ => {
+                    let len = self.input[self.offset..]
+                        .chars()
+                        .next()
+                        .map_or(1, char::len_utf8);
+
+                    if let Some(value) = &mut owned {
+                        value.push_str(&self.input[self.offset..self.offset + len]);
+                    }
+
+                    self.offset += len;
+                }
+            }
+        }
+    }
+}
+
+/// A serializer of s-expression documents.
+///
+/// Opcodes are written in stream order, the document is returned by [`finish`](Self::finish):
+///
+/// ```ignore
+/// let mut serializer = Serializer::new().with_indent(2);
+///
+/// for opcode in &opcodes {
+///     opcode.serialize(&mut serializer)?;
+/// }
+///
+/// let text = serializer.finish()?;
+/// ```
+pub struct Serializer {
+    output: String,
+    /// The attrs applied to the next node, `@name value`, written after its fields.
+    attrs: Vec<String>,
+    /// The number of open elements, `None` for a serializer of a single value.
+    depth: Option<usize>,
+    /// `Some` wrappers of the next value, only written if the value is `nil`.
+    some: usize,
+    /// Spaces per nesting level, `None` writes the document on one line.
+    indent: Option<usize>,
+    /// Write the applied attrs on their own lines.
+    attr_lines: bool,
+    non_finite: NonFinite,
+    float_format: FloatFormat,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer {
+    /// Create a serializer of an opcode stream, the document is written on one line.
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+            attrs: vec![],
+            depth: Some(0),
+            some: 0,
+            indent: None,
+            attr_lines: false,
+            non_finite: NonFinite::default(),
+            float_format: FloatFormat::default(),
+            number_policy: None,
+        }
+    }
+
+    /// A serializer of a single value, opcodes are not allowed.
+    fn value(non_finite: NonFinite) -> Self {
+        Self {
+            depth: None,
+            non_finite,
+            ..Self::new()
+        }
+    }
+
+    /// Pretty print the document, every node starts a line indented by `indent` spaces per nesting level.
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Write the applied attrs on their own lines below their node, only when pretty printing.
+    pub fn with_attr_lines(mut self, attr_lines: bool) -> Self {
+        self.attr_lines = attr_lines;
+        self
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Set the decimals of `float` and `double` values, the default is [`FloatFormat::Shortest`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Set the hook of the text of numbers, the numbers it rewrites are written as strings.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.number_policy = Some(policy);
+        self
+    }
+
+    /// Returns the document, fails if an attr isn't followed by its element or leaf, or an element isn't popped.
+    pub fn finish(self) -> Result<String, Error> {
+        if !self.attrs.is_empty() {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        if self.depth.is_some_and(|depth| depth != 0) {
+            return Err(Error::Stream("elements aren't popped"));
+        }
+
+        Ok(self.output)
+    }
+
+    /// Start writing a value, pending `Some` wrappers are dropped.
+    fn begin_value(&mut self) {
+        self.some = 0;
+    }
+
+    /// Start a line at `depth` when pretty printing, or write a space.
+    fn separator(&mut self, depth: usize) {
+        match self.indent {
+            Some(indent) => {
+                self.output.push('\n');
+                self.output.extend(iter::repeat_n(' ', indent * depth));
+            }
+            None => self.output.push(' '),
+        }
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.output.push('"');
+
+        for c in value.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                // writing to a `String` never fails.
+                c if c.is_control() => _ = write!(self.output, "\\u{{{:x}}}", c as u32),
+                c => self.output.push(c),
+            }
+        }
+
+        self.output.push('"');
+    }
+
+    /// Write a name, prefixed by `:` for keys or `@` for attrs.
+    fn write_name(&mut self, prefix: &str, name: &str) -> Result<(), Error> {
+        if name.is_empty() {
+            return Err(Error::Unnamed);
+        }
+
+        self.output.push_str(prefix);
+        self.output.push_str(name);
+
+        Ok(())
+    }
+
+    fn write_number(&mut self, kind: Kind, value: impl core::fmt::Display) {
+        self.begin_value();
+
+        let start = self.output.len();
+
+        _ = write!(self.output, "{}", value);
+
+        self.print_number(kind, start);
+    }
+
+    /// Rewrite the number written from `start` with the number policy.
+    fn print_number(&mut self, kind: Kind, start: usize) {
+        let Some(text) = self
+            .number_policy
+            .and_then(|policy| policy.print(kind, &self.output[start..]))
+        else {
+            return;
+        };
+
+        self.output.truncate(start);
+        self.write_string(&text);
+    }
+
+    fn write_float(&mut self, kind: Kind, value: f64, text: impl core::fmt::Debug) {
+        self.begin_value();
+
+        if let Some(token) = token(value) {
+            self.output.push_str(token);
+            return;
+        }
+
+        let start = self.output.len();
+
+        match self.float_format {
+            FloatFormat::Shortest => _ = write!(self.output, "{:?}", text),
+            FloatFormat::Decimals(decimals) => write_decimals(&mut self.output, value, decimals),
+        }
+
+        self.print_number(kind, start);
+    }
+
+    /// Open the list of an element or leaf, returns the number of open elements.
+    fn open_node(&mut self, name: &str) -> Result<usize, Error> {
+        let depth = self
+            .depth
+            .ok_or(Error::Stream("opcodes can't be serialized as values"))?;
+
+        if !self.output.is_empty() {
+            self.separator(depth);
+        }
+
+        self.output.push('(');
+        self.write_name("", name)?;
+
+        Ok(depth)
+    }
+
+    /// Write the attrs applied to the node at `depth`.
+    fn write_attrs(&mut self, depth: usize) {
+        for attr in mem::take(&mut self.attrs) {
+            if self.attr_lines {
+                self.separator(depth + 1);
+            } else {
+                self.output.push(' ');
+            }
+
+            self.output.push_str(&attr);
+        }
+    }
+}
+
+/// How the fields of a node are written.
+#[derive(Clone, Copy)]
+enum Layout {
+    /// The items of a node or variant list, unnamed fields are keyed by their index if `keyed`.
+    Items { keyed: bool },
+    /// An attr or data value, `None` until the first field.
+    Body(Option<Body>),
+}
+
+/// The value of the fields of an attr or data.
+#[derive(Clone, Copy)]
+enum Body {
+    /// An enum variant without fields.
+    Unit,
+    List,
+    Vector,
+    /// A single unnamed field, which starts at the offset.
+    Newtype(usize),
+}
+
+/// What [`NodeSerializer::finish`] writes after the fields.
+enum End {
+    Value,
+    /// Close the list of an enum variant.
+    Variant,
+    /// Move the attr, which starts at the offset, to the attrs of the next node.
+    Attr(usize),
+    Element(usize),
+    Leaf(usize),
+}
+
+/// Returns by [`Serializer`] to write the fields of a node.
+pub struct NodeSerializer<'a> {
+    serializer: &'a mut Serializer,
+    fields: usize,
+    layout: Layout,
+    end: End,
+    /// The `Some` wrappers of a data, its single unnamed field is written in its place so it gets them.
+    some: usize,
+}
+
+impl ser::SerializeNode for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let output = &mut self.serializer.output;
+
+        match (self.layout, name) {
+            (Layout::Body(None), Some(_)) => {
+                output.push('(');
+                self.layout = Layout::Body(Some(Body::List));
+            }
+            (Layout::Body(None), None) if self.fields == 1 => {
+                self.layout = Layout::Body(Some(Body::Newtype(output.len())));
+                self.serializer.some = self.some;
+            }
+            (Layout::Body(None), None) => {
+                output.push('[');
+                self.layout = Layout::Body(Some(Body::Vector));
+            }
+            (Layout::Body(Some(Body::List)), Some(_))
+            | (Layout::Body(Some(Body::Vector)), None)
+            | (Layout::Items { .. }, _) => {}
+            _ => return Err(Error::Unnamed),
+        }
+
+        let start = output.len();
+
+        if !matches!(self.layout, Layout::Body(Some(Body::Newtype(_))))
+            && !output.ends_with(['(', '['])
+        {
+            output.push(' ');
+        }
+
+        let keyed = match (name, self.layout) {
+            (Some(name), _) => {
+                self.serializer.write_name(":", name)?;
+                true
+            }
+            (None, Layout::Items { keyed: true }) => {
+                _ = write!(self.serializer.output, ":{}", index);
+                true
+            }
+            _ => false,
+        };
+
+        if keyed {
+            self.serializer.output.push(' ');
+        }
+
+        let value_start = self.serializer.output.len();
+
+        value.serialize(&mut *self.serializer)?;
+
+        let output = &mut self.serializer.output;
+
+        // keyed `None`s are omitted, missing fields are read as `nil`.
+        if keyed && output[value_start..] == *NIL {
+            output.truncate(start);
+        }
+
+        // a single unnamed field written as a vector is wrapped, it would be read as a vector of fields.
+        if let Layout::Body(Some(Body::Newtype(start))) = self.layout
+            && output[start..].starts_with('[')
+        {
+            output.insert(start, '[');
+            output.push(']');
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        let serializer = self.serializer;
+
+        match self.layout {
+            Layout::Body(Some(Body::List)) => serializer.output.push(')'),
+            Layout::Body(Some(Body::Vector)) => serializer.output.push(']'),
+            Layout::Body(None) => serializer.output.push_str("()"),
+            Layout::Body(Some(Body::Unit | Body::Newtype(_))) | Layout::Items { .. } => {}
+        }
+
+        match self.end {
+            End::Value => {}
+            End::Variant => serializer.output.push(')'),
+            End::Attr(start) => {
+                let attr = serializer.output.split_off(start);
+                serializer.attrs.push(attr);
+            }
+            End::Element(depth) => {
+                serializer.write_attrs(depth);
+                serializer.depth = Some(depth + 1);
+            }
+            End::Leaf(depth) => {
+                serializer.write_attrs(depth);
+                serializer.output.push(')');
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns by [`Serializer`] to write the items of a sequence.
+pub struct SeqSerializer<'a> {
+    serializer: &'a mut Serializer,
+    first: bool,
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Error = Error;
+
+    fn next_item<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.first {
+            self.serializer.output.push(' ');
+        }
+
+        self.first = false;
+
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        self.serializer.output.push(']');
+        Ok(())
+    }
+}
+
+/// Returns by [`Serializer`] to write the entries of a map.
+pub struct MapSerializer<'a> {
+    serializer: &'a mut Serializer,
+    first: bool,
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Error = Error;
+
+    fn next_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        if !self.first {
+            self.serializer.output.push(' ');
+        }
+
+        self.first = false;
+
+        key.serialize(&mut *self.serializer)?;
+        self.serializer.output.push(' ');
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        self.serializer.output.push('}');
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Error = Error;
+    type SerializeNode = NodeSerializer<'a>;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        let depth = self.open_node(name)?;
+
+        Ok(NodeSerializer {
+            serializer: self,
+            fields,
+            layout: Layout::Items { keyed: true },
+            end: End::Element(depth),
+            some: 0,
+        })
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        let depth = self.open_node(name)?;
+
+        Ok(NodeSerializer {
+            serializer: self,
+            fields,
+            layout: Layout::Items { keyed: false },
+            end: End::Leaf(depth),
+            some: 0,
+        })
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        if self.depth.is_none() {
+            return Err(Error::Stream("opcodes can't be serialized as values"));
+        }
+
+        let start = self.output.len();
+
+        self.write_name("@", name)?;
+        self.output.push(' ');
+
+        Ok(NodeSerializer {
+            serializer: self,
+            fields,
+            layout: Layout::Body(None),
+            end: End::Attr(start),
+            some: 0,
+        })
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name);
+
+        let some = core::mem::take(&mut self.some);
+
+        Ok(NodeSerializer {
+            serializer: self,
+            fields,
+            layout: Layout::Body(None),
+            end: End::Value,
+            some,
+        })
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant_index);
+
+        self.begin_value();
+
+        if fields == 0 {
+            self.write_name("", variant)?;
+
+            return Ok(NodeSerializer {
+                serializer: self,
+                fields,
+                layout: Layout::Body(Some(Body::Unit)),
+                end: End::Value,
+                some: 0,
+            });
+        }
+
+        self.output.push('(');
+        self.write_name("", variant)?;
+
+        Ok(NodeSerializer {
+            serializer: self,
+            fields,
+            layout: Layout::Items { keyed: false },
+            end: End::Variant,
+            some: 0,
+        })
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        let _ = len;
+
+        self.begin_value();
+        self.output.push('[');
+
+        Ok(SeqSerializer {
+            serializer: self,
+            first: true,
+        })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        let _ = len;
+
+        self.begin_value();
+        self.output.push('{');
+
+        Ok(MapSerializer {
+            serializer: self,
+            first: true,
+        })
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        self.begin_value();
+        _ = write!(self.output, "{}", value);
+        Ok(())
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        self.begin_value();
+        self.write_string(value);
+        Ok(())
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Byte, value);
+        Ok(())
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ubyte, value);
+        Ok(())
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Short, value);
+        Ok(())
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ushort, value);
+        Ok(())
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Int, value);
+        Ok(())
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Uint, value);
+        Ok(())
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Long, value);
+        Ok(())
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ulong, value);
+        Ok(())
+    }
+
+    fn serialize_int128(self, value: i128) -> Result<(), Self::Error> {
+        self.write_number(Kind::Int128, value);
+        Ok(())
+    }
+
+    fn serialize_uint128(self, value: u128) -> Result<(), Self::Error> {
+        self.write_number(Kind::Uint128, value);
+        Ok(())
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f32(value)?;
+
+        self.write_float(Kind::Float, f64::from(value), value);
+        Ok(())
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f64(value)?;
+
+        self.write_float(Kind::Double, value, value);
+        Ok(())
+    }
+
+    fn serialize_with_precision<T>(self, decimals: u32, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let format = mem::replace(&mut self.float_format, FloatFormat::Decimals(decimals));
+        let result = value.serialize(&mut *self);
+
+        self.float_format = format;
+
+        result
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        for _ in 0..self.some {
+            self.output.push('(');
+            self.output.push_str(SOME);
+            self.output.push(' ');
+        }
+
+        self.output.push_str(NIL);
+
+        for _ in 0..self.some {
+            self.output.push(')');
+        }
+
+        self.some = 0;
+
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.some += 1;
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        self.begin_value();
+        self.output.push('(');
+        self.output.push_str(VAR);
+        self.output.push(' ');
+
+        match path {
+            Path::Named(name) => self.write_string(name),
+            Path::Index(index) => _ = write!(self.output, "{}", index),
+        }
+
+        self.output.push(' ');
+        self.output.push_str(target_name(target));
+        self.output.push(')');
+
+        Ok(())
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        if !self.attrs.is_empty() {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        match self.depth {
+            Some(depth) if depth > 0 => self.depth = Some(depth - 1),
+            Some(_) => return Err(Error::Stream("pop without an open element")),
+            None => return Err(Error::Stream("opcodes can't be serialized as values")),
+        }
+
+        self.output.push(')');
+
+        Ok(())
+    }
+}
+
+/// Policies of the deserializer.
+#[derive(Clone, Copy, Default)]
+struct Options {
+    numeric_coercion: NumericCoercion,
+    non_finite: NonFinite,
+    unknown_names: UnknownNames,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+/// A deserializer of s-expression documents.
+///
+/// Opcodes are read in stream order, [`deserialize_opcode`](de::Deserializer::deserialize_opcode) returns the attrs
+/// and the node of a node list, or the `Pop` of an element at the end of its children.
+pub struct Deserializer<'a> {
+    input: &'a str,
+    /// The events of the document, once opcodes are read.
+    events: Option<vec::IntoIter<Event<'a>>>,
+    max_depth: usize,
+    started: bool,
+    options: Options,
+}
+
+impl<'a> Deserializer<'a> {
+    /// Create a deserializer of the s-expression `text`.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            input: text,
+            events: None,
+            max_depth: de::MAX_ELEMENT_DEPTH,
+            started: false,
+            options: Options::default(),
+        }
+    }
+
+    /// Set the policy for numbers of another kind than the expected one, the default is [`NumericCoercion::Strict`].
+    pub fn with_numeric_coercion(mut self, policy: NumericCoercion) -> Self {
+        self.options.numeric_coercion = policy;
+        self
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.options.non_finite = policy;
+        self
+    }
+
+    /// Set the policy for nodes with an unknown name, the default is [`UnknownNames::Error`].
+    pub fn with_unknown_names(mut self, policy: UnknownNames) -> Self {
+        self.options.unknown_names = policy;
+        self
+    }
+
+    /// Set the hook of numbers read from strings.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.options.number_policy = Some(policy);
+        self
+    }
+
+    /// Set the max nesting depth of elements, the default is [`MAX_ELEMENT_DEPTH`](de::MAX_ELEMENT_DEPTH).
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+/// The error of values read from the document or a node.
+const NOT_VALUES: &str = "values can only be deserialized from the fields of nodes";
+
+macro_rules! reject_deserialize {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let _ = visitor;
+
+                Err(Error::Stream(NOT_VALUES))
+            }
+        )+
+    };
+}
+
+macro_rules! reject_deserialize_node {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, type_id: usize, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let _ = (type_id, name, visitor);
+
+                Err(Error::Stream(NOT_VALUES))
+            }
+        )+
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn numeric_coercion(&self) -> NumericCoercion {
+        self.options.numeric_coercion
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.options.number_policy
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.options.non_finite
+    }
+
+    fn unknown_names(&self) -> UnknownNames {
+        self.options.unknown_names
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.started {
+            self.events = Some(Parser::document(self.input, self.max_depth)?.into_iter());
+            self.started = true;
+        }
+
+        let Some(events) = self.events.as_mut() else {
+            return Ok(None);
+        };
+
+        let node = match events.next() {
+            Some(Event::Node(node)) => node,
+            Some(Event::End) => return Ok(Some(vec![visitor.visit_pop::<Error>()?])),
+            Some(Event::Value(item)) => {
+                return Err(Error::Node(format!(
+                    "expect a node list, got `{}`",
+                    item.raw
+                )));
+            }
+            None => {
+                self.events = None;
+                return Ok(None);
+            }
+        };
+
+        let name = match node.items.first() {
+            Some(Item {
+                value: Value::Symbol(name),
+                ..
+            }) => *name,
+            _ => {
+                return Err(Error::Node(format!(
+                    "`{}` isn't headed by a name",
+                    node.raw
+                )));
+            }
+        };
+
+        let element = visitor.is_element(name);
+
+        if !element && !visitor.is_leaf(name) {
+            if self.options.unknown_names == UnknownNames::Skip {
+                // skip the event of the list and the nodes of its positional lists.
+                let mut depth = 1;
+
+                while depth > 0 {
+                    match events.next() {
+                        Some(Event::Node(_)) => depth += 1,
+                        Some(Event::End) => depth -= 1,
+                        Some(Event::Value(_)) => {}
+                        None => break,
+                    }
+                }
+
+                return Ok(Some(vec![]));
+            }
+
+            return Err(de::Error::UnknownTypeName(name.to_string()).into());
+        }
+
+        let list = if element {
+            // the positional lists of an element are its children, other positional items are invalid.
+            if let Some(item) = Items::positional(&node.items[1..]) {
+                return Err(Error::Node(format!(
+                    "expect a node list, got `{}`",
+                    item.raw
+                )));
+            }
+
+            node.items
+        } else {
+            node.into_items(events)?
+        };
+
+        let items = Items::new(&list[1..], element)?;
+
+        let attrs = NodeAttrs {
+            name,
+            attrs: items.attrs.clone(),
+            node: &items,
+            options: self.options,
+        };
+
+        visitor.visit_opcode_with_attrs(name, attrs).map(Some)
+    }
+
+    reject_deserialize_node!(
+        deserialize_element,
+        deserialize_leaf,
+        deserialize_attr,
+        deserialize_data,
+        deserialize_enum
+    );
+
+    reject_deserialize!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// The attrs and the node of a node list.
+struct NodeAttrs<'a, 'b> {
+    name: &'a str,
+    node: &'b Items<'a, 'b>,
+    attrs: Vec<(&'a str, &'b Item<'a>)>,
+    options: Options,
+}
+
+impl<'de> AttrsNodeAccess<'de> for NodeAttrs<'de, '_> {
+    type Error = Error;
+
+    fn attrs_with_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attrs.iter().map(|(name, item)| (*name, item.raw))
+    }
+
+    fn remove_attr(&mut self, index: usize) {
+        self.attrs.remove(index);
+    }
+
+    fn deserialize_attr<V>(&mut self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // the generated code reads the node itself as the last attr.
+        if name == self.name {
+            return visitor.visit_opcode_with(
+                name,
+                NodeDeserializer {
+                    items: self.node,
+                    options: self.options,
+                },
+            );
+        }
+
+        let (_, item) = self
+            .attrs
+            .iter()
+            .find(|(attr, _)| *attr == name)
+            .ok_or_else(|| Error::MissingAttr(name.to_string()))?;
+
+        visitor.visit_opcode_with(name, ValueDeserializer::new(&item.value, self.options))
+    }
+
+    fn deserialize_node<V>(self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_opcode_with(
+            name,
+            NodeDeserializer {
+                items: self.node,
+                options: self.options,
+            },
+        )
+    }
+}
+
+/// A deserializer of the fields of an element or leaf, the items of its list.
+struct NodeDeserializer<'a, 'b> {
+    items: &'b Items<'a, 'b>,
+    options: Options,
+}
+
+impl<'de> NodeDeserializer<'de, '_> {
+    fn visit_node<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_node(FieldsAccess::Items {
+            items: self.items,
+            options: self.options,
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for NodeDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn numeric_coercion(&self) -> NumericCoercion {
+        self.options.numeric_coercion
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.options.number_policy
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.options.non_finite
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        Err(Error::Stream("opcodes can't be nested in a node"))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    reject_deserialize_node!(deserialize_attr, deserialize_data, deserialize_enum);
+
+    reject_deserialize!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// A deserializer of a parsed value.
+#[derive(Clone, Copy)]
+struct ValueDeserializer<'a, 'b> {
+    value: &'b Value<'a>,
+    options: Options,
+}
+
+impl<'de, 'b> ValueDeserializer<'de, 'b> {
+    fn new(value: &'b Value<'de>, options: Options) -> Self {
+        Self { value, options }
+    }
+
+    fn unexpect<T>(&self) -> Result<T, Error> {
+        Err(de::Error::Unexpect(self.value.kind()).into())
+    }
+
+    fn visit_node<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_node(FieldsAccess::Body {
+            value: self.value,
+            options: self.options,
+        })
+    }
+}
+
+macro_rules! deserialize_int {
+    ($method:ident, $ty:ty, $visit:ident) => {
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.value {
+                Value::Number(text) if !text.contains(['.', 'e', 'E']) => {
+                    visitor.$visit(text.parse::<$ty>().map_err(de::Error::from)?)
+                }
+                Value::Number(text) => {
+                    visitor.visit_double(text.parse::<f64>().map_err(de::Error::from)?)
+                }
+                // rewritten by a number policy.
+                Value::String(text) => visitor.visit_string(&text),
+                _ => self.unexpect(),
+            }
+        }
+    };
+}
+
+macro_rules! deserialize_float {
+    ($method:ident, $ty:ty, $visit:ident) => {
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.value {
+                Value::Number(text) => {
+                    visitor.$visit(text.parse::<$ty>().map_err(de::Error::from)?)
+                }
+                // non-finite tokens.
+                Value::Symbol(text) => visitor.visit_string(text),
+                // rewritten by a number policy.
+                Value::String(text) => visitor.visit_string(&text),
+                _ => self.unexpect(),
+            }
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn numeric_coercion(&self) -> NumericCoercion {
+        self.options.numeric_coercion
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.options.number_policy
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.options.non_finite
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        Err(Error::Stream("opcodes can't be read from a value"))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        match self.value {
+            Value::Symbol(variant) => visitor.visit_enum_with(
+                variant,
+                FieldsAccess::Body {
+                    value: &NIL_VALUE,
+                    options: self.options,
+                },
+            ),
+            Value::List(items) => {
+                let Some((
+                    Item {
+                        value: Value::Symbol(variant),
+                        ..
+                    },
+                    items,
+                )) = items.split_first()
+                else {
+                    return self.unexpect();
+                };
+
+                let items = Items::new(items, false)?;
+
+                if !items.attrs.is_empty() {
+                    return Err(Error::Node(format!(
+                        "attrs can't be applied to variant `{}`",
+                        variant
+                    )));
+                }
+
+                visitor.visit_enum_with(
+                    variant,
+                    FieldsAccess::Items {
+                        items: &items,
+                        options: self.options,
+                    },
+                )
+            }
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Vector(items) => visitor.visit_seq(ItemsAccess {
+                items: items.iter(),
+                options: self.options,
+            }),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Map(items) => visitor.visit_map(ItemsAccess {
+                items: items.iter(),
+                options: self.options,
+            }),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Symbol(NIL) => visitor.visit_none(),
+            Value::List(items) if items.len() == 2 && items[0].value == Value::Symbol(SOME) => {
+                visitor.visit_some(Self::new(&items[1].value, self.options))
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let Value::List(items) = self.value else {
+            return visitor.visit_constant(self);
+        };
+
+        let [head, path, target] = items.as_slice() else {
+            return visitor.visit_constant(self);
+        };
+
+        if head.value != Value::Symbol(VAR) {
+            return visitor.visit_constant(self);
+        }
+
+        let path = match &path.value {
+            Value::String(name) => Path::Named(name.to_string()),
+            Value::Number(index) => Path::Index(index.parse().map_err(de::Error::from)?),
+            _ => return Err(de::Error::Unexpect(Kind::Variable).into()),
+        };
+
+        let target = [
+            Target::Register,
+            Target::ForeachItem,
+            Target::ForeachIndex,
+            Target::Range,
+        ]
+        .into_iter()
+        .find(|value| target.value == Value::Symbol(target_name(value)))
+        .ok_or(de::Error::Unexpect(Kind::Variable))?;
+
+        visitor.visit_variable(path, target)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(Cow::Borrowed(value)) => visitor.visit_borrowed_str(value),
+            Value::String(value) => visitor.visit_string(value),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(value) => visitor.visit_raw(value),
+            _ => self.unexpect(),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Symbol("true") => visitor.visit_bool(true),
+            Value::Symbol("false") => visitor.visit_bool(false),
+            _ => self.unexpect(),
+        }
+    }
+
+    deserialize_int!(deserialize_byte, i8, visit_byte);
+    deserialize_int!(deserialize_ubyte, u8, visit_ubyte);
+    deserialize_int!(deserialize_short, i16, visit_short);
+    deserialize_int!(deserialize_ushort, u16, visit_ushort);
+    deserialize_int!(deserialize_int, i32, visit_int);
+    deserialize_int!(deserialize_uint, u32, visit_uint);
+    deserialize_int!(deserialize_long, i64, visit_long);
+    deserialize_int!(deserialize_ulong, u64, visit_ulong);
+    deserialize_int!(deserialize_int128, i128, visit_int128);
+    deserialize_int!(deserialize_uint128, u128, visit_uint128);
+    deserialize_float!(deserialize_float, f32, visit_float);
+    deserialize_float!(deserialize_double, f64, visit_double);
+}
+
+/// Access to the fields of a node, see the [module docs](self) for their layouts.
+enum FieldsAccess<'a, 'b> {
+    /// The fields of an attr or data.
+    Body {
+        value: &'b Value<'a>,
+        options: Options,
+    },
+    /// The fields of an element, a leaf or an enum variant.
+    Items {
+        items: &'b Items<'a, 'b>,
+        options: Options,
+    },
+}
+
+impl<'de> NodeAccess<'de> for FieldsAccess<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_field<T>(
+        &mut self,
+        ty: &str,
+        index: usize,
+        field_name: Option<&str>,
+    ) -> Result<T::Value, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let (value, options) = match *self {
+            FieldsAccess::Body { value, options } => {
+                let field = match (value, field_name) {
+                    (Value::List(items), Some(name)) => keyed(items, name),
+                    (Value::Vector(items), None) => items.get(index).map(|item| &item.value),
+                    (value, None) if index == 0 => Some(value),
+                    _ => None,
+                };
+
+                (field, options)
+            }
+            FieldsAccess::Items { items, options } => {
+                let field = match field_name {
+                    Some(name) => items.get(name),
+                    None => items
+                        .positional
+                        .get(index)
+                        .copied()
+                        .or_else(|| items.get(&index.to_string())),
+                };
+
+                (field, options)
+            }
+        };
+
+        match value {
+            Some(value) => T::deserialize(ValueDeserializer::new(value, options)),
+            // missing fields are read as `nil`, so optional fields can be omitted.
+            None => T::deserialize(ValueDeserializer::new(&NIL_VALUE, options)).map_err(|_| {
+                Error::MissingField(
+                    ty.to_string(),
+                    field_name.map_or_else(|| index.to_string(), ToString::to_string),
+                )
+            }),
+        }
+    }
+}
+
+/// Access to the items of a vector, or the keys and values of a map.
+struct ItemsAccess<'a, 'b> {
+    items: slice::Iter<'b, Item<'a>>,
+    options: Options,
+}
+
+impl<'de> ItemsAccess<'de, '_> {
+    fn next<T>(&mut self) -> Result<Option<T::Value>, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.items
+            .next()
+            .map(|item| T::deserialize(ValueDeserializer::new(&item.value, self.options)))
+            .transpose()
+    }
+}
+
+impl<'de> SeqAccess<'de> for ItemsAccess<'de, '_> {
+    type Error = Error;
+
+    fn next_item<T>(&mut self) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.next::<T>()
+    }
+}
+
+impl<'de> MapAccess<'de> for ItemsAccess<'de, '_> {
+    type Error = Error;
+
+    fn next_key<K>(&mut self) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: Deserialize<'de>,
+    {
+        self.next::<K>()
+    }
+
+    fn next_value<V>(&mut self) -> Result<V::Value, Self::Error>
+    where
+        V: Deserialize<'de>,
+    {
+        // the parser checks that maps have a value for every key.
+        self.next::<V>()?
+            .ok_or_else(|| de::Error::Unexpect(Kind::Map).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        borrow::Cow,
+        collections::BTreeMap,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{
+        Error, Options, Parser, Serializer, Value, ValueDeserializer, value_from_str,
+        value_to_string,
+    };
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::{
+            de::{Deserialize, Kind},
+            float::{FloatFormat, NonFinite},
+            number::NumberPolicy,
+            ser::{Precision, Serialize},
+        },
+    };
+
+    #[test]
+    fn test_values() {
+        let map = BTreeMap::from([(1u32, vec![Some(1.5f32), None]), (2, vec![])]);
+        let text = value_to_string(&map).unwrap();
+
+        assert_eq!(text, "{1 [1.5 nil] 2 []}");
+        assert_eq!(
+            value_from_str::<BTreeMap<u32, Vec<Option<f32>>>>(&text),
+            Ok(map)
+        );
+
+        let text = "a \"b\"\n\u{1}é";
+        let sexpr = value_to_string(&text.to_string()).unwrap();
+
+        assert_eq!(sexpr, r#""a \"b\"\n\u{1}é""#);
+        assert_eq!(value_from_str::<String>(&sexpr), Ok(text.to_string()));
+
+        assert_eq!(value_to_string(&f64::NEG_INFINITY).unwrap(), "-INF");
+        assert_eq!(value_from_str::<f64>("-INF"), Ok(f64::NEG_INFINITY));
+        assert_eq!(value_from_str::<bool>(" true ; comment"), Ok(true));
+    }
+
+    #[test]
+    fn test_number_format() {
+        struct Comma;
+
+        impl NumberPolicy for Comma {
+            fn parse<'a>(&self, kind: Kind, text: &'a str) -> Cow<'a, str> {
+                let _ = kind;
+                Cow::Owned(text.trim_end_matches("px").replace(',', "."))
+            }
+
+            fn print(&self, kind: Kind, text: &str) -> Option<String> {
+                matches!(kind, Kind::Float | Kind::Double).then(|| text.replace('.', ","))
+            }
+        }
+
+        let mut serializer = Serializer::value(NonFinite::default())
+            .with_float_format(FloatFormat::Decimals(2))
+            .with_number_policy(&Comma);
+
+        (vec![1.2345f64, 2.0], 3u8)
+            .serialize(&mut serializer)
+            .unwrap();
+
+        assert_eq!(serializer.output, r#"[["1,23" "2"] 3]"#);
+
+        let value = Parser::parse_value(r#"[["1,23" "2px"] "3px"]"#).unwrap();
+        let options = Options {
+            number_policy: Some(&Comma),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            <(Vec<f64>, u8)>::deserialize(ValueDeserializer::new(&value, options)),
+            Ok((vec![1.23, 2.0], 3))
+        );
+
+        assert_eq!(
+            value_to_string(&(Precision(1, &vec![1.26f32]), 1.26f32)).unwrap(),
+            "[[1.3] 1.26]"
+        );
+    }
+
+    #[test]
+    fn test_nested_option() {
+        for value in [None, Some(None), Some(Some(None)), Some(Some(Some(7)))] {
+            let text = value_to_string(&value).unwrap();
+
+            assert_eq!(
+                value_from_str::<Option<Option<Option<i32>>>>(&text),
+                Ok(value)
+            );
+        }
+
+        assert_eq!(
+            value_to_string(&Some(Option::<i32>::None)).unwrap(),
+            "($some nil)"
+        );
+    }
+
+    #[test]
+    fn test_variable() {
+        let value = Variable::<i32>::Reference {
+            path: Path::Named("w".to_string()),
+            target: Target::ForeachItem,
+        };
+
+        let text = value_to_string(&value).unwrap();
+
+        assert_eq!(text, r#"($var "w" foreachItem)"#);
+        assert_eq!(value_from_str::<Variable<i32>>(&text), Ok(value));
+        assert_eq!(
+            value_from_str::<Variable<i32>>("5"),
+            Ok(Variable::Constant(5))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Parser::parse_value("(a :b -1 @c)").map(|value| matches!(value, Value::List(_))),
+            Ok(true)
+        );
+        assert_eq!(
+            value_from_str::<Vec<i32>>("[1 2"),
+            Err(Error::Syntax(4, "unterminated list"))
+        );
+        assert_eq!(
+            value_from_str::<Vec<i32>>("[1 2)"),
+            Err(Error::Syntax(4, "mismatched closing bracket"))
+        );
+        assert_eq!(
+            value_from_str::<i32>("1 2"),
+            Err(Error::Syntax(2, "trailing characters"))
+        );
+        assert!(value_from_str::<BTreeMap<i32, i32>>("{1}").is_err());
+        assert!(value_from_str::<u8>("256").is_err());
+        assert!(Parser::parse_value(&"(".repeat(300)).is_err());
+        assert!(Parser::document("(a (a (a)))", 3).is_ok());
+        assert_eq!(
+            Parser::document("(a (a (a)))", 2).map(|_| ()),
+            Err(Error::Syntax(6, "elements are nested too deeply"))
+        );
+
+        struct Pop;
+
+        impl Serialize for Pop {
+            fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+            where
+                S: crate::rt::serde::ser::Serializer,
+            {
+                serializer.serialize_pop()
+            }
+        }
+
+        assert_eq!(
+            Pop.serialize(&mut Serializer::new()),
+            Err(Error::Stream("pop without an open element"))
+        );
+        assert!(value_to_string(&Pop).is_err());
+        assert_eq!(Serializer::new().finish(), Ok("".to_string()));
+    }
+}
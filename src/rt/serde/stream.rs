@@ -0,0 +1,464 @@
+//! Asynchronous streaming of [`binary`] documents over tokio's [`AsyncRead`] and [`AsyncWrite`].
+//!
+//! [`AsyncSerializer`] writes every value as soon as it is serialized and [`AsyncDeserializer`] reads a value as
+//! soon as its bytes are received, so a service can send or receive an opcode document without blocking and
+//! without holding the whole document in memory. The bytes are the same as the ones of [`binary::to_bytes`].
+//!
+//! ```ignore
+//! let mut serializer = AsyncSerializer::new(writer);
+//!
+//! for opcode in &opcodes {
+//!     serializer.serialize(opcode).await?;
+//! }
+//!
+//! serializer.finish().await?;
+//!
+//! let mut deserializer = AsyncDeserializer::new(reader).await?;
+//!
+//! while let Some(batch) = deserializer.next::<Opcode>().await? {
+//!     // ...
+//! }
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::rt::serde::{
+    binary::{self, Deserializer, Error},
+    de::{self, DeserializeOwned},
+    float::NonFinite,
+    ser::Serialize,
+};
+
+/// The number of bytes read from the source at a time.
+const CHUNK: usize = 8 * 1024;
+
+/// Serialize `opcodes` as a binary document written to `writer`.
+pub async fn to_writer<'a, T, W>(
+    opcodes: impl IntoIterator<Item = &'a T>,
+    writer: W,
+) -> Result<W, Error>
+where
+    T: Serialize + 'a,
+    W: AsyncWrite + Unpin,
+{
+    let mut serializer = AsyncSerializer::new(writer);
+
+    for opcode in opcodes {
+        serializer.serialize(opcode).await?;
+    }
+
+    serializer.finish().await
+}
+
+/// Deserialize the opcodes of a binary document read from `reader`.
+pub async fn from_reader<T, R>(reader: R) -> Result<Vec<T>, Error>
+where
+    T: DeserializeOwned<Owned = Option<Vec<T>>>,
+    R: AsyncRead + Unpin,
+{
+    let mut deserializer = AsyncDeserializer::new(reader).await?;
+    let mut opcodes = vec![];
+
+    while let Some(batch) = deserializer.next::<T>().await? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Writes a binary document to an [`AsyncWrite`], the header is written with the first value.
+pub struct AsyncSerializer<W> {
+    writer: W,
+    serializer: binary::Serializer,
+    written: usize,
+}
+
+impl<W> AsyncSerializer<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Create a serializer that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            serializer: binary::Serializer::new(),
+            written: 0,
+        }
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.serializer = self.serializer.with_non_finite(policy);
+        self
+    }
+
+    /// Write the schema version of the document to the header, see [`binary::Serializer::with_schema_version`].
+    pub fn with_schema_version(mut self, version: &str) -> Self {
+        self.serializer = self.serializer.with_schema_version(version);
+        self
+    }
+
+    /// Serialize `value`, e.g. an opcode, and write it.
+    pub async fn serialize<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.serializer)?;
+
+        self.write().await
+    }
+
+    /// Flush the writer.
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        self.writer
+            .flush()
+            .await
+            .map_err(|err| Error::Io(self.written, err.kind()))
+    }
+
+    /// Write the header if no value is written, flush and return the writer.
+    pub async fn finish(mut self) -> Result<W, Error> {
+        self.write().await?;
+        self.flush().await?;
+
+        Ok(self.writer)
+    }
+
+    async fn write(&mut self) -> Result<(), Error> {
+        let bytes = self.serializer.take();
+
+        self.writer
+            .write_all(&bytes)
+            .await
+            .map_err(|err| Error::Io(self.written, err.kind()))?;
+
+        self.written += bytes.len();
+
+        Ok(())
+    }
+}
+
+/// Reads a binary document from an [`AsyncRead`].
+///
+/// The received bytes are buffered until a whole value is available, so only the bytes of the value being read are
+/// kept in memory.
+pub struct AsyncDeserializer<R> {
+    reader: R,
+    buf: Vec<u8>,
+    start: usize,
+    /// The position in the document of `buf[start]`.
+    pos: usize,
+    eof: bool,
+    non_finite: NonFinite,
+    schema_version: Option<String>,
+}
+
+impl<R> AsyncDeserializer<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Create a deserializer of the binary document read from `reader`, fails if the header is invalid.
+    pub async fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = vec![0; 4];
+
+        match reader.read_exact(&mut header).await {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Err(Error::Magic);
+            }
+            Err(err) => return Err(Error::Io(0, err.kind())),
+        }
+
+        // the schema version is read byte by byte, so no byte after the header is consumed.
+        if header[3] == binary::VERSION {
+            loop {
+                let pos = header.len();
+
+                match Deserializer::new(&header) {
+                    Err(Error::Eof(_)) => {}
+                    _ => break,
+                }
+
+                header.push(reader.read_u8().await.map_err(|err| {
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                        Error::Eof(pos)
+                    } else {
+                        Error::Io(pos, err.kind())
+                    }
+                })?);
+            }
+        }
+
+        let schema_version = Deserializer::new(&header)?
+            .schema_version()
+            .map(ToString::to_string);
+
+        Ok(Self {
+            reader,
+            buf: vec![],
+            start: 0,
+            pos: header.len(),
+            eof: false,
+            non_finite: NonFinite::default(),
+            schema_version,
+        })
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Returns the schema version written to the header, `None` if the document doesn't have one.
+    pub fn schema_version(&self) -> Option<&str> {
+        self.schema_version.as_deref()
+    }
+
+    /// Check the schema version of the document with `check`, see [`Deserializer::with_schema_check`].
+    pub fn with_schema_check<F>(self, check: F) -> Result<Self, Error>
+    where
+        F: FnOnce(&str) -> Result<(), de::Error>,
+    {
+        if let Some(version) = &self.schema_version {
+            check(version)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Read the next opcodes, returns `None` at the end of the document.
+    pub async fn next<T>(&mut self) -> Result<Option<Vec<T>>, Error>
+    where
+        T: DeserializeOwned<Owned = Option<Vec<T>>>,
+    {
+        if self.is_end().await? {
+            return Ok(None);
+        }
+
+        self.deserialize::<T>().await
+    }
+
+    /// Read a single value, e.g. a data or an enum.
+    pub async fn deserialize<T>(&mut self) -> Result<T::Owned, Error>
+    where
+        T: DeserializeOwned,
+    {
+        loop {
+            let mut deserializer = Deserializer::resume(&self.buf[self.start..], self.pos)
+                .with_non_finite(self.non_finite);
+
+            match T::deserialize(&mut deserializer) {
+                Ok(value) => {
+                    let read = deserializer.position() - self.pos;
+
+                    self.start += read;
+                    self.pos += read;
+
+                    return Ok(value);
+                }
+                // the value is parsed again when more bytes are received.
+                Err(Error::Eof(_)) if !self.eof => self.fill().await?,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns true if the whole document is read.
+    pub async fn is_end(&mut self) -> Result<bool, Error> {
+        while self.start == self.buf.len() && !self.eof {
+            self.fill().await?;
+        }
+
+        Ok(self.start == self.buf.len())
+    }
+
+    /// Returns the reader, the buffered bytes that are not read yet are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read more bytes from the reader, sets `eof` at the end of it.
+    async fn fill(&mut self) -> Result<(), Error> {
+        self.buf.drain(..self.start);
+        self.start = 0;
+
+        let filled = self.buf.len();
+
+        self.buf.resize(filled + CHUNK, 0);
+
+        let read = self.reader.read(&mut self.buf[filled..]).await;
+
+        self.buf
+            .truncate(filled + read.as_ref().map_or(0, |read| *read));
+
+        match read {
+            Ok(0) => self.eof = true,
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(Error::Io(self.pos + filled, err.kind())),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+    use core::{
+        future::Future,
+        pin::{Pin, pin},
+        task::{Context, Poll, Waker},
+    };
+
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use super::{AsyncDeserializer, AsyncSerializer};
+    use crate::rt::serde::binary::{self, Error};
+
+    /// Poll `future` until it is ready, the tests only use in-memory readers and writers.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    /// A reader that returns at most 3 bytes per read and is pending every other poll.
+    struct Chunks<'a> {
+        bytes: &'a [u8],
+        pending: bool,
+    }
+
+    impl AsyncRead for Chunks<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            self.pending = !self.pending;
+
+            if self.pending {
+                return Poll::Pending;
+            }
+
+            let len = buf.remaining().min(self.bytes.len()).min(3);
+
+            buf.put_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = vec![
+            vec!["a".repeat(100), "b".to_string()],
+            vec![],
+            vec!["c".to_string()],
+        ];
+
+        let bytes = block_on(async {
+            let mut serializer = AsyncSerializer::new(vec![]);
+
+            for value in &values {
+                serializer.serialize(value).await.unwrap();
+            }
+
+            serializer.finish().await.unwrap()
+        });
+
+        assert_eq!(bytes, binary::to_bytes(&values).unwrap());
+
+        let read = block_on(async {
+            let mut deserializer = AsyncDeserializer::new(Chunks {
+                bytes: &bytes,
+                pending: false,
+            })
+            .await
+            .unwrap();
+
+            let mut read = vec![];
+
+            while !deserializer.is_end().await.unwrap() {
+                read.push(deserializer.deserialize::<Vec<String>>().await.unwrap());
+            }
+
+            read
+        });
+
+        assert_eq!(read, values);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let bytes = block_on(async {
+            let mut serializer = AsyncSerializer::new(vec![]).with_schema_version("1.2.0");
+
+            serializer.serialize(&7u32).await.unwrap();
+
+            serializer.finish().await.unwrap()
+        });
+
+        assert_eq!(&bytes[..10], b"MLB\x02\x051.2.0");
+
+        let value = block_on(async {
+            let mut deserializer = AsyncDeserializer::new(Chunks {
+                bytes: &bytes,
+                pending: false,
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(deserializer.schema_version(), Some("1.2.0"));
+
+            deserializer.deserialize::<u32>().await.unwrap()
+        });
+
+        assert_eq!(value, 7);
+
+        assert!(matches!(
+            block_on(AsyncDeserializer::new(&bytes[..7])),
+            Err(Error::Eof(7))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            block_on(AsyncDeserializer::new(&b"ML"[..])),
+            Err(Error::Magic)
+        ));
+
+        let bytes = binary::value_to_bytes(&"abc".to_string()).unwrap();
+
+        let result = block_on(async {
+            let mut deserializer = AsyncDeserializer::new(&bytes[..6]).await.unwrap();
+
+            deserializer.deserialize::<String>().await
+        });
+
+        assert_eq!(result, Err(Error::Eof(5)));
+
+        let empty: Vec<Vec<u8>> = vec![];
+        let bytes = block_on(super::to_writer(&empty, vec![])).unwrap();
+
+        assert_eq!(bytes, b"MLB\x01");
+    }
+}
@@ -0,0 +1,2005 @@
+//! A markup format of opcode streams.
+//!
+//! A document is a sequence of xml elements, one for every element or leaf, named by its display name. The
+//! children of an element are nested in it, so `Pop` is implied:
+//!
+//! ```xml
+//! <group fill="none">
+//!     <rect x="1" y="2" paint="{&quot;color&quot;:{&quot;r&quot;:1,&quot;g&quot;:2,&quot;b&quot;:3}}"/>
+//!     <text fill='{"named":{"name":"red"}}'>hello</text>
+//! </group>
+//! ```
+//!
+//! The fields of nodes and the attrs applied to them are written as xml attributes:
+//!
+//! - named fields of nodes are keyed by their names, unnamed fields by `_0`, `_1`, ...; the first unnamed field
+//!   of a leaf is its text content instead;
+//! - named fields of attrs are keyed by their names, so that `apply ... to` stats can find the attr from the
+//!   name of any of its fields; unnamed fields are keyed by the attr name, `fill`, `fill.1`, ...;
+//! - an attr without fields, or whose named fields are all `None`, is written as an empty attr name: `hidden=""`;
+//!   the unnamed fields of an attr are found by the key of the first one, so a first field `None` is written as the
+//!   nil marker `{"$none":null}` instead of being omitted.
+//!
+//! The names of the fields of a node and of the attrs that can be applied to it share one namespace, so they must
+//! be distinct: writing a node fails with [`Error::Collision`] if they overlap, and a field of the node would be read
+//! as a field of the attr.
+//!
+//! Values of xml attributes are written as:
+//!
+//! | value                          | xml                                                     |
+//! |--------------------------------|---------------------------------------------------------|
+//! | `string` and other text forms  | the text, e.g. base64 for `bytes`                       |
+//! | numbers and `bool`             | the number or `true`/`false`, non-finite floats as `NaN`, `INF` or `-INF` |
+//! | enum variants without fields   | the variant name                                        |
+//! | `None`                         | the xml attribute is omitted                            |
+//! | `Some(v)`                      | `v`, nested `None`s as in the json format               |
+//! | other values                   | their [`json`] form                                     |
+//!
+//! The format uses display names, it doesn't work with code generated by `CodeGen::strip_names`.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::mem;
+
+use crate::rt::{
+    opcode::{Path, Target},
+    serde::{
+        de::{
+            self, AttrsNodeAccess, Deserialize, FieldContext, Kind, NodeAccess, UnknownNames,
+            Visitor,
+        },
+        float::{FloatError, FloatFormat, NonFinite, token, write_decimals},
+        json,
+        number::NumberPolicy,
+        ser::{self, Serialize},
+    },
+};
+
+/// The key of the nil marker, `{"$none":null}`, of the first field of an attr of unnamed fields.
+const NONE: &str = "$none";
+
+/// The error of values written outside of nodes.
+const VALUES: &str = "values can only be serialized as the fields of nodes";
+
+/// Error returns by the xml [`Serializer`] and [`Deserializer`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    De(#[from] de::Error),
+
+    #[error(transparent)]
+    Float(#[from] FloatError),
+
+    #[error(transparent)]
+    Json(#[from] json::Error),
+
+    #[error("Invalid xml at {0}, {1}.")]
+    Syntax(usize, &'static str),
+
+    #[error("Invalid opcode stream, {0}.")]
+    Stream(&'static str),
+
+    #[error("Invalid node, {0}.")]
+    Node(String),
+
+    #[error("Xml requires display names, the names of the schema are stripped.")]
+    Unnamed,
+
+    #[error(
+        "Xml attribute `{1}` of `{0}` is written twice, the names of fields and attrs collide."
+    )]
+    Collision(String, String),
+
+    #[error("Missing field `{1}` of `{0}`.")]
+    MissingField(String, String),
+}
+
+impl FieldContext for Error {
+    fn field_context(self, ty: &str, field: &str) -> Self {
+        match self {
+            Error::De(err) => Error::De(err.field_context(ty, field)),
+            Error::Json(err) => Error::Json(err.field_context(ty, field)),
+            // syntax errors already carry their position.
+            err => err,
+        }
+    }
+}
+
+/// Serialize `opcodes` as an xml document.
+pub fn to_string<'a, T>(opcodes: impl IntoIterator<Item = &'a T>) -> Result<String, Error>
+where
+    T: Serialize + 'a,
+{
+    let mut serializer = Serializer::new();
+
+    for opcode in opcodes {
+        opcode.serialize(&mut serializer)?;
+    }
+
+    serializer.finish()
+}
+
+/// Deserialize the opcodes of an xml document.
+pub fn from_str<'de, T>(text: &'de str) -> Result<Vec<T>, Error>
+where
+    T: Deserialize<'de, Value = Option<Vec<T>>>,
+{
+    let mut deserializer = Deserializer::new(text);
+    let mut opcodes = vec![];
+
+    while let Some(batch) = T::deserialize(&mut deserializer)? {
+        opcodes.extend(batch);
+    }
+
+    Ok(opcodes)
+}
+
+/// Returns true if `text` is a json object whose first key is `key`.
+fn is_json_member(text: &str, key: &str) -> bool {
+    text.strip_prefix('{')
+        .and_then(|text| text.trim_start().strip_prefix('"'))
+        .and_then(|text| text.strip_prefix(key))
+        .is_some_and(|text| text.starts_with('"'))
+}
+
+fn write_text(output: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#13;"),
+            c => output.push(c),
+        }
+    }
+}
+
+/// Write an xml attribute, values with `"` are quoted by `'` if possible.
+fn write_attr(output: &mut String, key: &str, value: &str) {
+    let quote = if value.contains('"') && !value.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+
+    output.push(' ');
+    output.push_str(key);
+    output.push('=');
+    output.push(quote);
+
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' if quote == '"' => output.push_str("&quot;"),
+            '\n' => output.push_str("&#10;"),
+            '\r' => output.push_str("&#13;"),
+            '\t' => output.push_str("&#9;"),
+            c => output.push(c),
+        }
+    }
+
+    output.push(quote);
+}
+
+/// A serializer of xml documents.
+///
+/// ```ignore
+/// let mut serializer = Serializer::new();
+///
+/// for opcode in &opcodes {
+///     opcode.serialize(&mut serializer)?;
+/// }
+///
+/// let xml = serializer.finish()?;
+/// ```
+pub struct Serializer {
+    output: String,
+    /// The xml attributes of the attrs applied to the next node.
+    attrs: Vec<(String, String)>,
+    /// The names of the open elements.
+    elements: Vec<String>,
+    /// The start tag of the last element isn't closed, it has no children yet.
+    open: bool,
+    non_finite: NonFinite,
+    float_format: FloatFormat,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer {
+    /// Create a serializer of an opcode stream.
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+            attrs: vec![],
+            elements: vec![],
+            open: false,
+            non_finite: NonFinite::default(),
+            float_format: FloatFormat::default(),
+            number_policy: None,
+        }
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Set the decimals of `float` and `double` values, the default is [`FloatFormat::Shortest`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Set the hook of the text of numbers.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.number_policy = Some(policy);
+        self
+    }
+
+    /// Returns the xml document, fails if an attr isn't followed by its element or leaf, or an element isn't popped.
+    pub fn finish(self) -> Result<String, Error> {
+        if !self.attrs.is_empty() {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        if !self.elements.is_empty() {
+            return Err(Error::Stream("elements aren't popped"));
+        }
+
+        Ok(self.output)
+    }
+
+    /// Start writing an opcode, closes the start tag of the parent element.
+    fn begin(
+        &mut self,
+        name: &str,
+        kind: NodeKind,
+        fields: usize,
+    ) -> Result<NodeSerializer<'_>, Error> {
+        if name.is_empty() {
+            return Err(Error::Unnamed);
+        }
+
+        if self.open {
+            self.output.push('>');
+            self.open = false;
+        }
+
+        Ok(NodeSerializer {
+            name: name.to_string(),
+            kind,
+            fields,
+            named: false,
+            attrs: vec![],
+            content: None,
+            serializer: self,
+        })
+    }
+}
+
+/// The kind of the opcode written by a [`NodeSerializer`].
+#[derive(Clone, Copy, PartialEq)]
+enum NodeKind {
+    Element,
+    Leaf,
+    Attr,
+}
+
+/// Returns by [`Serializer`] to write the fields of a node.
+pub struct NodeSerializer<'a> {
+    serializer: &'a mut Serializer,
+    name: String,
+    kind: NodeKind,
+    fields: usize,
+    /// The node has named fields.
+    named: bool,
+    /// The xml attributes of the fields.
+    attrs: Vec<(String, String)>,
+    /// The text content of a leaf.
+    content: Option<String>,
+}
+
+impl ser::SerializeNode for NodeSerializer<'_> {
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut text = TextSerializer::new(
+            self.serializer.non_finite,
+            self.serializer.float_format,
+            self.serializer.number_policy,
+        );
+
+        value.serialize(&mut text)?;
+
+        self.named |= name.is_some();
+
+        let text = match text.finish()? {
+            Some(text) => text,
+            // keeps the attr, its unnamed fields are found by the key of the first one.
+            None if name.is_none() && self.kind == NodeKind::Attr && index == 0 => {
+                format!("{{\"{}\":null}}", NONE)
+            }
+            None => return Ok(()),
+        };
+
+        let key = match (name, self.kind) {
+            (Some(name), _) => name.to_string(),
+            (None, NodeKind::Leaf) if index == 0 => {
+                self.content = Some(text);
+                return Ok(());
+            }
+            (None, NodeKind::Attr) if index == 0 => self.name.clone(),
+            (None, NodeKind::Attr) => format!("{}.{}", self.name, index),
+            (None, _) => format!("_{}", index),
+        };
+
+        self.attrs.push((key, text));
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Self::Error> {
+        let serializer = self.serializer;
+
+        if self.kind == NodeKind::Attr {
+            // keeps the attr, `apply ... to` stats find it by name.
+            if self.attrs.is_empty() && (self.named || self.fields == 0) {
+                self.attrs.push((self.name.clone(), String::new()));
+            }
+
+            for (key, value) in self.attrs {
+                if serializer.attrs.iter().any(|(name, _)| *name == key) {
+                    return Err(Error::Collision(self.name, key));
+                }
+
+                serializer.attrs.push((key, value));
+            }
+
+            return Ok(());
+        }
+
+        let attrs = mem::take(&mut serializer.attrs);
+
+        if let Some((key, _)) = attrs
+            .iter()
+            .find(|(key, _)| self.attrs.iter().any(|(name, _)| name == key))
+        {
+            return Err(Error::Collision(self.name, key.clone()));
+        }
+
+        serializer.output.push('<');
+        serializer.output.push_str(&self.name);
+
+        for (key, value) in self.attrs.iter().chain(attrs.iter()) {
+            write_attr(&mut serializer.output, key, value);
+        }
+
+        match (self.kind, self.content) {
+            (NodeKind::Leaf, Some(content)) => {
+                serializer.output.push('>');
+                write_text(&mut serializer.output, &content);
+                serializer.output.push_str("</");
+                serializer.output.push_str(&self.name);
+                serializer.output.push('>');
+            }
+            (NodeKind::Leaf, None) => serializer.output.push_str("/>"),
+            _ => {
+                serializer.open = true;
+                serializer.elements.push(self.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! reject_values {
+    ($($method:ident($ty:ty)),+) => {
+        $(
+            fn $method(self, value: $ty) -> Result<(), Self::Error> {
+                let _ = value;
+
+                Err(Error::Stream(VALUES))
+            }
+        )+
+    };
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Error = Error;
+    type SerializeNode = NodeSerializer<'a>;
+    type SerializeSeq = ser::Impossible<Error>;
+    type SerializeMap = ser::Impossible<Error>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.begin(name, NodeKind::Element, fields)
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.begin(name, NodeKind::Leaf, fields)
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = type_id;
+
+        self.begin(name, NodeKind::Attr, fields)
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, fields);
+
+        Err(Error::Stream(VALUES))
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        let _ = (type_id, name, variant, variant_index, fields);
+
+        Err(Error::Stream(VALUES))
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        let _ = len;
+
+        Err(Error::Stream(VALUES))
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        let _ = len;
+
+        Err(Error::Stream(VALUES))
+    }
+
+    reject_values!(
+        serialize_bool(bool),
+        serialize_string(&str),
+        serialize_byte(i8),
+        serialize_ubyte(u8),
+        serialize_short(i16),
+        serialize_ushort(u16),
+        serialize_int(i32),
+        serialize_uint(u32),
+        serialize_long(i64),
+        serialize_ulong(u64),
+        serialize_float(f32),
+        serialize_double(f64)
+    );
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        Err(Error::Stream(VALUES))
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        let _ = (path, target);
+
+        Err(Error::Stream(VALUES))
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        if !self.attrs.is_empty() {
+            return Err(Error::Stream("attrs aren't followed by an element or leaf"));
+        }
+
+        let name = self
+            .elements
+            .pop()
+            .ok_or(Error::Stream("pop without an open element"))?;
+
+        if self.open {
+            self.output.push_str("/>");
+            self.open = false;
+        } else {
+            self.output.push_str("</");
+            self.output.push_str(&name);
+            self.output.push('>');
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a field as the text of an xml attribute or the content of a leaf.
+struct TextSerializer {
+    text: String,
+    /// The serializer of values written in the json form.
+    json: json::Serializer,
+    /// `Some` wrappers of the value.
+    some: usize,
+    /// The value is `None`, the field is omitted.
+    none: bool,
+    /// The value is an enum variant without fields.
+    unit: bool,
+    non_finite: NonFinite,
+    float_format: FloatFormat,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl TextSerializer {
+    fn new(
+        non_finite: NonFinite,
+        float_format: FloatFormat,
+        number_policy: Option<&'static dyn NumberPolicy>,
+    ) -> Self {
+        let json = json::Serializer::value(non_finite).with_float_format(float_format);
+
+        Self {
+            text: String::new(),
+            json: match number_policy {
+                Some(policy) => json.with_number_policy(policy),
+                None => json,
+            },
+            some: 0,
+            none: false,
+            unit: false,
+            non_finite,
+            float_format,
+            number_policy,
+        }
+    }
+
+    /// Returns the text of the value, or `None` if the field is omitted.
+    fn finish(mut self) -> Result<Option<String>, json::Error> {
+        if self.none {
+            return Ok(None);
+        }
+
+        let value = self.json.into_value();
+
+        if self.unit {
+            // the variant name, unquoted.
+            return json::value_from_str::<String>(&value).map(Some);
+        }
+
+        self.text.push_str(&value);
+
+        Ok(Some(self.text))
+    }
+
+    fn write(&mut self, value: impl core::fmt::Display) -> Result<(), json::Error> {
+        // writing to a `String` never fails.
+        _ = core::fmt::Write::write_fmt(&mut self.text, format_args!("{}", value));
+        Ok(())
+    }
+
+    fn write_number(
+        &mut self,
+        kind: Kind,
+        value: impl core::fmt::Display,
+    ) -> Result<(), json::Error> {
+        let start = self.text.len();
+
+        self.write(value)?;
+        self.print_number(kind, start);
+
+        Ok(())
+    }
+
+    /// Rewrite the number written from `start` with the number policy, the text is written unquoted.
+    fn print_number(&mut self, kind: Kind, start: usize) {
+        let Some(text) = self
+            .number_policy
+            .and_then(|policy| policy.print(kind, &self.text[start..]))
+        else {
+            return;
+        };
+
+        self.text.truncate(start);
+        self.text.push_str(&text);
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut TextSerializer {
+    type Error = json::Error;
+    type SerializeNode = json::NodeSerializer<'a>;
+    type SerializeSeq = json::SeqSerializer<'a>;
+    type SerializeMap = json::MapSerializer<'a>;
+
+    fn serialize_el(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        (&mut self.json).serialize_el(type_id, name, fields)
+    }
+
+    fn serialize_leaf(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        (&mut self.json).serialize_leaf(type_id, name, fields)
+    }
+
+    fn serialize_attr(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        (&mut self.json).serialize_attr(type_id, name, fields)
+    }
+
+    fn serialize_data(
+        self,
+        type_id: usize,
+        name: &str,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        (&mut self.json).serialize_data(type_id, name, fields)
+    }
+
+    fn serialize_enum(
+        self,
+        type_id: usize,
+        name: &str,
+        variant: &str,
+        variant_index: usize,
+        fields: usize,
+    ) -> Result<Self::SerializeNode, Self::Error> {
+        self.unit = fields == 0;
+
+        (&mut self.json).serialize_enum(type_id, name, variant, variant_index, fields)
+    }
+
+    fn serialize_seq(self, len: usize) -> Result<Self::SerializeSeq, Self::Error> {
+        (&mut self.json).serialize_seq(len)
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        (&mut self.json).serialize_map(len)
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Self::Error> {
+        self.write(value)
+    }
+
+    fn serialize_string(self, value: &str) -> Result<(), Self::Error> {
+        self.write(value)
+    }
+
+    fn serialize_byte(self, value: i8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Byte, value)
+    }
+
+    fn serialize_ubyte(self, value: u8) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ubyte, value)
+    }
+
+    fn serialize_short(self, value: i16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Short, value)
+    }
+
+    fn serialize_ushort(self, value: u16) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ushort, value)
+    }
+
+    fn serialize_int(self, value: i32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Int, value)
+    }
+
+    fn serialize_uint(self, value: u32) -> Result<(), Self::Error> {
+        self.write_number(Kind::Uint, value)
+    }
+
+    fn serialize_long(self, value: i64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Long, value)
+    }
+
+    fn serialize_ulong(self, value: u64) -> Result<(), Self::Error> {
+        self.write_number(Kind::Ulong, value)
+    }
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn serialize_float(self, value: f32) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f32(value)?;
+
+        match (token(value.into()), self.float_format) {
+            (Some(token), _) => self.write(token),
+            (None, FloatFormat::Shortest) => self.write_number(Kind::Float, value),
+            (None, FloatFormat::Decimals(decimals)) => {
+                let start = self.text.len();
+
+                write_decimals(&mut self.text, value.into(), decimals);
+                self.print_number(Kind::Float, start);
+
+                Ok(())
+            }
+        }
+    }
+
+    fn serialize_double(self, value: f64) -> Result<(), Self::Error> {
+        let value = self.non_finite.check_f64(value)?;
+
+        match (token(value), self.float_format) {
+            (Some(token), _) => self.write(token),
+            (None, FloatFormat::Shortest) => self.write_number(Kind::Double, value),
+            (None, FloatFormat::Decimals(decimals)) => {
+                let start = self.text.len();
+
+                write_decimals(&mut self.text, value, decimals);
+                self.print_number(Kind::Double, start);
+
+                Ok(())
+            }
+        }
+    }
+
+    fn serialize_with_precision<T>(self, decimals: u32, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let format = FloatFormat::Decimals(decimals);
+        let text_format = mem::replace(&mut self.float_format, format);
+        let json_format = self.json.replace_float_format(format);
+        let result = value.serialize(&mut *self);
+
+        self.float_format = text_format;
+        self.json.replace_float_format(json_format);
+
+        result
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        if self.some == 0 {
+            self.none = true;
+            return Ok(());
+        }
+
+        // `Some(None)` can't be told from `None` by omitting the field, written as in the json format.
+        for _ in 0..self.some {
+            self.text.push_str("{\"");
+            self.text.push_str(json::SOME);
+            self.text.push_str("\":");
+        }
+
+        self.text.push_str("null");
+
+        for _ in 0..self.some {
+            self.text.push('}');
+        }
+
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.some += 1;
+        value.serialize(self)
+    }
+
+    fn serialize_variable(self, path: &Path, target: &Target) -> Result<(), Self::Error> {
+        (&mut self.json).serialize_variable(path, target)
+    }
+
+    fn serialize_pop(self) -> Result<(), Self::Error> {
+        Err(json::Error::Stream("opcodes can't be serialized as values"))
+    }
+}
+
+/// A parsed xml element, attribute values and text borrow the source text if possible.
+#[derive(Debug)]
+struct Node<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, Cow<'a, str>)>,
+    /// The text content, `None` for empty-element tags: `<rect/>`.
+    text: Option<Cow<'a, str>>,
+    /// The element has child elements, whose events follow its own.
+    children: bool,
+}
+
+impl<'a> Node<'a> {
+    fn attr(&self, key: &str) -> Option<&Cow<'a, str>> {
+        self.attrs
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// An element of a document or its end, in stream order.
+#[derive(Debug)]
+enum Event<'a> {
+    /// An element, its children follow until the matching [`End`](Self::End).
+    Node(Node<'a>),
+    End,
+}
+
+/// A parser of the xml subset written by [`Serializer`]: elements, attributes, text, character references,
+/// `CDATA` sections, comments and processing instructions.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse the elements of `input` as a flat list of events, so nesting elements doesn't recurse.
+    ///
+    /// Elements deeper than `max_depth` are rejected.
+    fn parse(input: &'a str, max_depth: usize) -> Result<Vec<Event<'a>>, Error> {
+        let mut parser = Parser { input, pos: 0 };
+
+        let mut events = vec![];
+        // the open elements, the index of their event, the element and its text so far.
+        let mut stack: Vec<(usize, Node<'a>, Cow<'a, str>)> = vec![];
+
+        loop {
+            let Some((_, node, text)) = stack.last_mut() else {
+                parser.skip_misc()?;
+
+                if parser.pos == input.len() {
+                    return Ok(events);
+                }
+
+                if !parser.rest().starts_with('<') {
+                    return parser.error("text outside of elements");
+                }
+
+                parser.start_tag(&mut events, &mut stack, max_depth)?;
+                continue;
+            };
+
+            let rest = parser.rest();
+
+            if rest.is_empty() {
+                return parser.error("unclosed element");
+            }
+
+            if rest.starts_with("</") {
+                parser.pos += 2;
+
+                if parser.name()? != node.name {
+                    return parser.error("mismatched end tag");
+                }
+
+                parser.skip_whitespace();
+                parser.expect('>', "expect `>`")?;
+
+                if let Some((event, mut node, text)) = stack.pop() {
+                    node.text = Some(text);
+                    events[event] = Event::Node(node);
+                    events.push(Event::End);
+                }
+            } else if rest.starts_with("<!--") {
+                parser.skip_past("-->", "unterminated comment")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let Some(len) = cdata.find("]]>") else {
+                    return parser.error("unterminated CDATA section");
+                };
+
+                append(text, Cow::Borrowed(&cdata[..len]));
+                parser.pos += "<![CDATA[".len() + len + "]]>".len();
+            } else if rest.starts_with('<') {
+                node.children = true;
+                parser.start_tag(&mut events, &mut stack, max_depth)?;
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+
+                append(text, parser.decode(&rest[..len], parser.pos)?);
+                parser.pos += len;
+            }
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T, Error> {
+        Err(Error::Syntax(self.pos, message))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip `text` and the input before it.
+    fn skip_past(&mut self, text: &str, message: &'static str) -> Result<(), Error> {
+        match self.rest().find(text) {
+            Some(offset) => {
+                self.pos += offset + text.len();
+                Ok(())
+            }
+            None => self.error(message),
+        }
+    }
+
+    /// Skip whitespace, comments and processing instructions.
+    fn skip_misc(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>", "unterminated processing instruction")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->", "unterminated comment")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), Error> {
+        if !self.rest().starts_with(c) {
+            return self.error(message);
+        }
+
+        self.pos += c.len_utf8();
+
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<&'a str, Error> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')))
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            return self.error("expect a name");
+        }
+
+        self.pos += len;
+
+        Ok(&rest[..len])
+    }
+
+    /// Decode the character references of `raw`, which starts at `pos`.
+    fn decode(&self, raw: &'a str, pos: usize) -> Result<Cow<'a, str>, Error> {
+        if !raw.contains('&') {
+            return Ok(Cow::Borrowed(raw));
+        }
+
+        let mut decoded = String::new();
+        let mut rest = raw;
+
+        while let Some(offset) = rest.find('&') {
+            decoded.push_str(&rest[..offset]);
+            rest = &rest[offset + 1..];
+
+            let at = pos + raw.len() - rest.len() - 1;
+
+            let end = rest
+                .find(';')
+                .ok_or(Error::Syntax(at, "unterminated character reference"))?;
+
+            let c = match &rest[..end] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+
+            decoded.push(c.ok_or(Error::Syntax(at, "invalid character reference"))?);
+            rest = &rest[end + 1..];
+        }
+
+        decoded.push_str(rest);
+
+        Ok(Cow::Owned(decoded))
+    }
+
+    /// Parse the start tag of an element, which is pushed to the `stack` of open elements unless it is empty.
+    fn start_tag(
+        &mut self,
+        events: &mut Vec<Event<'a>>,
+        stack: &mut Vec<(usize, Node<'a>, Cow<'a, str>)>,
+        max_depth: usize,
+    ) -> Result<(), Error> {
+        if stack.len() == max_depth {
+            return self.error("elements are nested too deeply");
+        }
+
+        self.expect('<', "expect `<`")?;
+
+        let name = self.name()?;
+        let mut attrs: Vec<(&'a str, Cow<'a, str>)> = vec![];
+
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+
+                events.push(Event::Node(Node {
+                    name,
+                    attrs,
+                    text: None,
+                    children: false,
+                }));
+                events.push(Event::End);
+
+                return Ok(());
+            }
+
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let start = self.pos;
+            let key = self.name()?;
+
+            if attrs.iter().any(|(name, _)| *name == key) {
+                return Err(Error::Syntax(start, "duplicate attribute"));
+            }
+
+            self.skip_whitespace();
+            self.expect('=', "expect `=`")?;
+            self.skip_whitespace();
+
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return self.error("expect a quoted attribute value"),
+            };
+
+            self.pos += 1;
+
+            let rest = self.rest();
+            let Some(len) = rest.find(quote) else {
+                return self.error("unterminated attribute value");
+            };
+
+            if rest[..len].contains('<') {
+                return self.error("`<` in attribute value");
+            }
+
+            attrs.push((key, self.decode(&rest[..len], self.pos)?));
+            self.pos += len + 1;
+        }
+
+        // the event is set when the element is closed.
+        events.push(Event::End);
+        stack.push((
+            events.len() - 1,
+            Node {
+                name,
+                attrs,
+                text: None,
+                children: false,
+            },
+            Cow::Borrowed(""),
+        ));
+
+        Ok(())
+    }
+}
+
+fn append<'a>(text: &mut Cow<'a, str>, segment: Cow<'a, str>) {
+    if text.is_empty() {
+        *text = segment;
+    } else {
+        text.to_mut().push_str(&segment);
+    }
+}
+
+/// A deserializer of xml documents.
+///
+/// Opcodes are read in stream order, [`deserialize_opcode`](de::Deserializer::deserialize_opcode) returns the attrs
+/// and the node of an xml element, or the `Pop` of an element at the end of its children.
+pub struct Deserializer<'a> {
+    input: &'a str,
+    /// The events of the document, once opcodes are read.
+    events: Option<vec::IntoIter<Event<'a>>>,
+    max_depth: usize,
+    started: bool,
+    non_finite: NonFinite,
+    number_policy: Option<&'static dyn NumberPolicy>,
+    unknown_names: UnknownNames,
+}
+
+impl<'a> Deserializer<'a> {
+    /// Create a deserializer of the xml `text`.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            input: text,
+            events: None,
+            max_depth: de::MAX_ELEMENT_DEPTH,
+            started: false,
+            non_finite: NonFinite::default(),
+            number_policy: None,
+            unknown_names: UnknownNames::default(),
+        }
+    }
+
+    /// Set the policy for `NaN` and infinite `float` and `double` values, the default is [`NonFinite::Keep`].
+    pub fn with_non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Set the hook of numbers read from the text of xml attributes and leaves.
+    pub fn with_number_policy(mut self, policy: &'static dyn NumberPolicy) -> Self {
+        self.number_policy = Some(policy);
+        self
+    }
+
+    /// Set the policy for nodes with an unknown name, the default is [`UnknownNames::Error`].
+    pub fn with_unknown_names(mut self, policy: UnknownNames) -> Self {
+        self.unknown_names = policy;
+        self
+    }
+
+    /// Set the max nesting depth of elements, the default is [`MAX_ELEMENT_DEPTH`](de::MAX_ELEMENT_DEPTH).
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+/// The error of values read from the document or a node.
+const NOT_VALUES: &str = "values can only be deserialized from the fields of nodes";
+
+macro_rules! reject_deserialize {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let _ = visitor;
+
+                Err(Error::Stream(NOT_VALUES))
+            }
+        )+
+    };
+}
+
+macro_rules! reject_deserialize_node {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, type_id: usize, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let _ = (type_id, name, visitor);
+
+                Err(Error::Stream(NOT_VALUES))
+            }
+        )+
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.number_policy
+    }
+
+    fn unknown_names(&self) -> UnknownNames {
+        self.unknown_names
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.started {
+            self.events = Some(Parser::parse(self.input, self.max_depth)?.into_iter());
+            self.started = true;
+        }
+
+        let Some(events) = self.events.as_mut() else {
+            return Ok(None);
+        };
+
+        let mut node = match events.next() {
+            Some(Event::Node(node)) => node,
+            Some(Event::End) => return Ok(Some(vec![visitor.visit_pop::<Error>()?])),
+            None => {
+                self.events = None;
+                return Ok(None);
+            }
+        };
+
+        let element = visitor.is_element(node.name);
+
+        if !element && !visitor.is_leaf(node.name) {
+            if self.unknown_names == UnknownNames::Skip {
+                // skip the element, with its children and its end.
+                let mut depth = 1;
+
+                while depth > 0 {
+                    match events.next() {
+                        Some(Event::Node(_)) => depth += 1,
+                        Some(Event::End) => depth -= 1,
+                        None => break,
+                    }
+                }
+
+                return Ok(Some(vec![]));
+            }
+
+            return Err(de::Error::UnknownTypeName(node.name.to_string()).into());
+        }
+
+        if element {
+            if node
+                .text
+                .as_deref()
+                .is_some_and(|text| !text.trim().is_empty())
+            {
+                return Err(Error::Node(format!(
+                    "element `{}` can't have text",
+                    node.name
+                )));
+            }
+
+            node.text = None;
+        } else if node.children {
+            return Err(Error::Node(format!(
+                "leaf `{}` can't have children",
+                node.name
+            )));
+        } else {
+            // the end of a leaf isn't a `Pop`.
+            events.next();
+        }
+
+        let attrs = NodeAttrs {
+            node: &node,
+            attrs: node
+                .attrs
+                .iter()
+                .map(|(key, value)| (*key, value.as_ref()))
+                .collect(),
+            non_finite: self.non_finite,
+            number_policy: self.number_policy,
+        };
+
+        visitor.visit_opcode_with_attrs(node.name, attrs).map(Some)
+    }
+
+    reject_deserialize_node!(
+        deserialize_element,
+        deserialize_leaf,
+        deserialize_attr,
+        deserialize_data,
+        deserialize_enum
+    );
+
+    reject_deserialize!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// The xml attributes of a node, read as the applied attrs and the node itself.
+struct NodeAttrs<'a, 'b> {
+    node: &'b Node<'a>,
+    attrs: Vec<(&'b str, &'b str)>,
+    non_finite: NonFinite,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl<'de> AttrsNodeAccess<'de> for NodeAttrs<'de, '_> {
+    type Error = Error;
+
+    fn attrs_with_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attrs.iter().copied()
+    }
+
+    fn remove_attr(&mut self, index: usize) {
+        self.attrs.remove(index);
+    }
+
+    fn deserialize_attr<V>(&mut self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // the generated code reads the node itself as the last attr.
+        let attr = (name != self.node.name).then_some(name);
+
+        visitor.visit_opcode_with(
+            name,
+            NodeDeserializer {
+                node: self.node,
+                attr,
+                non_finite: self.non_finite,
+                number_policy: self.number_policy,
+            },
+        )
+    }
+
+    fn deserialize_node<V>(self, name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_opcode_with(
+            name,
+            NodeDeserializer {
+                node: self.node,
+                attr: None,
+                non_finite: self.non_finite,
+                number_policy: self.number_policy,
+            },
+        )
+    }
+}
+
+/// A deserializer of a node or of an attr applied to it.
+struct NodeDeserializer<'a, 'b> {
+    node: &'b Node<'a>,
+    /// The name of the attr, `None` for the node itself.
+    attr: Option<&'b str>,
+    non_finite: NonFinite,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl<'de> NodeDeserializer<'de, '_> {
+    fn visit_node<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_node(FieldsAccess {
+            node: self.node,
+            attr: self.attr,
+            non_finite: self.non_finite,
+            number_policy: self.number_policy,
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for NodeDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.number_policy
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        Err(Error::Stream("opcodes can't be nested in a node"))
+    }
+
+    fn deserialize_element<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_leaf<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    fn deserialize_attr<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = (type_id, name);
+
+        self.visit_node(visitor)
+    }
+
+    reject_deserialize_node!(deserialize_data, deserialize_enum);
+
+    reject_deserialize!(
+        deserialize_seq,
+        deserialize_map,
+        deserialize_option,
+        deserialize_variable,
+        deserialize_string,
+        deserialize_raw,
+        deserialize_bool,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+/// Access to the fields of a node or an attr, by their xml attribute keys.
+struct FieldsAccess<'a, 'b> {
+    node: &'b Node<'a>,
+    attr: Option<&'b str>,
+    non_finite: NonFinite,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl<'de> NodeAccess<'de> for FieldsAccess<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_field<T>(
+        &mut self,
+        ty: &str,
+        index: usize,
+        field_name: Option<&str>,
+    ) -> Result<T::Value, Self::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let text = match (field_name, self.attr) {
+            (Some(name), _) => self.node.attr(name),
+            (None, Some(attr)) if index == 0 => self.node.attr(attr),
+            (None, Some(attr)) => self.node.attr(&format!("{}.{}", attr, index)),
+            (None, None) => self.node.attr(&format!("_{}", index)).or(self
+                .node
+                .text
+                .as_ref()
+                .filter(|_| index == 0)),
+        };
+
+        let deserializer = TextDeserializer {
+            text,
+            non_finite: self.non_finite,
+            number_policy: self.number_policy,
+        };
+
+        match text {
+            Some(_) => T::deserialize(deserializer),
+            // missing fields are read as `None`, so optional fields can be omitted.
+            None => T::deserialize(deserializer).map_err(|_| {
+                Error::MissingField(
+                    ty.to_string(),
+                    field_name.map_or_else(|| index.to_string(), ToString::to_string),
+                )
+            }),
+        }
+    }
+}
+
+/// A deserializer of the text of an xml attribute or the content of a leaf.
+#[derive(Clone, Copy)]
+struct TextDeserializer<'a, 'b> {
+    /// The text, `None` if the field is omitted.
+    text: Option<&'b Cow<'a, str>>,
+    non_finite: NonFinite,
+    number_policy: Option<&'static dyn NumberPolicy>,
+}
+
+impl<'de, 'b> TextDeserializer<'de, 'b> {
+    fn text(&self) -> Result<&'b str, Error> {
+        self.text
+            .map(AsRef::as_ref)
+            .ok_or(de::Error::Unexpect(Kind::None).into())
+    }
+
+    /// Returns the deserializer of a value in the json form, decoded text is copied by it.
+    fn json(&self) -> Result<json::Deserializer<'de>, Error> {
+        let deserializer = match self.text {
+            Some(Cow::Borrowed(text)) => json::Deserializer::new(text),
+            _ => json::Deserializer::owned(self.text()?)?,
+        };
+
+        let deserializer = deserializer.with_non_finite(self.non_finite);
+
+        Ok(match self.number_policy {
+            Some(policy) => deserializer.with_number_policy(policy),
+            None => deserializer,
+        })
+    }
+
+    fn visit_text<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.text {
+            Some(Cow::Borrowed(text)) => visitor.visit_borrowed_str(text),
+            _ => visitor.visit_string(self.text()?),
+        }
+    }
+}
+
+macro_rules! deserialize_text {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.visit_text(visitor)
+            }
+        )+
+    };
+}
+
+impl<'de> de::Deserializer<'de> for TextDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn non_finite(&self) -> NonFinite {
+        self.non_finite
+    }
+
+    fn number_policy(&self) -> Option<&'static dyn NumberPolicy> {
+        self.number_policy
+    }
+
+    fn deserialize_opcode<V>(self, visitor: V) -> Result<Option<Vec<V::Value>>, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+
+        Err(Error::Stream("opcodes can't be nested in a node"))
+    }
+
+    reject_deserialize_node!(deserialize_element, deserialize_leaf, deserialize_attr);
+
+    fn deserialize_data<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Ok(self.json()?.deserialize_data(type_id, name, visitor)?)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        type_id: usize,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let text = self.text()?;
+
+        if text.starts_with('{') {
+            return Ok(self.json()?.deserialize_enum(type_id, name, visitor)?);
+        }
+
+        // variants without fields are written unquoted.
+        let quoted = json::value_to_string(&text.to_string())?;
+
+        Ok(json::Deserializer::owned(&quoted)?.deserialize_enum(type_id, name, visitor)?)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Ok(self.json()?.deserialize_seq(visitor)?)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Ok(self.json()?.deserialize_map(visitor)?)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.text {
+            None => visitor.visit_none(),
+            Some(text) if is_json_member(text, NONE) => visitor.visit_none(),
+            Some(text) if is_json_member(text, json::SOME) => {
+                Ok(self.json()?.deserialize_option(visitor)?)
+            }
+            Some(_) => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_variable<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if is_json_member(self.text()?, json::VAR) {
+            return Ok(self.json()?.deserialize_variable(visitor)?);
+        }
+
+        visitor.visit_constant(self)
+    }
+
+    fn deserialize_raw<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_raw(self.text()?)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.text()? {
+            "true" => visitor.visit_bool(true),
+            "false" => visitor.visit_bool(false),
+            text => visitor.visit_string(text),
+        }
+    }
+
+    // numbers are parsed from their text by the visitors.
+    deserialize_text!(
+        deserialize_string,
+        deserialize_byte,
+        deserialize_ubyte,
+        deserialize_short,
+        deserialize_ushort,
+        deserialize_int,
+        deserialize_uint,
+        deserialize_long,
+        deserialize_ulong,
+        deserialize_float,
+        deserialize_double
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        borrow::Cow,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use super::{Deserializer, Error, Event, Parser, Serializer, TextDeserializer, TextSerializer};
+    use crate::rt::{
+        opcode::{Path, Target, Variable},
+        serde::{
+            de::{
+                self, AttrsNodeAccess, Deserialize, Deserializer as _, Kind, MAX_ELEMENT_DEPTH,
+                UnknownNames, Visitor,
+            },
+            float::FloatFormat,
+            number::NumberPolicy,
+            ser::{Precision, Serialize},
+        },
+    };
+
+    fn to_text<T>(value: &T) -> Option<String>
+    where
+        T: Serialize,
+    {
+        let mut serializer = TextSerializer::new(Default::default(), Default::default(), None);
+
+        value.serialize(&mut serializer).unwrap();
+
+        serializer.finish().unwrap()
+    }
+
+    fn from_text<'de, T>(text: Option<&'de str>) -> Result<T::Value, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let text = text.map(Cow::Borrowed);
+
+        T::deserialize(TextDeserializer {
+            text: text.as_ref(),
+            non_finite: Default::default(),
+            number_policy: None,
+        })
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(to_text(&"a\"b".to_string()).as_deref(), Some("a\"b"));
+        assert_eq!(from_text::<String>(Some("a\"b")), Ok("a\"b".to_string()));
+
+        assert_eq!(to_text(&f64::NEG_INFINITY).as_deref(), Some("-INF"));
+        assert_eq!(from_text::<f64>(Some("-INF")), Ok(f64::NEG_INFINITY));
+        assert_eq!(from_text::<bool>(Some("true")), Ok(true));
+
+        assert_eq!(to_text(&vec![1u8, 2]).as_deref(), Some("[1,2]"));
+        assert_eq!(from_text::<Vec<u8>>(Some("[1,2]")), Ok(vec![1, 2]));
+
+        let value = Variable::<i32>::Reference {
+            path: Path::Index(3),
+            target: Target::Range,
+        };
+
+        let text = to_text(&value).unwrap();
+
+        assert_eq!(from_text::<Variable<i32>>(Some(&text)), Ok(value));
+        assert_eq!(
+            from_text::<Variable<i32>>(Some("5")),
+            Ok(Variable::Constant(5))
+        );
+    }
+
+    #[test]
+    fn test_nested_option() {
+        for value in [None, Some(None), Some(Some(None)), Some(Some(Some(7)))] {
+            let text = to_text(&value);
+
+            assert_eq!(
+                from_text::<Option<Option<Option<i32>>>>(text.as_deref()),
+                Ok(value)
+            );
+        }
+
+        assert_eq!(to_text(&Option::<i32>::None), None);
+        assert_eq!(
+            to_text(&Some(Option::<i32>::None)).as_deref(),
+            Some(r#"{"$some":null}"#)
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        struct Comma;
+
+        impl NumberPolicy for Comma {
+            fn parse<'a>(&self, kind: Kind, text: &'a str) -> Cow<'a, str> {
+                let _ = kind;
+                Cow::Owned(text.trim_end_matches("px").replace(',', "."))
+            }
+
+            fn print(&self, kind: Kind, text: &str) -> Option<String> {
+                matches!(kind, Kind::Float | Kind::Double).then(|| text.replace('.', ","))
+            }
+        }
+
+        let mut serializer =
+            TextSerializer::new(Default::default(), FloatFormat::Decimals(2), Some(&Comma));
+
+        1.2345f64.serialize(&mut serializer).unwrap();
+
+        assert_eq!(serializer.finish().unwrap().as_deref(), Some("1,23"));
+
+        let mut serializer =
+            TextSerializer::new(Default::default(), Default::default(), Some(&Comma));
+
+        vec![1.5f32].serialize(&mut serializer).unwrap();
+
+        assert_eq!(serializer.finish().unwrap().as_deref(), Some(r#"["1,5"]"#));
+
+        let text = |text: &'static Cow<'static, str>| TextDeserializer {
+            text: Some(text),
+            non_finite: Default::default(),
+            number_policy: Some(&Comma),
+        };
+
+        assert_eq!(f32::deserialize(text(&Cow::Borrowed("1,5px"))), Ok(1.5));
+        assert_eq!(
+            <Vec<f32>>::deserialize(text(&Cow::Borrowed(r#"["1,5"]"#))),
+            Ok(vec![1.5])
+        );
+
+        assert_eq!(
+            to_text(&Precision(1, &vec![1.26f32])).as_deref(),
+            Some("[1.3]")
+        );
+        assert_eq!(to_text(&Precision(1, &1.26f64)).as_deref(), Some("1.3"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let events = Parser::parse(
+            "<?xml version=\"1.0\"?><a k='&lt;&#x41;&#66;'>x<!-- c --><b/> <![CDATA[&]]></a>",
+            MAX_ELEMENT_DEPTH,
+        )
+        .unwrap();
+
+        let [Event::Node(a), Event::Node(b), Event::End, Event::End] = events.as_slice() else {
+            panic!("{:?}", events);
+        };
+
+        assert_eq!(a.attr("k"), Some(&Cow::from("<AB")));
+        assert_eq!(a.text.as_deref(), Some("x &"));
+        assert!(a.children);
+        assert_eq!(b.name, "b");
+        assert_eq!(b.text, None);
+
+        assert_eq!(
+            Parser::parse("<a>", MAX_ELEMENT_DEPTH).err(),
+            Some(Error::Syntax(3, "unclosed element"))
+        );
+        assert_eq!(
+            Parser::parse("<a k=\"&bad;\"/>", MAX_ELEMENT_DEPTH).err(),
+            Some(Error::Syntax(6, "invalid character reference"))
+        );
+        assert_eq!(
+            Parser::parse("<a/>b", MAX_ELEMENT_DEPTH).err(),
+            Some(Error::Syntax(4, "text outside of elements"))
+        );
+        assert!(Parser::parse("<a><a/></a>", 2).is_ok());
+        assert_eq!(
+            Parser::parse("<a><a><a/></a></a>", 2).err(),
+            Some(Error::Syntax(6, "elements are nested too deeply"))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        struct Pop;
+
+        impl Serialize for Pop {
+            fn serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+            where
+                S: crate::rt::serde::ser::Serializer,
+            {
+                serializer.serialize_pop()
+            }
+        }
+
+        assert_eq!(
+            Pop.serialize(&mut Serializer::new()),
+            Err(Error::Stream("pop without an open element"))
+        );
+        assert!(1i32.serialize(&mut Serializer::new()).is_err());
+        assert_eq!(Serializer::new().finish(), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        /// Reads the names of `group` elements and `rect` leaves.
+        struct Names;
+
+        impl<'de> Visitor<'de> for Names {
+            type Value = String;
+
+            fn is_element(&self, name: &str) -> bool {
+                name == "group"
+            }
+
+            fn is_leaf(&self, name: &str) -> bool {
+                name == "rect"
+            }
+
+            fn visit_opcode_with_attrs<D>(self, name: &str, _: D) -> Result<Vec<String>, D::Error>
+            where
+                D: AttrsNodeAccess<'de>,
+            {
+                Ok(vec![name.to_string()])
+            }
+
+            fn visit_pop<E>(self) -> Result<String, E>
+            where
+                E: From<de::Error>,
+            {
+                Ok("pop".to_string())
+            }
+        }
+
+        fn read(mut deserializer: Deserializer<'_>) -> Result<Vec<String>, Error> {
+            let mut names = vec![];
+
+            while let Some(batch) = (&mut deserializer).deserialize_opcode(Names)? {
+                names.extend(batch);
+            }
+
+            Ok(names)
+        }
+
+        let text = r#"<group><rect/><path><rect/></path></group><rect hidden=""/>"#;
+
+        assert_eq!(
+            read(Deserializer::new(text)),
+            Err(de::Error::UnknownTypeName("path".to_string()).into())
+        );
+        assert_eq!(
+            read(Deserializer::new(text).with_unknown_names(UnknownNames::Skip)),
+            Ok(vec![
+                "group".to_string(),
+                "rect".to_string(),
+                "pop".to_string(),
+                "rect".to_string()
+            ])
+        );
+    }
+}
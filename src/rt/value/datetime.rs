@@ -0,0 +1,352 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Error returned by [`DateTime`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum DateTimeError {
+    #[error("Invalid RFC3339 datetime syntax at {0}.")]
+    Syntax(usize),
+
+    #[error("The {0} of datetime is out of range.")]
+    Range(&'static str),
+}
+
+/// A RFC3339 timestamp: `2024-05-01T12:30:00Z` or `2024-05-01T12:30:00.25+08:00`.
+///
+/// The instant is stored as seconds since the unix epoch, the utc offset is kept to print the value back
+/// in its original local time. Binary formats should encode [`unix_timestamp`](Self::unix_timestamp),
+/// [`nanos`](Self::nanos) and [`offset`](Self::offset) instead of the text.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct DateTime {
+    seconds: i64,
+    nanos: u32,
+    offset: i16,
+}
+
+impl DateTime {
+    /// Create a timestamp from seconds and nanoseconds since the unix epoch, and a utc offset in minutes.
+    pub fn from_unix(seconds: i64, nanos: u32, offset: i16) -> Result<Self, DateTimeError> {
+        if nanos >= 1_000_000_000 {
+            return Err(DateTimeError::Range("nanosecond"));
+        }
+
+        if offset.unsigned_abs() >= 24 * 60 {
+            return Err(DateTimeError::Range("offset"));
+        }
+
+        Ok(Self {
+            seconds,
+            nanos,
+            offset,
+        })
+    }
+
+    /// Returns the seconds since the unix epoch.
+    pub fn unix_timestamp(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Returns the nanoseconds within the second.
+    pub fn nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Returns the utc offset in minutes.
+    pub fn offset(&self) -> i16 {
+        self.offset
+    }
+
+    /// Returns the same instant with the utc offset `offset` in minutes.
+    pub fn with_offset(&self, offset: i16) -> Result<Self, DateTimeError> {
+        Self::from_unix(self.seconds, self.nanos, offset)
+    }
+
+    /// Convert to a [`SystemTime`].
+    #[cfg(feature = "std")]
+    pub fn to_system_time(&self) -> SystemTime {
+        let since_epoch = Duration::new(self.seconds.unsigned_abs(), 0);
+
+        if self.seconds >= 0 {
+            UNIX_EPOCH + since_epoch + Duration::from_nanos(self.nanos as u64)
+        } else {
+            UNIX_EPOCH - since_epoch + Duration::from_nanos(self.nanos as u64)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SystemTime> for DateTime {
+    fn from(value: SystemTime) -> Self {
+        let (seconds, nanos) = match value.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+            Err(err) => {
+                let duration = err.duration();
+                let seconds = -(duration.as_secs() as i64);
+
+                match duration.subsec_nanos() {
+                    0 => (seconds, 0),
+                    nanos => (seconds - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+
+        Self {
+            seconds,
+            nanos,
+            offset: 0,
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// The gregorian date of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = DateTimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bytes = s.as_bytes();
+
+        let digits = |offset: usize, len: usize| -> Result<u32, DateTimeError> {
+            let field = bytes
+                .get(offset..offset + len)
+                .ok_or(DateTimeError::Syntax(offset))?;
+
+            field.iter().enumerate().try_fold(0, |value, (idx, c)| {
+                if c.is_ascii_digit() {
+                    Ok(value * 10 + (c - b'0') as u32)
+                } else {
+                    Err(DateTimeError::Syntax(offset + idx))
+                }
+            })
+        };
+
+        let expect = |offset: usize, chars: &[u8]| {
+            if bytes.get(offset).is_some_and(|c| chars.contains(c)) {
+                Ok(())
+            } else {
+                Err(DateTimeError::Syntax(offset))
+            }
+        };
+
+        let year = digits(0, 4)? as i64;
+        expect(4, b"-")?;
+        let month = digits(5, 2)?;
+        expect(7, b"-")?;
+        let day = digits(8, 2)?;
+        expect(10, b"Tt ")?;
+        let hour = digits(11, 2)?;
+        expect(13, b":")?;
+        let minute = digits(14, 2)?;
+        expect(16, b":")?;
+        let second = digits(17, 2)?;
+
+        let mut offset = 19;
+        let mut nanos = 0;
+
+        if bytes.get(offset) == Some(&b'.') {
+            offset += 1;
+
+            let start = offset;
+
+            while bytes.get(offset).is_some_and(u8::is_ascii_digit) {
+                if offset - start < 9 {
+                    nanos = nanos * 10 + (bytes[offset] - b'0') as u32;
+                }
+
+                offset += 1;
+            }
+
+            if offset == start {
+                return Err(DateTimeError::Syntax(offset));
+            }
+
+            for _ in (offset - start)..9 {
+                nanos *= 10;
+            }
+        }
+
+        let utc_offset = match bytes.get(offset) {
+            Some(b'Z' | b'z') => {
+                offset += 1;
+                0
+            }
+            Some(sign @ (b'+' | b'-')) => {
+                let hours = digits(offset + 1, 2)?;
+                expect(offset + 3, b":")?;
+                let minutes = digits(offset + 4, 2)?;
+
+                if hours >= 24 || minutes >= 60 {
+                    return Err(DateTimeError::Range("offset"));
+                }
+
+                let value = (hours * 60 + minutes) as i16;
+
+                offset += 6;
+
+                if *sign == b'-' { -value } else { value }
+            }
+            _ => return Err(DateTimeError::Syntax(offset)),
+        };
+
+        if offset != bytes.len() {
+            return Err(DateTimeError::Syntax(offset));
+        }
+
+        if !(1..=12).contains(&month) {
+            return Err(DateTimeError::Range("month"));
+        }
+
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(DateTimeError::Range("day"));
+        }
+
+        // `60` is a leap second.
+        if hour >= 24 || minute >= 60 || second > 60 {
+            return Err(DateTimeError::Range("time"));
+        }
+
+        let seconds = days_from_civil(year, month, day) * 86400
+            + (hour * 3600 + minute * 60 + second) as i64
+            - utc_offset as i64 * 60;
+
+        Ok(Self {
+            seconds,
+            nanos,
+            offset: utc_offset,
+        })
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let local = self.seconds + self.offset as i64 * 60;
+        let (year, month, day) = civil_from_days(local.div_euclid(86400));
+        let time = local.rem_euclid(86400);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )?;
+
+        if self.nanos != 0 {
+            let fraction = format!("{:09}", self.nanos);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+
+        match self.offset {
+            0 => write!(f, "Z"),
+            offset => write!(
+                f,
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.unsigned_abs() / 60,
+                offset.unsigned_abs() % 60
+            ),
+        }
+    }
+}
+
+impl TryFrom<String> for DateTime {
+    type Error = DateTimeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<DateTime> for String {
+    fn from(value: DateTime) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{DateTime, DateTimeError};
+
+    #[test]
+    fn test_datetime() {
+        let datetime = "2024-02-29T12:30:05.250+08:00".parse::<DateTime>().unwrap();
+
+        assert_eq!(datetime.unix_timestamp(), 1709181005);
+        assert_eq!(datetime.nanos(), 250_000_000);
+        assert_eq!(datetime.offset(), 480);
+        assert_eq!(datetime.to_string(), "2024-02-29T12:30:05.25+08:00");
+        assert_eq!(
+            datetime.with_offset(0).unwrap().to_string(),
+            "2024-02-29T04:30:05.25Z"
+        );
+
+        let epoch = "1969-12-31t23:59:59z".parse::<DateTime>().unwrap();
+
+        assert_eq!(epoch.unix_timestamp(), -1);
+        assert_eq!(epoch.to_string(), "1969-12-31T23:59:59Z");
+        #[cfg(feature = "std")]
+        assert_eq!(DateTime::from(epoch.to_system_time()), epoch);
+
+        assert_eq!(
+            "2023-02-29T00:00:00Z".parse::<DateTime>(),
+            Err(DateTimeError::Range("day"))
+        );
+        assert_eq!(
+            "2024-01-01 00:00:00".parse::<DateTime>(),
+            Err(DateTimeError::Syntax(19))
+        );
+        assert_eq!(
+            "2024-01-01T00:00:00.Z".parse::<DateTime>(),
+            Err(DateTimeError::Syntax(20))
+        );
+    }
+}
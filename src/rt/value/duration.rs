@@ -0,0 +1,139 @@
+use core::{fmt::Display, str::FromStr};
+
+use super::Scanner;
+
+/// Error returned by [`Duration`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum DurationError {
+    #[error("Expect a number of duration at {0}.")]
+    Number(usize),
+
+    #[error("Unexpect character `{1}` of duration at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Duration must not be negative.")]
+    Negative,
+}
+
+/// An animation duration: a clock value `2s`, `150ms`, or a percentage `50%` of the timeline.
+///
+/// A number without unit is in seconds, `h` and `min` are accepted too.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Duration {
+    /// Clock value, in seconds.
+    Time(f32),
+    /// Percentage of the timeline.
+    Percent(f32),
+}
+
+impl Default for Duration {
+    fn default() -> Self {
+        Self::Time(0.0)
+    }
+}
+
+impl Duration {
+    /// Create a clock value from milliseconds.
+    pub fn from_millis(millis: f32) -> Self {
+        Self::Time(millis / 1000.0)
+    }
+
+    /// Resolve this duration to seconds, percentages are relative to `timeline` seconds.
+    pub fn to_seconds(&self, timeline: f32) -> f32 {
+        match *self {
+            Duration::Time(seconds) => seconds,
+            Duration::Percent(percent) => timeline * percent / 100.0,
+        }
+    }
+
+    /// Resolve this duration to a [`core::time::Duration`], percentages are relative to `timeline`.
+    pub fn to_std(&self, timeline: core::time::Duration) -> core::time::Duration {
+        core::time::Duration::from_secs_f32(self.to_seconds(timeline.as_secs_f32()))
+    }
+}
+
+impl From<core::time::Duration> for Duration {
+    fn from(value: core::time::Duration) -> Self {
+        Self::Time(value.as_secs_f32())
+    }
+}
+
+impl FromStr for Duration {
+    type Err = DurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scanner = Scanner::new(s);
+
+        let value = scanner.number().map_err(DurationError::Number)?;
+
+        // `ms` and `min` must be tried before `s` and `h`.
+        let duration = if scanner.keyword("ms") {
+            Duration::from_millis(value)
+        } else if scanner.keyword("min") {
+            Duration::Time(value * 60.0)
+        } else if scanner.keyword("h") {
+            Duration::Time(value * 3600.0)
+        } else if scanner.keyword("%") {
+            Duration::Percent(value)
+        } else {
+            scanner.keyword("s");
+            Duration::Time(value)
+        };
+
+        scanner.skip_separators(false);
+
+        if scanner.peek().is_some() {
+            return Err(DurationError::Unexpect(
+                scanner.offset,
+                s[scanner.offset..].chars().next().unwrap(),
+            ));
+        }
+
+        if value < 0.0 {
+            return Err(DurationError::Negative);
+        }
+
+        Ok(duration)
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Duration::Time(seconds) => write!(f, "{}s", seconds),
+            Duration::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Duration, DurationError};
+
+    #[test]
+    fn test_duration() {
+        assert_eq!("2s".parse::<Duration>(), Ok(Duration::Time(2.0)));
+        assert_eq!(" 150ms ".parse::<Duration>(), Ok(Duration::Time(0.15)));
+        assert_eq!("1.5min".parse::<Duration>(), Ok(Duration::Time(90.0)));
+        assert_eq!("3".parse::<Duration>(), Ok(Duration::Time(3.0)));
+        assert_eq!("25%".parse::<Duration>(), Ok(Duration::Percent(25.0)));
+
+        assert_eq!(Duration::Percent(25.0).to_seconds(8.0), 2.0);
+        assert_eq!(
+            Duration::Time(0.25).to_std(core::time::Duration::ZERO),
+            core::time::Duration::from_millis(250)
+        );
+        assert_eq!(Duration::from_millis(500.0).to_string(), "0.5s");
+
+        assert_eq!("s".parse::<Duration>(), Err(DurationError::Number(0)));
+        assert_eq!(
+            "2sec".parse::<Duration>(),
+            Err(DurationError::Unexpect(2, 'e'))
+        );
+        assert_eq!("-1s".parse::<Duration>(), Err(DurationError::Negative));
+    }
+}
@@ -0,0 +1,158 @@
+use alloc::string::{String, ToString};
+use core::{fmt::Display, str::FromStr};
+
+/// Error returned by [`Iri`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum IriError {
+    #[error("Unexpect character `{1}` of iri at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Invalid scheme of iri at {0}.")]
+    Scheme(usize),
+
+    #[error("Invalid percent encoding of iri at {0}.")]
+    Percent(usize),
+}
+
+/// A validated IRI reference: `https://example.com/a.svg#b`, `../a.png` or `#id`.
+///
+/// Whitespaces, control characters and the delimiters `<>"{}|\^` and backtick are rejected,
+/// so the text can be displayed and written back into markup without escaping.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(try_from = "String"))]
+pub struct Iri(String);
+
+impl Iri {
+    /// Returns the text of this iri.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the scheme of an absolute iri: `https`.
+    pub fn scheme(&self) -> Option<&str> {
+        let end = self.0.find([':', '/', '?', '#'])?;
+
+        self.0[end..].starts_with(':').then(|| &self.0[..end])
+    }
+
+    /// Returns the fragment after `#`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.0.split_once('#').map(|(_, fragment)| fragment)
+    }
+
+    /// Returns true if this iri only refers to a fragment of the current document: `#id`.
+    pub fn is_local(&self) -> bool {
+        self.0.starts_with('#')
+    }
+
+    /// Resolve this iri against `base`, or parse it as an absolute url if `base` is `None`.
+    #[cfg(feature = "url")]
+    pub fn to_url(&self, base: Option<&url::Url>) -> Result<url::Url, url::ParseError> {
+        match base {
+            Some(base) => base.join(&self.0),
+            None => url::Url::parse(&self.0),
+        }
+    }
+}
+
+#[cfg(feature = "url")]
+impl From<url::Url> for Iri {
+    fn from(value: url::Url) -> Self {
+        Self(value.into())
+    }
+}
+
+impl FromStr for Iri {
+    type Err = IriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+
+        for (offset, c) in s.char_indices() {
+            match c {
+                '%' if !bytes
+                    .get(offset + 1..offset + 3)
+                    .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) =>
+                {
+                    return Err(IriError::Percent(offset));
+                }
+                '<' | '>' | '"' | '{' | '}' | '|' | '\\' | '^' | '`' => {
+                    return Err(IriError::Unexpect(offset, c));
+                }
+                c if c.is_whitespace() || c.is_control() => {
+                    return Err(IriError::Unexpect(offset, c));
+                }
+                _ => {}
+            }
+        }
+
+        let iri = Self(s.to_string());
+
+        if let Some(scheme) = iri.scheme() {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+            if !valid {
+                return Err(IriError::Scheme(0));
+            }
+        }
+
+        Ok(iri)
+    }
+}
+
+impl TryFrom<String> for Iri {
+    type Error = IriError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Iri> for String {
+    fn from(value: Iri) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for Iri {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Iri {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Iri, IriError};
+
+    #[test]
+    fn test_iri() {
+        let iri = "https://example.com/%E4%B8%AD/文.svg#a"
+            .parse::<Iri>()
+            .unwrap();
+
+        assert_eq!(iri.scheme(), Some("https"));
+        assert_eq!(iri.fragment(), Some("a"));
+        assert!(!iri.is_local());
+
+        let iri = "#clip".parse::<Iri>().unwrap();
+
+        assert_eq!(iri.scheme(), None);
+        assert!(iri.is_local());
+        assert_eq!("../a:b.png".parse::<Iri>().unwrap().scheme(), None);
+
+        assert_eq!("a b".parse::<Iri>(), Err(IriError::Unexpect(1, ' ')));
+        assert_eq!("a%2".parse::<Iri>(), Err(IriError::Percent(1)));
+        assert_eq!("1http://a".parse::<Iri>(), Err(IriError::Scheme(0)));
+    }
+}
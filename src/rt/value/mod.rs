@@ -0,0 +1,136 @@
+//! Builtin value types shared by vector vocabularies.
+
+mod datetime;
+pub use datetime::*;
+mod duration;
+pub use duration::*;
+mod iri;
+pub use iri::*;
+mod path;
+pub use path::*;
+mod rect;
+pub use rect::*;
+mod transform;
+pub use transform::*;
+mod uuid;
+pub use self::uuid::*;
+
+/// Runtime type of `decimal(precision, scale)` fields.
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
+/// A cursor over the bytes of a value's text form.
+struct Scanner<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            offset: 0,
+        }
+    }
+
+    /// Skip whitespaces and, if `comma` is true, at most one comma.
+    fn skip_separators(&mut self, comma: bool) {
+        let mut comma = comma;
+
+        while let Some(c) = self.input.get(self.offset) {
+            match c {
+                b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' => {}
+                b',' if comma => comma = false,
+                _ => break,
+            }
+
+            self.offset += 1;
+        }
+    }
+
+    /// Consume `keyword` if the remaining input starts with it.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if self.input[self.offset..].starts_with(keyword.as_bytes()) {
+            self.offset += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.offset).copied()
+    }
+
+    /// Returns true if the next token is the start of a number.
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    /// Scan a number, returns the offset of the invalid number on error.
+    fn number(&mut self) -> Result<f32, usize> {
+        self.skip_separators(true);
+
+        let start = self.offset;
+
+        let digits = |scanner: &mut Self| {
+            let start = scanner.offset;
+
+            while let Some(b'0'..=b'9') = scanner.peek() {
+                scanner.offset += 1;
+            }
+
+            scanner.offset > start
+        };
+
+        if let Some(b'-' | b'+') = self.peek() {
+            self.offset += 1;
+        }
+
+        let mut valid = digits(self);
+
+        if let Some(b'.') = self.peek() {
+            self.offset += 1;
+            valid |= digits(self);
+        }
+
+        if !valid {
+            return Err(start);
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            let mantissa_end = self.offset;
+
+            self.offset += 1;
+
+            if let Some(b'-' | b'+') = self.peek() {
+                self.offset += 1;
+            }
+
+            if !digits(self) {
+                self.offset = mantissa_end;
+            }
+        }
+
+        // the scanned bytes are all ascii.
+        core::str::from_utf8(&self.input[start..self.offset])
+            .unwrap()
+            .parse()
+            .map_err(|_| start)
+    }
+
+    /// Scan a flag `0` or `1`, returns the offset of the invalid flag on error.
+    fn flag(&mut self) -> Result<bool, usize> {
+        self.skip_separators(true);
+
+        let value = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.offset),
+        };
+
+        self.offset += 1;
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,344 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt::Display, str::FromStr};
+
+use super::Scanner;
+
+/// Error returned by [`PathData`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum PathDataError {
+    #[error("Path data must start with a move command.")]
+    MoveTo,
+
+    #[error("Unexpect character `{1}` of path data at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Expect a number of path data at {0}.")]
+    Number(usize),
+
+    #[error("Expect an arc flag `0` or `1` of path data at {0}.")]
+    Flag(usize),
+}
+
+/// A command of [`PathData`], coordinates are absolute if `abs` is true, otherwise
+/// they are relative to the current point.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum PathCommand {
+    /// `M`/`m`: start a new sub-path.
+    MoveTo { abs: bool, x: f32, y: f32 },
+    /// `L`/`l`: draw a straight line.
+    LineTo { abs: bool, x: f32, y: f32 },
+    /// `H`/`h`: draw a horizontal line.
+    HorizontalLineTo { abs: bool, x: f32 },
+    /// `V`/`v`: draw a vertical line.
+    VerticalLineTo { abs: bool, y: f32 },
+    /// `C`/`c`: draw a cubic bézier curve.
+    CubicTo {
+        abs: bool,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        x: f32,
+        y: f32,
+    },
+    /// `S`/`s`: draw a cubic bézier curve, the first control point is the reflection of the previous one.
+    SmoothCubicTo {
+        abs: bool,
+        x2: f32,
+        y2: f32,
+        x: f32,
+        y: f32,
+    },
+    /// `Q`/`q`: draw a quadratic bézier curve.
+    QuadraticTo {
+        abs: bool,
+        x1: f32,
+        y1: f32,
+        x: f32,
+        y: f32,
+    },
+    /// `T`/`t`: draw a quadratic bézier curve, the control point is the reflection of the previous one.
+    SmoothQuadraticTo { abs: bool, x: f32, y: f32 },
+    /// `A`/`a`: draw an elliptical arc.
+    ArcTo {
+        abs: bool,
+        rx: f32,
+        ry: f32,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        x: f32,
+        y: f32,
+    },
+    /// `Z`/`z`: close the current sub-path.
+    Close,
+}
+
+/// SVG-style path data, parsed from and printed to its text form: `M10 10h20v20z`.
+#[derive(Debug, Default, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PathData(pub Vec<PathCommand>);
+
+impl From<Vec<PathCommand>> for PathData {
+    fn from(value: Vec<PathCommand>) -> Self {
+        Self(value)
+    }
+}
+
+fn number(scanner: &mut Scanner<'_>) -> Result<f32, PathDataError> {
+    scanner.number().map_err(PathDataError::Number)
+}
+
+fn flag(scanner: &mut Scanner<'_>) -> Result<bool, PathDataError> {
+    scanner.flag().map_err(PathDataError::Flag)
+}
+
+impl FromStr for PathData {
+    type Err = PathDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scanner = Scanner::new(s);
+
+        let mut commands = vec![];
+
+        scanner.skip_separators(false);
+
+        while let Some(c) = scanner.peek() {
+            let abs = c.is_ascii_uppercase();
+
+            if commands.is_empty() && !matches!(c, b'M' | b'm') {
+                return Err(PathDataError::MoveTo);
+            }
+
+            scanner.offset += 1;
+
+            // each command can be repeated by giving further argument sets, except `Z`.
+            let mut first = true;
+
+            while first || scanner.at_number() {
+                let command = match c.to_ascii_uppercase() {
+                    b'M' if first => PathCommand::MoveTo {
+                        abs,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    // subsequent pairs of a move command are implicit line commands.
+                    b'M' | b'L' => PathCommand::LineTo {
+                        abs,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'H' => PathCommand::HorizontalLineTo {
+                        abs,
+                        x: number(&mut scanner)?,
+                    },
+                    b'V' => PathCommand::VerticalLineTo {
+                        abs,
+                        y: number(&mut scanner)?,
+                    },
+                    b'C' => PathCommand::CubicTo {
+                        abs,
+                        x1: number(&mut scanner)?,
+                        y1: number(&mut scanner)?,
+                        x2: number(&mut scanner)?,
+                        y2: number(&mut scanner)?,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'S' => PathCommand::SmoothCubicTo {
+                        abs,
+                        x2: number(&mut scanner)?,
+                        y2: number(&mut scanner)?,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'Q' => PathCommand::QuadraticTo {
+                        abs,
+                        x1: number(&mut scanner)?,
+                        y1: number(&mut scanner)?,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'T' => PathCommand::SmoothQuadraticTo {
+                        abs,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'A' => PathCommand::ArcTo {
+                        abs,
+                        rx: number(&mut scanner)?,
+                        ry: number(&mut scanner)?,
+                        x_axis_rotation: number(&mut scanner)?,
+                        large_arc: flag(&mut scanner)?,
+                        sweep: flag(&mut scanner)?,
+                        x: number(&mut scanner)?,
+                        y: number(&mut scanner)?,
+                    },
+                    b'Z' if first => PathCommand::Close,
+                    _ => {
+                        let offset = scanner.offset - 1;
+
+                        return Err(PathDataError::Unexpect(
+                            offset,
+                            s[offset..].chars().next().unwrap(),
+                        ));
+                    }
+                };
+
+                commands.push(command);
+
+                first = false;
+
+                if command == PathCommand::Close {
+                    break;
+                }
+
+                scanner.skip_separators(true);
+            }
+
+            scanner.skip_separators(false);
+        }
+
+        Ok(Self(commands))
+    }
+}
+
+impl Display for PathData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let letter = |abs: bool, c: char| if abs { c } else { c.to_ascii_lowercase() };
+        let flag = |v: bool| if v { 1 } else { 0 };
+
+        for (idx, command) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+
+            match *command {
+                PathCommand::MoveTo { abs, x, y } => write!(f, "{}{} {}", letter(abs, 'M'), x, y)?,
+                PathCommand::LineTo { abs, x, y } => write!(f, "{}{} {}", letter(abs, 'L'), x, y)?,
+                PathCommand::HorizontalLineTo { abs, x } => write!(f, "{}{}", letter(abs, 'H'), x)?,
+                PathCommand::VerticalLineTo { abs, y } => write!(f, "{}{}", letter(abs, 'V'), y)?,
+                PathCommand::CubicTo {
+                    abs,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => write!(
+                    f,
+                    "{}{} {} {} {} {} {}",
+                    letter(abs, 'C'),
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y
+                )?,
+                PathCommand::SmoothCubicTo { abs, x2, y2, x, y } => {
+                    write!(f, "{}{} {} {} {}", letter(abs, 'S'), x2, y2, x, y)?
+                }
+                PathCommand::QuadraticTo { abs, x1, y1, x, y } => {
+                    write!(f, "{}{} {} {} {}", letter(abs, 'Q'), x1, y1, x, y)?
+                }
+                PathCommand::SmoothQuadraticTo { abs, x, y } => {
+                    write!(f, "{}{} {}", letter(abs, 'T'), x, y)?
+                }
+                PathCommand::ArcTo {
+                    abs,
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x,
+                    y,
+                } => write!(
+                    f,
+                    "{}{} {} {} {} {} {} {}",
+                    letter(abs, 'A'),
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    flag(large_arc),
+                    flag(sweep),
+                    x,
+                    y
+                )?,
+                PathCommand::Close => write!(f, "Z")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{PathCommand, PathData, PathDataError};
+
+    #[test]
+    fn test_path_data() {
+        let path = "M10,20 30 40h-5.5V.5e1c1 2 3 4 5 6s1-2-3-4a25 25 -30 0110 5z m1 1Z"
+            .parse::<PathData>()
+            .unwrap();
+
+        assert_eq!(
+            path.0[..4],
+            [
+                PathCommand::MoveTo {
+                    abs: true,
+                    x: 10.0,
+                    y: 20.0
+                },
+                PathCommand::LineTo {
+                    abs: true,
+                    x: 30.0,
+                    y: 40.0
+                },
+                PathCommand::HorizontalLineTo {
+                    abs: false,
+                    x: -5.5
+                },
+                PathCommand::VerticalLineTo { abs: true, y: 5.0 },
+            ]
+        );
+
+        assert_eq!(
+            path.0[6],
+            PathCommand::ArcTo {
+                abs: false,
+                rx: 25.0,
+                ry: 25.0,
+                x_axis_rotation: -30.0,
+                large_arc: false,
+                sweep: true,
+                x: 10.0,
+                y: 5.0
+            }
+        );
+
+        assert_eq!(path.0.len(), 10);
+
+        assert_eq!(
+            path.to_string(),
+            "M10 20 L30 40 h-5.5 V5 c1 2 3 4 5 6 s1 -2 -3 -4 a25 25 -30 0 1 10 5 Z m1 1 Z"
+        );
+
+        assert_eq!(path.to_string().parse::<PathData>(), Ok(path));
+
+        assert_eq!("L1 1".parse::<PathData>(), Err(PathDataError::MoveTo));
+        assert_eq!("M1".parse::<PathData>(), Err(PathDataError::Number(2)));
+        assert_eq!(
+            "M1 1 X".parse::<PathData>(),
+            Err(PathDataError::Unexpect(5, 'X'))
+        );
+    }
+}
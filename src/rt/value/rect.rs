@@ -0,0 +1,174 @@
+use core::{fmt::Display, str::FromStr};
+
+use super::{Matrix, Scanner};
+
+/// Error returned by [`Rect`]/[`ViewBox`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum RectError {
+    #[error("Expect a number of rect at {0}.")]
+    Number(usize),
+
+    #[error("Unexpect character `{1}` of rect at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Width and height of view box must not be negative.")]
+    Negative,
+}
+
+/// A rectangle in the textual form `x y width height`: `0 0 100 100`.
+#[derive(Debug, Default, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Create a rect from its origin and size.
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the x coordinate of the right edge.
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    /// Returns the y coordinate of the bottom edge.
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// Returns true if the area of this rect is zero.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0.0 || self.height == 0.0
+    }
+
+    /// Returns true if point `(x, y)` is inside this rect.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+}
+
+impl FromStr for Rect {
+    type Err = RectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scanner = Scanner::new(s);
+
+        let mut values = [0f32; 4];
+
+        for value in values.iter_mut() {
+            *value = scanner.number().map_err(RectError::Number)?;
+        }
+
+        scanner.skip_separators(false);
+
+        if scanner.peek().is_some() {
+            return Err(RectError::Unexpect(
+                scanner.offset,
+                s[scanner.offset..].chars().next().unwrap(),
+            ));
+        }
+
+        let [x, y, width, height] = values;
+
+        Ok(Self::new(x, y, width, height))
+    }
+}
+
+impl Display for Rect {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} {} {} {}", self.x, self.y, self.width, self.height)
+    }
+}
+
+/// A view box: the rect of user space that is mapped to the bounds of a viewport,
+/// its width and height are never negative.
+#[derive(Debug, Default, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ViewBox(pub Rect);
+
+impl TryFrom<Rect> for ViewBox {
+    type Error = RectError;
+
+    fn try_from(value: Rect) -> Result<Self, Self::Error> {
+        if value.width < 0.0 || value.height < 0.0 {
+            return Err(RectError::Negative);
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl ViewBox {
+    /// Returns the matrix that maps this view box onto a `width` x `height` viewport, stretching it if needed.
+    pub fn to_viewport(&self, width: f32, height: f32) -> Matrix {
+        let sx = if self.0.width == 0.0 {
+            0.0
+        } else {
+            width / self.0.width
+        };
+
+        let sy = if self.0.height == 0.0 {
+            0.0
+        } else {
+            height / self.0.height
+        };
+
+        Matrix::new(sx, 0.0, 0.0, sy, -self.0.x * sx, -self.0.y * sy)
+    }
+}
+
+impl FromStr for ViewBox {
+    type Err = RectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Rect>()?.try_into()
+    }
+}
+
+impl Display for ViewBox {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Rect, RectError, ViewBox};
+
+    #[test]
+    fn test_rect() {
+        let rect = "0,-10 100 .5e2".parse::<Rect>().unwrap();
+
+        assert_eq!(rect, Rect::new(0.0, -10.0, 100.0, 50.0));
+        assert_eq!(rect.to_string(), "0 -10 100 50");
+        assert!(rect.contains(99.0, 39.0) && !rect.contains(100.0, 0.0));
+
+        assert_eq!("0 0 1".parse::<Rect>(), Err(RectError::Number(5)));
+        assert_eq!(
+            "0 0 1 1 1".parse::<Rect>(),
+            Err(RectError::Unexpect(8, '1'))
+        );
+        assert_eq!("0 0 -1 1".parse::<ViewBox>(), Err(RectError::Negative));
+
+        let viewport = "10 10 50 50"
+            .parse::<ViewBox>()
+            .unwrap()
+            .to_viewport(100.0, 100.0);
+
+        assert_eq!(viewport.apply(10.0, 60.0), (0.0, 100.0));
+    }
+}
@@ -0,0 +1,342 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt::Display, ops::Mul, str::FromStr};
+
+use super::Scanner;
+
+/// Error returned by [`Transform`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TransformError {
+    #[error("Unexpect character `{1}` of transform at {0}.")]
+    Unexpect(usize, char),
+
+    #[error("Expect a number of transform at {0}.")]
+    Number(usize),
+
+    #[error("Expect `(` of transform function at {0}.")]
+    ParenStart(usize),
+
+    #[error("Expect `)` of transform function at {0}.")]
+    ParenEnd(usize),
+
+    #[error("Invalid arguments count of transform function at {0}.")]
+    Args(usize),
+}
+
+/// An affine transform matrix `[a c e; b d f; 0 0 1]`.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Matrix {
+    /// The identity matrix.
+    pub const IDENTITY: Matrix = Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    /// Create a matrix from its six components.
+    pub const fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Returns `self * other`, the transform that applies `other` first and then `self`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    /// Apply this matrix to point `(x, y)`.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(&rhs)
+    }
+}
+
+/// A function of [`Transform`], angles are in degrees.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum TransformFunction {
+    /// `matrix(a b c d e f)`
+    Matrix(Matrix),
+    /// `translate(x [y])`, `y` defaults to `0`.
+    Translate { x: f32, y: f32 },
+    /// `scale(x [y])`, `y` defaults to `x`.
+    Scale { x: f32, y: f32 },
+    /// `rotate(angle [cx cy])`, rotate around point `(cx, cy)`, which defaults to the origin.
+    Rotate { angle: f32, cx: f32, cy: f32 },
+    /// `skewX(angle)`
+    SkewX(f32),
+    /// `skewY(angle)`
+    SkewY(f32),
+}
+
+impl TransformFunction {
+    /// Convert this function to a transform matrix, the trigonometric functions require `std`.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn to_matrix(&self) -> Matrix {
+        match *self {
+            TransformFunction::Matrix(matrix) => matrix,
+            TransformFunction::Translate { x, y } => Matrix::new(1.0, 0.0, 0.0, 1.0, x, y),
+            TransformFunction::Scale { x, y } => Matrix::new(x, 0.0, 0.0, y, 0.0, 0.0),
+            TransformFunction::Rotate { angle, cx, cy } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+
+                Matrix::new(1.0, 0.0, 0.0, 1.0, cx, cy)
+                    * Matrix::new(cos, sin, -sin, cos, 0.0, 0.0)
+                    * Matrix::new(1.0, 0.0, 0.0, 1.0, -cx, -cy)
+            }
+            TransformFunction::SkewX(angle) => {
+                Matrix::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+            }
+            TransformFunction::SkewY(angle) => {
+                Matrix::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+            }
+        }
+    }
+}
+
+/// A transform list in the standard textual form: `translate(10 20) rotate(45)`.
+///
+/// Like SVG, the functions are composed from left to right, so the right-most function is applied to a point first.
+#[derive(Debug, Default, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Transform(pub Vec<TransformFunction>);
+
+impl From<Vec<TransformFunction>> for Transform {
+    fn from(value: Vec<TransformFunction>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Matrix> for Transform {
+    fn from(value: Matrix) -> Self {
+        Self(vec![TransformFunction::Matrix(value)])
+    }
+}
+
+impl Transform {
+    /// Append `function` to the end of the list, it is applied to a point before the existing functions.
+    pub fn then(mut self, function: TransformFunction) -> Self {
+        self.0.push(function);
+        self
+    }
+
+    /// Append all functions of `other`, the result applies `other` first and then `self`.
+    pub fn concat(mut self, other: &Transform) -> Self {
+        self.0.extend_from_slice(&other.0);
+        self
+    }
+
+    /// Compose the whole list into one matrix.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn to_matrix(&self) -> Matrix {
+        self.0.iter().fold(Matrix::IDENTITY, |matrix, function| {
+            matrix * function.to_matrix()
+        })
+    }
+}
+
+impl FromStr for Transform {
+    type Err = TransformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scanner = Scanner::new(s);
+
+        let mut functions = vec![];
+
+        scanner.skip_separators(false);
+
+        while scanner.peek().is_some() {
+            let start = scanner.offset;
+
+            let name = ["matrix", "translate", "scale", "rotate", "skewX", "skewY"]
+                .into_iter()
+                .find(|name| scanner.keyword(name))
+                .ok_or_else(|| {
+                    TransformError::Unexpect(start, s[start..].chars().next().unwrap())
+                })?;
+
+            scanner.skip_separators(false);
+
+            if !scanner.keyword("(") {
+                return Err(TransformError::ParenStart(scanner.offset));
+            }
+
+            let mut args = vec![];
+
+            scanner.skip_separators(false);
+
+            while scanner.at_number() {
+                args.push(scanner.number().map_err(TransformError::Number)?);
+                scanner.skip_separators(true);
+            }
+
+            if !scanner.keyword(")") {
+                return Err(TransformError::ParenEnd(scanner.offset));
+            }
+
+            let function = match (name, args.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => {
+                    TransformFunction::Matrix(Matrix::new(a, b, c, d, e, f))
+                }
+                ("translate", &[x]) => TransformFunction::Translate { x, y: 0.0 },
+                ("translate", &[x, y]) => TransformFunction::Translate { x, y },
+                ("scale", &[x]) => TransformFunction::Scale { x, y: x },
+                ("scale", &[x, y]) => TransformFunction::Scale { x, y },
+                ("rotate", &[angle]) => TransformFunction::Rotate {
+                    angle,
+                    cx: 0.0,
+                    cy: 0.0,
+                },
+                ("rotate", &[angle, cx, cy]) => TransformFunction::Rotate { angle, cx, cy },
+                ("skewX", &[angle]) => TransformFunction::SkewX(angle),
+                ("skewY", &[angle]) => TransformFunction::SkewY(angle),
+                _ => return Err(TransformError::Args(start)),
+            };
+
+            functions.push(function);
+
+            scanner.skip_separators(true);
+        }
+
+        Ok(Self(functions))
+    }
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (idx, function) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+
+            match *function {
+                TransformFunction::Matrix(Matrix {
+                    a,
+                    b,
+                    c,
+                    d,
+                    e,
+                    f: ty,
+                }) => write!(f, "matrix({} {} {} {} {} {})", a, b, c, d, e, ty)?,
+                TransformFunction::Translate { x, y } => write!(f, "translate({} {})", x, y)?,
+                TransformFunction::Scale { x, y } => write!(f, "scale({} {})", x, y)?,
+                TransformFunction::Rotate { angle, cx, cy } => {
+                    if cx == 0.0 && cy == 0.0 {
+                        write!(f, "rotate({})", angle)?
+                    } else {
+                        write!(f, "rotate({} {} {})", angle, cx, cy)?
+                    }
+                }
+                TransformFunction::SkewX(angle) => write!(f, "skewX({})", angle)?,
+                TransformFunction::SkewY(angle) => write!(f, "skewY({})", angle)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Transform, TransformError, TransformFunction};
+
+    #[test]
+    fn test_transform() {
+        let transform = "translate(10,20), rotate(90 5 5)\nscale(2)skewX(0)"
+            .parse::<Transform>()
+            .unwrap();
+
+        assert_eq!(
+            transform.0,
+            [
+                TransformFunction::Translate { x: 10.0, y: 20.0 },
+                TransformFunction::Rotate {
+                    angle: 90.0,
+                    cx: 5.0,
+                    cy: 5.0
+                },
+                TransformFunction::Scale { x: 2.0, y: 2.0 },
+                TransformFunction::SkewX(0.0),
+            ]
+        );
+
+        assert_eq!(
+            transform.to_string(),
+            "translate(10 20) rotate(90 5 5) scale(2 2) skewX(0)"
+        );
+
+        assert_eq!(
+            transform.to_string().parse::<Transform>(),
+            Ok(transform.clone())
+        );
+
+        assert_eq!(
+            "scale(1 2 3)".parse::<Transform>(),
+            Err(TransformError::Args(0))
+        );
+        assert_eq!(
+            "rotate(1".parse::<Transform>(),
+            Err(TransformError::ParenEnd(8))
+        );
+        assert_eq!(
+            "move(1)".parse::<Transform>(),
+            Err(TransformError::Unexpect(0, 'm'))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_matrix() {
+        use super::Matrix;
+
+        let transform = "translate(10 20) rotate(90 5 5) scale(2)"
+            .parse::<Transform>()
+            .unwrap();
+
+        // (1, 0) => scale: (2, 0) => rotate around (5, 5): (10, 2) => translate: (20, 22)
+        let (x, y) = transform.to_matrix().apply(1.0, 0.0);
+
+        assert!((x - 20.0).abs() < 1e-4 && (y - 22.0).abs() < 1e-4);
+
+        assert_eq!(
+            Transform::default()
+                .then(TransformFunction::Translate { x: 1.0, y: 2.0 })
+                .to_matrix(),
+            Matrix::new(1.0, 0.0, 0.0, 1.0, 1.0, 2.0)
+        );
+    }
+}
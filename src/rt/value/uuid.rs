@@ -0,0 +1,178 @@
+use alloc::string::{String, ToString};
+use core::{fmt::Display, str::FromStr};
+
+/// Error returned by [`Uuid`] parsing.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum UuidError {
+    #[error("Invalid uuid syntax at {0}, expect `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`.")]
+    Syntax(usize),
+
+    #[error("Invalid uuid length {0}, expect 36 characters.")]
+    Length(usize),
+}
+
+/// A 128-bit universally unique identifier: `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+///
+/// The text form is the hyphenated lowercase hex, binary formats should encode the 16 [`bytes`](Self::as_bytes).
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    /// The nil uuid, all bits are zero.
+    pub const NIL: Uuid = Uuid([0; 16]);
+
+    /// Create a uuid from its big-endian bytes.
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the big-endian bytes of this uuid.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Create a uuid from a 128-bit value.
+    pub const fn from_u128(value: u128) -> Self {
+        Self(value.to_be_bytes())
+    }
+
+    /// Returns this uuid as a 128-bit value.
+    pub fn as_u128(&self) -> u128 {
+        u128::from_be_bytes(self.0)
+    }
+
+    /// Returns true if all bits are zero.
+    pub fn is_nil(&self) -> bool {
+        self.0 == [0; 16]
+    }
+}
+
+impl From<[u8; 16]> for Uuid {
+    fn from(value: [u8; 16]) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Uuid> for [u8; 16] {
+    fn from(value: Uuid) -> Self {
+        value.0
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<::uuid::Uuid> for Uuid {
+    fn from(value: ::uuid::Uuid) -> Self {
+        Self(value.into_bytes())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for ::uuid::Uuid {
+    fn from(value: Uuid) -> Self {
+        ::uuid::Uuid::from_bytes(value.0)
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = UuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.len() != 36 {
+            return Err(UuidError::Length(s.len()));
+        }
+
+        let mut bytes = [0u8; 16];
+        let mut digits = 0;
+
+        for (offset, c) in s.bytes().enumerate() {
+            if matches!(offset, 8 | 13 | 18 | 23) {
+                if c != b'-' {
+                    return Err(UuidError::Syntax(offset));
+                }
+
+                continue;
+            }
+
+            let value = match c {
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'f' => c - b'a' + 10,
+                b'A'..=b'F' => c - b'A' + 10,
+                _ => return Err(UuidError::Syntax(offset)),
+            };
+
+            bytes[digits / 2] = bytes[digits / 2] << 4 | value;
+            digits += 1;
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (idx, byte) in self.0.iter().enumerate() {
+            if matches!(idx, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for Uuid {
+    type Error = UuidError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Uuid> for String {
+    fn from(value: Uuid) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Uuid, UuidError};
+
+    #[test]
+    fn test_uuid() {
+        let uuid = "67E55044-10b1-426f-9247-bb680e5fe0c8"
+            .parse::<Uuid>()
+            .unwrap();
+
+        assert_eq!(uuid.as_u128(), 0x67e5504410b1426f9247bb680e5fe0c8);
+        assert_eq!(uuid.as_bytes()[0], 0x67);
+        assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert!(!uuid.is_nil());
+        assert_eq!(
+            Uuid::NIL.to_string(),
+            "00000000-0000-0000-0000-000000000000"
+        );
+
+        assert_eq!(
+            "67e5504410b1426f9247bb680e5fe0c8".parse::<Uuid>(),
+            Err(UuidError::Length(32))
+        );
+        assert_eq!(
+            "67e55044-10b1_426f-9247-bb680e5fe0c8".parse::<Uuid>(),
+            Err(UuidError::Syntax(13))
+        );
+        assert_eq!(
+            "67e55044-10b1-426f-9247-bb680e5fe0cg".parse::<Uuid>(),
+            Err(UuidError::Syntax(35))
+        );
+    }
+}